@@ -60,6 +60,22 @@ impl MBC1 {
             || self.rom[cartridge::header::TYPE_ADDR] == cartridge::mbc_id::MBC1_RAM_BATTERY;
     }
 
+    /// ROM bank mapped at 0x4000 ~ 0x7FFF.
+    fn rom_bank(&self) -> usize {
+        let mut bank_number: usize = self.rom_bank_select_register;
+
+        if self.rom[cartridge::header::ROM_SIZE_ADDR] >= cartridge::rom_size_id::ONE_MEGABYTE {
+            bank_number |= self.ram_bank_select_register << 5;
+        }
+
+        match bank_number {
+            0x00 | 0x20 | 0x40 | 0x60 => bank_number += 1,
+            _ => {}
+        }
+
+        return bank_number;
+    }
+
     fn read(&self, addr: usize) -> Option<u8> {
         log::trace!("MBC1 read at address: {:#X}", addr);
         if addr < 0x4000 {
@@ -88,19 +104,7 @@ impl MBC1 {
 
         // ROM Banks 0x01 - 0x7F. See https://gbdev.io/pandocs/MBC1.html#40007fff--rom-bank-01-7f-read-only for more details.
         if addr >= 0x4000 && addr < 0x8000 {
-            let mut translated_addr = addr - 0x4000;
-            let mut bank_number: usize = self.rom_bank_select_register;
-
-            if self.rom[cartridge::header::ROM_SIZE_ADDR] >= cartridge::rom_size_id::ONE_MEGABYTE {
-                bank_number |= self.ram_bank_select_register << 5;
-            }
-
-            match bank_number {
-                0x00 | 0x20 | 0x40 | 0x60 => bank_number += 1,
-                _ => {}
-            }
-
-            translated_addr += bank_number * 0x4000;
+            let translated_addr = (addr - 0x4000) + self.rom_bank() * 0x4000;
             return Some(self.rom[translated_addr].clone());
         }
 
@@ -185,4 +189,8 @@ impl cartridge::Interface for MBC1 {
     fn write(&mut self, addr: usize, val: u8) {
         self.write(addr, val);
     }
+
+    fn get_rom_bank(&self) -> usize {
+        return self.rom_bank();
+    }
 }
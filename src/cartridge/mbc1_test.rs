@@ -598,3 +598,20 @@ fn write() {
         (tc.run_fn)();
     }
 }
+
+#[test]
+fn rom_bank() {
+    let mut cart_data: Vec<u8> = vec![0x00; 0x4000 * 64];
+    cart_data[cartridge::header::ROM_SIZE_ADDR] = cartridge::rom_size_id::ONE_MEGABYTE;
+    cart_data[cartridge::header::TYPE_ADDR] = cartridge::mbc_id::MBC1;
+    let mut mbc1 = MBC1::new(cart_data);
+    assert_eq!(cartridge::Interface::get_rom_bank(mbc1.as_ref()), 1);
+
+    mbc1.write(0x2000, 0x05);
+    assert_eq!(cartridge::Interface::get_rom_bank(mbc1.as_ref()), 0x05);
+
+    // Bank 0x20 can't be mapped there, so bank 0x21 is instead.
+    mbc1.write(0x2000, 0x00);
+    mbc1.write(0x4000, 0x01);
+    assert_eq!(cartridge::Interface::get_rom_bank(mbc1.as_ref()), 0x21);
+}
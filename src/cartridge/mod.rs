@@ -15,6 +15,11 @@ pub trait Interface: Any + Debug + Send {
     //fn as_any(&self) -> &dyn Any;
     fn read(&self, addr: usize) -> Option<u8>;
     fn write(&mut self, addr: usize, val: u8);
+
+    /// ROM bank mapped at 0x4000 ~ 0x7FFF.
+    fn get_rom_bank(&self) -> usize {
+        return 1;
+    }
 }
 
 /// Module containing important addresses in the cartridge
@@ -0,0 +1,217 @@
+use crate::cheats::{self, Cheat, Code, RamWrite, RomPatch};
+
+#[test]
+fn parse_code() {
+    struct TestCase {
+        description: String,
+        text: String,
+        expected: Result<Code, String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("Game Genie code"),
+            text: String::from("3E0-F5D"),
+            expected: Ok(Code::GameGenie(RomPatch {
+                address: 0x20F5,
+                value: 0x3E,
+                compare: None,
+            })),
+        },
+        TestCase {
+            description: String::from("Game Genie code with compare value"),
+            text: String::from("3e0-f5d-4c1"),
+            expected: Ok(Code::GameGenie(RomPatch {
+                address: 0x20F5,
+                value: 0x3E,
+                compare: Some(0xEA),
+            })),
+        },
+        TestCase {
+            description: String::from("Game Genie code outside of ROM"),
+            text: String::from("000-000"),
+            expected: Err(String::from("'000-000': patches 0xF000, outside of ROM")),
+        },
+        TestCase {
+            description: String::from("GameShark code"),
+            text: String::from("0163A4C0"),
+            expected: Ok(Code::GameShark(RamWrite {
+                bank: 0x01,
+                address: 0xC0A4,
+                value: 0x63,
+            })),
+        },
+        TestCase {
+            description: String::from("GameShark code outside of RAM"),
+            text: String::from("01FF4080"),
+            expected: Err(String::from("'01FF4080': writes to 0x8040, outside of RAM")),
+        },
+        TestCase {
+            description: String::from("misplaced dash"),
+            text: String::from("3E0F-5D"),
+            expected: Err(String::from("invalid code '3E0F-5D'")),
+        },
+        TestCase {
+            description: String::from("not hexadecimal"),
+            text: String::from("0163A4CG"),
+            expected: Err(String::from("invalid code '0163A4CG'")),
+        },
+        TestCase {
+            description: String::from("wrong length"),
+            text: String::from("0163A4C"),
+            expected: Err(String::from("invalid code '0163A4C'")),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            cheats::parse_code(&tc.text),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn parse_codes() {
+    assert_eq!(
+        cheats::parse_codes("3E0-F5D + 0163A4C0,\n0A2-06F"),
+        Ok(vec![
+            cheats::parse_code("3E0-F5D").unwrap(),
+            cheats::parse_code("0163A4C0").unwrap(),
+            cheats::parse_code("0A2-06F").unwrap(),
+        ])
+    );
+    assert_eq!(cheats::parse_codes(" "), Err(String::from("no codes")));
+    assert!(cheats::parse_codes("3E0-F5D XYZ").is_err());
+}
+
+#[test]
+fn code_text() {
+    for text in ["3E0-F5D", "3E0-F5D-4C1", "0A2-06F", "0163A4C0", "020580FF"] {
+        let code = cheats::parse_code(text).unwrap();
+        assert_eq!(cheats::code_text(&code), text);
+    }
+}
+
+#[test]
+fn game_shark_code() {
+    assert_eq!(cheats::game_shark_code(0xC0A4, 0x63), "0163A4C0");
+    assert_eq!(
+        cheats::parse_code(&cheats::game_shark_code(0xFF80, 0x05)),
+        Ok(Code::GameShark(RamWrite {
+            bank: 0x01,
+            address: 0xFF80,
+            value: 0x05,
+        }))
+    );
+}
+
+#[test]
+fn patch_rom() {
+    let patches = vec![
+        RomPatch {
+            address: 0x4000,
+            value: 0x00,
+            compare: Some(0x3D),
+        },
+        RomPatch {
+            address: 0x4000,
+            value: 0x01,
+            compare: Some(0x05),
+        },
+        RomPatch {
+            address: 0x0150,
+            value: 0xC9,
+            compare: None,
+        },
+    ];
+
+    assert_eq!(cheats::patch_rom(&patches, 0x4000, 0x3D), 0x00);
+    assert_eq!(cheats::patch_rom(&patches, 0x4000, 0x05), 0x01);
+
+    // Banks holding another value are left untouched.
+    assert_eq!(cheats::patch_rom(&patches, 0x4000, 0x3C), 0x3C);
+
+    assert_eq!(cheats::patch_rom(&patches, 0x0150, 0x3E), 0xC9);
+    assert_eq!(cheats::patch_rom(&patches, 0x0151, 0x3E), 0x3E);
+}
+
+#[test]
+fn enabled_codes() {
+    let cheats = vec![
+        Cheat {
+            name: String::from("Infinite lives"),
+            code: String::from("3E0-F5D 0163A4C0"),
+            enabled: true,
+        },
+        Cheat {
+            name: String::from("Moon jump"),
+            code: String::from("0A2-06F"),
+            enabled: false,
+        },
+    ];
+
+    let codes = cheats::enabled_codes(&cheats);
+    assert_eq!(codes, cheats::parse_codes("3E0-F5D 0163A4C0").unwrap());
+    assert_eq!(
+        cheats::rom_patches(&codes),
+        vec![RomPatch {
+            address: 0x20F5,
+            value: 0x3E,
+            compare: None,
+        }]
+    );
+    assert_eq!(
+        cheats::ram_writes(&codes),
+        vec![RamWrite {
+            bank: 0x01,
+            address: 0xC0A4,
+            value: 0x63,
+        }]
+    );
+}
+
+#[test]
+fn file() {
+    let cheats = vec![
+        Cheat {
+            name: String::from("Infinite \"lives\""),
+            code: String::from("3E0-F5D-4C1"),
+            enabled: true,
+        },
+        Cheat {
+            name: String::from("Max coins"),
+            code: String::from("0199A0C0 0199A1C0"),
+            enabled: false,
+        },
+    ];
+    assert_eq!(cheats::parse(&cheats::to_toml(&cheats)), Ok(cheats.clone()));
+
+    assert_eq!(cheats::parse(""), Ok(vec![]));
+    assert_eq!(
+        cheats::parse("[[cheats]]\nname = \"Lives\"\ncode = \"3E0-F5D\"\n"),
+        Ok(vec![Cheat {
+            name: String::from("Lives"),
+            code: String::from("3E0-F5D"),
+            enabled: true,
+        }])
+    );
+    assert_eq!(
+        cheats::parse("[[cheats]]\nname = \"Lives\"\n"),
+        Err(String::from("cheat is missing 'code'"))
+    );
+    assert_eq!(
+        cheats::parse("[[cheats]]\nname = \"Lives\"\ncode = \"3E0\"\n"),
+        Err(String::from("cheat 'Lives': invalid code '3E0'"))
+    );
+
+    let path = std::env::temp_dir()
+        .join(format!("rusty_fuugbemu_cheats_test_{}", std::process::id()))
+        .join("cheats.toml");
+    assert_eq!(cheats::load(&path), Ok(vec![]));
+    cheats::save(&path, &cheats).unwrap();
+    assert_eq!(cheats::load(&path), Ok(cheats));
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
@@ -0,0 +1,324 @@
+//! Cheat codes: Game Genie codes, which patch the bytes the CPU reads from the cartridge
+//! ROM, and GameShark codes, which write a value to RAM on every frame.
+//!
+//! Cheats are saved per ROM as TOML, e.g.
+//!
+//! ```text
+//! [[cheats]]
+//! name = "Infinite lives"
+//! code = "3E0-F5D-4C1"
+//! enabled = true
+//! ```
+#[path = "cheats_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::movie;
+
+/// Replaces the byte read at a ROM address. With a compare value, only bytes holding that
+/// value are replaced, so that the patch only applies to one of the banks mapped there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RomPatch {
+    pub address: u16,
+    pub value: u8,
+    pub compare: Option<u8>,
+}
+
+/// Writes a value to a RAM address. The bank is kept as written in the code, but the
+/// value is always written to the bank currently mapped at the address.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RamWrite {
+    pub bank: u8,
+    pub address: u16,
+    pub value: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Code {
+    GameGenie(RomPatch),
+    GameShark(RamWrite),
+}
+
+/// Parses a single code: `ABC-DEF` or `ABC-DEF-GHI` for Game Genie codes, and `BBVVLLHH`
+/// for GameShark codes.
+pub fn parse_code(text: &str) -> Result<Code, String> {
+    let error = || format!("invalid code '{}'", text);
+
+    let digits: Vec<u8> = text
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(error)?;
+
+    let is_game_genie = match text.len() {
+        7 => digits.len() == 6 && text.find('-') == Some(3),
+        11 => digits.len() == 9 && text.find('-') == Some(3) && text.rfind('-') == Some(7),
+        _ => false,
+    };
+
+    if is_game_genie {
+        return parse_game_genie(&digits).map_err(|err| format!("'{}': {}", text, err));
+    }
+
+    if digits.len() == 8 && !text.contains('-') {
+        return parse_game_shark(&digits).map_err(|err| format!("'{}': {}", text, err));
+    }
+
+    return Err(error());
+}
+
+// Digits `AB` are the new value and `FCDE` the address, with `F` complemented. Digits `G`
+// and `I` are the compare value, XORed with 0xBA and then rotated left by 2; `H` is unused.
+fn parse_game_genie(digits: &[u8]) -> Result<Code, String> {
+    let value = (digits[0] << 4) | digits[1];
+    let address = (((digits[5] ^ 0xF) as u16) << 12)
+        | ((digits[2] as u16) << 8)
+        | ((digits[3] as u16) << 4)
+        | digits[4] as u16;
+
+    if address >= 0x8000 {
+        return Err(format!("patches {:#06X}, outside of ROM", address));
+    }
+
+    let compare = match digits.len() {
+        9 => Some(((digits[6] << 4) | digits[8]).rotate_right(2) ^ 0xBA),
+        _ => None,
+    };
+
+    return Ok(Code::GameGenie(RomPatch {
+        address,
+        value,
+        compare,
+    }));
+}
+
+// Bytes are the bank, the value, then the address in little endian.
+fn parse_game_shark(digits: &[u8]) -> Result<Code, String> {
+    let bytes: Vec<u8> = digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect();
+    let address = u16::from_le_bytes([bytes[2], bytes[3]]);
+
+    if !is_ram_address(address) {
+        return Err(format!("writes to {:#06X}, outside of RAM", address));
+    }
+
+    return Ok(Code::GameShark(RamWrite {
+        bank: bytes[0],
+        address,
+        value: bytes[1],
+    }));
+}
+
+/// GameShark code writing the value to the address on every frame, freezing it there.
+pub fn game_shark_code(address: u16, value: u8) -> String {
+    let [low, high] = address.to_le_bytes();
+    return format!("01{:02X}{:02X}{:02X}", value, low, high);
+}
+
+/// Text of a code, which parses back to the same code. The unused `H` digit of Game Genie
+/// compare values is written as `G` XOR 8, as in most published codes.
+pub fn code_text(code: &Code) -> String {
+    match code {
+        Code::GameGenie(patch) => {
+            let mut text = format!(
+                "{:02X}{:01X}-{:02X}{:01X}",
+                patch.value,
+                (patch.address >> 8) & 0xF,
+                patch.address & 0xFF,
+                (patch.address >> 12) ^ 0xF
+            );
+            if let Some(compare) = patch.compare {
+                let digits = (compare ^ 0xBA).rotate_left(2);
+                text.push_str(&format!(
+                    "-{:01X}{:01X}{:01X}",
+                    digits >> 4,
+                    (digits >> 4) ^ 0x8,
+                    digits & 0xF
+                ));
+            }
+            return text;
+        }
+        Code::GameShark(write) => {
+            let [low, high] = write.address.to_le_bytes();
+            return format!(
+                "{:02X}{:02X}{:02X}{:02X}",
+                write.bank, write.value, low, high
+            );
+        }
+    }
+}
+
+/// Cartridge, work and high RAM, which GameShark codes may write to.
+fn is_ram_address(address: u16) -> bool {
+    return (0xA000..0xE000).contains(&address) || (0xFF80..0xFFFF).contains(&address);
+}
+
+/// Parses codes separated by spaces, commas or `+`, since cheats often take several.
+pub fn parse_codes(text: &str) -> Result<Vec<Code>, String> {
+    let codes = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '+')
+        .filter(|code| !code.is_empty())
+        .map(parse_code)
+        .collect::<Result<Vec<Code>, String>>()?;
+
+    if codes.is_empty() {
+        return Err(String::from("no codes"));
+    }
+
+    return Ok(codes);
+}
+
+/// Byte the CPU reads at a ROM address, given the byte stored in the cartridge.
+pub fn patch_rom(patches: &[RomPatch], address: usize, val: u8) -> u8 {
+    for patch in patches {
+        if patch.address as usize != address {
+            continue;
+        }
+
+        match patch.compare {
+            Some(compare) if compare != val => continue,
+            _ => return patch.value,
+        }
+    }
+
+    return val;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cheat {
+    pub name: String,
+
+    /// Codes as entered, which are valid per `parse_codes`.
+    pub code: String,
+
+    pub enabled: bool,
+}
+
+impl Cheat {
+    pub fn get_codes(&self) -> Vec<Code> {
+        return parse_codes(&self.code).unwrap_or_default();
+    }
+}
+
+/// Codes of the enabled cheats, to send to the emulator.
+pub fn enabled_codes(cheats: &[Cheat]) -> Vec<Code> {
+    return cheats
+        .iter()
+        .filter(|cheat| cheat.enabled)
+        .flat_map(|cheat| cheat.get_codes())
+        .collect();
+}
+
+pub fn rom_patches(codes: &[Code]) -> Vec<RomPatch> {
+    return codes
+        .iter()
+        .filter_map(|code| match code {
+            Code::GameGenie(patch) => Some(*patch),
+            Code::GameShark(_) => None,
+        })
+        .collect();
+}
+
+pub fn ram_writes(codes: &[Code]) -> Vec<RamWrite> {
+    return codes
+        .iter()
+        .filter_map(|code| match code {
+            Code::GameGenie(_) => None,
+            Code::GameShark(write) => Some(*write),
+        })
+        .collect();
+}
+
+pub fn parse(contents: &str) -> Result<Vec<Cheat>, String> {
+    let table = contents
+        .parse::<toml::Table>()
+        .map_err(|err| err.message().to_string())?;
+
+    let entries = match table.get("cheats") {
+        Some(toml::Value::Array(entries)) => entries.as_slice(),
+        Some(_) => return Err(String::from("'cheats' should be a list of tables")),
+        None => &[],
+    };
+
+    let mut cheats = Vec::new();
+    for entry in entries {
+        let entry = match entry {
+            toml::Value::Table(entry) => entry,
+            _ => return Err(String::from("'cheats' should be a list of tables")),
+        };
+
+        let get_string = |key: &str| match entry.get(key) {
+            Some(toml::Value::String(value)) => Ok(value.clone()),
+            _ => Err(format!("cheat is missing '{}'", key)),
+        };
+
+        let cheat = Cheat {
+            name: get_string("name")?,
+            code: get_string("code")?,
+            enabled: match entry.get("enabled") {
+                Some(toml::Value::Boolean(enabled)) => *enabled,
+                Some(_) => return Err(String::from("'enabled' should be true or false")),
+                None => true,
+            },
+        };
+
+        parse_codes(&cheat.code).map_err(|err| format!("cheat '{}': {}", cheat.name, err))?;
+        cheats.push(cheat);
+    }
+
+    return Ok(cheats);
+}
+
+pub fn to_toml(cheats: &[Cheat]) -> String {
+    let entries = cheats
+        .iter()
+        .map(|cheat| {
+            let mut entry = toml::Table::new();
+            entry.insert(String::from("name"), toml::Value::from(cheat.name.as_str()));
+            entry.insert(String::from("code"), toml::Value::from(cheat.code.as_str()));
+            entry.insert(String::from("enabled"), toml::Value::from(cheat.enabled));
+            return toml::Value::Table(entry);
+        })
+        .collect();
+
+    let mut table = toml::Table::new();
+    table.insert(String::from("cheats"), toml::Value::Array(entries));
+
+    return table.to_string();
+}
+
+/// Location of the cheats of a ROM, `cheats/<ROM hash>.toml` in the config directory.
+pub fn path(rom_data: &[u8]) -> Option<PathBuf> {
+    let config_path = config::path()?;
+    let directory = config_path.parent()?.join("cheats");
+
+    return Some(directory.join(format!("{:016x}.toml", movie::hash(rom_data))));
+}
+
+/// Loads the cheats saved to the file, if any.
+pub fn load(path: &Path) -> Result<Vec<Cheat>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("could not read {}: {}", path.display(), err)),
+    };
+
+    return parse(&contents).map_err(|err| format!("{}: {}", path.display(), err));
+}
+
+pub fn save(path: &Path, cheats: &[Cheat]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("could not create {}: {}", parent.display(), err))?;
+    }
+
+    return fs::write(path, to_toml(cheats))
+        .map_err(|err| format!("could not write {}: {}", path.display(), err));
+}
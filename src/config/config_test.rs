@@ -0,0 +1,389 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use glium::glutin::event::VirtualKeyCode;
+
+use crate::config::{
+    self, Config, DebugWindow, KeyBindings, Overrides, PaletteSelection, WindowLayout,
+};
+use crate::gamepad;
+use crate::input;
+use crate::joypad;
+use crate::palette;
+use crate::ppu::Layer;
+use crate::renderer::Scaling;
+
+#[test]
+fn parse() {
+    struct TestCase {
+        description: String,
+        contents: String,
+        expected: Result<Config, String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("empty file uses the defaults"),
+            contents: String::from(""),
+            expected: Ok(Config::new()),
+        },
+        TestCase {
+            description: String::from("full window layout"),
+            contents: String::from(
+                "[window]\nwidth = 960\nheight = 884\nx = -20\ny = 40\nfullscreen = true\n\
+                 hide_menubar = true\nscaling = \"aspect_fit\"\n",
+            ),
+            expected: Ok(Config {
+                window: WindowLayout {
+                    size: Some((960, 884)),
+                    position: Some((-20, 40)),
+                    fullscreen: true,
+                    hide_menubar: true,
+                    scaling: Scaling::AspectFit,
+                },
+                ..Config::new()
+            }),
+        },
+        TestCase {
+            description: String::from("size is ignored unless both dimensions are set"),
+            contents: String::from("[window]\nwidth = 960\nfullscreen = true\n"),
+            expected: Ok(Config {
+                window: WindowLayout {
+                    fullscreen: true,
+                    ..WindowLayout::new()
+                },
+                ..Config::new()
+            }),
+        },
+        TestCase {
+            description: String::from("general, palette, key and debug settings"),
+            contents: String::from(
+                "[general]\nskip_boot_rom = false\nlast_rom_directory = \"/roms\"\n\
+                 [palettes]\nbackground = \"Pocket\"\nobject1 = \"Mine\"\n\
+                 files = [\"/palettes/mine.pal\"]\n\
+                 [keys]\na = \"Z\"\nstart = \"Return\"\nleft = \"Numpad4\"\n\
+                 [debug]\nvram_viewer = true\ncall_stack = true\n",
+            ),
+            expected: Ok(Config {
+                skip_boot_rom: false,
+                last_rom_directory: Some(PathBuf::from("/roms")),
+                palettes: PaletteSelection {
+                    background: String::from("Pocket"),
+                    object1: String::from("Mine"),
+                    files: vec![PathBuf::from("/palettes/mine.pal")],
+                    ..PaletteSelection::new()
+                },
+                key_bindings: KeyBindings {
+                    a: VirtualKeyCode::Z,
+                    start: VirtualKeyCode::Return,
+                    left: VirtualKeyCode::Numpad4,
+                    ..KeyBindings::new()
+                },
+                shown_windows: vec![DebugWindow::VramViewer, DebugWindow::CallStack],
+                ..Config::new()
+            }),
+        },
+        TestCase {
+            description: String::from("gamepad settings"),
+            contents: String::from(
+                "[gamepad]\ndeadzone = 0.25\n\
+                 [gamepad.mappings.\"Pro Controller\"]\na = 0\nb = 1\nup = 13\n",
+            ),
+            expected: Ok(Config {
+                gamepad: gamepad::Settings {
+                    deadzone: 0.25,
+                    mappings: BTreeMap::from([(
+                        String::from("Pro Controller"),
+                        gamepad::Mapping {
+                            a: Some(0),
+                            b: Some(1),
+                            start: None,
+                            select: None,
+                            up: Some(13),
+                            down: None,
+                            left: None,
+                            right: None,
+                        },
+                    )]),
+                },
+                ..Config::new()
+            }),
+        },
+        TestCase {
+            description: String::from("integer gamepad deadzone"),
+            contents: String::from("[gamepad]\ndeadzone = 1\n"),
+            expected: Ok(Config {
+                gamepad: gamepad::Settings {
+                    deadzone: gamepad::MAX_DEADZONE,
+                    ..gamepad::Settings::new()
+                },
+                ..Config::new()
+            }),
+        },
+        TestCase {
+            description: String::from("turbo and macros"),
+            contents: String::from(
+                "[turbo]\nb = \"C\"\nrate = 10\n\
+                 [[macros]]\nname = \"Hadoken\"\nkey = \"Q\"\nsteps = \"Down 2, Right+A 1\"\n\
+                 [[macros]]\nname = \"Pause\"\nsteps = \"Start 1\"\n",
+            ),
+            expected: Ok(Config {
+                turbo: input::Turbo {
+                    b: Some(VirtualKeyCode::C),
+                    rate: 10,
+                    ..input::Turbo::new()
+                },
+                macros: vec![
+                    input::Macro {
+                        name: String::from("Hadoken"),
+                        key: Some(VirtualKeyCode::Q),
+                        steps: input::parse_steps("Down 2, Right+A 1").unwrap(),
+                    },
+                    input::Macro {
+                        name: String::from("Pause"),
+                        key: None,
+                        steps: input::parse_steps("Start 1").unwrap(),
+                    },
+                ],
+                ..Config::new()
+            }),
+        },
+        TestCase {
+            description: String::from("turbo rate out of range"),
+            contents: String::from("[turbo]\nrate = 60\n"),
+            expected: Err(String::from("'rate' should be an integer between 1 and 30")),
+        },
+        TestCase {
+            description: String::from("invalid macro steps"),
+            contents: String::from("[[macros]]\nname = \"Jump\"\nsteps = \"Jump 1\"\n"),
+            expected: Err(String::from("macro 'Jump': unknown button 'Jump'")),
+        },
+        TestCase {
+            description: String::from("gamepad button out of range"),
+            contents: String::from("[gamepad.mappings.Pad]\nstart = 256\n"),
+            expected: Err(String::from(
+                "'start' should be an integer between 0 and 255",
+            )),
+        },
+        TestCase {
+            description: String::from("unknown key name"),
+            contents: String::from("[keys]\nb = \"Hyper\"\n"),
+            expected: Err(String::from("unknown key 'Hyper' for 'b'")),
+        },
+        TestCase {
+            description: String::from("palette files should be paths"),
+            contents: String::from("[palettes]\nfiles = [1]\n"),
+            expected: Err(String::from("'files' should be a list of paths")),
+        },
+        TestCase {
+            description: String::from("unknown keys are ignored"),
+            contents: String::from("version = 2\n[window]\nopacity = 0.5\n"),
+            expected: Ok(Config::new()),
+        },
+        TestCase {
+            description: String::from("wrong type"),
+            contents: String::from("[window]\nfullscreen = \"yes\"\n"),
+            expected: Err(String::from("'fullscreen' should be true or false")),
+        },
+        TestCase {
+            description: String::from("out of range size"),
+            contents: String::from("[window]\nwidth = 0\nheight = 100\n"),
+            expected: Err(String::from(
+                "'width' should be an integer between 1 and 4294967295",
+            )),
+        },
+        TestCase {
+            description: String::from("unknown scaling mode"),
+            contents: String::from("[window]\nscaling = \"zoom\"\n"),
+            expected: Err(String::from("invalid scaling mode 'zoom'")),
+        },
+        TestCase {
+            description: String::from("window is not a table"),
+            contents: String::from("window = 1\n"),
+            expected: Err(String::from("'window' should be a table")),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            Config::parse(&tc.contents),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+
+    assert!(Config::parse("[window").is_err(), "invalid TOML");
+}
+
+#[test]
+fn round_trip() {
+    let mut config = Config::new();
+    assert_eq!(Config::parse(&config.to_toml()), Ok(config.clone()));
+
+    config.window = WindowLayout {
+        size: Some((800, 740)),
+        position: Some((100, -5)),
+        fullscreen: false,
+        hide_menubar: true,
+        scaling: Scaling::Stretch,
+    };
+    config.skip_boot_rom = false;
+    config.last_rom_directory = Some(PathBuf::from("/home/user/roms"));
+    config.palettes.set(crate::ppu::Layer::Object0, "DMG Green");
+    config.palettes.files = vec![PathBuf::from("a.pal"), PathBuf::from("b.pal")];
+    config.key_bindings.select = VirtualKeyCode::RShift;
+    config.key_bindings.up = VirtualKeyCode::W;
+    config.set_shown(DebugWindow::HexEditor, true);
+    config.set_shown(DebugWindow::Cheats, true);
+    config.turbo.a = None;
+    config.turbo.rate = 30;
+    config.macros = vec![input::Macro {
+        name: String::from("Spin \"attack\""),
+        key: Some(VirtualKeyCode::Key1),
+        steps: input::parse_steps("Up 1, Right 1, Down 1, Left+B 3, - 5").unwrap(),
+    }];
+    config.gamepad.deadzone = 0.35;
+    let mut mapping = gamepad::Mapping::new();
+    mapping.set(joypad::Button::Select, None);
+    mapping.set(joypad::Button::Left, Some(14));
+    config
+        .gamepad
+        .mappings
+        .insert(String::from("Wireless Controller"), mapping);
+    assert_eq!(Config::parse(&config.to_toml()), Ok(config.clone()));
+
+    let path = std::env::temp_dir()
+        .join(format!("rusty_fuugbemu_config_test_{}", std::process::id()))
+        .join("config.toml");
+    config.save_to(&path).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(Config::parse(&contents), Ok(config));
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn path_from() {
+    struct TestCase {
+        description: String,
+        xdg_config_home: Option<PathBuf>,
+        home: Option<PathBuf>,
+        expected: Option<PathBuf>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("XDG_CONFIG_HOME takes precedence"),
+            xdg_config_home: Some(PathBuf::from("/xdg")),
+            home: Some(PathBuf::from("/home/user")),
+            expected: Some(PathBuf::from("/xdg/rustyfuugbemu/config.toml")),
+        },
+        TestCase {
+            description: String::from("defaults to ~/.config"),
+            xdg_config_home: None,
+            home: Some(PathBuf::from("/home/user")),
+            expected: Some(PathBuf::from(
+                "/home/user/.config/rustyfuugbemu/config.toml",
+            )),
+        },
+        TestCase {
+            description: String::from("relative XDG_CONFIG_HOME is ignored"),
+            xdg_config_home: Some(PathBuf::from("relative")),
+            home: Some(PathBuf::from("/home/user")),
+            expected: Some(PathBuf::from(
+                "/home/user/.config/rustyfuugbemu/config.toml",
+            )),
+        },
+        TestCase {
+            description: String::from("no config directory"),
+            xdg_config_home: None,
+            home: None,
+            expected: None,
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            config::path_from(tc.xdg_config_home, tc.home),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn with_overrides() {
+    let mut config = Config::new();
+    config.skip_boot_rom = false;
+    config.window.scaling = Scaling::Stretch;
+
+    assert_eq!(config.with_overrides(&Overrides::default()), config);
+
+    let overridden = config.with_overrides(&Overrides {
+        skip_boot_rom: Some(true),
+        fullscreen: Some(true),
+        scaling: None,
+    });
+    assert!(overridden.skip_boot_rom);
+    assert!(overridden.window.fullscreen);
+    assert_eq!(overridden.window.scaling, Scaling::Stretch);
+
+    // The overrides only apply to the returned settings.
+    assert!(!config.skip_boot_rom);
+    assert!(!config.window.fullscreen);
+}
+
+#[test]
+fn shown_windows() {
+    let mut config = Config::new();
+    config.set_shown(DebugWindow::CallStack, true);
+    config.set_shown(DebugWindow::RamSearch, true);
+    config.set_shown(DebugWindow::RamSearch, true);
+    assert_eq!(
+        config.shown_windows,
+        vec![DebugWindow::RamSearch, DebugWindow::CallStack]
+    );
+
+    config.set_shown(DebugWindow::CallStack, false);
+    assert!(config.is_shown(DebugWindow::RamSearch));
+    assert!(!config.is_shown(DebugWindow::CallStack));
+}
+
+#[test]
+fn layer_palettes() {
+    let presets = palette::presets();
+    let mut selection = PaletteSelection::new();
+    selection.set(Layer::Object0, presets[1].get_name());
+    selection.set(Layer::Object1, "Missing");
+
+    // Unknown palettes fall back to the default one.
+    let layer_palettes = selection.layer_palettes(&presets);
+    assert_eq!(layer_palettes.get(Layer::Background), &presets[0]);
+    assert_eq!(layer_palettes.get(Layer::Object0), &presets[1]);
+    assert_eq!(layer_palettes.get(Layer::Object1), &presets[0]);
+
+    // Without palette files, only the presets are available.
+    assert_eq!(selection.load_palettes(), presets);
+}
+
+#[test]
+fn load_invalid() {
+    let directory = std::env::temp_dir().join(format!(
+        "rusty_fuugbemu_config_invalid_test_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("config.toml");
+    let contents = "[general]\nskip_boot_rom = 1\n";
+    std::fs::write(&path, contents).unwrap();
+
+    // The file is backed up before the defaults can be saved over it.
+    assert_eq!(Config::load_from(&path), Config::new());
+    let backup_path = config::backup_path(&path);
+    assert_eq!(backup_path, directory.join("config.toml.bak"));
+    assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), contents);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,754 @@
+//! Settings persisted between runs, stored as TOML in the XDG config directory.
+#[path = "config_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use glium::glutin::event::VirtualKeyCode;
+
+use crate::gamepad;
+use crate::input;
+use crate::joypad;
+use crate::palette;
+use crate::ppu;
+use crate::renderer::Scaling;
+
+mod keys;
+
+const APP_DIRECTORY: &str = "rustyfuugbemu";
+const FILE_NAME: &str = "config.toml";
+
+/// Size, position and mode of the main window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowLayout {
+    /// Inner size of the window in physical pixels, while not fullscreen.
+    pub size: Option<(u32, u32)>,
+
+    /// Outer position of the window in physical pixels, while not fullscreen.
+    pub position: Option<(i32, i32)>,
+
+    pub fullscreen: bool,
+    pub hide_menubar: bool,
+    pub scaling: Scaling,
+}
+
+impl WindowLayout {
+    pub fn new() -> Self {
+        Self {
+            size: None,
+            position: None,
+            fullscreen: false,
+            hide_menubar: false,
+            scaling: Scaling::Integer,
+        }
+    }
+}
+
+/// Windows of the debugging tools, whose visibility is saved between runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugWindow {
+    Cheats,
+    VramViewer,
+    SpriteViewer,
+    RamSearch,
+    HexEditor,
+    IoRegisters,
+    CallStack,
+}
+
+impl DebugWindow {
+    pub const ALL: [DebugWindow; 7] = [
+        DebugWindow::Cheats,
+        DebugWindow::VramViewer,
+        DebugWindow::SpriteViewer,
+        DebugWindow::RamSearch,
+        DebugWindow::HexEditor,
+        DebugWindow::IoRegisters,
+        DebugWindow::CallStack,
+    ];
+
+    fn get_key(self) -> &'static str {
+        return match self {
+            DebugWindow::Cheats => "cheats",
+            DebugWindow::VramViewer => "vram_viewer",
+            DebugWindow::SpriteViewer => "sprite_viewer",
+            DebugWindow::RamSearch => "ram_search",
+            DebugWindow::HexEditor => "hex_editor",
+            DebugWindow::IoRegisters => "io_registers",
+            DebugWindow::CallStack => "call_stack",
+        };
+    }
+}
+
+/// Keys mapped to each of the Gameboy's buttons.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    pub a: VirtualKeyCode,
+    pub b: VirtualKeyCode,
+    pub start: VirtualKeyCode,
+    pub select: VirtualKeyCode,
+    pub up: VirtualKeyCode,
+    pub down: VirtualKeyCode,
+    pub left: VirtualKeyCode,
+    pub right: VirtualKeyCode,
+}
+
+impl KeyBindings {
+    pub fn new() -> Self {
+        Self {
+            a: VirtualKeyCode::A,
+            b: VirtualKeyCode::S,
+            start: VirtualKeyCode::D,
+            select: VirtualKeyCode::F,
+            up: VirtualKeyCode::Up,
+            down: VirtualKeyCode::Down,
+            left: VirtualKeyCode::Left,
+            right: VirtualKeyCode::Right,
+        }
+    }
+
+    fn entries(&self) -> [(&'static str, VirtualKeyCode); 8] {
+        [
+            ("a", self.a),
+            ("b", self.b),
+            ("start", self.start),
+            ("select", self.select),
+            ("up", self.up),
+            ("down", self.down),
+            ("left", self.left),
+            ("right", self.right),
+        ]
+    }
+
+    fn entries_mut(&mut self) -> [(&'static str, &mut VirtualKeyCode); 8] {
+        [
+            ("a", &mut self.a),
+            ("b", &mut self.b),
+            ("start", &mut self.start),
+            ("select", &mut self.select),
+            ("up", &mut self.up),
+            ("down", &mut self.down),
+            ("left", &mut self.left),
+            ("right", &mut self.right),
+        ]
+    }
+}
+
+/// Names of the palettes each layer is displayed with, and the user palette files they
+/// may come from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaletteSelection {
+    pub background: String,
+    pub object0: String,
+    pub object1: String,
+    pub files: Vec<PathBuf>,
+}
+
+impl PaletteSelection {
+    pub fn new() -> Self {
+        let name = palette::Palette::default().get_name().to_string();
+
+        Self {
+            background: name.clone(),
+            object0: name.clone(),
+            object1: name,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, layer: ppu::Layer) -> &str {
+        match layer {
+            ppu::Layer::Background => &self.background,
+            ppu::Layer::Object0 => &self.object0,
+            ppu::Layer::Object1 => &self.object1,
+        }
+    }
+
+    pub fn set(&mut self, layer: ppu::Layer, name: &str) {
+        match layer {
+            ppu::Layer::Background => self.background = name.to_string(),
+            ppu::Layer::Object0 => self.object0 = name.to_string(),
+            ppu::Layer::Object1 => self.object1 = name.to_string(),
+        }
+    }
+
+    /// Built-in palettes followed by those of the user palette files. Files that fail to
+    /// load are logged and skipped.
+    pub fn load_palettes(&self) -> Vec<palette::Palette> {
+        let mut palettes = palette::presets();
+        for path in self.files.iter() {
+            if let Err(err) = palette::load_file(&mut palettes, path) {
+                log::error!("Failed to load palette file {}: {}", path.display(), err);
+            }
+        }
+
+        return palettes;
+    }
+
+    /// Looks up the selected palette of each layer among the given palettes. Layers whose
+    /// palette is unknown keep the default one.
+    pub fn layer_palettes(&self, palettes: &[palette::Palette]) -> palette::LayerPalettes {
+        let mut layer_palettes = palette::LayerPalettes::new(palette::Palette::default());
+        for layer in [
+            ppu::Layer::Background,
+            ppu::Layer::Object0,
+            ppu::Layer::Object1,
+        ] {
+            let name = self.get(layer);
+            match palettes.iter().find(|palette| palette.get_name() == name) {
+                Some(palette) => layer_palettes.set(layer, palette.clone()),
+                None => log::error!("Unknown palette '{}'", name),
+            }
+        }
+
+        return layer_palettes;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub skip_boot_rom: bool,
+
+    /// Directory the ROM file dialog opens in.
+    pub last_rom_directory: Option<PathBuf>,
+
+    pub window: WindowLayout,
+    pub palettes: PaletteSelection,
+    pub key_bindings: KeyBindings,
+    pub turbo: input::Turbo,
+    pub macros: Vec<input::Macro>,
+    pub gamepad: gamepad::Settings,
+
+    /// Debug windows that are shown, in the order of `DebugWindow::ALL`.
+    pub shown_windows: Vec<DebugWindow>,
+}
+
+/// Settings given on the command line, which take precedence over the config file for
+/// the current run without being saved to it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides {
+    pub skip_boot_rom: Option<bool>,
+    pub fullscreen: Option<bool>,
+    pub scaling: Option<Scaling>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self {
+            skip_boot_rom: true,
+            last_rom_directory: None,
+            window: WindowLayout::new(),
+            palettes: PaletteSelection::new(),
+            key_bindings: KeyBindings::new(),
+            turbo: input::Turbo::new(),
+            macros: Vec::new(),
+            gamepad: gamepad::Settings::new(),
+            shown_windows: Vec::new(),
+        }
+    }
+
+    pub fn is_shown(&self, window: DebugWindow) -> bool {
+        return self.shown_windows.contains(&window);
+    }
+
+    pub fn set_shown(&mut self, window: DebugWindow, shown: bool) {
+        self.shown_windows = DebugWindow::ALL
+            .into_iter()
+            .filter(|other| match *other == window {
+                true => shown,
+                false => self.is_shown(*other),
+            })
+            .collect();
+    }
+
+    /// Returns the settings to run with, this config with the given overrides applied.
+    pub fn with_overrides(&self, overrides: &Overrides) -> Self {
+        let mut config = self.clone();
+
+        if let Some(skip_boot_rom) = overrides.skip_boot_rom {
+            config.skip_boot_rom = skip_boot_rom;
+        }
+
+        if let Some(fullscreen) = overrides.fullscreen {
+            config.window.fullscreen = fullscreen;
+        }
+
+        if let Some(scaling) = overrides.scaling {
+            config.window.scaling = scaling;
+        }
+
+        return config;
+    }
+
+    /// Parses a config file. Missing settings keep their default value.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let table = contents
+            .parse::<toml::Table>()
+            .map_err(|err| err.message().to_string())?;
+
+        let mut config = Config::new();
+
+        if let Some(general) = get_table(&table, "general")? {
+            if let Some(skip_boot_rom) = get_bool(general, "skip_boot_rom")? {
+                config.skip_boot_rom = skip_boot_rom;
+            }
+
+            if let Some(directory) = get_string(general, "last_rom_directory")? {
+                config.last_rom_directory = Some(PathBuf::from(directory));
+            }
+        }
+
+        if let Some(window) = get_table(&table, "window")? {
+            let layout = &mut config.window;
+
+            let width = get_integer(window, "width", 1, u32::MAX as i64)?;
+            let height = get_integer(window, "height", 1, u32::MAX as i64)?;
+            if let (Some(width), Some(height)) = (width, height) {
+                layout.size = Some((width as u32, height as u32));
+            }
+
+            let x = get_integer(window, "x", i32::MIN as i64, i32::MAX as i64)?;
+            let y = get_integer(window, "y", i32::MIN as i64, i32::MAX as i64)?;
+            if let (Some(x), Some(y)) = (x, y) {
+                layout.position = Some((x as i32, y as i32));
+            }
+
+            if let Some(fullscreen) = get_bool(window, "fullscreen")? {
+                layout.fullscreen = fullscreen;
+            }
+
+            if let Some(hide_menubar) = get_bool(window, "hide_menubar")? {
+                layout.hide_menubar = hide_menubar;
+            }
+
+            if let Some(scaling) = get_string(window, "scaling")? {
+                layout.scaling = match parse_scaling(scaling) {
+                    Some(scaling) => scaling,
+                    None => return Err(format!("invalid scaling mode '{}'", scaling)),
+                };
+            }
+        }
+
+        if let Some(palettes) = get_table(&table, "palettes")? {
+            for layer in [
+                ppu::Layer::Background,
+                ppu::Layer::Object0,
+                ppu::Layer::Object1,
+            ] {
+                if let Some(name) = get_string(palettes, layer_key(layer))? {
+                    config.palettes.set(layer, name);
+                }
+            }
+
+            match palettes.get("files") {
+                Some(toml::Value::Array(files)) => {
+                    for file in files {
+                        match file {
+                            toml::Value::String(file) => {
+                                config.palettes.files.push(PathBuf::from(file))
+                            }
+                            _ => return Err(String::from("'files' should be a list of paths")),
+                        }
+                    }
+                }
+                Some(_) => return Err(String::from("'files' should be a list of paths")),
+                None => {}
+            }
+        }
+
+        if let Some(keys) = get_table(&table, "keys")? {
+            for (button, key) in config.key_bindings.entries_mut() {
+                if let Some(name) = get_string(keys, button)? {
+                    *key = parse_key(name, button)?;
+                }
+            }
+        }
+
+        if let Some(turbo) = get_table(&table, "turbo")? {
+            for (button, key) in [("a", &mut config.turbo.a), ("b", &mut config.turbo.b)] {
+                // Unbound keys are saved as an empty name.
+                match get_string(turbo, button)? {
+                    Some("") => *key = None,
+                    Some(name) => *key = Some(parse_key(name, button)?),
+                    None => {}
+                }
+            }
+
+            let rate = get_integer(
+                turbo,
+                "rate",
+                input::MIN_TURBO_RATE as i64,
+                input::MAX_TURBO_RATE as i64,
+            )?;
+            if let Some(rate) = rate {
+                config.turbo.rate = rate as u32;
+            }
+        }
+
+        match table.get("macros") {
+            Some(toml::Value::Array(macros)) => {
+                for definition in macros {
+                    match definition {
+                        toml::Value::Table(definition) => {
+                            config.macros.push(parse_macro(definition)?)
+                        }
+                        _ => return Err(String::from("'macros' should be a list of tables")),
+                    }
+                }
+            }
+            Some(_) => return Err(String::from("'macros' should be a list of tables")),
+            None => {}
+        }
+
+        if let Some(gamepad) = get_table(&table, "gamepad")? {
+            let deadzone = match gamepad.get("deadzone") {
+                Some(toml::Value::Float(deadzone)) => Some(*deadzone),
+                Some(toml::Value::Integer(deadzone)) => Some(*deadzone as f64),
+                Some(_) => return Err(String::from("'deadzone' should be a number")),
+                None => None,
+            };
+            if let Some(deadzone) = deadzone {
+                config.gamepad.deadzone =
+                    (deadzone as f32).clamp(gamepad::MIN_DEADZONE, gamepad::MAX_DEADZONE);
+            }
+
+            if let Some(mappings) = get_table(gamepad, "mappings")? {
+                for name in mappings.keys() {
+                    let buttons = get_table(mappings, name)?.unwrap();
+
+                    // Buttons left out of a custom mapping are unmapped.
+                    let mut mapping = gamepad::Mapping::new();
+                    for input in joypad::Button::ALL {
+                        let key = input.get_name().to_lowercase();
+                        let button = get_integer(buttons, &key, 0, u8::MAX as i64)?;
+                        mapping.set(input, button.map(|button| button as u8));
+                    }
+
+                    config.gamepad.mappings.insert(name.clone(), mapping);
+                }
+            }
+        }
+
+        if let Some(debug) = get_table(&table, "debug")? {
+            for window in DebugWindow::ALL {
+                if let Some(shown) = get_bool(debug, window.get_key())? {
+                    config.set_shown(window, shown);
+                }
+            }
+        }
+
+        return Ok(config);
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut general = toml::Table::new();
+        general.insert(
+            String::from("skip_boot_rom"),
+            toml::Value::from(self.skip_boot_rom),
+        );
+        if let Some(directory) = &self.last_rom_directory {
+            general.insert(
+                String::from("last_rom_directory"),
+                toml::Value::from(directory.to_string_lossy().to_string()),
+            );
+        }
+
+        let mut window = toml::Table::new();
+        let layout = &self.window;
+
+        if let Some((width, height)) = layout.size {
+            window.insert(String::from("width"), toml::Value::from(width as i64));
+            window.insert(String::from("height"), toml::Value::from(height as i64));
+        }
+
+        if let Some((x, y)) = layout.position {
+            window.insert(String::from("x"), toml::Value::from(x as i64));
+            window.insert(String::from("y"), toml::Value::from(y as i64));
+        }
+
+        window.insert(
+            String::from("fullscreen"),
+            toml::Value::from(layout.fullscreen),
+        );
+        window.insert(
+            String::from("hide_menubar"),
+            toml::Value::from(layout.hide_menubar),
+        );
+        window.insert(
+            String::from("scaling"),
+            toml::Value::from(scaling_name(layout.scaling)),
+        );
+
+        let mut palettes = toml::Table::new();
+        for layer in [
+            ppu::Layer::Background,
+            ppu::Layer::Object0,
+            ppu::Layer::Object1,
+        ] {
+            palettes.insert(
+                layer_key(layer).to_string(),
+                toml::Value::from(self.palettes.get(layer)),
+            );
+        }
+        palettes.insert(
+            String::from("files"),
+            toml::Value::Array(
+                self.palettes
+                    .files
+                    .iter()
+                    .map(|file| toml::Value::from(file.to_string_lossy().to_string()))
+                    .collect(),
+            ),
+        );
+
+        let mut keys = toml::Table::new();
+        for (button, key) in self.key_bindings.entries() {
+            if let Some(name) = keys::key_name(key) {
+                keys.insert(button.to_string(), toml::Value::from(name));
+            }
+        }
+
+        let mut turbo = toml::Table::new();
+        for (button, key) in [("a", self.turbo.a), ("b", self.turbo.b)] {
+            let name = key.and_then(keys::key_name).unwrap_or_default();
+            turbo.insert(button.to_string(), toml::Value::from(name));
+        }
+        turbo.insert(
+            String::from("rate"),
+            toml::Value::from(self.turbo.rate as i64),
+        );
+
+        let mut macros = Vec::new();
+        for definition in self.macros.iter() {
+            let mut table = toml::Table::new();
+            table.insert(
+                String::from("name"),
+                toml::Value::from(definition.name.as_str()),
+            );
+            if let Some(name) = definition.key.and_then(keys::key_name) {
+                table.insert(String::from("key"), toml::Value::from(name));
+            }
+            table.insert(
+                String::from("steps"),
+                toml::Value::from(input::format_steps(&definition.steps)),
+            );
+            macros.push(toml::Value::Table(table));
+        }
+
+        let mut mappings = toml::Table::new();
+        for (name, mapping) in self.gamepad.mappings.iter() {
+            let mut buttons = toml::Table::new();
+            for input in joypad::Button::ALL {
+                if let Some(button) = mapping.get(input) {
+                    buttons.insert(
+                        input.get_name().to_lowercase(),
+                        toml::Value::from(button as i64),
+                    );
+                }
+            }
+            mappings.insert(name.clone(), toml::Value::Table(buttons));
+        }
+
+        let mut gamepad = toml::Table::new();
+        gamepad.insert(
+            String::from("deadzone"),
+            toml::Value::from(self.gamepad.deadzone as f64),
+        );
+        gamepad.insert(String::from("mappings"), toml::Value::Table(mappings));
+
+        let mut debug = toml::Table::new();
+        for window in DebugWindow::ALL {
+            debug.insert(
+                String::from(window.get_key()),
+                toml::Value::from(self.is_shown(window)),
+            );
+        }
+
+        let mut table = toml::Table::new();
+        table.insert(String::from("general"), toml::Value::Table(general));
+        table.insert(String::from("window"), toml::Value::Table(window));
+        table.insert(String::from("palettes"), toml::Value::Table(palettes));
+        table.insert(String::from("keys"), toml::Value::Table(keys));
+        table.insert(String::from("turbo"), toml::Value::Table(turbo));
+        table.insert(String::from("macros"), toml::Value::Array(macros));
+        table.insert(String::from("gamepad"), toml::Value::Table(gamepad));
+        table.insert(String::from("debug"), toml::Value::Table(debug));
+
+        return table.to_string();
+    }
+
+    /// Loads the config file, falling back to the defaults if it doesn't exist or is invalid.
+    pub fn load() -> Self {
+        return match path() {
+            Some(path) => Config::load_from(&path),
+            None => Config::new(),
+        };
+    }
+
+    /// Loads the config file at the path. Invalid files are backed up before falling back
+    /// to the defaults, since saving the settings overwrites them.
+    pub fn load_from(path: &Path) -> Self {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return Config::new(),
+        };
+
+        match Config::parse(&contents) {
+            Ok(config) => {
+                log::info!("Loaded config: {}", path.display());
+                return config;
+            }
+            Err(err) => {
+                log::error!("Invalid config file {}: {}", path.display(), err);
+
+                let backup_path = backup_path(path);
+                match fs::copy(path, &backup_path) {
+                    Ok(_) => {
+                        log::error!("Backed up the invalid config to {}", backup_path.display())
+                    }
+                    Err(err) => log::error!(
+                        "Failed to back up the invalid config to {}: {}",
+                        backup_path.display(),
+                        err
+                    ),
+                }
+
+                return Config::new();
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = match path() {
+            Some(path) => path,
+            None => {
+                log::error!("Failed to save config: no config directory found");
+                return;
+            }
+        };
+
+        match self.save_to(&path) {
+            Ok(_) => log::debug!("Saved config: {}", path.display()),
+            Err(err) => log::error!("Failed to save config {}: {}", path.display(), err),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        return fs::write(path, self.to_toml());
+    }
+}
+
+/// Location of the config file, `$XDG_CONFIG_HOME/rustyfuugbemu/config.toml`.
+pub fn path() -> Option<PathBuf> {
+    return path_from(
+        std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+        std::env::var_os("HOME").map(PathBuf::from),
+    );
+}
+
+/// Where an invalid config file is backed up, `config.toml.bak`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".bak");
+    return path.with_file_name(file_name);
+}
+
+/// Resolves the config file location, following the XDG base directory spec: relative
+/// XDG_CONFIG_HOME values are ignored, and it defaults to `$HOME/.config`.
+pub fn path_from(xdg_config_home: Option<PathBuf>, home: Option<PathBuf>) -> Option<PathBuf> {
+    let config_home = match xdg_config_home {
+        Some(directory) if directory.is_absolute() => directory,
+        _ => home?.join(".config"),
+    };
+
+    return Some(config_home.join(APP_DIRECTORY).join(FILE_NAME));
+}
+
+pub fn parse_scaling(name: &str) -> Option<Scaling> {
+    match name {
+        "integer" => Some(Scaling::Integer),
+        "stretch" => Some(Scaling::Stretch),
+        "aspect_fit" => Some(Scaling::AspectFit),
+        _ => None,
+    }
+}
+
+fn scaling_name(scaling: Scaling) -> &'static str {
+    match scaling {
+        Scaling::Integer => "integer",
+        Scaling::Stretch => "stretch",
+        Scaling::AspectFit => "aspect_fit",
+    }
+}
+
+fn layer_key(layer: ppu::Layer) -> &'static str {
+    match layer {
+        ppu::Layer::Background => "background",
+        ppu::Layer::Object0 => "object0",
+        ppu::Layer::Object1 => "object1",
+    }
+}
+
+fn parse_key(name: &str, button: &str) -> Result<VirtualKeyCode, String> {
+    match keys::parse_key(name) {
+        Some(key) => Ok(key),
+        None => Err(format!("unknown key '{}' for '{}'", name, button)),
+    }
+}
+
+fn parse_macro(table: &toml::Table) -> Result<input::Macro, String> {
+    let name = get_string(table, "name")?.ok_or("macro is missing 'name'")?;
+    let steps = get_string(table, "steps")?.ok_or("macro is missing 'steps'")?;
+
+    let key = match get_string(table, "key")? {
+        Some(key) => Some(parse_key(key, name)?),
+        None => None,
+    };
+
+    return Ok(input::Macro {
+        name: name.to_string(),
+        key,
+        steps: input::parse_steps(steps).map_err(|err| format!("macro '{}': {}", name, err))?,
+    });
+}
+
+fn get_table<'a>(table: &'a toml::Table, key: &str) -> Result<Option<&'a toml::Table>, String> {
+    match table.get(key) {
+        Some(toml::Value::Table(value)) => Ok(Some(value)),
+        Some(_) => Err(format!("'{}' should be a table", key)),
+        None => Ok(None),
+    }
+}
+
+fn get_integer(table: &toml::Table, key: &str, min: i64, max: i64) -> Result<Option<i64>, String> {
+    match table.get(key) {
+        Some(toml::Value::Integer(value)) if (min..=max).contains(value) => Ok(Some(*value)),
+        Some(_) => Err(format!(
+            "'{}' should be an integer between {} and {}",
+            key, min, max
+        )),
+        None => Ok(None),
+    }
+}
+
+fn get_bool(table: &toml::Table, key: &str) -> Result<Option<bool>, String> {
+    match table.get(key) {
+        Some(toml::Value::Boolean(value)) => Ok(Some(*value)),
+        Some(_) => Err(format!("'{}' should be true or false", key)),
+        None => Ok(None),
+    }
+}
+
+fn get_string<'a>(table: &'a toml::Table, key: &str) -> Result<Option<&'a str>, String> {
+    match table.get(key) {
+        Some(toml::Value::String(value)) => Ok(Some(value.as_str())),
+        Some(_) => Err(format!("'{}' should be a string", key)),
+        None => Ok(None),
+    }
+}
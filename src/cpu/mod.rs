@@ -4,6 +4,7 @@ mod bit;
 pub mod opcode;
 mod opcode_ext;
 mod register;
+pub mod trace;
 
 #[cfg(feature = "serial_debug")]
 use crate::memory::io_registers;
@@ -38,6 +39,9 @@ pub struct LR35902 {
     interrupt_master_enable: bool,
     halted: bool,
     bugged_halt: bool,
+
+    /// How the CPU got to the current instruction, for debugging.
+    trace: trace::Trace,
 }
 
 const INTERRUPT_ENABLE_REGISTER_ADDR: usize = 0xFFFF;
@@ -92,6 +96,7 @@ impl LR35902 {
             interrupt_master_enable: false,
             halted: false,
             bugged_halt: false,
+            trace: trace::Trace::new(),
         }
     }
 
@@ -125,13 +130,22 @@ impl LR35902 {
         step_fn: &mut impl FnMut(),
     ) -> u32 {
         step_fn();
-        let op = match memory.lock().unwrap().read(usize::from(self.pc)) {
-            Some(x) => Opcode::from(x),
+        let (byte, rom_bank) = {
+            let mut memory = memory.lock().unwrap();
+            (memory.read(usize::from(self.pc)), memory.get_rom_bank())
+        };
+        let byte = match byte {
+            Some(x) => x,
             None => panic!(
                 "memory returned empty value when attempting to fetch op code. Dumping cpu state...\n
                 {:?}", self
             ),
         };
+        let op = Opcode::from(byte);
+
+        let location = trace::Location::new(self.pc, rom_bank);
+        let sp = self.sp;
+        self.trace.record(location, byte);
 
         self.pc = self.pc.wrapping_add(1);
         if self.bugged_halt {
@@ -142,7 +156,39 @@ impl LR35902 {
         #[cfg(feature = "serial_debug")]
         LR35902::serial_debug_output(memory);
 
-        return op.execute(self, memory, step_fn);
+        let cycles = op.execute(self, memory, step_fn);
+
+        // Conditional calls and returns only move the stack pointer when taken.
+        match trace::Flow::of(byte) {
+            Some(trace::Flow::Call) if self.sp == sp.wrapping_sub(2) => {
+                let to = trace::Location::new(self.pc, rom_bank);
+                self.trace.enter(trace::Entry::Call, location, to, self.sp);
+            }
+            Some(trace::Flow::Reset) => {
+                let to = trace::Location::new(self.pc, rom_bank);
+                self.trace.enter(trace::Entry::Reset, location, to, self.sp);
+            }
+            Some(trace::Flow::Return) if self.sp == sp.wrapping_add(2) => self.trace.exit(sp),
+            _ => {}
+        }
+
+        return cycles;
+    }
+
+    pub fn get_pc(&self) -> u16 {
+        return self.pc;
+    }
+
+    /// B, C, D, E, H and L, in which test ROMs such as mooneye's report their result.
+    #[cfg(test)]
+    pub fn get_result_registers(&self) -> [u8; 6] {
+        return [
+            self.bc.hi, self.bc.lo, self.de.hi, self.de.lo, self.hl.hi, self.hl.lo,
+        ];
+    }
+
+    pub fn get_trace(&self) -> &trace::Trace {
+        return &self.trace;
     }
 
     pub fn is_stopped(&self) -> bool {
@@ -176,6 +222,7 @@ impl LR35902 {
         match pending_interrupt {
             Some(interrupt) => {
                 self.interrupt_master_enable = false;
+                let from = trace::Location::new(self.pc, memory.lock().unwrap().get_rom_bank());
                 self.push_16bit_register_on_stack(ID16::PC, &memory, step_fn);
 
                 match interrupt {
@@ -188,6 +235,10 @@ impl LR35902 {
                     interrupt::Interrupt::Joypad => self.pc = CONTROLLER_IO_INTERRUPT_VECTOR,
                 }
 
+                let to = trace::Location::new(self.pc, 0);
+                self.trace
+                    .enter(trace::Entry::Interrupt(interrupt), from, to, self.sp);
+
                 interrupt_bus.lock().unwrap().clear_interrupt(interrupt);
             }
             None => {}
@@ -0,0 +1,238 @@
+//! Record of how the CPU got to where it is: a shadow call stack, following CALL, RST and
+//! interrupt entries and RET/RETI exits, and a history of the last executed instructions.
+#[path = "trace_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::interrupt::Interrupt;
+use crate::symbols::Symbols;
+
+/// Number of executed instructions kept in the history, repeats of the same instruction
+/// counting once.
+pub const HISTORY_LENGTH: usize = 1024;
+
+/// Number of frames kept on the call stack, past which the outermost ones are forgotten.
+pub const MAX_CALL_DEPTH: usize = 256;
+
+/// Address along with the ROM bank mapped when it was executed, which only matters for
+/// addresses in the switchable bank.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Location {
+    pub bank: usize,
+    pub address: u16,
+}
+
+impl Location {
+    pub fn new(address: u16, rom_bank: usize) -> Self {
+        let bank = match address {
+            0x4000..=0x7FFF => rom_bank,
+            _ => 0,
+        };
+
+        Self { bank, address }
+    }
+}
+
+/// Formatted as `bank:address`, the way RGBDS symbol files write them.
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{:02X}:{:04X}", self.bank, self.address);
+    }
+}
+
+/// Effect an instruction has on the call stack.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flow {
+    Call,
+    Reset,
+    Return,
+}
+
+impl Flow {
+    /// CALL, RST and RET/RETI instructions, conditional or not.
+    pub fn of(opcode: u8) -> Option<Flow> {
+        return match opcode {
+            0xC4 | 0xCC | 0xCD | 0xD4 | 0xDC => Some(Flow::Call),
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => Some(Flow::Reset),
+            0xC0 | 0xC8 | 0xC9 | 0xD0 | 0xD8 | 0xD9 => Some(Flow::Return),
+            _ => None,
+        };
+    }
+}
+
+impl fmt::Display for Flow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Flow::Call => write!(f, "CALL"),
+            Flow::Reset => write!(f, "RST"),
+            Flow::Return => write!(f, "RET"),
+        };
+    }
+}
+
+/// How a frame of the call stack was entered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Entry {
+    Call,
+    Reset,
+    Interrupt(Interrupt),
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Entry::Call => write!(f, "CALL"),
+            Entry::Reset => write!(f, "RST"),
+            Entry::Interrupt(Interrupt::VBlank) => write!(f, "VBlank interrupt"),
+            Entry::Interrupt(Interrupt::LcdStat) => write!(f, "LCD STAT interrupt"),
+            Entry::Interrupt(Interrupt::TimerOverflow) => write!(f, "Timer interrupt"),
+            Entry::Interrupt(Interrupt::Serial) => write!(f, "Serial interrupt"),
+            Entry::Interrupt(Interrupt::Joypad) => write!(f, "Joypad interrupt"),
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub entry: Entry,
+
+    /// Instruction that called, or that was interrupted.
+    pub from: Location,
+    pub to: Location,
+
+    /// Times the same call was made again right from the frame, as an RST 38 executing
+    /// itself does, which are kept as a single frame.
+    pub count: u32,
+
+    // Address the return address was pushed to, the first time when repeated.
+    sp: u16,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Executed {
+    pub location: Location,
+    pub opcode: u8,
+
+    /// Times the instruction was executed in a row.
+    pub count: u32,
+}
+
+#[derive(Debug)]
+pub struct Trace {
+    calls: Vec<Frame>,
+    history: VecDeque<Executed>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self {
+            calls: Vec::new(),
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+        }
+    }
+
+    /// Adds an instruction about to be executed to the history.
+    pub fn record(&mut self, location: Location, opcode: u8) {
+        if let Some(last) = self.history.back_mut() {
+            if last.location == location && last.opcode == opcode {
+                last.count += 1;
+                return;
+            }
+        }
+
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(Executed {
+            location,
+            opcode,
+            count: 1,
+        });
+    }
+
+    /// Pushes a frame, once the return address was pushed to `sp`.
+    pub fn enter(&mut self, entry: Entry, from: Location, to: Location, sp: u16) {
+        if let Some(top) = self.calls.last_mut() {
+            if top.entry == entry && top.from == from && top.to == to {
+                top.count += 1;
+                return;
+            }
+        }
+
+        if self.calls.len() == MAX_CALL_DEPTH {
+            self.calls.remove(0);
+        }
+        self.calls.push(Frame {
+            entry,
+            from,
+            to,
+            count: 1,
+            sp,
+        });
+    }
+
+    /// Pops the frame whose return address was popped from `sp`.
+    pub fn exit(&mut self, sp: u16) {
+        // Frames deeper than the return address were abandoned, e.g. by resetting SP.
+        self.calls.retain(|frame| frame.sp >= sp);
+
+        match self.calls.last_mut() {
+            Some(top) if top.sp == sp => {
+                self.calls.pop();
+            }
+            Some(top) if top.count > 1 => top.count -= 1,
+
+            // Returns through an address pushed by hand, e.g. to jump through a table.
+            _ => {}
+        }
+    }
+
+    /// Frames from the outermost to the innermost.
+    pub fn get_calls(&self) -> &[Frame] {
+        return &self.calls;
+    }
+
+    /// Executed instructions from the oldest to the most recent.
+    pub fn get_history(&self) -> &VecDeque<Executed> {
+        return &self.history;
+    }
+
+    /// Call stack and history as a log, innermost and most recent first, with locations
+    /// named after the labels they fall under.
+    pub fn to_text(&self, symbols: &Symbols) -> String {
+        let mut text = String::from("Calls, innermost first\n");
+        for frame in self.calls.iter().rev() {
+            text += &format!(
+                "{} from {} to {}",
+                frame.entry,
+                symbols.format(frame.from),
+                symbols.format(frame.to)
+            );
+            if frame.count > 1 {
+                text += &format!(" x{}", frame.count);
+            }
+            text.push('\n');
+        }
+
+        text += "\nExecuted instructions, most recent first\n";
+        for executed in self.history.iter().rev() {
+            text += &format!(
+                "{} {:02X}",
+                symbols.format(executed.location),
+                executed.opcode
+            );
+            if let Some(flow) = Flow::of(executed.opcode) {
+                text += &format!(" {}", flow);
+            }
+            if executed.count > 1 {
+                text += &format!(" x{}", executed.count);
+            }
+            text.push('\n');
+        }
+
+        return text;
+    }
+}
@@ -0,0 +1,237 @@
+use std::sync::{Arc, Mutex};
+
+use crate::cpu::trace::{Entry, Flow, Location, Trace, HISTORY_LENGTH};
+use crate::cpu::LR35902;
+use crate::interrupt::{self, Interrupt};
+use crate::memory::Memory;
+use crate::symbols::Symbols;
+use crate::timers;
+
+fn new_memory() -> Arc<Mutex<Memory>> {
+    Arc::new(Mutex::new(Memory::default(
+        Arc::new(Mutex::new(timers::Timers::new())),
+        Arc::new(Mutex::new(interrupt::Bus::new())),
+    )))
+}
+
+// Runs the program from the start of WRAM for the given number of instructions.
+fn run(program: &[(u16, &[u8])], instructions: usize) -> LR35902 {
+    let memory = new_memory();
+    for (address, bytes) in program {
+        for (offset, byte) in bytes.iter().enumerate() {
+            memory
+                .lock()
+                .unwrap()
+                .poke(*address as usize + offset, *byte);
+        }
+    }
+
+    let mut cpu = LR35902::new();
+    cpu.pc = 0xC000;
+    cpu.sp = 0xDFF0;
+    for _ in 0..instructions {
+        cpu.execute_next_opcode(&memory, &mut || {});
+    }
+
+    return cpu;
+}
+
+#[test]
+fn location() {
+    assert_eq!(
+        Location::new(0x0150, 5),
+        Location {
+            bank: 0,
+            address: 0x0150
+        }
+    );
+    assert_eq!(
+        Location::new(0x4000, 5),
+        Location {
+            bank: 5,
+            address: 0x4000
+        }
+    );
+    assert_eq!(format!("{}", Location::new(0x7FFF, 0x1A)), "1A:7FFF");
+    assert_eq!(format!("{}", Location::new(0xC000, 5)), "00:C000");
+}
+
+#[test]
+fn flow() {
+    assert_eq!(Flow::of(0xCD), Some(Flow::Call));
+    assert_eq!(Flow::of(0xDC), Some(Flow::Call));
+    assert_eq!(Flow::of(0xFF), Some(Flow::Reset));
+    assert_eq!(Flow::of(0xD9), Some(Flow::Return));
+    assert_eq!(Flow::of(0xC3), None);
+}
+
+#[test]
+fn call_stack() {
+    struct TestCase {
+        description: String,
+        program: Vec<(u16, Vec<u8>)>,
+        instructions: usize,
+        expected: Vec<(Entry, u16, u16, u32)>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("call"),
+            program: vec![(0xC000, vec![0xCD, 0x10, 0xC0])],
+            instructions: 1,
+            expected: vec![(Entry::Call, 0xC000, 0xC010, 1)],
+        },
+        TestCase {
+            description: String::from("nested calls and a return"),
+            program: vec![
+                (0xC000, vec![0xCD, 0x10, 0xC0]),
+                (0xC010, vec![0xCD, 0x20, 0xC0]),
+                (0xC020, vec![0xC9]),
+            ],
+            instructions: 3,
+            expected: vec![(Entry::Call, 0xC000, 0xC010, 1)],
+        },
+        TestCase {
+            description: String::from("conditional call not taken"),
+            // XOR A sets the zero flag, so CALL NZ falls through.
+            program: vec![(0xC000, vec![0xAF, 0xC4, 0x10, 0xC0])],
+            instructions: 2,
+            expected: vec![],
+        },
+        TestCase {
+            description: String::from("conditional return not taken"),
+            program: vec![(0xC000, vec![0xCD, 0x10, 0xC0]), (0xC010, vec![0xAF, 0xC0])],
+            instructions: 3,
+            expected: vec![(Entry::Call, 0xC000, 0xC010, 1)],
+        },
+        TestCase {
+            description: String::from("return through an address pushed by hand"),
+            // LD HL, $C020; PUSH HL; RET from within a call.
+            program: vec![
+                (0xC000, vec![0xCD, 0x10, 0xC0]),
+                (0xC010, vec![0x21, 0x20, 0xC0, 0xE5, 0xC9]),
+            ],
+            instructions: 4,
+            expected: vec![(Entry::Call, 0xC000, 0xC010, 1)],
+        },
+        TestCase {
+            description: String::from("rst"),
+            program: vec![(0xC000, vec![0xFF])],
+            instructions: 1,
+            expected: vec![(Entry::Reset, 0xC000, 0x0038, 1)],
+        },
+    ];
+
+    for tc in test_cases {
+        let program: Vec<(u16, &[u8])> = tc
+            .program
+            .iter()
+            .map(|(address, bytes)| (*address, bytes.as_slice()))
+            .collect();
+        let cpu = run(&program, tc.instructions);
+
+        let calls: Vec<(Entry, u16, u16, u32)> = cpu
+            .get_trace()
+            .get_calls()
+            .iter()
+            .map(|frame| {
+                (
+                    frame.entry,
+                    frame.from.address,
+                    frame.to.address,
+                    frame.count,
+                )
+            })
+            .collect();
+        assert_eq!(calls, tc.expected, "{}", tc.description);
+    }
+}
+
+#[test]
+fn repeated_calls() {
+    let mut trace = Trace::new();
+    let from = Location::new(0x0038, 1);
+    for sp in [0xDFFE, 0xDFFC, 0xDFFA] {
+        trace.enter(Entry::Reset, from, from, sp);
+    }
+    assert_eq!(trace.get_calls().len(), 1);
+    assert_eq!(trace.get_calls()[0].count, 3);
+
+    // Returning from the innermost of the repeated calls.
+    trace.exit(0xDFFA);
+    assert_eq!(trace.get_calls()[0].count, 2);
+
+    // Returning from the outermost one drops the frame.
+    trace.exit(0xDFFE);
+    assert!(trace.get_calls().is_empty());
+}
+
+#[test]
+fn abandoned_frames() {
+    let mut trace = Trace::new();
+    let vblank = Location::new(0x0040, 1);
+    trace.enter(
+        Entry::Call,
+        Location::new(0x0150, 1),
+        Location::new(0x4000, 1),
+        0xDFFE,
+    );
+    trace.enter(
+        Entry::Interrupt(Interrupt::VBlank),
+        Location::new(0x4010, 1),
+        vblank,
+        0xDFFC,
+    );
+    trace.enter(Entry::Call, vblank, Location::new(0x2000, 1), 0xDFFA);
+
+    // The handler drops the call's return address and returns from the interrupt.
+    trace.exit(0xDFFC);
+    assert_eq!(trace.get_calls().len(), 1);
+    assert_eq!(trace.get_calls()[0].to, Location::new(0x4000, 1));
+}
+
+#[test]
+fn history() {
+    // JR -2 loops on itself, after a NOP.
+    let cpu = run(&[(0xC000, &[0x00, 0x18, 0xFE])], 10);
+    let history = cpu.get_trace().get_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].location.address, 0xC000);
+    assert_eq!(history[0].opcode, 0x00);
+    assert_eq!(history[1].location.address, 0xC001);
+    assert_eq!(history[1].count, 9);
+
+    let mut trace = Trace::new();
+    for address in 0..HISTORY_LENGTH as u16 + 10 {
+        trace.record(Location::new(address, 1), 0x00);
+    }
+    assert_eq!(trace.get_history().len(), HISTORY_LENGTH);
+    assert_eq!(trace.get_history()[0].location.address, 10);
+}
+
+#[test]
+fn to_text() {
+    let symbols = Symbols::parse("00:0150 Main\n01:4000 Player\n").unwrap();
+    let mut trace = Trace::new();
+    trace.record(Location::new(0x0150, 1), 0xCD);
+    trace.enter(
+        Entry::Call,
+        Location::new(0x0150, 1),
+        Location::new(0x4000, 1),
+        0xDFFE,
+    );
+    trace.record(Location::new(0x4000, 1), 0x00);
+    trace.record(Location::new(0x4001, 1), 0x18);
+    trace.record(Location::new(0x4001, 1), 0x18);
+
+    assert_eq!(
+        trace.to_text(&symbols),
+        "Calls, innermost first\n\
+         CALL from 00:0150 Main to 01:4000 Player\n\
+         \n\
+         Executed instructions, most recent first\n\
+         01:4001 Player+$1 18 x2\n\
+         01:4000 Player 00\n\
+         00:0150 Main CD CALL\n"
+    );
+}
@@ -0,0 +1,111 @@
+use crate::effects::{blend_frames, color_bleed, pixel_grid, Effects};
+use crate::filters::{Image, Rgba};
+use crate::ppu;
+
+const W: Rgba = [0xFF, 0xFF, 0xFF, 0xFF];
+const K: Rgba = [0x00, 0x00, 0x00, 0xFF];
+
+fn native_image(color: Rgba) -> Image {
+    Image::from_pixels(
+        ppu::NATIVE_SCREEN_WIDTH,
+        ppu::NATIVE_SCREEN_HEIGHT,
+        vec![color; ppu::NATIVE_SCREEN_WIDTH * ppu::NATIVE_SCREEN_HEIGHT],
+    )
+}
+
+#[test]
+fn ghosting() {
+    let mut effects = Effects::new();
+
+    // Disabled, frames are passed through.
+    assert_eq!(effects.apply_ghosting(native_image(K)), native_image(K));
+
+    effects.set_ghosting(true);
+    assert_eq!(effects.apply_ghosting(native_image(W)), native_image(W));
+
+    // A sprite flickering every other frame ends up half transparent.
+    let mut frame = native_image(W);
+    frame.set(0, 0, K);
+    let output = effects.apply_ghosting(frame);
+    assert_eq!(output.get(0, 0), [0x80, 0x80, 0x80, 0xFF]);
+    assert_eq!(output.get(1, 0), W);
+
+    let output = effects.apply_ghosting(native_image(W));
+    assert_eq!(output.get(0, 0), [0xC0, 0xC0, 0xC0, 0xFF]);
+
+    // Turning ghosting off and on again forgets about previous frames.
+    effects.set_ghosting(false);
+    effects.set_ghosting(true);
+    assert_eq!(effects.apply_ghosting(native_image(K)), native_image(K));
+}
+
+#[test]
+fn blend_frames_averages() {
+    let current = Image::from_pixels(2, 1, vec![K, W]);
+    let previous = Image::from_pixels(2, 1, vec![W, W]);
+    assert_eq!(
+        blend_frames(&current, &previous),
+        Image::from_pixels(2, 1, vec![[0x80, 0x80, 0x80, 0xFF], W])
+    );
+}
+
+#[test]
+fn color_bleed_reference() {
+    let input = Image::from_pixels(3, 3, vec![W, W, W, W, K, W, W, W, W]);
+    let output = color_bleed(&input);
+
+    // The dark pixel takes on 4/16 of its white neighbours.
+    assert_eq!(output.get(1, 1), [0x40, 0x40, 0x40, 0xFF]);
+
+    // Its direct neighbours lose 1/16, diagonal ones are unaffected.
+    assert_eq!(output.get(1, 0), [0xEF, 0xEF, 0xEF, 0xFF]);
+    assert_eq!(output.get(0, 1), [0xEF, 0xEF, 0xEF, 0xFF]);
+    assert_eq!(output.get(0, 0), W);
+
+    // Flat areas are left as is.
+    assert_eq!(color_bleed(&native_image(K)), native_image(K));
+}
+
+#[test]
+fn pixel_grid_native_image() {
+    // Native images are upscaled to 3x3 cells, whose right column and bottom row are darkened.
+    let output = pixel_grid(&native_image(W));
+    assert_eq!(output.get_width(), ppu::NATIVE_SCREEN_WIDTH * 3);
+    assert_eq!(output.get_height(), ppu::NATIVE_SCREEN_HEIGHT * 3);
+
+    let line: Rgba = [0xBF, 0xBF, 0xBF, 0xFF];
+    for (x, y, expected) in [
+        (0, 0, W),
+        (1, 1, W),
+        (2, 0, line),
+        (0, 2, line),
+        (2, 2, line),
+        (3, 3, W),
+        (5, 4, line),
+    ] {
+        assert_eq!(output.get(x, y), expected, "pixel ({}, {})", x, y);
+    }
+}
+
+#[test]
+fn pixel_grid_upscaled_image() {
+    // Images already upscaled 4x keep their size, with a grid every 4 pixels.
+    let image = Image::from_pixels(
+        ppu::NATIVE_SCREEN_WIDTH * 4,
+        ppu::NATIVE_SCREEN_HEIGHT * 4,
+        vec![K; ppu::NATIVE_SCREEN_WIDTH * ppu::NATIVE_SCREEN_HEIGHT * 16],
+    );
+    assert_eq!(pixel_grid(&image), image);
+
+    let image = Image::from_pixels(
+        ppu::NATIVE_SCREEN_WIDTH * 4,
+        ppu::NATIVE_SCREEN_HEIGHT * 4,
+        vec![W; ppu::NATIVE_SCREEN_WIDTH * ppu::NATIVE_SCREEN_HEIGHT * 16],
+    );
+    let output = pixel_grid(&image);
+    assert_eq!(output.get_width(), image.get_width());
+    assert_eq!(output.get(2, 2), W);
+    assert_eq!(output.get(3, 2), [0xBF, 0xBF, 0xBF, 0xFF]);
+    assert_eq!(output.get(4, 4), W);
+    assert_eq!(output.get(4, 7), [0xBF, 0xBF, 0xBF, 0xFF]);
+}
@@ -0,0 +1,192 @@
+//! Post-processing reproducing the look of the original DMG screen. Effects operate on
+//! coloured frames, ghosting and colour bleed at the native resolution before upscaling
+//! filters are applied, and the pixel grid on the final image.
+#[path = "effects_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::filters::{blend, Image};
+use crate::ppu;
+
+/// Weight of the previously displayed frame when blending it with a new one.
+/// The DMG's LCD is slow enough that games rely on it to make flickering sprites
+/// look transparent.
+const GHOSTING_PREVIOUS_WEIGHT: u32 = 1;
+const GHOSTING_CURRENT_WEIGHT: u32 = 1;
+
+/// Smallest number of screen pixels per Gameboy pixel at which the grid is drawn.
+/// Smaller images are upscaled first, so that the grid does not swallow the image.
+const MIN_GRID_CELL_SIZE: usize = 3;
+
+/// Brightness of the grid lines relative to the pixels they cover, out of 256.
+const GRID_BRIGHTNESS: u32 = 192;
+
+/// Weight of the pixel itself compared to each of its 4 neighbours for colour bleed.
+const BLEED_CENTER_WEIGHT: u32 = 12;
+const BLEED_NEIGHBOUR_WEIGHT: u32 = 1;
+
+#[derive(Debug)]
+pub struct Effects {
+    ghosting: bool,
+    color_bleed: bool,
+    pixel_grid: bool,
+    previous_frame: Option<Image>,
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        Self {
+            ghosting: false,
+            color_bleed: false,
+            pixel_grid: false,
+            previous_frame: None,
+        }
+    }
+
+    pub fn is_ghosting_enabled(&self) -> bool {
+        self.ghosting
+    }
+
+    pub fn set_ghosting(&mut self, enabled: bool) {
+        self.ghosting = enabled;
+        self.previous_frame = None;
+    }
+
+    pub fn is_color_bleed_enabled(&self) -> bool {
+        self.color_bleed
+    }
+
+    pub fn set_color_bleed(&mut self, enabled: bool) {
+        self.color_bleed = enabled;
+    }
+
+    pub fn is_pixel_grid_enabled(&self) -> bool {
+        self.pixel_grid
+    }
+
+    pub fn set_pixel_grid(&mut self, enabled: bool) {
+        self.pixel_grid = enabled;
+    }
+
+    /// Called once for every new frame, blends it with the previously displayed one.
+    pub fn apply_ghosting(&mut self, frame: Image) -> Image {
+        if !self.ghosting {
+            return frame;
+        }
+
+        let output = match &self.previous_frame {
+            Some(previous_frame) => blend_frames(&frame, previous_frame),
+            None => frame,
+        };
+
+        self.previous_frame = Some(output.clone());
+        return output;
+    }
+
+    /// Starts ghosting over from the given frame, for when the frame has been redrawn
+    /// without the emulation advancing.
+    pub fn reset_ghosting(&mut self, frame: &Image) {
+        if self.ghosting {
+            self.previous_frame = Some(frame.clone());
+        }
+    }
+
+    /// Effects applied at the native resolution, before any upscaling filter.
+    pub fn apply_pre_filter(&self, image: Image) -> Image {
+        match self.color_bleed {
+            true => color_bleed(&image),
+            false => image,
+        }
+    }
+
+    /// Effects applied to the final image.
+    pub fn apply_post_filter(&self, image: Image) -> Image {
+        match self.pixel_grid {
+            true => pixel_grid(&image),
+            false => image,
+        }
+    }
+}
+
+pub fn blend_frames(current: &Image, previous: &Image) -> Image {
+    let mut output = Image::new(current.get_width(), current.get_height());
+
+    for y in 0..current.get_height() {
+        for x in 0..current.get_width() {
+            let (xi, yi) = (x as isize, y as isize);
+            output.set(
+                x,
+                y,
+                blend(&[
+                    (current.get(xi, yi), GHOSTING_CURRENT_WEIGHT),
+                    (previous.get(xi, yi), GHOSTING_PREVIOUS_WEIGHT),
+                ]),
+            );
+        }
+    }
+
+    return output;
+}
+
+/// Slightly bleeds every pixel into its horizontal and vertical neighbours.
+pub fn color_bleed(image: &Image) -> Image {
+    let mut output = Image::new(image.get_width(), image.get_height());
+
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            let (xi, yi) = (x as isize, y as isize);
+            output.set(
+                x,
+                y,
+                blend(&[
+                    (image.get(xi, yi), BLEED_CENTER_WEIGHT),
+                    (image.get(xi - 1, yi), BLEED_NEIGHBOUR_WEIGHT),
+                    (image.get(xi + 1, yi), BLEED_NEIGHBOUR_WEIGHT),
+                    (image.get(xi, yi - 1), BLEED_NEIGHBOUR_WEIGHT),
+                    (image.get(xi, yi + 1), BLEED_NEIGHBOUR_WEIGHT),
+                ]),
+            );
+        }
+    }
+
+    return output;
+}
+
+/// Darkens the right and bottom edges of every Gameboy pixel, mimicking the gaps between
+/// the DMG's LCD dots. The image may have been upscaled, the cell size of each Gameboy
+/// pixel is derived from the image's width.
+pub fn pixel_grid(image: &Image) -> Image {
+    let mut cell_size = (image.get_width() / ppu::NATIVE_SCREEN_WIDTH).max(1);
+
+    let image = match cell_size < MIN_GRID_CELL_SIZE {
+        true => {
+            let factor = MIN_GRID_CELL_SIZE.div_ceil(cell_size);
+            cell_size *= factor;
+            image.resize_nearest(image.get_width() * factor, image.get_height() * factor)
+        }
+        false => image.clone(),
+    };
+
+    let mut output = image.clone();
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            if x % cell_size != cell_size - 1 && y % cell_size != cell_size - 1 {
+                continue;
+            }
+
+            let color = image.get(x as isize, y as isize);
+            output.set(
+                x,
+                y,
+                [
+                    (color[0] as u32 * GRID_BRIGHTNESS / 256) as u8,
+                    (color[1] as u32 * GRID_BRIGHTNESS / 256) as u8,
+                    (color[2] as u32 * GRID_BRIGHTNESS / 256) as u8,
+                    color[3],
+                ],
+            );
+        }
+    }
+
+    return output;
+}
@@ -0,0 +1,86 @@
+use crate::filters::{blend, differs, Filter, Image, Rgba};
+use crate::palette::{LayerPalettes, Palette};
+use crate::ppu::{self, Layer, Pixel};
+
+#[test]
+fn blend_weights() {
+    assert_eq!(
+        blend(&[([0, 0, 0, 255], 1), ([255, 255, 255, 255], 1)]),
+        [128, 128, 128, 255]
+    );
+    assert_eq!(
+        blend(&[([0, 100, 200, 255], 3), ([255, 0, 0, 255], 1)]),
+        [64, 75, 150, 255]
+    );
+}
+
+#[test]
+fn differs_thresholds() {
+    assert!(!differs([100, 100, 100, 255], [100, 100, 100, 255]));
+    assert!(!differs([100, 100, 100, 255], [130, 130, 130, 255]));
+    assert!(differs([100, 100, 100, 255], [150, 150, 150, 255]));
+    assert!(differs([255, 0, 0, 255], [235, 0, 20, 255]));
+}
+
+#[test]
+fn uniform_image_is_unchanged() {
+    let color: Rgba = [12, 34, 56, 255];
+    let input = Image::from_pixels(4, 3, vec![color; 12]);
+
+    for filter in Filter::ALL {
+        let output = filter.apply(&input);
+        let scale = output.get_width() / 4;
+        assert_eq!(
+            output,
+            Image::from_pixels(4 * scale, 3 * scale, vec![color; 12 * scale * scale]),
+            "{}",
+            filter.get_name()
+        );
+    }
+}
+
+#[test]
+fn from_frame() {
+    let mut frame = ppu::Frame::new();
+    frame.pixels[0][1] = Pixel::Black;
+    frame.pixels[0][2] = Pixel::Black;
+    frame.layers[0][2] = Layer::Object1;
+
+    let mut palettes = LayerPalettes::new(Palette::default());
+    let green = Palette::new(
+        "Green",
+        [(155, 188, 15), (139, 172, 15), (48, 98, 48), (15, 56, 15)],
+    );
+    palettes.set(Layer::Object1, green);
+
+    let image = Image::from_frame(&frame, &palettes);
+    assert_eq!(image.get_width(), ppu::NATIVE_SCREEN_WIDTH);
+    assert_eq!(image.get_height(), ppu::NATIVE_SCREEN_HEIGHT);
+    assert_eq!(image.get(0, 0), [250, 251, 246, 255]);
+    assert_eq!(image.get(1, 0), [14, 14, 26, 255]);
+    assert_eq!(image.get(2, 0), [15, 56, 15, 255]);
+}
+
+#[test]
+fn resize_nearest() {
+    let (a, b, c, d) = (
+        [0x10, 0x00, 0x00, 0xFF],
+        [0x20, 0x00, 0x00, 0xFF],
+        [0x30, 0x00, 0x00, 0xFF],
+        [0x40, 0x00, 0x00, 0xFF],
+    );
+    let image = Image::from_pixels(2, 2, vec![a, b, c, d]);
+
+    assert_eq!(
+        image.resize_nearest(4, 2),
+        Image::from_pixels(4, 2, vec![a, a, b, b, c, c, d, d])
+    );
+    assert_eq!(
+        image.resize_nearest(3, 3),
+        Image::from_pixels(3, 3, vec![a, a, b, a, a, b, c, c, d])
+    );
+    assert_eq!(
+        image.resize_nearest(1, 1),
+        Image::from_pixels(1, 1, vec![a])
+    );
+}
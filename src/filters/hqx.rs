@@ -0,0 +1,319 @@
+#[path = "hqx_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::filters::{differs, Image, Rgba};
+
+// hq2x and hq3x (Maxim Stepin). Each pixel is compared to its 8 neighbours with the hqx YUV
+// thresholds, and the resulting 8-bit pattern indexes the original 256-case tables, which pick
+// how each output pixel interpolates the center with its neighbours. The tables are written
+// here as rules, as in FFmpeg's hqx filter: a rule applies when the pattern bits under one of
+// its masks equal the given value and, for some rules, when two of the neighbours also differ
+// from each other. The first rule that applies gives the output pixel.
+//
+// Rules are written for the top left corner (and the top edge for hq3x) of this neighbourhood,
+// and the neighbourhood is mirrored or transposed to compute the other output pixels:
+//   w0 w1 w2
+//   w3 w4 w5
+//   w6 w7 w8
+
+struct Rule {
+    patterns: &'static [(u8, u8)],
+    differ: Option<(usize, usize)>,
+
+    /// Neighbours and their weights, which add up to a power of two.
+    weights: &'static [(usize, u32)],
+}
+
+const fn rule(
+    patterns: &'static [(u8, u8)],
+    differ: Option<(usize, usize)>,
+    weights: &'static [(usize, u32)],
+) -> Rule {
+    Rule {
+        patterns,
+        differ,
+        weights,
+    }
+}
+
+// Patterns shared between the tables.
+const LINE_LEFT: &[(u8, u8)] = &[(0xBF, 0x37), (0xDB, 0x13)];
+const LINE_UP: &[(u8, u8)] = &[(0xDB, 0x49), (0xEF, 0x6D)];
+const CORNER: &[(u8, u8)] = &[(0x0B, 0x0B), (0xFE, 0x4A), (0xFE, 0x1A)];
+const THIN_CORNER: &[(u8, u8)] = &[
+    (0x6F, 0x2A),
+    (0x5B, 0x0A),
+    (0xBF, 0x3A),
+    (0xDF, 0x5A),
+    (0x9F, 0x8A),
+    (0xCF, 0x8A),
+    (0xEF, 0x4E),
+    (0x3F, 0x0E),
+    (0xFB, 0x5A),
+    (0xBB, 0x8A),
+    (0x7F, 0x5A),
+    (0xAF, 0x8A),
+    (0xEB, 0x8A),
+];
+const SOFT_CORNER: &[(u8, u8)] = &[
+    (0x4F, 0x4B),
+    (0x9F, 0x1B),
+    (0x2F, 0x0B),
+    (0xBE, 0x0A),
+    (0xEE, 0x0A),
+    (0x7E, 0x0A),
+    (0xEB, 0x4B),
+    (0x3B, 0x1B),
+];
+const ANY: &[(u8, u8)] = &[(0x00, 0x00)];
+
+const HQ2X: [Rule; 15] = [
+    rule(LINE_LEFT, Some((1, 5)), &[(4, 3), (3, 1)]),
+    rule(LINE_UP, Some((7, 3)), &[(4, 3), (1, 1)]),
+    rule(CORNER, Some((3, 1)), &[(4, 1)]),
+    rule(THIN_CORNER, Some((3, 1)), &[(4, 3), (0, 1)]),
+    rule(&[(0x0B, 0x08)], None, &[(4, 2), (0, 1), (1, 1)]),
+    rule(&[(0x0B, 0x02)], None, &[(4, 2), (0, 1), (3, 1)]),
+    rule(&[(0x2F, 0x2F)], None, &[(4, 14), (3, 1), (1, 1)]),
+    rule(LINE_LEFT, None, &[(4, 5), (1, 2), (3, 1)]),
+    rule(LINE_UP, None, &[(4, 5), (3, 2), (1, 1)]),
+    rule(
+        &[(0x1B, 0x03), (0x4F, 0x43), (0x8B, 0x83), (0x6B, 0x43)],
+        None,
+        &[(4, 3), (3, 1)],
+    ),
+    rule(
+        &[(0x4B, 0x09), (0x8B, 0x89), (0x1F, 0x19), (0x3B, 0x19)],
+        None,
+        &[(4, 3), (1, 1)],
+    ),
+    rule(
+        &[(0x7E, 0x2A), (0xEF, 0xAB), (0xBF, 0x8F), (0x7E, 0x0E)],
+        None,
+        &[(4, 2), (3, 3), (1, 3)],
+    ),
+    rule(
+        &[
+            (0xFB, 0x6A),
+            (0x6F, 0x6E),
+            (0x3F, 0x3E),
+            (0xFB, 0xFA),
+            (0xDF, 0xDE),
+            (0xDF, 0x1E),
+        ],
+        None,
+        &[(4, 3), (0, 1)],
+    ),
+    rule(
+        &[
+            (0x0A, 0x00),
+            (0x4F, 0x4B),
+            (0x9F, 0x1B),
+            (0x2F, 0x0B),
+            (0xBE, 0x0A),
+            (0xEE, 0x0A),
+            (0x7E, 0x0A),
+            (0xEB, 0x4B),
+            (0x3B, 0x1B),
+        ],
+        None,
+        &[(4, 2), (3, 1), (1, 1)],
+    ),
+    rule(ANY, None, &[(4, 6), (3, 1), (1, 1)]),
+];
+
+const HQ3X_CORNER: [Rule; 7] = [
+    rule(LINE_LEFT, Some((1, 5)), &[(4, 3), (3, 1)]),
+    rule(LINE_UP, Some((7, 3)), &[(4, 3), (1, 1)]),
+    rule(CORNER, Some((3, 1)), &[(4, 1)]),
+    rule(THIN_CORNER, Some((3, 1)), &[(4, 3), (0, 1)]),
+    rule(SOFT_CORNER, None, &[(4, 2), (3, 7), (1, 7)]),
+    rule(
+        &[
+            (0x0B, 0x08),
+            (0xF9, 0x68),
+            (0xF3, 0x62),
+            (0x6D, 0x6C),
+            (0x67, 0x66),
+            (0x3D, 0x3C),
+            (0x37, 0x36),
+            (0xF9, 0xF8),
+            (0xDD, 0xDC),
+            (0xF3, 0xF2),
+            (0xD7, 0xD6),
+            (0xDD, 0x1C),
+            (0xD7, 0x16),
+            (0x0B, 0x02),
+        ],
+        None,
+        &[(4, 3), (0, 1)],
+    ),
+    rule(ANY, None, &[(4, 2), (3, 1), (1, 1)]),
+];
+
+// Edges crossing the top right and top left corners, which keep the top edge pixel sharp.
+const EDGE_RIGHT: &[(u8, u8)] = &[
+    (0xFE, 0xDE),
+    (0x9E, 0x16),
+    (0xDA, 0x12),
+    (0x17, 0x16),
+    (0x5B, 0x12),
+    (0xBB, 0x12),
+];
+const EDGE_LEFT: &[(u8, u8)] = &[
+    (0xFB, 0x7B),
+    (0x3B, 0x0B),
+    (0x7A, 0x0A),
+    (0x0F, 0x0B),
+    (0x5E, 0x0A),
+    (0xBE, 0x0A),
+];
+
+const HQ3X_EDGE: [Rule; 7] = [
+    rule(EDGE_RIGHT, Some((1, 5)), &[(4, 1)]),
+    rule(EDGE_LEFT, Some((3, 1)), &[(4, 1)]),
+    rule(
+        &[(0xBF, 0x8F), (0x7E, 0x0E), (0xBF, 0x37), (0xDB, 0x13)],
+        None,
+        &[(1, 3), (4, 1)],
+    ),
+    rule(EDGE_RIGHT, None, &[(4, 7), (1, 1)]),
+    rule(EDGE_LEFT, None, &[(4, 7), (1, 1)]),
+    rule(&[(0x02, 0x02)], None, &[(4, 1)]),
+    rule(ANY, None, &[(4, 3), (1, 1)]),
+];
+
+// Neighbourhood orientations, mapping each index above to the index of the actual neighbour.
+const IDENTITY: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+const MIRROR_X: [usize; 9] = [2, 1, 0, 5, 4, 3, 8, 7, 6];
+const MIRROR_Y: [usize; 9] = [6, 7, 8, 3, 4, 5, 0, 1, 2];
+const MIRROR_XY: [usize; 9] = [8, 7, 6, 5, 4, 3, 2, 1, 0];
+const TRANSPOSE: [usize; 9] = [0, 3, 6, 1, 4, 7, 2, 5, 8];
+const TRANSPOSE_MIRROR_X: [usize; 9] = [2, 5, 8, 1, 4, 7, 0, 3, 6];
+
+// Bit of each neighbour in the pattern, the center having none.
+const PATTERN_BITS: [u8; 9] = [0, 1, 2, 3, 0, 4, 5, 6, 7];
+
+struct Neighbourhood {
+    w: [Rgba; 9],
+    differs: [bool; 9],
+}
+
+impl Neighbourhood {
+    fn new(image: &Image, x: usize, y: usize) -> Self {
+        let mut w = [[0; 4]; 9];
+        for (index, pixel) in w.iter_mut().enumerate() {
+            let dx = (index % 3) as isize - 1;
+            let dy = (index / 3) as isize - 1;
+            *pixel = image.get(x as isize + dx, y as isize + dy);
+        }
+
+        let differs = w.map(|pixel| differs(w[4], pixel));
+        Self { w, differs }
+    }
+
+    /// Output pixel given by the first rule that applies, with the neighbourhood seen in the
+    /// given orientation.
+    fn interpolate(&self, rules: &[Rule], orientation: &[usize; 9]) -> Rgba {
+        let w = orientation.map(|index| self.w[index]);
+
+        let mut pattern = 0u8;
+        for (index, bit) in PATTERN_BITS.iter().enumerate() {
+            if index != 4 && self.differs[orientation[index]] {
+                pattern |= 1 << bit;
+            }
+        }
+
+        for rule in rules {
+            let matches = rule
+                .patterns
+                .iter()
+                .any(|(mask, value)| pattern & mask == *value);
+            let neighbours_differ = match rule.differ {
+                Some((a, b)) => differs(w[a], w[b]),
+                None => true,
+            };
+
+            if matches && neighbours_differ {
+                return mix(&w, rule.weights);
+            }
+        }
+
+        return w[4];
+    }
+}
+
+// Weighted average of the neighbours, truncated as in the original implementation.
+fn mix(w: &[Rgba; 9], weights: &[(usize, u32)]) -> Rgba {
+    let total_weight: u32 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut result = [0u8; 4];
+
+    for channel in 0..4 {
+        let sum: u32 = weights
+            .iter()
+            .map(|(index, weight)| w[*index][channel] as u32 * weight)
+            .sum();
+        result[channel] = (sum / total_weight) as u8;
+    }
+
+    return result;
+}
+
+pub fn hq2x(image: &Image) -> Image {
+    let mut output = Image::new(image.get_width() * 2, image.get_height() * 2);
+
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            let neighbourhood = Neighbourhood::new(image, x, y);
+
+            for (dx, dy, orientation) in [
+                (0, 0, &IDENTITY),
+                (1, 0, &MIRROR_X),
+                (0, 1, &MIRROR_Y),
+                (1, 1, &MIRROR_XY),
+            ] {
+                let color = neighbourhood.interpolate(&HQ2X, orientation);
+                output.set(x * 2 + dx, y * 2 + dy, color);
+            }
+        }
+    }
+
+    return output;
+}
+
+pub fn hq3x(image: &Image) -> Image {
+    let mut output = Image::new(image.get_width() * 3, image.get_height() * 3);
+
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            let neighbourhood = Neighbourhood::new(image, x, y);
+
+            for (dx, dy, orientation) in [
+                (0, 0, &IDENTITY),
+                (2, 0, &MIRROR_X),
+                (0, 2, &MIRROR_Y),
+                (2, 2, &MIRROR_XY),
+            ] {
+                let color = neighbourhood.interpolate(&HQ3X_CORNER, orientation);
+                output.set(x * 3 + dx, y * 3 + dy, color);
+            }
+
+            // Edge rules are written for the top edge, so the left and right edges transpose
+            // the neighbourhood.
+            for (dx, dy, orientation) in [
+                (1, 0, &IDENTITY),
+                (1, 2, &MIRROR_Y),
+                (0, 1, &TRANSPOSE),
+                (2, 1, &TRANSPOSE_MIRROR_X),
+            ] {
+                let color = neighbourhood.interpolate(&HQ3X_EDGE, orientation);
+                output.set(x * 3 + dx, y * 3 + dy, color);
+            }
+
+            output.set(x * 3 + 1, y * 3 + 1, neighbourhood.w[4]);
+        }
+    }
+
+    return output;
+}
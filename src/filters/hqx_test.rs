@@ -0,0 +1,97 @@
+use crate::filters::hqx::{hq2x, hq3x};
+use crate::filters::{Image, Rgba};
+
+const W: Rgba = [0xFF, 0xFF, 0xFF, 0xFF];
+const K: Rgba = [0x00, 0x00, 0x00, 0xFF];
+const R: Rgba = [0xFF, 0x00, 0x00, 0xFF];
+const B: Rgba = [0x00, 0x00, 0xFF, 0xFF];
+
+fn gray(value: u8) -> Rgba {
+    [value, value, value, 0xFF]
+}
+
+// Pixel surrounded by neighbours, given row by row from the top left, skipping the center.
+fn neighbourhood(center: Rgba, neighbours: [Rgba; 8]) -> Image {
+    let mut pixels = neighbours.to_vec();
+    pixels.insert(4, center);
+    Image::from_pixels(3, 3, pixels)
+}
+
+// Output pixels of the center pixel, row by row.
+fn center_block(output: &Image, scale: usize) -> Vec<Rgba> {
+    let mut pixels = Vec::new();
+    for y in scale..scale * 2 {
+        for x in scale..scale * 2 {
+            pixels.push(output.get(x as isize, y as isize));
+        }
+    }
+    pixels
+}
+
+#[test]
+fn hq2x_case_0() {
+    // None of the neighbours are perceptibly different, so each corner is interpolated
+    // 2:1:1 with the two neighbours next to it (PIXEL00_20).
+    let input = neighbourhood(gray(100), [gray(110); 8]);
+    assert_eq!(center_block(&hq2x(&input), 2), vec![gray(105); 4]);
+}
+
+#[test]
+fn hq2x_case_255() {
+    // Every neighbour differs, and the neighbours next to each corner differ from each other,
+    // so the pixel is left as is (PIXEL00_0).
+    let input = neighbourhood(K, [W, R, W, B, B, W, R, W]);
+    assert_eq!(center_block(&hq2x(&input), 2), vec![K; 4]);
+
+    // A lone pixel, its corners are interpolated 14:1:1 (PIXEL00_100).
+    let input = neighbourhood(K, [W; 8]);
+    assert_eq!(center_block(&hq2x(&input), 2), vec![gray(31); 4]);
+}
+
+#[test]
+fn hq3x_case_0() {
+    // Corners are interpolated 2:1:1 (PIXEL00_2), edges 3:1 with their neighbour
+    // (PIXEL01_1), and the center is kept.
+    let input = neighbourhood(gray(100), [gray(110); 8]);
+    let (c, e) = (gray(105), gray(102));
+    assert_eq!(
+        center_block(&hq3x(&input), 3),
+        vec![c, e, c, e, gray(100), e, c, e, c]
+    );
+}
+
+#[test]
+fn hq3x_case_18() {
+    // The top and right neighbours differ, and match each other: the line crossing the top
+    // right corner pulls it 2:7:7 towards them (PIXEL02_4) and the edge pixels next to it
+    // 7:1 (PIXEL01_3, PIXEL12_3). The other corners are tinted by their diagonal neighbour
+    // (PIXEL00_1M), which matches the center here.
+    let input = neighbourhood(W, [W, K, W, W, K, W, W, W]);
+    assert_eq!(
+        center_block(&hq3x(&input), 3),
+        vec![W, gray(223), gray(31), W, W, gray(223), W, W, W]
+    );
+
+    // When they differ from each other, the top right corner is only tinted (PIXEL02_1M)
+    // and the edge pixels are kept (PIXEL01_C, PIXEL12_C).
+    let input = neighbourhood(W, [W, R, W, W, B, W, W, W]);
+    assert_eq!(
+        center_block(&hq3x(&input), 3),
+        vec![W, W, W, W, W, W, W, W, W]
+    );
+}
+
+#[test]
+fn hq3x_case_255() {
+    let input = neighbourhood(K, [W, R, W, B, B, W, R, W]);
+    assert_eq!(center_block(&hq3x(&input), 3), vec![K; 9]);
+
+    // A lone pixel, its corners are interpolated 2:1:1 (PIXEL00_2) and its edges kept
+    // (PIXEL01_C).
+    let input = neighbourhood(K, [W; 8]);
+    let corner = gray(127);
+    assert_eq!(
+        center_block(&hq3x(&input), 3),
+        vec![corner, K, corner, K, K, K, corner, K, corner]
+    );
+}
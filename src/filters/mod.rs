@@ -0,0 +1,175 @@
+//! Pixel-art upscaling filters, applied to the coloured frame on the CPU before it
+//! is uploaded to the renderer.
+#[path = "filters_test.rs"]
+#[cfg(test)]
+mod test;
+
+mod hqx;
+mod scale;
+mod xbr;
+
+use crate::palette::LayerPalettes;
+use crate::ppu;
+
+pub type Rgba = [u8; 4];
+
+/// RGBA image stored row by row, starting from the top left pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgba>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0x00, 0x00, 0x00, 0xFF]; width * height],
+        }
+    }
+
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Rgba>) -> Self {
+        assert_eq!(pixels.len(), width * height, "invalid image dimensions");
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Colours a frame output by the PPU with the given palettes.
+    pub fn from_frame(frame: &ppu::Frame, palettes: &LayerPalettes) -> Self {
+        let mut pixels = Vec::with_capacity(ppu::NATIVE_SCREEN_WIDTH * ppu::NATIVE_SCREEN_HEIGHT);
+
+        for (frame_pixels, layers) in frame.pixels.iter().zip(frame.layers.iter()) {
+            for (pixel, layer) in frame_pixels.iter().zip(layers.iter()) {
+                let (r, g, b) = palettes.rgb(*pixel, *layer);
+                pixels.push([r, g, b, 0xFF]);
+            }
+        }
+
+        return Image::from_pixels(ppu::NATIVE_SCREEN_WIDTH, ppu::NATIVE_SCREEN_HEIGHT, pixels);
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the pixel at the given coordinates, clamped to the edges of the image.
+    pub fn get(&self, x: isize, y: isize) -> Rgba {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Rgba) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Scales the image to the given size with nearest neighbour sampling.
+    pub fn resize_nearest(&self, width: usize, height: usize) -> Image {
+        let mut output = Image::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let source_x = x * self.width / width;
+                let source_y = y * self.height / height;
+                output.set(x, y, self.pixels[source_y * self.width + source_x]);
+            }
+        }
+
+        return output;
+    }
+
+    pub fn to_rgba_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flatten().copied().collect()
+    }
+}
+
+/// Upscaling filter applied to each frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    None,
+    Scale2x,
+    Scale3x,
+    Hq2x,
+    Hq3x,
+    Xbr2x,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 6] = [
+        Filter::None,
+        Filter::Scale2x,
+        Filter::Scale3x,
+        Filter::Hq2x,
+        Filter::Hq3x,
+        Filter::Xbr2x,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Filter::None => "None",
+            Filter::Scale2x => "Scale2x",
+            Filter::Scale3x => "Scale3x",
+            Filter::Hq2x => "HQ2x",
+            Filter::Hq3x => "HQ3x",
+            Filter::Xbr2x => "xBR 2x",
+        }
+    }
+
+    pub fn apply(&self, image: &Image) -> Image {
+        match self {
+            Filter::None => image.clone(),
+            Filter::Scale2x => scale::scale2x(image),
+            Filter::Scale3x => scale::scale3x(image),
+            Filter::Hq2x => hqx::hq2x(image),
+            Filter::Hq3x => hqx::hq3x(image),
+            Filter::Xbr2x => xbr::xbr2x(image),
+        }
+    }
+}
+
+/// Weighted average of the given colours, each channel rounded to the nearest value.
+pub fn blend(colors: &[(Rgba, u32)]) -> Rgba {
+    let total_weight: u32 = colors.iter().map(|(_, weight)| weight).sum();
+    let mut result = [0u8; 4];
+
+    for channel in 0..4 {
+        let sum: u32 = colors
+            .iter()
+            .map(|(color, weight)| color[channel] as u32 * weight)
+            .sum();
+        result[channel] = ((sum + total_weight / 2) / total_weight) as u8;
+    }
+
+    return result;
+}
+
+/// Converts a colour to the YUV colour space, which the hqx and xBR filters use to
+/// compare colours the way the eye perceives them.
+fn to_yuv(color: Rgba) -> (f32, f32, f32) {
+    let (r, g, b) = (color[0] as f32, color[1] as f32, color[2] as f32);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let u = -0.169 * r - 0.331 * g + 0.5 * b;
+    let v = 0.5 * r - 0.419 * g - 0.081 * b;
+    return (y, u, v);
+}
+
+/// Whether two colours are perceptibly different, using the hqx YUV thresholds.
+fn differs(a: Rgba, b: Rgba) -> bool {
+    if a == b {
+        return false;
+    }
+
+    let (y1, u1, v1) = to_yuv(a);
+    let (y2, u2, v2) = to_yuv(b);
+
+    return (y1 - y2).abs() > 48.0 || (u1 - u2).abs() > 7.0 || (v1 - v2).abs() > 6.0;
+}
@@ -0,0 +1,105 @@
+#[path = "scale_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::filters::Image;
+
+/// Scale2x (AdvMAME2x). Each pixel becomes a 2x2 block, where a corner takes the colour
+/// of its two adjacent neighbours when they match each other but not the opposite ones.
+pub fn scale2x(image: &Image) -> Image {
+    let mut output = Image::new(image.get_width() * 2, image.get_height() * 2);
+
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            let (xi, yi) = (x as isize, y as isize);
+
+            //   A
+            // C P B
+            //   D
+            let p = image.get(xi, yi);
+            let a = image.get(xi, yi - 1);
+            let b = image.get(xi + 1, yi);
+            let c = image.get(xi - 1, yi);
+            let d = image.get(xi, yi + 1);
+
+            let mut e = [p; 4];
+            if c == a && c != d && a != b {
+                e[0] = a;
+            }
+            if a == b && a != c && b != d {
+                e[1] = b;
+            }
+            if d == c && d != b && c != a {
+                e[2] = c;
+            }
+            if b == d && b != a && d != c {
+                e[3] = d;
+            }
+
+            output.set(x * 2, y * 2, e[0]);
+            output.set(x * 2 + 1, y * 2, e[1]);
+            output.set(x * 2, y * 2 + 1, e[2]);
+            output.set(x * 2 + 1, y * 2 + 1, e[3]);
+        }
+    }
+
+    return output;
+}
+
+/// Scale3x (AdvMAME3x). Same principle as Scale2x, over a 3x3 output block.
+pub fn scale3x(image: &Image) -> Image {
+    let mut output = Image::new(image.get_width() * 3, image.get_height() * 3);
+
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            let (xi, yi) = (x as isize, y as isize);
+
+            // A B C
+            // D E F
+            // G H I
+            let a = image.get(xi - 1, yi - 1);
+            let b = image.get(xi, yi - 1);
+            let c = image.get(xi + 1, yi - 1);
+            let d = image.get(xi - 1, yi);
+            let e = image.get(xi, yi);
+            let f = image.get(xi + 1, yi);
+            let g = image.get(xi - 1, yi + 1);
+            let h = image.get(xi, yi + 1);
+            let i = image.get(xi + 1, yi + 1);
+
+            let mut out = [e; 9];
+            if b != h && d != f {
+                if d == b {
+                    out[0] = d;
+                }
+                if (d == b && e != c) || (b == f && e != a) {
+                    out[1] = b;
+                }
+                if b == f {
+                    out[2] = f;
+                }
+                if (d == b && e != g) || (d == h && e != a) {
+                    out[3] = d;
+                }
+                if (b == f && e != i) || (h == f && e != c) {
+                    out[5] = f;
+                }
+                if d == h {
+                    out[6] = d;
+                }
+                if (d == h && e != i) || (h == f && e != g) {
+                    out[7] = h;
+                }
+                if h == f {
+                    out[8] = f;
+                }
+            }
+
+            for (idx, color) in out.iter().enumerate() {
+                output.set(x * 3 + idx % 3, y * 3 + idx / 3, *color);
+            }
+        }
+    }
+
+    return output;
+}
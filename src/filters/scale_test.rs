@@ -0,0 +1,76 @@
+use crate::filters::scale::{scale2x, scale3x};
+use crate::filters::{Image, Rgba};
+
+const W: Rgba = [0xFF, 0xFF, 0xFF, 0xFF];
+const K: Rgba = [0x00, 0x00, 0x00, 0xFF];
+
+#[test]
+fn scale2x_reference() {
+    // The outer corner of a block is rounded off.
+    let input = Image::from_pixels(3, 3, vec![W, W, W, W, K, K, W, K, K]);
+
+    #[rustfmt::skip]
+    let expected = Image::from_pixels(6, 6, vec![
+        W, W, W, W, W, W,
+        W, W, W, W, W, W,
+        W, W, W, K, K, K,
+        W, W, K, K, K, K,
+        W, W, K, K, K, K,
+        W, W, K, K, K, K,
+    ]);
+
+    assert_eq!(scale2x(&input), expected);
+}
+
+#[test]
+fn scale2x_diagonal() {
+    let input = Image::from_pixels(3, 3, vec![K, W, W, W, K, W, W, W, K]);
+
+    #[rustfmt::skip]
+    let expected = Image::from_pixels(6, 6, vec![
+        K, K, W, W, W, W,
+        K, W, K, W, W, W,
+        W, K, K, K, W, W,
+        W, W, K, K, K, W,
+        W, W, W, K, W, K,
+        W, W, W, W, K, K,
+    ]);
+
+    assert_eq!(scale2x(&input), expected);
+}
+
+#[test]
+fn scale2x_keeps_isolated_pixels() {
+    let input = Image::from_pixels(3, 3, vec![W, W, W, W, K, W, W, W, W]);
+    let output = scale2x(&input);
+
+    for y in 0..6 {
+        for x in 0..6 {
+            let expected = match (2..4).contains(&x) && (2..4).contains(&y) {
+                true => K,
+                false => W,
+            };
+            assert_eq!(output.get(x, y), expected, "pixel ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn scale3x_reference() {
+    let input = Image::from_pixels(3, 3, vec![K, W, W, W, K, W, W, W, K]);
+
+    #[rustfmt::skip]
+    let expected = Image::from_pixels(9, 9, vec![
+        K, K, K, W, W, W, W, W, W,
+        K, K, W, K, W, W, W, W, W,
+        K, W, W, K, W, W, W, W, W,
+        W, K, K, K, K, K, W, W, W,
+        W, W, W, K, K, K, W, W, W,
+        W, W, W, K, K, K, K, K, W,
+        W, W, W, W, W, K, W, W, K,
+        W, W, W, W, W, K, W, K, K,
+        W, W, W, W, W, W, K, K, K,
+    ]);
+
+    assert_eq!(scale3x(&input), expected);
+}
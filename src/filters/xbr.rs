@@ -0,0 +1,128 @@
+#[path = "xbr_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::filters::{differs, to_yuv, Image, Rgba};
+
+// Distance between two colours, weighted towards luminance.
+fn distance(a: Rgba, b: Rgba) -> u32 {
+    let (y1, u1, v1) = to_yuv(a);
+    let (y2, u2, v2) = to_yuv(b);
+    return (48.0 * (y1 - y2).abs() + 7.0 * (u1 - u2).abs() + 6.0 * (v1 - v2).abs()) as u32;
+}
+
+// Moves `dst` towards `src` by `alpha` / 256.
+fn alpha_blend(dst: Rgba, src: Rgba, alpha: i32) -> Rgba {
+    let mut result = dst;
+    for channel in 0..4 {
+        let delta = src[channel] as i32 - dst[channel] as i32;
+        result[channel] = (dst[channel] as i32 + delta * alpha / 256) as u8;
+    }
+    return result;
+}
+
+/// xBR level 1 at 2x (Hyllian). Each corner of a pixel compares the strength of the two
+/// diagonal edges over a 5x5 neighbourhood, and blends towards the colour across the
+/// dominant edge. Shallow and steep edges also blend the adjacent output pixels.
+pub fn xbr2x(image: &Image) -> Image {
+    let mut output = Image::new(image.get_width() * 2, image.get_height() * 2);
+
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            // Output pixels, in the order top left, top right, bottom left, bottom right.
+            let mut out = [image.get(x as isize, y as isize); 4];
+
+            // Corners are processed by mirroring the neighbourhood so that each one is
+            // handled as the bottom right corner: (mirror x, mirror y, corner, "left"
+            // neighbour along the H side, "up" neighbour along the F side).
+            for (sx, sy, n3, n2, n1) in [
+                (1, 1, 3, 2, 1),
+                (-1, 1, 2, 3, 0),
+                (1, -1, 1, 0, 3),
+                (-1, -1, 0, 1, 2),
+            ] {
+                let px =
+                    |dx: isize, dy: isize| image.get(x as isize + dx * sx, y as isize + dy * sy);
+                filter_corner(&px, &mut out, n3, n2, n1);
+            }
+
+            output.set(x * 2, y * 2, out[0]);
+            output.set(x * 2 + 1, y * 2, out[1]);
+            output.set(x * 2, y * 2 + 1, out[2]);
+            output.set(x * 2 + 1, y * 2 + 1, out[3]);
+        }
+    }
+
+    return output;
+}
+
+//     A1 B1 C1
+//  A0 A  B  C  C4
+//  D0 D  E  F  F4
+//  G0 G  H  I  I4
+//     G5 H5 I5
+fn filter_corner(
+    px: &dyn Fn(isize, isize) -> Rgba,
+    out: &mut [Rgba; 4],
+    n3: usize,
+    n2: usize,
+    n1: usize,
+) {
+    let eq = |a: Rgba, b: Rgba| !differs(a, b);
+
+    let e = px(0, 0);
+    let b = px(0, -1);
+    let c = px(1, -1);
+    let d = px(-1, 0);
+    let f = px(1, 0);
+    let g = px(-1, 1);
+    let h = px(0, 1);
+    let i = px(1, 1);
+    let f4 = px(2, 0);
+    let i4 = px(2, 1);
+    let h5 = px(0, 2);
+    let i5 = px(1, 2);
+
+    if e == h || e == f {
+        return;
+    }
+
+    let wd1 =
+        distance(e, c) + distance(e, g) + distance(i, h5) + distance(i, f4) + 4 * distance(h, f);
+    let wd2 =
+        distance(h, d) + distance(h, i5) + distance(f, i4) + distance(f, b) + 4 * distance(e, i);
+
+    let new_color = match distance(e, f) <= distance(e, h) {
+        true => f,
+        false => h,
+    };
+
+    let is_edge =
+        (!eq(f, b) && !eq(h, d)) || (eq(e, i) && !eq(f, i4) && !eq(h, i5)) || eq(e, g) || eq(e, c);
+
+    if wd1 < wd2 && is_edge {
+        let ke = distance(f, g);
+        let ki = distance(h, c);
+        let ex2 = e != c && b != c;
+        let ex3 = e != g && d != g;
+
+        let shallow = ke * 2 <= ki && ex3;
+        let steep = ke >= ki * 2 && ex2;
+
+        if shallow && steep {
+            out[n3] = alpha_blend(out[n3], new_color, 224);
+            out[n2] = alpha_blend(out[n2], new_color, 64);
+            out[n1] = out[n2];
+        } else if shallow {
+            out[n3] = alpha_blend(out[n3], new_color, 192);
+            out[n2] = alpha_blend(out[n2], new_color, 64);
+        } else if steep {
+            out[n3] = alpha_blend(out[n3], new_color, 192);
+            out[n1] = alpha_blend(out[n1], new_color, 64);
+        } else {
+            out[n3] = alpha_blend(out[n3], new_color, 128);
+        }
+    } else if wd1 <= wd2 {
+        out[n3] = alpha_blend(out[n3], new_color, 128);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::filters::xbr::xbr2x;
+use crate::filters::{Image, Rgba};
+
+const W: Rgba = [0xFF, 0xFF, 0xFF, 0xFF];
+const K: Rgba = [0x00, 0x00, 0x00, 0xFF];
+
+#[test]
+fn xbr2x_reference() {
+    // A 2x2 block in the corner of a white field. Only the block's outer corner pixel is
+    // filtered: its top left output pixel is blended 224/256 towards white, and both
+    // neighbouring output pixels 64/256.
+    let input = Image::from_pixels(3, 3, vec![W, W, W, W, K, K, W, K, K]);
+    let output = xbr2x(&input);
+
+    let gray = |value: u8| [value, value, value, 0xFF];
+    assert_eq!(output.get(2, 2), gray(223));
+    assert_eq!(output.get(3, 2), gray(63));
+    assert_eq!(output.get(2, 3), gray(63));
+    assert_eq!(output.get(3, 3), K);
+
+    for y in 0..6 {
+        for x in 0..6 {
+            if (2..4).contains(&x) && (2..4).contains(&y) {
+                continue;
+            }
+
+            let expected = input.get(x / 2, y / 2);
+            assert_eq!(output.get(x, y), expected, "pixel ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn xbr2x_preserves_straight_edges() {
+    let input = Image::from_pixels(4, 2, vec![W, W, K, K, W, W, K, K]);
+    let output = xbr2x(&input);
+
+    for y in 0..4 {
+        for x in 0..8 {
+            assert_eq!(
+                output.get(x, y),
+                input.get(x / 2, y / 2),
+                "pixel ({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
@@ -1,19 +1,34 @@
 use glium::glutin::event::ElementState;
 
 use crate::{
+    cheats,
+    cpu::trace::Location,
+    input,
     joypad::{ActionButton, DirectionButton},
-    ppu::{self, Pixel, NATIVE_SCREEN_HEIGHT, NATIVE_SCREEN_WIDTH},
+    movie, ppu,
 };
-use std::sync::mpsc::{Receiver, SyncSender, TryRecvError};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, SyncSender, TryRecvError};
+use std::time::Duration;
+
+// How often a paused emulator checks whether the front end closed it.
+const CLOSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 pub struct Backend {
     close_receiver: Receiver<()>,
     ack_sender: SyncSender<()>,
     rom_data_receiver: Receiver<Vec<u8>>,
-    frame_data_sender: SyncSender<[[Pixel; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT]>,
+    frame_data_sender: SyncSender<ppu::Frame>,
     skip_boot_rom_recv: Receiver<bool>,
     joypad_recv: Receiver<(Option<DirectionButton>, Option<ActionButton>, ElementState)>,
     pause_recv: Receiver<bool>,
+    renderer_recv: Receiver<ppu::Renderer>,
+    ppu_access_restrictions_recv: Receiver<bool>,
+    movie_command_recv: Receiver<movie::Command>,
+    movie_event_sender: Sender<movie::Event>,
+    cheats_recv: Receiver<Vec<cheats::Code>>,
+    input_command_recv: Receiver<input::Command>,
+    breakpoints_recv: Receiver<Vec<Location>>,
+    breakpoint_hit_sender: Sender<Location>,
 }
 
 impl Backend {
@@ -21,10 +36,18 @@ impl Backend {
         close_receiver: Receiver<()>,
         ack_sender: SyncSender<()>,
         rom_data_receiver: Receiver<Vec<u8>>,
-        frame_data_sender: SyncSender<[[Pixel; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT]>,
+        frame_data_sender: SyncSender<ppu::Frame>,
         skip_boot_rom_recv: Receiver<bool>,
         joypad_recv: Receiver<(Option<DirectionButton>, Option<ActionButton>, ElementState)>,
         pause_recv: Receiver<bool>,
+        renderer_recv: Receiver<ppu::Renderer>,
+        ppu_access_restrictions_recv: Receiver<bool>,
+        movie_command_recv: Receiver<movie::Command>,
+        movie_event_sender: Sender<movie::Event>,
+        cheats_recv: Receiver<Vec<cheats::Code>>,
+        input_command_recv: Receiver<input::Command>,
+        breakpoints_recv: Receiver<Vec<Location>>,
+        breakpoint_hit_sender: Sender<Location>,
     ) -> Self {
         return Self {
             close_receiver,
@@ -34,6 +57,14 @@ impl Backend {
             skip_boot_rom_recv,
             joypad_recv,
             pause_recv,
+            renderer_recv,
+            ppu_access_restrictions_recv,
+            movie_command_recv,
+            movie_event_sender,
+            cheats_recv,
+            input_command_recv,
+            breakpoints_recv,
+            breakpoint_hit_sender,
         };
     }
 
@@ -58,6 +89,23 @@ impl Backend {
         return;
     }
 
+    /// Blocks until the front end resumes the emulator, ignoring requests to pause it.
+    /// Returns false if the front end closed the emulator instead.
+    pub fn wait_resume(&self) -> bool {
+        loop {
+            if self.should_close() {
+                return false;
+            }
+
+            match self.pause_recv.recv_timeout(CLOSE_POLL_INTERVAL) {
+                Ok(true) => {}
+                Ok(false) => return true,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(err) => panic!("error occurred receiving resume signal: {:?}", err),
+            }
+        }
+    }
+
     pub fn should_close(&self) -> bool {
         let should_close_result = self.close_receiver.try_recv();
         match should_close_result {
@@ -79,6 +127,29 @@ impl Backend {
         }
     }
 
+    pub fn should_set_renderer(&self) -> Option<ppu::Renderer> {
+        match self.renderer_recv.try_recv() {
+            Ok(renderer) => Some(renderer),
+            Err(err) => match err {
+                TryRecvError::Empty => None,
+                _ => panic!("error occured receiving renderer signal: {:?}", err),
+            },
+        }
+    }
+
+    pub fn should_set_ppu_access_restrictions(&self) -> Option<bool> {
+        match self.ppu_access_restrictions_recv.try_recv() {
+            Ok(enabled) => Some(enabled),
+            Err(err) => match err {
+                TryRecvError::Empty => None,
+                _ => panic!(
+                    "error occured receiving PPU access restrictions signal: {:?}",
+                    err
+                ),
+            },
+        }
+    }
+
     pub fn should_load_rom(&self) -> Option<Vec<u8>> {
         match self.rom_data_receiver.try_recv() {
             Ok(rom_data) => Some(rom_data),
@@ -89,10 +160,7 @@ impl Backend {
         }
     }
 
-    pub fn send_frame_data_front_end(
-        &self,
-        frame_data: [[ppu::Pixel; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT],
-    ) {
+    pub fn send_frame_data_front_end(&self, frame_data: ppu::Frame) {
         match self.frame_data_sender.send(frame_data) {
             Ok(_) => {}
             Err(err) => panic!(
@@ -120,4 +188,46 @@ impl Backend {
             },
         }
     }
+
+    /// Receives all of the joypad data sent since the last call.
+    pub fn recv_all_joypad_data(
+        &self,
+    ) -> Vec<(Option<DirectionButton>, Option<ActionButton>, ElementState)> {
+        return self.joypad_recv.try_iter().collect();
+    }
+
+    pub fn should_run_movie_command(&self) -> Option<movie::Command> {
+        match self.movie_command_recv.try_recv() {
+            Ok(command) => Some(command),
+            Err(err) => match err {
+                TryRecvError::Empty => None,
+                _ => panic!("error occured receiving movie command: {:?}", err),
+            },
+        }
+    }
+
+    pub fn send_movie_event_front_end(&self, event: movie::Event) {
+        // The front end may have stopped listening while shutting down.
+        let _ = self.movie_event_sender.send(event);
+    }
+
+    /// Receives the latest codes of the enabled cheats, if they changed since the last call.
+    pub fn should_set_cheats(&self) -> Option<Vec<cheats::Code>> {
+        return self.cheats_recv.try_iter().last();
+    }
+
+    /// Receives the turbo and macro commands sent since the last call.
+    pub fn recv_input_commands(&self) -> Vec<input::Command> {
+        return self.input_command_recv.try_iter().collect();
+    }
+
+    /// Receives the latest locations to break at, if they changed since the last call.
+    pub fn should_set_breakpoints(&self) -> Option<Vec<Location>> {
+        return self.breakpoints_recv.try_iter().last();
+    }
+
+    pub fn send_breakpoint_hit_front_end(&self, location: Location) {
+        // The front end may have stopped listening while shutting down.
+        let _ = self.breakpoint_hit_sender.send(location);
+    }
 }
@@ -1,7 +1,11 @@
 use glium::glutin::event::ElementState;
 
+use crate::cheats;
+use crate::cpu::trace::Location;
+use crate::input;
 use crate::joypad::{ActionButton, DirectionButton};
-use crate::ppu::{self, Pixel};
+use crate::movie;
+use crate::ppu;
 use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
 use std::time::Duration;
 
@@ -9,11 +13,18 @@ pub struct Frontend {
     close_sender: mpsc::SyncSender<()>,
     ack_receiver: mpsc::Receiver<()>,
     rom_data_sender: mpsc::SyncSender<Vec<u8>>,
-    frame_data_receiver:
-        mpsc::Receiver<[[ppu::Pixel; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT]>,
+    frame_data_receiver: mpsc::Receiver<ppu::Frame>,
     skip_boot_rom_sender: mpsc::SyncSender<bool>,
     joypad_sender: mpsc::Sender<(Option<DirectionButton>, Option<ActionButton>, ElementState)>,
     pause_sender: mpsc::SyncSender<bool>,
+    renderer_sender: mpsc::SyncSender<ppu::Renderer>,
+    ppu_access_restrictions_sender: mpsc::SyncSender<bool>,
+    movie_command_sender: mpsc::Sender<movie::Command>,
+    movie_event_receiver: mpsc::Receiver<movie::Event>,
+    cheats_sender: mpsc::Sender<Vec<cheats::Code>>,
+    input_command_sender: mpsc::Sender<input::Command>,
+    breakpoints_sender: mpsc::Sender<Vec<Location>>,
+    breakpoint_hit_receiver: mpsc::Receiver<Location>,
 }
 
 impl Frontend {
@@ -21,12 +32,18 @@ impl Frontend {
         close_sender: mpsc::SyncSender<()>,
         ack_receiver: mpsc::Receiver<()>,
         rom_data_sender: mpsc::SyncSender<Vec<u8>>,
-        frame_data_receiver: mpsc::Receiver<
-            [[Pixel; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT],
-        >,
+        frame_data_receiver: mpsc::Receiver<ppu::Frame>,
         skip_boot_rom_sender: mpsc::SyncSender<bool>,
         joypad_sender: mpsc::Sender<(Option<DirectionButton>, Option<ActionButton>, ElementState)>,
         pause_sender: mpsc::SyncSender<bool>,
+        renderer_sender: mpsc::SyncSender<ppu::Renderer>,
+        ppu_access_restrictions_sender: mpsc::SyncSender<bool>,
+        movie_command_sender: mpsc::Sender<movie::Command>,
+        movie_event_receiver: mpsc::Receiver<movie::Event>,
+        cheats_sender: mpsc::Sender<Vec<cheats::Code>>,
+        input_command_sender: mpsc::Sender<input::Command>,
+        breakpoints_sender: mpsc::Sender<Vec<Location>>,
+        breakpoint_hit_receiver: mpsc::Receiver<Location>,
     ) -> Self {
         return Self {
             close_sender,
@@ -36,6 +53,14 @@ impl Frontend {
             skip_boot_rom_sender,
             joypad_sender,
             pause_sender,
+            renderer_sender,
+            ppu_access_restrictions_sender,
+            movie_command_sender,
+            movie_event_receiver,
+            cheats_sender,
+            input_command_sender,
+            breakpoints_sender,
+            breakpoint_hit_receiver,
         };
     }
 
@@ -71,9 +96,24 @@ impl Frontend {
         }
     }
 
-    pub fn should_render_screen(
-        &self,
-    ) -> Option<[[ppu::Pixel; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT]> {
+    pub fn send_set_renderer_back_end(&self, renderer: ppu::Renderer) {
+        match self.renderer_sender.send(renderer) {
+            Ok(_) => {}
+            Err(err) => panic!("error occured when sending renderer to back end: {:?}", err),
+        }
+    }
+
+    pub fn send_set_ppu_access_restrictions_back_end(&self, enabled: bool) {
+        match self.ppu_access_restrictions_sender.send(enabled) {
+            Ok(_) => {}
+            Err(err) => panic!(
+                "error occured when sending PPU access restrictions to back end: {:?}",
+                err
+            ),
+        }
+    }
+
+    pub fn should_render_screen(&self) -> Option<ppu::Frame> {
         match self.frame_data_receiver.try_recv() {
             Ok(frame) => Some(frame),
             Err(TryRecvError::Empty) => None,
@@ -81,6 +121,14 @@ impl Frontend {
         }
     }
 
+    /// Blocks until the back end outputs its next frame. Returns None if the back end has exited.
+    pub fn recv_frame(&self) -> Option<ppu::Frame> {
+        match self.frame_data_receiver.recv() {
+            Ok(frame) => Some(frame),
+            Err(_) => None,
+        }
+    }
+
     pub fn send_joypad_data(
         &self,
         direction_press: Option<DirectionButton>,
@@ -95,4 +143,50 @@ impl Frontend {
             Err(err) => panic!("error occurred sending joypad data to backend: {:?}", err),
         }
     }
+
+    pub fn send_movie_command(&self, command: movie::Command) {
+        match self.movie_command_sender.send(command) {
+            Ok(_) => {}
+            Err(err) => panic!("error occurred sending movie command to backend: {:?}", err),
+        }
+    }
+
+    pub fn recv_movie_event(&self) -> Option<movie::Event> {
+        match self.movie_event_receiver.try_recv() {
+            Ok(event) => Some(event),
+            Err(_) => None,
+        }
+    }
+
+    /// Replaces the codes applied by the emulator with those of the enabled cheats.
+    pub fn send_cheats(&self, codes: Vec<cheats::Code>) {
+        match self.cheats_sender.send(codes) {
+            Ok(_) => {}
+            Err(err) => panic!("error occurred sending cheats to backend: {:?}", err),
+        }
+    }
+
+    /// Holds turbo buttons or plays macros, which the emulator advances once per frame.
+    pub fn send_input_command(&self, command: input::Command) {
+        match self.input_command_sender.send(command) {
+            Ok(_) => {}
+            Err(err) => panic!("error occurred sending input command to backend: {:?}", err),
+        }
+    }
+
+    /// Replaces the locations at which the emulator pauses, before executing them.
+    pub fn send_breakpoints(&self, locations: Vec<Location>) {
+        match self.breakpoints_sender.send(locations) {
+            Ok(_) => {}
+            Err(err) => panic!("error occurred sending breakpoints to backend: {:?}", err),
+        }
+    }
+
+    /// Location of the breakpoint the emulator paused at, if it did since the last call.
+    pub fn recv_breakpoint_hit(&self) -> Option<Location> {
+        match self.breakpoint_hit_receiver.try_recv() {
+            Ok(location) => Some(location),
+            Err(_) => None,
+        }
+    }
 }
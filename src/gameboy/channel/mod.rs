@@ -2,8 +2,11 @@ pub mod back_end;
 pub mod front_end;
 
 use crate::{
+    cheats,
+    cpu::trace::Location,
+    input,
     joypad::{ActionButton, DirectionButton},
-    ppu,
+    movie, ppu,
 };
 use back_end::Backend;
 use front_end::Frontend;
@@ -14,13 +17,20 @@ pub fn new() -> (Frontend, Backend) {
     let (close_sender, close_receiver) = mpsc::sync_channel::<()>(1);
     let (ack_sender, ack_receiver) = mpsc::sync_channel::<()>(1);
     let (rom_data_sender, rom_data_receiver) = mpsc::sync_channel::<Vec<u8>>(1);
-    let (frame_data_sender, frame_data_receiver) = mpsc::sync_channel::<
-        [[ppu::Pixel; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT],
-    >(1);
+    let (frame_data_sender, frame_data_receiver) = mpsc::sync_channel::<ppu::Frame>(1);
     let (skip_boot_rom_sender, skip_boot_rom_recv) = mpsc::sync_channel::<bool>(1);
     let (joypad_sender, joypad_recv) =
         mpsc::channel::<(Option<DirectionButton>, Option<ActionButton>, ElementState)>();
     let (pause_sender, pause_receiver) = mpsc::sync_channel::<bool>(1);
+    let (renderer_sender, renderer_receiver) = mpsc::sync_channel::<ppu::Renderer>(1);
+    let (ppu_access_restrictions_sender, ppu_access_restrictions_receiver) =
+        mpsc::sync_channel::<bool>(1);
+    let (movie_command_sender, movie_command_receiver) = mpsc::channel::<movie::Command>();
+    let (movie_event_sender, movie_event_receiver) = mpsc::channel::<movie::Event>();
+    let (cheats_sender, cheats_receiver) = mpsc::channel::<Vec<cheats::Code>>();
+    let (input_command_sender, input_command_receiver) = mpsc::channel::<input::Command>();
+    let (breakpoints_sender, breakpoints_receiver) = mpsc::channel::<Vec<Location>>();
+    let (breakpoint_hit_sender, breakpoint_hit_receiver) = mpsc::channel::<Location>();
 
     return (
         Frontend::new(
@@ -31,6 +41,14 @@ pub fn new() -> (Frontend, Backend) {
             skip_boot_rom_sender,
             joypad_sender,
             pause_sender,
+            renderer_sender,
+            ppu_access_restrictions_sender,
+            movie_command_sender,
+            movie_event_receiver,
+            cheats_sender,
+            input_command_sender,
+            breakpoints_sender,
+            breakpoint_hit_receiver,
         ),
         Backend::new(
             close_receiver,
@@ -40,6 +58,14 @@ pub fn new() -> (Frontend, Backend) {
             skip_boot_rom_recv,
             joypad_recv,
             pause_receiver,
+            renderer_receiver,
+            ppu_access_restrictions_receiver,
+            movie_command_receiver,
+            movie_event_sender,
+            cheats_receiver,
+            input_command_receiver,
+            breakpoints_receiver,
+            breakpoint_hit_sender,
         ),
     );
 }
@@ -0,0 +1,52 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cpu::trace::Location;
+use crate::gameboy::channel::front_end::Frontend;
+use crate::gameboy::Gameboy;
+use crate::symbols::Symbols;
+
+// Waits for the emulator to pause at a breakpoint.
+fn wait_breakpoint_hit(frontend: &Frontend) -> Location {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if let Some(location) = frontend.recv_breakpoint_hit() {
+            return location;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    panic!("no breakpoint hit");
+}
+
+#[test]
+fn breakpoints() {
+    // Increments A in a loop.
+    let mut rom_data = vec![0x00; 0x8000];
+    rom_data[0x100..0x104].copy_from_slice(&[
+        0x00, // NOP
+        0x3C, // INC A
+        0x18, 0xFD, // JR -3
+    ]);
+    let symbols = Symbols::parse("00:0101 Loop\n").unwrap();
+    let location = symbols.resolve("Loop").unwrap();
+
+    let (gameboy, _, cpu_ref) = Gameboy::new(true);
+    let frontend = gameboy.start();
+    frontend.send_breakpoints(vec![location]);
+    frontend.send_rom_data_back_end(rom_data);
+
+    // The emulator pauses before executing the instruction, and executes it once resumed.
+    assert_eq!(wait_breakpoint_hit(&frontend), location);
+    assert_eq!(cpu_ref.lock().unwrap().get_pc(), 0x0101);
+    frontend.send_pause(false);
+
+    assert_eq!(wait_breakpoint_hit(&frontend), location);
+    let history = cpu_ref.lock().unwrap().get_trace().get_history().clone();
+    assert_eq!(history.back().unwrap().location, Location::new(0x0102, 0));
+
+    // Closing the emulator while it is paused at a breakpoint does not wait for a resume.
+    frontend.send_close_back_end();
+    let _ = frontend.should_render_screen();
+    frontend.join_back_end().unwrap();
+}
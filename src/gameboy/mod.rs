@@ -1,19 +1,29 @@
+#[path = "gameboy_test.rs"]
+#[cfg(test)]
+mod test;
+
 use std::sync;
 use std::sync::Arc;
 use std::sync::Mutex;
 
 use crate::cartridge;
+use crate::cheats;
 use crate::cpu;
+use crate::cpu::trace::Location;
 use crate::cpu::CPU_CYCLES_PER_FRAME;
+use crate::input;
 use crate::interrupt;
+use crate::joypad;
 use crate::memory;
 use crate::memory::Memory;
+use crate::movie;
 use crate::ppu;
 use crate::timers;
 
 pub mod channel;
 use channel::back_end::Backend;
 use channel::front_end::Frontend;
+use glium::glutin::event::ElementState;
 
 #[derive(Debug)]
 enum State {
@@ -58,10 +68,40 @@ pub struct Gameboy {
     ppu: ppu::PPU,
     timers: Arc<sync::Mutex<timers::Timers>>,
     interrupt_bus: Arc<sync::Mutex<interrupt::Bus>>,
+
+    /// ROM currently loaded, which movies power back on.
+    rom_data: Option<Vec<u8>>,
+
+    /// Movie being recorded or played, during which inputs are applied once per frame.
+    movie: Option<movie::Session>,
+
+    /// Buttons held on the front end, as a `joypad::button_mask`.
+    live_buttons: u8,
+
+    /// Turbo buttons and macros, pressed on top of the live buttons and advanced once per
+    /// frame.
+    sequencer: input::Sequencer,
+
+    /// Codes of the enabled cheats, kept across power cycles.
+    cheats: Vec<cheats::Code>,
+
+    /// Codes of the cheats enabled on the front end while a movie runs with its own,
+    /// which are enabled once it ends.
+    held_cheats: Option<Vec<cheats::Code>>,
+
+    /// Locations at which emulation pauses, before executing the instruction there.
+    breakpoints: Vec<Location>,
+
+    /// PPU renderer and VRAM/OAM access restrictions selected on the front end. Movies run
+    /// with their own, and these apply again once they end.
+    renderer: ppu::Renderer,
+    ppu_access_restrictions: bool,
 }
 
 impl Gameboy {
-    pub fn new(skip_boot_rom: bool) -> (Self, Arc<Mutex<Memory>>) {
+    /// Returns the memory and the CPU along with the Gameboy, for the debugging tools to
+    /// inspect while it runs.
+    pub fn new(skip_boot_rom: bool) -> (Self, Arc<Mutex<Memory>>, Arc<Mutex<cpu::LR35902>>) {
         let timers = Arc::new(sync::Mutex::new(timers::Timers::new()));
         let ppu = ppu::PPU::new();
 
@@ -72,6 +112,7 @@ impl Gameboy {
         )));
         let memory_ref = memory.clone();
         let cpu = Arc::new(sync::Mutex::new(cpu::LR35902::new()));
+        let cpu_ref = cpu.clone();
 
         return (
             Self {
@@ -82,19 +123,38 @@ impl Gameboy {
                 skip_boot_rom,
                 timers,
                 interrupt_bus,
+                rom_data: None,
+                movie: None,
+                live_buttons: 0,
+                sequencer: input::Sequencer::new(input::DEFAULT_TURBO_RATE),
+                cheats: Vec::new(),
+                held_cheats: None,
+                breakpoints: Vec::new(),
+                renderer: ppu::Renderer::PixelFifo,
+                ppu_access_restrictions: true,
             },
             memory_ref,
+            cpu_ref,
         );
     }
 
     fn load_rom(&mut self, rom_data: Vec<u8>) {
+        self.rom_data = Some(rom_data.clone());
+        self.power_on(rom_data, self.skip_boot_rom);
+    }
+
+    fn power_on(&mut self, rom_data: Vec<u8>, skip_boot_rom: bool) {
         self.cpu.lock().unwrap().reset();
         self.ppu.reset();
         self.memory.lock().unwrap().reset(cartridge::new(rom_data));
+        self.memory
+            .lock()
+            .unwrap()
+            .set_rom_patches(cheats::rom_patches(&self.cheats));
         self.timers.lock().unwrap().reset();
         self.interrupt_bus.lock().unwrap().reset();
 
-        if self.skip_boot_rom {
+        if skip_boot_rom {
             self.cpu.lock().unwrap().set_post_boot_rom_state();
             self.memory.lock().unwrap().set_post_boot_rom_state();
             self.timers.lock().unwrap().set_post_boot_rom_state();
@@ -149,17 +209,91 @@ impl Gameboy {
             _ => {}
         }
 
+        self.apply_ppu_settings(backend);
+
+        if let Some(codes) = backend.should_set_cheats() {
+            self.set_cheats(codes);
+        }
+
+        // Checked before the ROM, which is always sent first when both are sent together.
+        let movie_command = backend.should_run_movie_command();
+
         match backend.should_load_rom() {
             Some(rom_data) => {
+                // The cheats of a ROM are sent right before it, as are the PPU settings to
+                // record a movie with.
+                if let Some(codes) = backend.should_set_cheats() {
+                    self.set_cheats(codes);
+                }
+                self.apply_ppu_settings(backend);
+
                 self.load_rom(rom_data);
                 log::debug!("rom cartridge loaded!");
                 self.state.transition(State::COMPUTING);
+
+                if let Some(command) = movie_command {
+                    self.run_movie_command(command, backend);
+                }
+                return;
             }
             _ => {}
         }
+
+        match movie_command {
+            Some(movie::Command::Stop) | None => {}
+            Some(_) => {
+                backend
+                    .send_movie_event_front_end(movie::Event::Error(String::from("no ROM loaded")));
+            }
+        }
     }
 
     fn compute(&mut self, backend: &Backend) {
+        if let Some(command) = backend.should_run_movie_command() {
+            self.run_movie_command(command, backend);
+        }
+
+        if let Some(codes) = backend.should_set_cheats() {
+            self.set_cheats(codes);
+        }
+
+        // GameShark codes hold their value from the start of every frame.
+        for write in cheats::ram_writes(&self.cheats) {
+            self.memory
+                .lock()
+                .unwrap()
+                .poke(write.address as usize, write.value);
+        }
+
+        if let Some(locations) = backend.should_set_breakpoints() {
+            self.breakpoints = locations;
+        }
+
+        for command in backend.recv_input_commands() {
+            self.sequencer.apply(command);
+        }
+        let sequenced = self.sequencer.get_buttons();
+
+        // While a movie runs, the buttons held on the front end only change between frames.
+        if let Some(session) = &mut self.movie {
+            for (direction_press, action_press, input_state) in backend.recv_all_joypad_data() {
+                self.live_buttons = update_buttons(
+                    self.live_buttons,
+                    direction_press,
+                    action_press,
+                    input_state,
+                );
+            }
+
+            let buttons = session.next_input(self.live_buttons | sequenced);
+            self.memory.lock().unwrap().set_joypad_pressed(buttons);
+        } else {
+            self.memory
+                .lock()
+                .unwrap()
+                .set_joypad_pressed(self.live_buttons | sequenced);
+        }
+
         let mut cycles_this_frame_so_far: u32 = 0;
         while cycles_this_frame_so_far < CPU_CYCLES_PER_FRAME {
             if backend.should_close() {
@@ -171,26 +305,61 @@ impl Gameboy {
                 backend.wait_pause_resume();
             }
 
-            let (direction_press, action_press, input_state) = backend.recv_joypad_data();
-            self.memory.lock().unwrap().write_joypad_queue(
-                direction_press,
-                action_press,
-                input_state,
-            );
+            if self.movie.is_none() {
+                let (direction_press, action_press, input_state) = backend.recv_joypad_data();
+                self.live_buttons = update_buttons(
+                    self.live_buttons,
+                    direction_press,
+                    action_press,
+                    input_state,
+                );
+
+                // Buttons held by turbo or a macro stay pressed when released on the front end.
+                let mut memory = self.memory.lock().unwrap();
+                match sequenced {
+                    0 => memory.set_joypad_button(direction_press, action_press, input_state),
+                    _ => memory.set_joypad_pressed(self.live_buttons | sequenced),
+                }
+            }
 
             match backend.should_set_skip_bootrom() {
                 Some(skip_bootrom) => self.skip_boot_rom = skip_bootrom,
                 None => {}
             }
 
+            self.apply_ppu_settings(backend);
+
             match backend.should_load_rom() {
                 Some(rom_data) => {
+                    self.stop_movie(backend);
+
+                    // The cheats of a ROM are sent right before it.
+                    if let Some(codes) = backend.should_set_cheats() {
+                        self.set_cheats(codes);
+                    }
+
                     self.load_rom(rom_data);
                     log::debug!("rom cartridge loaded!");
                 }
                 None => {}
             }
 
+            if !self.breakpoints.is_empty() && !self.cpu.lock().unwrap().is_halted() {
+                let location = Location::new(
+                    self.cpu.lock().unwrap().get_pc(),
+                    self.memory.lock().unwrap().get_rom_bank(),
+                );
+
+                // Resuming executes the instruction, instead of breaking on it again.
+                if self.breakpoints.contains(&location) {
+                    backend.send_breakpoint_hit_front_end(location);
+                    if !backend.wait_resume() {
+                        self.state.transition(State::EXITING);
+                        return;
+                    }
+                }
+            }
+
             let step_fn = &mut || {
                 self.timers.lock().unwrap().step(&self.interrupt_bus);
                 self.memory.lock().unwrap().step_dma();
@@ -221,6 +390,185 @@ impl Gameboy {
             cycles_this_frame_so_far += self.timers.lock().unwrap().get_elapsed_cycles();
         }
 
+        if let Some(session) = &mut self.movie {
+            let hash = movie::frame_hash(&self.ppu.get_frame_data());
+            if let Some(event) = session.end_frame(hash) {
+                let ended = matches!(event, movie::Event::PlaybackEnded(_));
+                backend.send_movie_event_front_end(event);
+
+                if ended {
+                    self.end_movie();
+                }
+            }
+        }
+
+        self.sequencer.tick();
+
         self.state.transition(State::RENDERING);
     }
+
+    fn set_cheats(&mut self, codes: Vec<cheats::Code>) {
+        // Cheats are part of movies, so they can't change until the movie ends.
+        if self.movie.is_some() {
+            log::warn!("Cheats will change once the movie ends");
+            self.held_cheats = Some(codes);
+            return;
+        }
+
+        self.enable_cheats(codes);
+    }
+
+    fn apply_ppu_settings(&mut self, backend: &Backend) {
+        if let Some(renderer) = backend.should_set_renderer() {
+            self.set_renderer(renderer);
+        }
+
+        if let Some(enabled) = backend.should_set_ppu_access_restrictions() {
+            self.set_ppu_access_restrictions(enabled);
+        }
+    }
+
+    fn set_renderer(&mut self, renderer: ppu::Renderer) {
+        self.renderer = renderer;
+
+        // The renderer is part of movies, so it can't change until the movie ends.
+        if self.movie.is_some() {
+            log::warn!("The PPU renderer will change once the movie ends");
+            return;
+        }
+
+        self.ppu.set_renderer(renderer);
+    }
+
+    fn set_ppu_access_restrictions(&mut self, enabled: bool) {
+        self.ppu_access_restrictions = enabled;
+
+        // As are the access restrictions.
+        if self.movie.is_some() {
+            log::warn!("The VRAM/OAM access restrictions will change once the movie ends");
+            return;
+        }
+
+        self.memory
+            .lock()
+            .unwrap()
+            .set_ppu_access_restrictions(enabled);
+    }
+
+    fn enable_cheats(&mut self, codes: Vec<cheats::Code>) {
+        self.memory
+            .lock()
+            .unwrap()
+            .set_rom_patches(cheats::rom_patches(&codes));
+        self.cheats = codes;
+    }
+
+    fn run_movie_command(&mut self, command: movie::Command, backend: &Backend) {
+        if let movie::Command::Stop = command {
+            self.stop_movie(backend);
+            return;
+        }
+
+        let rom_data = match &self.rom_data {
+            Some(rom_data) => rom_data.clone(),
+            None => {
+                backend
+                    .send_movie_event_front_end(movie::Event::Error(String::from("no ROM loaded")));
+                return;
+            }
+        };
+
+        self.stop_movie(backend);
+
+        match command {
+            movie::Command::Record => {
+                self.power_on(rom_data.clone(), self.skip_boot_rom);
+                backend.send_movie_event_front_end(movie::Event::Recording(movie::Movie::new(
+                    &rom_data,
+                    self.skip_boot_rom,
+                    self.renderer,
+                    self.ppu_access_restrictions,
+                    self.cheats.clone(),
+                )));
+                self.movie = Some(movie::Session::Recording { buttons: 0 });
+            }
+            movie::Command::Play(movie) => {
+                if let Err(err) = movie.check_rom(&rom_data) {
+                    backend.send_movie_event_front_end(movie::Event::Error(err));
+                    return;
+                }
+
+                if movie.emulator_version != movie::EMULATOR_VERSION {
+                    log::warn!(
+                        "Movie was recorded with version {} of the emulator, playback may desync",
+                        movie.emulator_version
+                    );
+                }
+
+                if movie.frames.is_empty() {
+                    backend.send_movie_event_front_end(movie::Event::PlaybackEnded(
+                        movie::Playback {
+                            frames_played: 0,
+                            total_frames: 0,
+                            first_desync: None,
+                        },
+                    ));
+                    return;
+                }
+
+                self.held_cheats = Some(self.cheats.clone());
+                self.enable_cheats(movie.cheats.clone());
+                self.ppu.set_renderer(movie.renderer);
+                self.memory
+                    .lock()
+                    .unwrap()
+                    .set_ppu_access_restrictions(movie.ppu_access_restrictions);
+                self.power_on(rom_data, movie.skip_boot_rom);
+                self.movie = Some(movie::Session::play(movie));
+            }
+            movie::Command::Stop => {}
+        }
+    }
+
+    fn stop_movie(&mut self, backend: &Backend) {
+        if let Some(session) = self.movie.take() {
+            if let Some(event) = session.stop() {
+                backend.send_movie_event_front_end(event);
+            }
+
+            self.end_movie();
+        }
+    }
+
+    // Gives the inputs, the cheats and the PPU settings back to the front end.
+    fn end_movie(&mut self) {
+        self.movie = None;
+        self.ppu.set_renderer(self.renderer);
+        self.memory
+            .lock()
+            .unwrap()
+            .set_ppu_access_restrictions(self.ppu_access_restrictions);
+        self.memory
+            .lock()
+            .unwrap()
+            .set_joypad_pressed(self.live_buttons | self.sequencer.get_buttons());
+
+        if let Some(codes) = self.held_cheats.take() {
+            self.enable_cheats(codes);
+        }
+    }
+}
+
+// Applies a button press or release to a `joypad::button_mask`.
+fn update_buttons(
+    buttons: u8,
+    direction_press: Option<joypad::DirectionButton>,
+    action_press: Option<joypad::ActionButton>,
+    input_state: ElementState,
+) -> u8 {
+    let mask = joypad::button_mask(direction_press, action_press);
+    match input_state {
+        ElementState::Pressed => buttons | mask,
+        ElementState::Released => buttons & !mask,
+    }
 }
@@ -0,0 +1,71 @@
+//! Backend driven by the tests, standing in for real controllers.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{Backend, Event, GamepadId};
+
+pub struct FakeBackend {
+    events: Rc<RefCell<Vec<Event>>>,
+}
+
+/// Simulates controllers being plugged in and used. Events are reported on the next poll.
+#[derive(Clone)]
+pub struct FakeDevices {
+    events: Rc<RefCell<Vec<Event>>>,
+}
+
+impl FakeBackend {
+    pub fn new() -> (Self, FakeDevices) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+
+        return (
+            Self {
+                events: events.clone(),
+            },
+            FakeDevices { events },
+        );
+    }
+}
+
+impl Backend for FakeBackend {
+    fn poll(&mut self) -> Vec<Event> {
+        return self.events.borrow_mut().drain(..).collect();
+    }
+}
+
+impl FakeDevices {
+    pub fn connect(&self, id: GamepadId, name: &str) {
+        self.push(Event::Connected {
+            id,
+            name: name.to_string(),
+        });
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.push(Event::Disconnected { id });
+    }
+
+    pub fn press(&self, id: GamepadId, button: u8) {
+        self.push(Event::Button {
+            id,
+            button,
+            pressed: true,
+        });
+    }
+
+    pub fn release(&self, id: GamepadId, button: u8) {
+        self.push(Event::Button {
+            id,
+            button,
+            pressed: false,
+        });
+    }
+
+    pub fn move_axis(&self, id: GamepadId, axis: u8, value: i16) {
+        self.push(Event::Axis { id, axis, value });
+    }
+
+    fn push(&self, event: Event) {
+        self.events.borrow_mut().push(event);
+    }
+}
@@ -0,0 +1,261 @@
+use glium::glutin::event::ElementState;
+
+use crate::gamepad::fake::{FakeBackend, FakeDevices};
+use crate::gamepad::{Event, Gamepads, Mapping, Settings};
+use crate::joypad::Button;
+
+fn new_gamepads(settings: Settings) -> (Gamepads, FakeDevices) {
+    let (backend, devices) = FakeBackend::new();
+    return (Gamepads::new(Box::new(backend), settings), devices);
+}
+
+#[test]
+fn buttons() {
+    let (mut gamepads, devices) = new_gamepads(Settings::new());
+    assert_eq!(gamepads.poll(), vec![]);
+
+    devices.connect(0, "Pad");
+    devices.press(0, 1);
+    devices.press(0, 7);
+    assert_eq!(
+        gamepads.poll(),
+        vec![
+            (Button::A, ElementState::Pressed),
+            (Button::Start, ElementState::Pressed)
+        ]
+    );
+    assert_eq!(gamepads.get_connected(), vec![(0, String::from("Pad"))]);
+
+    // Held buttons aren't reported again.
+    assert_eq!(gamepads.poll(), vec![]);
+
+    // Unmapped buttons are ignored.
+    devices.press(0, 4);
+    devices.release(0, 1);
+    assert_eq!(gamepads.poll(), vec![(Button::A, ElementState::Released)]);
+
+    // Events of unknown controllers are ignored.
+    devices.press(3, 0);
+    assert_eq!(gamepads.poll(), vec![]);
+}
+
+#[test]
+fn hot_plugging() {
+    let (mut gamepads, devices) = new_gamepads(Settings::new());
+
+    devices.connect(0, "Pad");
+    devices.connect(1, "Other Pad");
+    devices.press(0, 0);
+    devices.press(1, 0);
+    assert_eq!(gamepads.poll(), vec![(Button::B, ElementState::Pressed)]);
+
+    // B stays pressed while held on the other controller.
+    devices.disconnect(0);
+    assert_eq!(gamepads.poll(), vec![]);
+    assert_eq!(
+        gamepads.get_connected(),
+        vec![(1, String::from("Other Pad"))]
+    );
+
+    // Unplugging a controller releases its buttons.
+    devices.disconnect(1);
+    assert_eq!(gamepads.poll(), vec![(Button::B, ElementState::Released)]);
+    assert_eq!(gamepads.get_connected(), vec![]);
+
+    // A reconnected controller starts with all buttons released.
+    devices.connect(1, "Other Pad");
+    assert_eq!(gamepads.poll(), vec![]);
+}
+
+#[test]
+fn stick_deadzone() {
+    struct TestCase {
+        description: String,
+        deadzone: f32,
+        axes: Vec<(u8, i16)>,
+        expected: Vec<Button>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("centered stick"),
+            deadzone: 0.5,
+            axes: vec![(0, 0), (1, 0)],
+            expected: vec![],
+        },
+        TestCase {
+            description: String::from("within the deadzone"),
+            deadzone: 0.5,
+            axes: vec![(0, 16000), (1, -16000)],
+            expected: vec![],
+        },
+        TestCase {
+            description: String::from("past the deadzone"),
+            deadzone: 0.5,
+            axes: vec![(0, 17000), (1, 0)],
+            expected: vec![Button::Right],
+        },
+        TestCase {
+            description: String::from("smaller deadzone"),
+            deadzone: 0.2,
+            axes: vec![(0, -8000), (1, 0)],
+            expected: vec![Button::Left],
+        },
+        TestCase {
+            description: String::from("diagonal"),
+            deadzone: 0.5,
+            axes: vec![(0, i16::MIN), (1, i16::MIN)],
+            expected: vec![Button::Up, Button::Left],
+        },
+        TestCase {
+            description: String::from("hat D-pad"),
+            deadzone: 0.9,
+            axes: vec![(6, i16::MAX), (7, i16::MAX)],
+            expected: vec![Button::Down, Button::Right],
+        },
+        TestCase {
+            description: String::from("other axes are ignored"),
+            deadzone: 0.5,
+            axes: vec![(2, i16::MAX), (3, i16::MIN)],
+            expected: vec![],
+        },
+    ];
+
+    for tc in test_cases {
+        let (mut gamepads, devices) = new_gamepads(Settings::new());
+        gamepads.set_deadzone(tc.deadzone);
+
+        devices.connect(0, "Pad");
+        for (axis, value) in tc.axes {
+            devices.move_axis(0, axis, value);
+        }
+
+        let expected: Vec<(Button, ElementState)> = tc
+            .expected
+            .iter()
+            .map(|input| (*input, ElementState::Pressed))
+            .collect();
+        assert_eq!(gamepads.poll(), expected, "{}", tc.description);
+    }
+}
+
+#[test]
+fn stick_and_dpad_together() {
+    let (mut gamepads, devices) = new_gamepads(Settings::new());
+
+    devices.connect(0, "Pad");
+    devices.move_axis(0, 0, i16::MAX);
+    devices.move_axis(0, 6, i16::MAX);
+    assert_eq!(
+        gamepads.poll(),
+        vec![(Button::Right, ElementState::Pressed)]
+    );
+
+    devices.move_axis(0, 0, 0);
+    assert_eq!(gamepads.poll(), vec![]);
+
+    devices.move_axis(0, 6, 0);
+    assert_eq!(
+        gamepads.poll(),
+        vec![(Button::Right, ElementState::Released)]
+    );
+}
+
+#[test]
+fn remapping() {
+    let (mut gamepads, devices) = new_gamepads(Settings::new());
+
+    devices.connect(0, "Pad");
+    devices.connect(1, "Other Pad");
+    gamepads.poll();
+
+    gamepads.start_remap(0, Button::Up);
+    assert_eq!(gamepads.get_remap(), Some((0, Button::Up)));
+
+    // Presses on other controllers don't complete the remap.
+    devices.press(1, 1);
+    assert_eq!(gamepads.poll(), vec![(Button::A, ElementState::Pressed)]);
+    assert_eq!(gamepads.get_remap(), Some((0, Button::Up)));
+
+    // The binding press isn't passed on to the emulator.
+    devices.press(0, 13);
+    devices.release(1, 1);
+    assert_eq!(gamepads.poll(), vec![(Button::A, ElementState::Released)]);
+    assert_eq!(gamepads.get_remap(), None);
+
+    let mut expected = Mapping::new();
+    expected.up = Some(13);
+    assert_eq!(gamepads.get_settings().get_mapping("Pad"), expected);
+    assert_eq!(
+        gamepads.get_settings().get_mapping("Other Pad"),
+        Mapping::new()
+    );
+
+    devices.release(0, 13);
+    devices.press(0, 13);
+    assert_eq!(gamepads.poll(), vec![(Button::Up, ElementState::Pressed)]);
+
+    gamepads.reset_mapping("Pad");
+    assert_eq!(gamepads.poll(), vec![(Button::Up, ElementState::Released)]);
+    assert_eq!(gamepads.get_settings().get_mapping("Pad"), Mapping::new());
+
+    // Unplugging the controller cancels the remap.
+    gamepads.start_remap(1, Button::B);
+    devices.disconnect(1);
+    gamepads.poll();
+    assert_eq!(gamepads.get_remap(), None);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn parse_joystick_event() {
+    struct TestCase {
+        description: String,
+        bytes: [u8; 8],
+        expected: Option<Event>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("button press"),
+            bytes: [0x10, 0x20, 0x30, 0x40, 0x01, 0x00, 0x01, 0x03],
+            expected: Some(Event::Button {
+                id: 2,
+                button: 3,
+                pressed: true,
+            }),
+        },
+        TestCase {
+            description: String::from("initial button state"),
+            bytes: [0, 0, 0, 0, 0x00, 0x00, 0x81, 0x05],
+            expected: Some(Event::Button {
+                id: 2,
+                button: 5,
+                pressed: false,
+            }),
+        },
+        TestCase {
+            description: String::from("axis"),
+            bytes: [0, 0, 0, 0, 0x01, 0x80, 0x02, 0x01],
+            expected: Some(Event::Axis {
+                id: 2,
+                axis: 1,
+                value: -32767,
+            }),
+        },
+        TestCase {
+            description: String::from("unknown type"),
+            bytes: [0, 0, 0, 0, 0, 0, 0x04, 0],
+            expected: None,
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            super::linux::parse_event(2, &tc.bytes),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Controllers read through the Linux joystick interface, `/dev/input/js*`, which every
+//! controller driver exposes without needing extra libraries.
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{Backend, Event, GamepadId};
+
+/// Size of a `struct js_event`.
+pub(super) const EVENT_SIZE: usize = 8;
+
+const JS_EVENT_BUTTON: u8 = 0x01;
+const JS_EVENT_AXIS: u8 = 0x02;
+
+// Set on the events reporting the initial state of each button and axis when a device is
+// opened, which are otherwise handled like regular events.
+const JS_EVENT_INIT: u8 = 0x80;
+
+const MAX_DEVICES: GamepadId = 32;
+
+/// How often new devices are looked for, to pick up controllers plugged in while running.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parses a `struct js_event`: a timestamp, the value, the event type and the number of
+/// the button or axis.
+pub(super) fn parse_event(id: GamepadId, bytes: &[u8; EVENT_SIZE]) -> Option<Event> {
+    let value = i16::from_le_bytes([bytes[4], bytes[5]]);
+    let number = bytes[7];
+
+    match bytes[6] & !JS_EVENT_INIT {
+        JS_EVENT_BUTTON => Some(Event::Button {
+            id,
+            button: number,
+            pressed: value != 0,
+        }),
+        JS_EVENT_AXIS => Some(Event::Axis {
+            id,
+            axis: number,
+            value,
+        }),
+        _ => None,
+    }
+}
+
+pub struct Joystick {
+    event_sender: mpsc::Sender<Event>,
+    event_receiver: mpsc::Receiver<Event>,
+
+    // Devices currently read by a thread of their own.
+    open_devices: Arc<Mutex<HashSet<GamepadId>>>,
+
+    last_scan: Option<Instant>,
+}
+
+impl Joystick {
+    pub fn new() -> Self {
+        let (event_sender, event_receiver) = mpsc::channel();
+
+        Self {
+            event_sender,
+            event_receiver,
+            open_devices: Arc::new(Mutex::new(HashSet::new())),
+            last_scan: None,
+        }
+    }
+
+    fn scan(&mut self) {
+        for id in 0..MAX_DEVICES {
+            if self.open_devices.lock().unwrap().contains(&id) {
+                continue;
+            }
+
+            let path = PathBuf::from(format!("/dev/input/js{}", id));
+            let file = match fs::File::open(&path) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+
+            self.open_devices.lock().unwrap().insert(id);
+
+            let name = fs::read_to_string(format!("/sys/class/input/js{}/device/name", id))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_else(|_| format!("Joystick {}", id));
+
+            let event_sender = self.event_sender.clone();
+            let open_devices = self.open_devices.clone();
+            thread::spawn(move || {
+                read_device(id, name, file, &event_sender);
+
+                // Disconnecting before the device can be picked up again by a scan, so that
+                // its events come after this one.
+                let mut open_devices = open_devices.lock().unwrap();
+                let _ = event_sender.send(Event::Disconnected { id });
+                open_devices.remove(&id);
+            });
+        }
+    }
+}
+
+// Reads the device's events until it is unplugged.
+fn read_device(
+    id: GamepadId,
+    name: String,
+    mut file: fs::File,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    if event_sender.send(Event::Connected { id, name }).is_err() {
+        return;
+    }
+
+    let mut bytes = [0; EVENT_SIZE];
+    while file.read_exact(&mut bytes).is_ok() {
+        if let Some(event) = parse_event(id, &bytes) {
+            if event_sender.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl Backend for Joystick {
+    fn poll(&mut self) -> Vec<Event> {
+        let should_scan = match self.last_scan {
+            Some(last_scan) => last_scan.elapsed() >= SCAN_INTERVAL,
+            None => true,
+        };
+
+        if should_scan {
+            self.scan();
+            self.last_scan = Some(Instant::now());
+        }
+
+        return self.event_receiver.try_iter().collect();
+    }
+}
@@ -0,0 +1,339 @@
+//! Game controller input. Controllers are read through a `Backend`, and their buttons, analog
+//! stick and D-pad are translated into presses of the Gameboy's buttons.
+#[path = "gamepad_test.rs"]
+#[cfg(test)]
+mod test;
+
+#[cfg(test)]
+pub mod fake;
+#[cfg(target_os = "linux")]
+mod linux;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use glium::glutin::event::ElementState;
+
+use crate::joypad::Button;
+
+/// Identifies a connected controller for as long as it stays connected.
+pub type GamepadId = u32;
+
+pub const DEFAULT_DEADZONE: f32 = 0.5;
+pub const MIN_DEADZONE: f32 = 0.1;
+pub const MAX_DEADZONE: f32 = 0.9;
+
+// Axes of the left analog stick and of D-pads reported as a hat, as numbered by most
+// controller drivers. Negative values point left and up.
+const STICK_X_AXIS: u8 = 0;
+const STICK_Y_AXIS: u8 = 1;
+const HAT_X_AXIS: u8 = 6;
+const HAT_Y_AXIS: u8 = 7;
+
+/// Raw controller event reported by a backend.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Connected {
+        id: GamepadId,
+        name: String,
+    },
+    Disconnected {
+        id: GamepadId,
+    },
+    Button {
+        id: GamepadId,
+        button: u8,
+        pressed: bool,
+    },
+    Axis {
+        id: GamepadId,
+        axis: u8,
+        value: i16,
+    },
+}
+
+/// Source of controller events.
+pub trait Backend {
+    /// Returns the events which occurred since the last poll, including controllers being
+    /// connected or disconnected.
+    fn poll(&mut self) -> Vec<Event>;
+}
+
+/// Backend used when controllers aren't supported on the current platform.
+#[cfg(not(target_os = "linux"))]
+struct NoBackend;
+
+#[cfg(not(target_os = "linux"))]
+impl Backend for NoBackend {
+    fn poll(&mut self) -> Vec<Event> {
+        Vec::new()
+    }
+}
+
+/// Backend reading the controllers of the current platform.
+pub fn default_backend() -> Box<dyn Backend> {
+    #[cfg(target_os = "linux")]
+    return Box::new(linux::Joystick::new());
+
+    #[cfg(not(target_os = "linux"))]
+    return Box::new(NoBackend);
+}
+
+/// Controller buttons mapped to each of the Gameboy's buttons. Directions are also always
+/// read from the analog stick and hat D-pad, so they usually don't need a button.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mapping {
+    pub a: Option<u8>,
+    pub b: Option<u8>,
+    pub start: Option<u8>,
+    pub select: Option<u8>,
+    pub up: Option<u8>,
+    pub down: Option<u8>,
+    pub left: Option<u8>,
+    pub right: Option<u8>,
+}
+
+impl Mapping {
+    /// Xbox style layout: the Gameboy's A and B on the east and south face buttons,
+    /// Select and Start on Back and Start.
+    pub fn new() -> Self {
+        Self {
+            a: Some(1),
+            b: Some(0),
+            start: Some(7),
+            select: Some(6),
+            up: None,
+            down: None,
+            left: None,
+            right: None,
+        }
+    }
+
+    pub fn get(&self, input: Button) -> Option<u8> {
+        match input {
+            Button::A => self.a,
+            Button::B => self.b,
+            Button::Start => self.start,
+            Button::Select => self.select,
+            Button::Up => self.up,
+            Button::Down => self.down,
+            Button::Left => self.left,
+            Button::Right => self.right,
+        }
+    }
+
+    pub fn set(&mut self, input: Button, button: Option<u8>) {
+        match input {
+            Button::A => self.a = button,
+            Button::B => self.b = button,
+            Button::Start => self.start = button,
+            Button::Select => self.select = button,
+            Button::Up => self.up = button,
+            Button::Down => self.down = button,
+            Button::Left => self.left = button,
+            Button::Right => self.right = button,
+        }
+    }
+}
+
+/// Controller settings persisted in the config file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Fraction of an axis' range which has to be exceeded to press a direction.
+    pub deadzone: f32,
+
+    /// Custom button mappings by controller name. Other controllers use `Mapping::new`.
+    pub mappings: BTreeMap<String, Mapping>,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            deadzone: DEFAULT_DEADZONE,
+            mappings: BTreeMap::new(),
+        }
+    }
+
+    pub fn get_mapping(&self, name: &str) -> Mapping {
+        match self.mappings.get(name) {
+            Some(mapping) => mapping.clone(),
+            None => Mapping::new(),
+        }
+    }
+}
+
+struct Gamepad {
+    name: String,
+    buttons: HashSet<u8>,
+    axes: HashMap<u8, i16>,
+}
+
+impl Gamepad {
+    fn is_pressed(&self, input: Button, mapping: &Mapping, deadzone: f32) -> bool {
+        if let Some(button) = mapping.get(input) {
+            if self.buttons.contains(&button) {
+                return true;
+            }
+        }
+
+        // Each axis is compared against the deadzone on its own, so that diagonals press
+        // both directions once the stick is pushed far enough.
+        let threshold = (deadzone * i16::MAX as f32) as i32;
+        let exceeds = |axis: u8, sign: i32| -> bool {
+            let value = *self.axes.get(&axis).unwrap_or(&0) as i32;
+            return value * sign > threshold;
+        };
+
+        return match input {
+            Button::Up => exceeds(STICK_Y_AXIS, -1) || exceeds(HAT_Y_AXIS, -1),
+            Button::Down => exceeds(STICK_Y_AXIS, 1) || exceeds(HAT_Y_AXIS, 1),
+            Button::Left => exceeds(STICK_X_AXIS, -1) || exceeds(HAT_X_AXIS, -1),
+            Button::Right => exceeds(STICK_X_AXIS, 1) || exceeds(HAT_X_AXIS, 1),
+            _ => false,
+        };
+    }
+}
+
+/// The connected controllers, combined into a single set of Gameboy button states.
+pub struct Gamepads {
+    backend: Box<dyn Backend>,
+    settings: Settings,
+    gamepads: BTreeMap<GamepadId, Gamepad>,
+    pressed: [bool; 8],
+
+    // Gameboy button waiting for the next button press on the given controller.
+    remap: Option<(GamepadId, Button)>,
+}
+
+impl Gamepads {
+    pub fn new(backend: Box<dyn Backend>, settings: Settings) -> Self {
+        Self {
+            backend,
+            settings,
+            gamepads: BTreeMap::new(),
+            pressed: [false; 8],
+            remap: None,
+        }
+    }
+
+    pub fn get_settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn set_deadzone(&mut self, deadzone: f32) {
+        self.settings.deadzone = deadzone.clamp(MIN_DEADZONE, MAX_DEADZONE);
+    }
+
+    /// IDs and names of the connected controllers.
+    pub fn get_connected(&self) -> Vec<(GamepadId, String)> {
+        return self
+            .gamepads
+            .iter()
+            .map(|(id, gamepad)| (*id, gamepad.name.clone()))
+            .collect();
+    }
+
+    /// Maps the given Gameboy button to the next button pressed on the controller.
+    pub fn start_remap(&mut self, id: GamepadId, input: Button) {
+        self.remap = Some((id, input));
+    }
+
+    pub fn cancel_remap(&mut self) {
+        self.remap = None;
+    }
+
+    pub fn get_remap(&self) -> Option<(GamepadId, Button)> {
+        self.remap
+    }
+
+    /// Restores the default mapping of the named controller.
+    pub fn reset_mapping(&mut self, name: &str) {
+        self.settings.mappings.remove(name);
+    }
+
+    /// Processes the backend's events and returns the Gameboy buttons which were pressed
+    /// or released since the last poll. A button stays pressed while held on any controller.
+    pub fn poll(&mut self) -> Vec<(Button, ElementState)> {
+        for event in self.backend.poll() {
+            self.process_event(event);
+        }
+
+        let mut changes = Vec::new();
+        for input in Button::ALL {
+            let pressed = self.gamepads.values().any(|gamepad| {
+                let mapping = self.settings.get_mapping(&gamepad.name);
+                gamepad.is_pressed(input, &mapping, self.settings.deadzone)
+            });
+
+            if pressed != self.pressed[input.index()] {
+                self.pressed[input.index()] = pressed;
+                changes.push((
+                    input,
+                    match pressed {
+                        true => ElementState::Pressed,
+                        false => ElementState::Released,
+                    },
+                ));
+            }
+        }
+
+        return changes;
+    }
+
+    fn process_event(&mut self, event: Event) {
+        match event {
+            Event::Connected { id, name } => {
+                log::info!("Controller connected: {}", name);
+                self.gamepads.insert(
+                    id,
+                    Gamepad {
+                        name,
+                        buttons: HashSet::new(),
+                        axes: HashMap::new(),
+                    },
+                );
+            }
+            Event::Disconnected { id } => {
+                if let Some(gamepad) = self.gamepads.remove(&id) {
+                    log::info!("Controller disconnected: {}", gamepad.name);
+                }
+
+                if matches!(self.remap, Some((remap_id, _)) if remap_id == id) {
+                    self.remap = None;
+                }
+            }
+            Event::Button {
+                id,
+                button,
+                pressed,
+            } => {
+                let gamepad = match self.gamepads.get_mut(&id) {
+                    Some(gamepad) => gamepad,
+                    None => return,
+                };
+
+                if !pressed {
+                    gamepad.buttons.remove(&button);
+                    return;
+                }
+
+                match self.remap {
+                    // The press binding a button isn't passed on to the emulator.
+                    Some((remap_id, input)) if remap_id == id => {
+                        let mut mapping = self.settings.get_mapping(&gamepad.name);
+                        mapping.set(input, Some(button));
+                        self.settings.mappings.insert(gamepad.name.clone(), mapping);
+                        self.remap = None;
+                    }
+                    _ => {
+                        gamepad.buttons.insert(button);
+                    }
+                }
+            }
+            Event::Axis { id, axis, value } => {
+                if let Some(gamepad) = self.gamepads.get_mut(&id) {
+                    gamepad.axes.insert(axis, value);
+                }
+            }
+        }
+    }
+}
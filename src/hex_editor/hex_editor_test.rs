@@ -0,0 +1,110 @@
+use crate::hex_editor;
+
+#[test]
+fn parse_address() {
+    struct TestCase {
+        description: String,
+        text: String,
+        expected: Result<u16, String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("plain"),
+            text: String::from("c0a4"),
+            expected: Ok(0xC0A4),
+        },
+        TestCase {
+            description: String::from("dollar prefix"),
+            text: String::from(" $FF80"),
+            expected: Ok(0xFF80),
+        },
+        TestCase {
+            description: String::from("0x prefix"),
+            text: String::from("0x150"),
+            expected: Ok(0x0150),
+        },
+        TestCase {
+            description: String::from("out of range"),
+            text: String::from("10000"),
+            expected: Err(String::from("invalid address '10000'")),
+        },
+        TestCase {
+            description: String::from("not hexadecimal"),
+            text: String::from("C0G4"),
+            expected: Err(String::from("invalid address 'C0G4'")),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            hex_editor::parse_address(&tc.text),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn parse_pattern() {
+    struct TestCase {
+        description: String,
+        text: String,
+        expected: Result<Vec<Option<u8>>, String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("separated bytes"),
+            text: String::from("3E ?? e0 40"),
+            expected: Ok(vec![Some(0x3E), None, Some(0xE0), Some(0x40)]),
+        },
+        TestCase {
+            description: String::from("joined bytes"),
+            text: String::from("3E??E040"),
+            expected: Ok(vec![Some(0x3E), None, Some(0xE0), Some(0x40)]),
+        },
+        TestCase {
+            description: String::from("empty"),
+            text: String::from("  "),
+            expected: Err(String::from("no bytes")),
+        },
+        TestCase {
+            description: String::from("odd digit count"),
+            text: String::from("3E E04"),
+            expected: Err(String::from("invalid bytes 'E04'")),
+        },
+        TestCase {
+            description: String::from("not hexadecimal"),
+            text: String::from("3E XY"),
+            expected: Err(String::from("invalid byte 'XY'")),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            hex_editor::parse_pattern(&tc.text),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn find_pattern() {
+    let bytes = vec![0x00, 0x3E, 0x01, 0xE0, 0x40, 0x3E, 0x02, 0xE0, 0x40, 0x3E];
+    let pattern = hex_editor::parse_pattern("3E ?? E0 40").unwrap();
+
+    assert_eq!(hex_editor::find_pattern(&bytes, &pattern, 0), Some(1));
+    assert_eq!(hex_editor::find_pattern(&bytes, &pattern, 1), Some(1));
+    assert_eq!(hex_editor::find_pattern(&bytes, &pattern, 2), Some(5));
+
+    // Searches wrap around, but matches don't.
+    assert_eq!(hex_editor::find_pattern(&bytes, &pattern, 6), Some(1));
+    assert_eq!(hex_editor::find_pattern(&bytes, &pattern, 0xFFFF), Some(1));
+
+    let pattern = hex_editor::parse_pattern("3E 03").unwrap();
+    assert_eq!(hex_editor::find_pattern(&bytes, &pattern, 0), None);
+}
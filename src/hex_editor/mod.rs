@@ -0,0 +1,83 @@
+//! Helpers of the hex editor, which views and edits the whole address space as the CPU
+//! sees it.
+#[path = "hex_editor_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::memory::Memory;
+
+/// Size of the address space.
+pub const ADDRESS_SPACE_SIZE: usize = 0x10000;
+
+/// Parses a hexadecimal address, optionally prefixed with `$` or `0x`.
+pub fn parse_address(text: &str) -> Result<u16, String> {
+    let text = text.trim();
+    let digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+
+    return u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address '{}'", text));
+}
+
+/// Parses a pattern of hexadecimal bytes, where `??` matches any byte, e.g. `3E ?? E0 40`.
+/// Bytes may also be written without spaces between them.
+pub fn parse_pattern(text: &str) -> Result<Vec<Option<u8>>, String> {
+    let mut pattern = Vec::new();
+    for group in text.split_whitespace() {
+        if group.len() % 2 != 0 || !group.is_ascii() {
+            return Err(format!("invalid bytes '{}'", group));
+        }
+
+        for index in (0..group.len()).step_by(2) {
+            let byte = &group[index..index + 2];
+            if byte == "??" {
+                pattern.push(None);
+                continue;
+            }
+
+            let value =
+                u8::from_str_radix(byte, 16).map_err(|_| format!("invalid byte '{}'", byte))?;
+            pattern.push(Some(value));
+        }
+    }
+
+    if pattern.is_empty() {
+        return Err(String::from("no bytes"));
+    }
+
+    return Ok(pattern);
+}
+
+/// Reads the whole address space without side effects, with unmapped addresses read as
+/// 0xFF.
+pub fn read_all(memory: &Memory) -> Vec<u8> {
+    return (0..ADDRESS_SPACE_SIZE)
+        .map(|address| memory.dma_read(address).unwrap_or(0xFF))
+        .collect();
+}
+
+/// Address of the first match of the pattern starting at or after `start`, wrapping
+/// around to the start of the address space.
+pub fn find_pattern(bytes: &[u8], pattern: &[Option<u8>], start: u16) -> Option<u16> {
+    if pattern.is_empty() || pattern.len() > bytes.len() {
+        return None;
+    }
+
+    let last = bytes.len() - pattern.len();
+    let start = (start as usize).min(last + 1);
+
+    return (start..=last)
+        .chain(0..start)
+        .find(|address| {
+            return pattern
+                .iter()
+                .zip(&bytes[*address..])
+                .all(|(expected, byte)| match expected {
+                    Some(expected) => expected == byte,
+                    None => true,
+                });
+        })
+        .map(|address| address as u16);
+}
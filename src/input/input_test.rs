@@ -0,0 +1,239 @@
+use glium::glutin::event::ElementState;
+
+use crate::input::{self, Command, Mixer, Sequencer, Source, Step};
+use crate::joypad::Button;
+
+#[test]
+fn parse_steps() {
+    struct TestCase {
+        description: String,
+        text: String,
+        expected: Result<Vec<Step>, String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("single step"),
+            text: String::from("A 4"),
+            expected: Ok(vec![Step {
+                buttons: Button::A.mask(),
+                frames: 4,
+            }]),
+        },
+        TestCase {
+            description: String::from("combined buttons and pauses"),
+            text: String::from("down+right 2,  - 10 ,Right+A+B 1"),
+            expected: Ok(vec![
+                Step {
+                    buttons: Button::Down.mask() | Button::Right.mask(),
+                    frames: 2,
+                },
+                Step {
+                    buttons: 0,
+                    frames: 10,
+                },
+                Step {
+                    buttons: Button::Right.mask() | Button::A.mask() | Button::B.mask(),
+                    frames: 1,
+                },
+            ]),
+        },
+        TestCase {
+            description: String::from("empty"),
+            text: String::from(" "),
+            expected: Err(String::from("no steps")),
+        },
+        TestCase {
+            description: String::from("missing frame count"),
+            text: String::from("A 4, B"),
+            expected: Err(String::from(
+                "invalid step 'B', expected '<buttons> <frames>'",
+            )),
+        },
+        TestCase {
+            description: String::from("zero frames"),
+            text: String::from("A 0"),
+            expected: Err(String::from(
+                "invalid step 'A 0', expected '<buttons> <frames>'",
+            )),
+        },
+        TestCase {
+            description: String::from("unknown button"),
+            text: String::from("A+C 2"),
+            expected: Err(String::from("unknown button 'C'")),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            input::parse_steps(&tc.text),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn format_steps() {
+    let steps = input::parse_steps("right+down 2, - 10, B+Right+a 1").unwrap();
+
+    // Buttons are listed in a fixed order.
+    assert_eq!(
+        input::format_steps(&steps),
+        "Down+Right 2, - 10, A+B+Right 1"
+    );
+    assert_eq!(input::parse_steps(&input::format_steps(&steps)), Ok(steps));
+}
+
+#[test]
+fn sources() {
+    let mut mixer = Mixer::new();
+    assert_eq!(mixer.changes(), vec![]);
+
+    mixer.set_held(Source::Keyboard, Button::A, ElementState::Pressed);
+    mixer.set_held(Source::Gamepad, Button::A, ElementState::Pressed);
+    mixer.set_held(Source::Gamepad, Button::Up, ElementState::Pressed);
+    assert_eq!(
+        mixer.changes(),
+        vec![
+            (Button::A, ElementState::Pressed),
+            (Button::Up, ElementState::Pressed)
+        ]
+    );
+    assert_eq!(mixer.changes(), vec![]);
+
+    // A stays pressed while held on the keyboard.
+    mixer.set_held(Source::Gamepad, Button::A, ElementState::Released);
+    assert_eq!(mixer.changes(), vec![]);
+
+    mixer.set_held(Source::Keyboard, Button::A, ElementState::Released);
+    assert_eq!(mixer.changes(), vec![(Button::A, ElementState::Released)]);
+    assert_eq!(mixer.get_buttons(), Button::Up.mask());
+}
+
+// Whether A is pressed on each of the frames, starting with the current one.
+fn turbo_frames(sequencer: &mut Sequencer, count: usize) -> Vec<bool> {
+    let mut frames = Vec::new();
+    for _ in 0..count {
+        frames.push(sequencer.get_buttons() & Button::A.mask() != 0);
+        sequencer.tick();
+    }
+
+    return frames;
+}
+
+#[test]
+fn turbo() {
+    struct TestCase {
+        description: String,
+        rate: u32,
+        expected: Vec<bool>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("fastest rate"),
+            rate: 30,
+            expected: vec![true, false, true, false, true, false],
+        },
+        TestCase {
+            description: String::from("default rate"),
+            rate: 15,
+            expected: vec![true, true, false, false, true, true],
+        },
+        TestCase {
+            description: String::from("odd period"),
+            rate: 20,
+            expected: vec![true, true, false, true, true, false],
+        },
+        TestCase {
+            description: String::from("rate out of range"),
+            rate: 60,
+            expected: vec![true, false, true, false, true, false],
+        },
+    ];
+
+    for tc in test_cases {
+        let mut sequencer = Sequencer::new(tc.rate);
+        sequencer.set_turbo(Button::A, ElementState::Pressed);
+        assert_eq!(
+            turbo_frames(&mut sequencer, tc.expected.len()),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn turbo_held() {
+    let mut sequencer = Sequencer::new(15);
+    sequencer.set_turbo(Button::A, ElementState::Pressed);
+    turbo_frames(&mut sequencer, 3);
+
+    // Holding the other turbo button keeps the phase.
+    sequencer.set_turbo(Button::B, ElementState::Pressed);
+    assert_eq!(sequencer.get_buttons(), 0);
+    sequencer.tick();
+    assert_eq!(sequencer.get_buttons(), Button::A.mask() | Button::B.mask());
+
+    // Turbo presses on the frame it is held again.
+    sequencer.set_turbo(Button::A, ElementState::Released);
+    sequencer.set_turbo(Button::B, ElementState::Released);
+    sequencer.tick();
+    sequencer.tick();
+    sequencer.set_turbo(Button::A, ElementState::Pressed);
+    assert_eq!(turbo_frames(&mut sequencer, 3), vec![true, true, false]);
+
+    sequencer.set_turbo(Button::A, ElementState::Released);
+    assert_eq!(sequencer.get_buttons(), 0);
+}
+
+#[test]
+fn commands() {
+    let mut sequencer = Sequencer::new(input::DEFAULT_TURBO_RATE);
+    sequencer.apply(Command::TurboRate(30));
+    sequencer.apply(Command::Turbo(Button::A, ElementState::Pressed));
+    assert_eq!(
+        turbo_frames(&mut sequencer, 4),
+        vec![true, false, true, false]
+    );
+
+    sequencer.apply(Command::Turbo(Button::A, ElementState::Released));
+    sequencer.apply(Command::Macro(input::parse_steps("Start 2").unwrap()));
+    assert_eq!(sequencer.get_buttons(), Button::Start.mask());
+    sequencer.tick();
+    sequencer.tick();
+    assert_eq!(sequencer.get_buttons(), 0);
+}
+
+#[test]
+fn macros() {
+    let mut sequencer = Sequencer::new(input::DEFAULT_TURBO_RATE);
+    let steps = input::parse_steps("Down 2, Down+Right 1, - 1, A 1").unwrap();
+    sequencer.start_macro(&steps);
+
+    let mut frames = Vec::new();
+    for _ in 0..6 {
+        frames.push(sequencer.get_buttons());
+        sequencer.tick();
+    }
+
+    let down = Button::Down.mask();
+    let right = Button::Right.mask();
+    assert_eq!(
+        frames,
+        vec![down, down, down | right, 0, Button::A.mask(), 0]
+    );
+
+    // Macros overlap when started again before they end.
+    sequencer.start_macro(&steps);
+    sequencer.tick();
+    sequencer.tick();
+    sequencer.start_macro(&input::parse_steps("Up 1").unwrap());
+    assert_eq!(
+        sequencer.get_buttons(),
+        Button::Down.mask() | Button::Right.mask() | Button::Up.mask()
+    );
+}
@@ -0,0 +1,288 @@
+//! Buttons held on the emulator: those held on the keyboard and controllers, combined by
+//! the frontend, along with the turbo buttons and input macros the emulator core plays on
+//! top of them frame by frame.
+#[path = "input_test.rs"]
+#[cfg(test)]
+mod test;
+
+use glium::glutin::event::{ElementState, VirtualKeyCode};
+
+use crate::joypad::Button;
+
+/// Rate at which `Sequencer::tick` is called, once per emulated frame, rounded from the
+/// Gameboy's ~59.73 Hz.
+pub const TICKS_PER_SECOND: u32 = 60;
+
+/// Presses per second of the turbo buttons.
+pub const DEFAULT_TURBO_RATE: u32 = 15;
+pub const MIN_TURBO_RATE: u32 = 1;
+pub const MAX_TURBO_RATE: u32 = TICKS_PER_SECOND / 2;
+
+const NO_BUTTONS: &str = "-";
+
+/// Keys pressing A and B repeatedly while held, and how fast.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turbo {
+    pub a: Option<VirtualKeyCode>,
+    pub b: Option<VirtualKeyCode>,
+    pub rate: u32,
+}
+
+impl Turbo {
+    pub fn new() -> Self {
+        Self {
+            a: Some(VirtualKeyCode::Z),
+            b: Some(VirtualKeyCode::X),
+            rate: DEFAULT_TURBO_RATE,
+        }
+    }
+}
+
+/// Buttons held for a number of frames, as a `joypad::button_mask`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    pub buttons: u8,
+    pub frames: u32,
+}
+
+/// Sequence of button states played once each time its key is pressed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    pub key: Option<VirtualKeyCode>,
+    pub steps: Vec<Step>,
+}
+
+/// Parses macro steps written as comma separated `<buttons> <frames>` pairs, with the
+/// buttons joined by `+` or `-` for none, e.g. `Down 2, Down+Right 2, Right+A 4, - 10`.
+pub fn parse_steps(text: &str) -> Result<Vec<Step>, String> {
+    if text.trim().is_empty() {
+        return Err(String::from("no steps"));
+    }
+
+    let mut steps = Vec::new();
+    for step in text.split(',') {
+        let step = step.trim();
+        let error = || format!("invalid step '{}', expected '<buttons> <frames>'", step);
+
+        let (names, frames) = step.split_once(char::is_whitespace).ok_or_else(error)?;
+        let frames = match frames.trim().parse::<u32>() {
+            Ok(frames) if frames > 0 => frames,
+            _ => return Err(error()),
+        };
+
+        let mut buttons = 0;
+        if names != NO_BUTTONS {
+            for name in names.split('+') {
+                let button = Button::ALL
+                    .iter()
+                    .find(|button| button.get_name().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| format!("unknown button '{}'", name))?;
+                buttons |= button.mask();
+            }
+        }
+
+        steps.push(Step { buttons, frames });
+    }
+
+    return Ok(steps);
+}
+
+pub fn format_steps(steps: &[Step]) -> String {
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            let names: Vec<&str> = Button::ALL
+                .iter()
+                .filter(|button| step.buttons & button.mask() != 0)
+                .map(|button| button.get_name())
+                .collect();
+
+            match names.is_empty() {
+                true => format!("{} {}", NO_BUTTONS, step.frames),
+                false => format!("{} {}", names.join("+"), step.frames),
+            }
+        })
+        .collect();
+
+    return steps.join(", ");
+}
+
+/// Requests sent by the frontend to the emulator core's `Sequencer`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Holds or releases the turbo variant of a button.
+    Turbo(Button, ElementState),
+    TurboRate(u32),
+
+    /// Plays the steps of a macro.
+    Macro(Vec<Step>),
+}
+
+/// Devices holding buttons down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    Keyboard,
+    Gamepad,
+}
+
+// Macro being played, at the given frame of one of its steps.
+struct Playing {
+    steps: Vec<Step>,
+    step: usize,
+    frame: u32,
+}
+
+/// Combines the buttons held by every source into the state of the Gameboy's buttons.
+/// A button is pressed while any source holds it.
+pub struct Mixer {
+    keyboard: u8,
+    gamepad: u8,
+
+    // Buttons last reported by `changes`.
+    reported: u8,
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Self {
+            keyboard: 0,
+            gamepad: 0,
+            reported: 0,
+        }
+    }
+
+    pub fn set_held(&mut self, source: Source, button: Button, state: ElementState) {
+        let held = match source {
+            Source::Keyboard => &mut self.keyboard,
+            Source::Gamepad => &mut self.gamepad,
+        };
+
+        match state {
+            ElementState::Pressed => *held |= button.mask(),
+            ElementState::Released => *held &= !button.mask(),
+        }
+    }
+
+    /// Buttons currently pressed, as a `joypad::button_mask`.
+    pub fn get_buttons(&self) -> u8 {
+        return self.keyboard | self.gamepad;
+    }
+
+    /// Buttons pressed or released since the last call.
+    pub fn changes(&mut self) -> Vec<(Button, ElementState)> {
+        let buttons = self.get_buttons();
+
+        let mut changes = Vec::new();
+        for button in Button::ALL {
+            let mask = button.mask();
+            if (buttons ^ self.reported) & mask == 0 {
+                continue;
+            }
+
+            match buttons & mask != 0 {
+                true => changes.push((button, ElementState::Pressed)),
+                false => changes.push((button, ElementState::Released)),
+            }
+        }
+
+        self.reported = buttons;
+        return changes;
+    }
+}
+
+/// Turbo buttons and macros, pressing buttons on top of the held ones. The emulator core
+/// ticks it at the start of each frame, where movie inputs are applied too, so that macros
+/// are frame exact and don't advance while paused.
+pub struct Sequencer {
+    // Turbo buttons held, and the frames elapsed since the first one was pressed.
+    turbo: u8,
+    turbo_rate: u32,
+    turbo_frame: u32,
+
+    macros: Vec<Playing>,
+}
+
+impl Sequencer {
+    pub fn new(turbo_rate: u32) -> Self {
+        Self {
+            turbo: 0,
+            turbo_rate: turbo_rate.clamp(MIN_TURBO_RATE, MAX_TURBO_RATE),
+            turbo_frame: 0,
+            macros: Vec::new(),
+        }
+    }
+
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::Turbo(button, state) => self.set_turbo(button, state),
+            Command::TurboRate(rate) => self.set_turbo_rate(rate),
+            Command::Macro(steps) => self.start_macro(&steps),
+        }
+    }
+
+    pub fn set_turbo_rate(&mut self, rate: u32) {
+        self.turbo_rate = rate.clamp(MIN_TURBO_RATE, MAX_TURBO_RATE);
+    }
+
+    /// Holds or releases the turbo variant of a button. Turbo buttons press on the frame
+    /// they are held, then alternate at the turbo rate.
+    pub fn set_turbo(&mut self, button: Button, state: ElementState) {
+        if self.turbo == 0 {
+            self.turbo_frame = 0;
+        }
+
+        match state {
+            ElementState::Pressed => self.turbo |= button.mask(),
+            ElementState::Released => self.turbo &= !button.mask(),
+        }
+    }
+
+    /// Starts playing the steps from the current frame, alongside any macro already playing.
+    pub fn start_macro(&mut self, steps: &[Step]) {
+        if steps.is_empty() {
+            return;
+        }
+
+        self.macros.push(Playing {
+            steps: steps.to_vec(),
+            step: 0,
+            frame: 0,
+        });
+    }
+
+    /// Advances the turbo buttons and macros by a frame.
+    pub fn tick(&mut self) {
+        if self.turbo != 0 {
+            self.turbo_frame += 1;
+        }
+
+        for playing in self.macros.iter_mut() {
+            playing.frame += 1;
+            if playing.frame >= playing.steps[playing.step].frames {
+                playing.step += 1;
+                playing.frame = 0;
+            }
+        }
+
+        self.macros
+            .retain(|playing| playing.step < playing.steps.len());
+    }
+
+    /// Buttons currently pressed, as a `joypad::button_mask`.
+    pub fn get_buttons(&self) -> u8 {
+        let mut buttons = 0;
+
+        // Pressed for the first half of each period, rounded up.
+        let period = (TICKS_PER_SECOND / self.turbo_rate).max(2);
+        if self.turbo_frame % period < period.div_ceil(2) {
+            buttons |= self.turbo;
+        }
+
+        for playing in self.macros.iter() {
+            buttons |= playing.steps[playing.step].buttons;
+        }
+
+        return buttons;
+    }
+}
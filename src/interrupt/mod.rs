@@ -6,6 +6,7 @@ pub struct Bus {
     enable_bits: u8,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Interrupt {
     VBlank,
     LcdStat,
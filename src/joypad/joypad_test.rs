@@ -0,0 +1,128 @@
+use glium::glutin::event::ElementState;
+
+use crate::joypad::{self, ActionButton, DirectionButton, Joypad};
+
+#[test]
+fn read() {
+    struct TestCase {
+        description: String,
+        select: u8,
+        directions: Vec<DirectionButton>,
+        actions: Vec<ActionButton>,
+        expected: u8,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("nothing selected"),
+            select: 0x30,
+            directions: vec![DirectionButton::Up],
+            actions: vec![ActionButton::A],
+            expected: 0xFF,
+        },
+        TestCase {
+            description: String::from("directions selected"),
+            select: 0x20,
+            directions: vec![DirectionButton::Up, DirectionButton::Right],
+            actions: vec![ActionButton::B],
+            expected: 0xEA,
+        },
+        TestCase {
+            description: String::from("actions selected"),
+            select: 0x10,
+            directions: vec![DirectionButton::Down],
+            actions: vec![ActionButton::Start, ActionButton::A],
+            expected: 0xD6,
+        },
+        TestCase {
+            description: String::from("both groups selected"),
+            select: 0x00,
+            directions: vec![DirectionButton::Left],
+            actions: vec![ActionButton::Select],
+            expected: 0xC9,
+        },
+        TestCase {
+            description: String::from("only the select bits are written"),
+            select: 0xEF,
+            directions: vec![],
+            actions: vec![],
+            expected: 0xEF,
+        },
+    ];
+
+    for tc in test_cases {
+        let mut joypad = Joypad::new();
+        for direction in tc.directions {
+            joypad.set_button(Some(direction), None, ElementState::Pressed);
+        }
+        for action in tc.actions {
+            joypad.set_button(None, Some(action), ElementState::Pressed);
+        }
+        joypad.write(tc.select);
+
+        assert_eq!(joypad.read(), tc.expected, "{}", tc.description);
+    }
+}
+
+#[test]
+fn simultaneous_presses() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x20);
+
+    joypad.set_button(Some(DirectionButton::Up), None, ElementState::Pressed);
+    joypad.set_button(Some(DirectionButton::Left), None, ElementState::Pressed);
+    assert_eq!(joypad.read() & 0x0F, 0x09);
+
+    joypad.set_button(Some(DirectionButton::Up), None, ElementState::Released);
+    assert_eq!(joypad.read() & 0x0F, 0x0D);
+
+    // Releasing a released button has no effect.
+    joypad.set_button(Some(DirectionButton::Down), None, ElementState::Released);
+    assert_eq!(joypad.read() & 0x0F, 0x0D);
+
+    // The state is kept while the group isn't selected.
+    joypad.write(0x30);
+    assert_eq!(joypad.read() & 0x0F, 0x0F);
+    joypad.write(0x20);
+    assert_eq!(joypad.read() & 0x0F, 0x0D);
+}
+
+#[test]
+fn interrupt() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x10);
+
+    // Only presses of a selected button pull a line low.
+    assert!(joypad.set_button(None, Some(ActionButton::A), ElementState::Pressed));
+    assert!(!joypad.set_button(None, Some(ActionButton::A), ElementState::Pressed));
+    assert!(!joypad.set_button(None, Some(ActionButton::A), ElementState::Released));
+    assert!(!joypad.set_button(Some(DirectionButton::Up), None, ElementState::Pressed));
+
+    // Selecting a group whose button is held pulls its line low.
+    assert!(joypad.write(0x00));
+
+    // Up and Select share a line, which is already low.
+    assert!(!joypad.set_button(None, Some(ActionButton::Select), ElementState::Pressed));
+
+    // Deselecting both groups only raises the lines.
+    assert!(!joypad.write(0x30));
+}
+
+#[test]
+fn button_mask() {
+    let mask = joypad::button_mask(Some(DirectionButton::Down), Some(ActionButton::B));
+    assert_eq!(mask, 0x28);
+    assert_eq!(joypad::button_mask(None, None), 0x00);
+
+    let mut joypad = Joypad::new();
+    joypad.write(0x20);
+    assert!(joypad.set_pressed(mask));
+    assert_eq!(joypad.read(), 0xE7);
+    joypad.write(0x10);
+    assert_eq!(joypad.read(), 0xDD);
+
+    // Setting the same buttons again doesn't pull any line low.
+    assert!(!joypad.set_pressed(mask));
+    assert!(!joypad.set_pressed(0x00));
+    assert_eq!(joypad.read(), 0xDF);
+}
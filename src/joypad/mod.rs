@@ -1,3 +1,16 @@
+//! The joypad's buttons and the P1 register through which the CPU reads them.
+#[path = "joypad_test.rs"]
+#[cfg(test)]
+mod test;
+
+use glium::glutin::event::ElementState;
+
+// Bits of P1 selecting which button group is read on the P10-P13 lines, active low.
+const SELECT_DIRECTIONS: u8 = 1 << 4;
+const SELECT_ACTIONS: u8 = 1 << 5;
+const SELECT_MASK: u8 = SELECT_DIRECTIONS | SELECT_ACTIONS;
+const LINES_MASK: u8 = 0x0F;
+
 #[derive(Clone, Copy, Debug)]
 pub enum DirectionButton {
     Right,
@@ -35,3 +48,175 @@ impl ActionButton {
         }
     }
 }
+
+/// One of the Gameboy's buttons.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Button {
+    A,
+    B,
+    Start,
+    Select,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Button {
+    pub const ALL: [Button; 8] = [
+        Button::A,
+        Button::B,
+        Button::Start,
+        Button::Select,
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Button::A => "A",
+            Button::B => "B",
+            Button::Start => "Start",
+            Button::Select => "Select",
+            Button::Up => "Up",
+            Button::Down => "Down",
+            Button::Left => "Left",
+            Button::Right => "Right",
+        }
+    }
+
+    /// The button as sent to the emulator with `Frontend::send_joypad_data`.
+    pub fn to_joypad(self) -> (Option<DirectionButton>, Option<ActionButton>) {
+        match self {
+            Button::A => (None, Some(ActionButton::A)),
+            Button::B => (None, Some(ActionButton::B)),
+            Button::Start => (None, Some(ActionButton::Start)),
+            Button::Select => (None, Some(ActionButton::Select)),
+            Button::Up => (Some(DirectionButton::Up), None),
+            Button::Down => (Some(DirectionButton::Down), None),
+            Button::Left => (Some(DirectionButton::Left), None),
+            Button::Right => (Some(DirectionButton::Right), None),
+        }
+    }
+
+    /// The button's bit in `button_mask`.
+    pub fn mask(self) -> u8 {
+        let (direction, action) = self.to_joypad();
+        return button_mask(direction, action);
+    }
+
+    pub fn index(&self) -> usize {
+        Button::ALL
+            .iter()
+            .position(|button| button == self)
+            .unwrap()
+    }
+}
+
+/// Bit mask of the given button, with the directions in the lower nibble and the actions
+/// in the upper nibble, in P1 order. Set bits are pressed buttons.
+pub fn button_mask(direction: Option<DirectionButton>, action: Option<ActionButton>) -> u8 {
+    let mut mask = 0;
+
+    if let Some(direction) = direction {
+        mask |= !direction.to_u8() & LINES_MASK;
+    }
+
+    if let Some(action) = action {
+        mask |= (!action.to_u8() & LINES_MASK) << 4;
+    }
+
+    return mask;
+}
+
+/// Pressed state of the eight buttons, and the button groups selected through P1.
+#[derive(Debug)]
+pub struct Joypad {
+    // Lower nibbles laid out like the P10-P13 lines, with a pressed button reading as 0.
+    directions: u8,
+    actions: u8,
+
+    select: u8,
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Self {
+            directions: LINES_MASK,
+            actions: LINES_MASK,
+            select: 0x00,
+        }
+    }
+
+    /// Value of the P1 register. The unused upper bits always read as 1.
+    pub fn read(&self) -> u8 {
+        return 0xC0 | self.select | self.lines();
+    }
+
+    /// Writes the select bits of P1. Returns whether a line went from high to low, which
+    /// requests the Joypad interrupt.
+    pub fn write(&mut self, val: u8) -> bool {
+        let lines = self.lines();
+        self.select = val & SELECT_MASK;
+        return self.has_falling_edge(lines);
+    }
+
+    /// Presses or releases a button. Returns whether a line went from high to low, which
+    /// requests the Joypad interrupt.
+    pub fn set_button(
+        &mut self,
+        direction: Option<DirectionButton>,
+        action: Option<ActionButton>,
+        state: ElementState,
+    ) -> bool {
+        let lines = self.lines();
+
+        let set = |buttons: &mut u8, mask: u8| match state {
+            ElementState::Pressed => *buttons &= mask,
+            ElementState::Released => *buttons |= !mask & LINES_MASK,
+        };
+
+        if let Some(direction) = direction {
+            set(&mut self.directions, direction.to_u8());
+        }
+
+        if let Some(action) = action {
+            set(&mut self.actions, action.to_u8());
+        }
+
+        return self.has_falling_edge(lines);
+    }
+
+    /// Sets the state of all buttons from a `button_mask`. Returns whether a line went from
+    /// high to low, which requests the Joypad interrupt.
+    pub fn set_pressed(&mut self, pressed: u8) -> bool {
+        let lines = self.lines();
+
+        self.directions = !pressed & LINES_MASK;
+        self.actions = !(pressed >> 4) & LINES_MASK;
+
+        return self.has_falling_edge(lines);
+    }
+
+    // Both groups are wired to the same lines, so a button of either selected group pulls
+    // its line low.
+    fn lines(&self) -> u8 {
+        let mut lines = LINES_MASK;
+
+        if self.select & SELECT_DIRECTIONS == 0 {
+            lines &= self.directions;
+        }
+
+        if self.select & SELECT_ACTIONS == 0 {
+            lines &= self.actions;
+        }
+
+        return lines;
+    }
+
+    fn has_falling_edge(&self, previous_lines: u8) -> bool {
+        return previous_lines & !self.lines() & LINES_MASK != 0;
+    }
+}
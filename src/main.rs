@@ -1,14 +1,30 @@
 mod cartridge;
+mod cheats;
+mod config;
 mod cpu;
+mod effects;
+mod filters;
 mod gameboy;
+mod gamepad;
+mod hex_editor;
+mod input;
 mod interrupt;
 mod joypad;
 mod memory;
+mod movie;
+mod palette;
 mod ppu;
+mod ram_search;
+mod recorder;
+mod registers;
 mod renderer;
+mod screenshot;
+mod symbols;
+mod tiles;
 mod timers;
 mod ui;
 
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use clap::Parser;
@@ -17,7 +33,7 @@ use gameboy::channel::front_end::Frontend;
 use glium::glutin::event::{Event, WindowEvent};
 use glium::glutin::event_loop::{ControlFlow, EventLoop};
 use glium::glutin::platform::unix::WindowBuilderExtUnix;
-use glium::glutin::window::Theme;
+use glium::glutin::window::{Fullscreen, Theme};
 use glium::Display;
 use glium::{glutin, Surface};
 
@@ -30,8 +46,34 @@ struct Args {
     #[arg(short, long)]
     rom_path: Option<String>,
 
-    #[arg(short, long, default_value_t = true)]
-    skip_boot_rom: bool,
+    /// Skips the boot ROM, `-s false` runs it. Defaults to the config file's setting.
+    #[arg(short, long, num_args = 0..=1, default_missing_value = "true")]
+    skip_boot_rom: Option<bool>,
+
+    /// Starts in fullscreen, `--fullscreen false` starts windowed.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    fullscreen: Option<bool>,
+
+    /// Scaling mode: integer, stretch or aspect_fit.
+    #[arg(long, value_parser = parse_scaling)]
+    scaling: Option<renderer::Scaling>,
+
+    /// Runs the ROM without a window and saves its last frame to this PNG file.
+    #[arg(long, requires = "rom_path")]
+    screenshot: Option<PathBuf>,
+
+    /// Number of frames to run before taking a headless screenshot.
+    #[arg(long, default_value_t = 60)]
+    screenshot_frames: u32,
+
+    /// Integer scale applied to headless screenshots.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    screenshot_scale: u32,
+
+    /// Plays an input movie back without a window, and exits with an error if its frames
+    /// don't match the recording.
+    #[arg(long, requires = "rom_path", conflicts_with = "screenshot")]
+    play_movie: Option<PathBuf>,
 }
 
 fn main() {
@@ -41,18 +83,39 @@ fn main() {
     log::info!("Starting RustyFuuGBemu");
     let args = Args::parse();
 
-    let (program_loop, display) = init_glium();
+    let config = config::Config::load();
+    let settings = config.with_overrides(&config::Overrides {
+        skip_boot_rom: args.skip_boot_rom,
+        fullscreen: args.fullscreen,
+        scaling: args.scaling,
+    });
+
+    if let Some(screenshot_path) = &args.screenshot {
+        let exit_code = take_headless_screenshot(&args, &settings, screenshot_path);
+        std::process::exit(exit_code);
+    }
+
+    if let Some(movie_path) = &args.play_movie {
+        let exit_code = play_headless_movie(&args, movie_path);
+        std::process::exit(exit_code);
+    }
+
+    let (program_loop, display) = init_glium(&settings.window);
     let egui_glium_client = egui_glium::EguiGlium::new(&display, &program_loop);
     let mut opengl_renderer = renderer::OpenGL::new(&display);
+    opengl_renderer.set_scaling(settings.window.scaling);
 
-    let (gameboy, memory_ref) = gameboy::Gameboy::new(args.skip_boot_rom);
+    let (gameboy, memory_ref, cpu_ref) = gameboy::Gameboy::new(settings.skip_boot_rom);
 
     let mut ui = ui::Ui::new(
         egui_glium_client,
         program_loop.create_proxy(),
-        args.skip_boot_rom,
         memory_ref,
+        cpu_ref,
+        config,
+        &settings,
     );
+    ui.restore_palettes(&settings, &mut opengl_renderer);
     let mut frontend = gameboy.start();
     let mut next_frame_time = Instant::now() + FRAME_INTERVAL;
 
@@ -65,6 +128,9 @@ fn main() {
                 ..
             } => match window_event {
                 WindowEvent::CloseRequested => {
+                    ui.stop_recording();
+                    ui.stop_movie(&frontend);
+                    ui.save_config();
                     handle_app_close(control_flow, &mut frontend);
                 }
 
@@ -72,14 +138,26 @@ fn main() {
             },
             Event::UserEvent(custom_event) => match custom_event {
                 ui::events::UiEvent::CloseWindow => {
+                    ui.stop_recording();
+                    ui.stop_movie(&frontend);
+                    ui.save_config();
                     handle_app_close(control_flow, &mut frontend);
                 }
+                ui::events::UiEvent::Screenshot(resolution) => {
+                    ui.take_screenshot(&display, &opengl_renderer, resolution);
+                }
             },
             Event::RedrawRequested(_) => {
                 let mut frame = display.draw();
                 frame.clear_color(1.0, 1.0, 1.0, 1.0);
-                opengl_renderer.render(&mut frame);
-                ui.draw(control_flow, &display, &mut frame, &mut frontend);
+                opengl_renderer.render(&mut frame, ui.top_inset());
+                ui.draw(
+                    control_flow,
+                    &display,
+                    &mut frame,
+                    &mut frontend,
+                    &mut opengl_renderer,
+                );
                 frame.finish().unwrap();
             }
             _ => {}
@@ -87,9 +165,13 @@ fn main() {
 
         if Instant::now() >= next_frame_time {
             next_frame_time = Instant::now() + FRAME_INTERVAL;
+            ui.poll_input(&frontend);
+            ui.poll_movie(&frontend);
+            ui.poll_breakpoints(&frontend);
             match frontend.should_render_screen() {
                 Some(frame_data) => {
-                    opengl_renderer.update_frame(&display, frame_data);
+                    opengl_renderer.update_frame(frame_data);
+                    ui.record_frame(&opengl_renderer);
                 }
                 _ => {}
             }
@@ -98,20 +180,105 @@ fn main() {
     });
 }
 
-fn init_glium() -> (EventLoop<ui::events::UiEvent>, Display) {
+fn parse_scaling(name: &str) -> Result<renderer::Scaling, String> {
+    return config::parse_scaling(name)
+        .ok_or_else(|| String::from("expected one of integer, stretch or aspect_fit"));
+}
+
+fn play_headless_movie(args: &Args, movie_path: &Path) -> i32 {
+    let rom_path = args.rom_path.as_ref().unwrap();
+    let rom_data = match std::fs::read(rom_path) {
+        Ok(rom_data) => rom_data,
+        Err(err) => {
+            log::error!("Failed to load ROM {}: {}", rom_path, err);
+            return 1;
+        }
+    };
+
+    let movie = match movie::Movie::load(movie_path) {
+        Ok(movie) => movie,
+        Err(err) => {
+            log::error!("Failed to load movie: {}", err);
+            return 1;
+        }
+    };
+
+    match movie::play_headless(rom_data, movie) {
+        Ok(playback) if playback.is_in_sync() => {
+            log::info!("Movie {}: {}", movie_path.display(), playback.describe());
+            return 0;
+        }
+        Ok(playback) => {
+            log::error!("Movie {}: {}", movie_path.display(), playback.describe());
+            return 1;
+        }
+        Err(err) => {
+            log::error!("Failed to play movie: {}", err);
+            return 1;
+        }
+    }
+}
+
+fn take_headless_screenshot(args: &Args, settings: &config::Config, screenshot_path: &Path) -> i32 {
+    let rom_path = args.rom_path.as_ref().unwrap();
+    let rom_data = match std::fs::read(rom_path) {
+        Ok(rom_data) => rom_data,
+        Err(err) => {
+            log::error!("Failed to load ROM {}: {}", rom_path, err);
+            return 1;
+        }
+    };
+
+    let frame =
+        screenshot::capture_headless(rom_data, settings.skip_boot_rom, args.screenshot_frames);
+    let palettes = settings
+        .palettes
+        .layer_palettes(&settings.palettes.load_palettes());
+    let image = filters::Image::from_frame(&frame, &palettes);
+    let scale = args.screenshot_scale as usize;
+    let image = image.resize_nearest(image.get_width() * scale, image.get_height() * scale);
+
+    match screenshot::save_png(&image, screenshot_path) {
+        Ok(_) => {
+            log::info!("Saved screenshot: {}", screenshot_path.display());
+            return 0;
+        }
+        Err(err) => {
+            log::error!("Failed to save screenshot: {}", err);
+            return 1;
+        }
+    }
+}
+
+fn init_glium(window_layout: &config::WindowLayout) -> (EventLoop<ui::events::UiEvent>, Display) {
     let events_loop =
         glium::glutin::event_loop::EventLoopBuilder::<ui::events::UiEvent>::with_user_event()
             .build();
 
-    let window_builder = glium::glutin::window::WindowBuilder::new()
-        .with_inner_size(glium::glutin::dpi::LogicalSize::new(
-            (ppu::NATIVE_SCREEN_WIDTH as i32) * ui::SCALE_FACTOR,
-            ((ppu::NATIVE_SCREEN_HEIGHT as i32) * ui::SCALE_FACTOR) + ui::TOP_MENUBAR_HEIGHT as i32,
-        ))
+    let mut window_builder = glium::glutin::window::WindowBuilder::new()
         .with_title("RustyFuuGBemu")
         .with_wayland_csd_theme(Theme::Dark)
         .with_resizable(true);
 
+    window_builder = match window_layout.size {
+        Some((width, height)) => {
+            window_builder.with_inner_size(glium::glutin::dpi::PhysicalSize::new(width, height))
+        }
+        None => window_builder.with_inner_size(glium::glutin::dpi::LogicalSize::new(
+            (ppu::NATIVE_SCREEN_WIDTH as i32) * ui::SCALE_FACTOR,
+            ((ppu::NATIVE_SCREEN_HEIGHT as i32) * ui::SCALE_FACTOR) + ui::TOP_MENUBAR_HEIGHT as i32,
+        )),
+    };
+
+    if let Some((x, y)) = window_layout.position {
+        window_builder =
+            window_builder.with_position(glium::glutin::dpi::PhysicalPosition::new(x, y));
+    }
+
+    if window_layout.fullscreen {
+        window_builder = window_builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
+
     let context = glium::glutin::ContextBuilder::new()
         .with_vsync(true)
         .with_hardware_acceleration(Some(true));
@@ -0,0 +1,57 @@
+//! Regions of the address space as the CPU sees it.
+#[path = "map_test.rs"]
+#[cfg(test)]
+mod test;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Region {
+    RomBank0,
+    /// Switchable ROM bank, mapped at 0x4000 ~ 0x7FFF.
+    RomBankN,
+    VideoRam,
+    CartridgeRam,
+    WorkRam,
+    EchoRam,
+    Oam,
+    Unusable,
+    Io,
+    HighRam,
+    InterruptEnable,
+}
+
+impl Region {
+    pub fn of(address: u16) -> Self {
+        return match address {
+            0x0000..=0x3FFF => Region::RomBank0,
+            0x4000..=0x7FFF => Region::RomBankN,
+            0x8000..=0x9FFF => Region::VideoRam,
+            0xA000..=0xBFFF => Region::CartridgeRam,
+            0xC000..=0xDFFF => Region::WorkRam,
+            0xE000..=0xFDFF => Region::EchoRam,
+            0xFE00..=0xFE9F => Region::Oam,
+            0xFEA0..=0xFEFF => Region::Unusable,
+            0xFF00..=0xFF7F => Region::Io,
+            0xFF80..=0xFFFE => Region::HighRam,
+            0xFFFF => Region::InterruptEnable,
+        };
+    }
+
+    /// Name of the region, given the ROM bank mapped at 0x4000 ~ 0x7FFF.
+    pub fn get_name(&self, rom_bank: usize) -> String {
+        let name = match self {
+            Region::RomBank0 => "ROM bank 0",
+            Region::RomBankN => return format!("ROM bank {}", rom_bank),
+            Region::VideoRam => "VRAM",
+            Region::CartridgeRam => "Cartridge RAM",
+            Region::WorkRam => "WRAM",
+            Region::EchoRam => "Echo RAM",
+            Region::Oam => "OAM",
+            Region::Unusable => "Unusable",
+            Region::Io => "IO",
+            Region::HighRam => "HRAM",
+            Region::InterruptEnable => "IE",
+        };
+
+        return String::from(name);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::memory::map::Region;
+
+#[test]
+fn regions() {
+    struct TestCase {
+        description: String,
+        address: u16,
+        expected: Region,
+        name: String,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("fixed ROM bank"),
+            address: 0x0150,
+            expected: Region::RomBank0,
+            name: String::from("ROM bank 0"),
+        },
+        TestCase {
+            description: String::from("switchable ROM bank"),
+            address: 0x4000,
+            expected: Region::RomBankN,
+            name: String::from("ROM bank 3"),
+        },
+        TestCase {
+            description: String::from("VRAM"),
+            address: 0x9800,
+            expected: Region::VideoRam,
+            name: String::from("VRAM"),
+        },
+        TestCase {
+            description: String::from("echo RAM"),
+            address: 0xE000,
+            expected: Region::EchoRam,
+            name: String::from("Echo RAM"),
+        },
+        TestCase {
+            description: String::from("unusable"),
+            address: 0xFEA0,
+            expected: Region::Unusable,
+            name: String::from("Unusable"),
+        },
+        TestCase {
+            description: String::from("IO registers"),
+            address: 0xFF40,
+            expected: Region::Io,
+            name: String::from("IO"),
+        },
+        TestCase {
+            description: String::from("end of HRAM"),
+            address: 0xFFFE,
+            expected: Region::HighRam,
+            name: String::from("HRAM"),
+        },
+        TestCase {
+            description: String::from("interrupt enable register"),
+            address: 0xFFFF,
+            expected: Region::InterruptEnable,
+            name: String::from("IE"),
+        },
+    ];
+
+    for tc in test_cases {
+        let region = Region::of(tc.address);
+        assert_eq!(region, tc.expected, "{}", tc.description);
+        assert_eq!(region.get_name(3), tc.name, "{}", tc.description);
+    }
+}
@@ -0,0 +1,271 @@
+use std::sync::{Arc, Mutex};
+
+use glium::glutin::event::ElementState;
+
+use crate::cartridge;
+use crate::cheats::RomPatch;
+use crate::interrupt;
+use crate::joypad::ActionButton;
+use crate::memory::{io_registers, Memory};
+use crate::timers;
+
+fn new_memory() -> Memory {
+    Memory::default(
+        Arc::new(Mutex::new(timers::Timers::new())),
+        Arc::new(Mutex::new(interrupt::Bus::new())),
+    )
+}
+
+#[test]
+fn oam_write() {
+    let mut memory = new_memory();
+
+    memory.write(0xFE00, 0x7F);
+    memory.write(0xFE9F, 0x6F);
+    assert_eq!(memory.read(0xFE00).unwrap(), 0x7F);
+    assert_eq!(memory.read(0xFE9F).unwrap(), 0x6F);
+
+    // The area right after OAM is unusable.
+    memory.write(0xFEA0, 0x7F);
+    assert_eq!(memory.read(0xFEA0).unwrap(), 0xFF);
+}
+
+#[test]
+fn ppu_access_restrictions() {
+    struct TestCase {
+        description: String,
+        mode: u8,
+        restrictions_enabled: bool,
+        vram_accessible: bool,
+        oam_accessible: bool,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("h-blank allows VRAM and OAM access"),
+            mode: 0,
+            restrictions_enabled: true,
+            vram_accessible: true,
+            oam_accessible: true,
+        },
+        TestCase {
+            description: String::from("v-blank allows VRAM and OAM access"),
+            mode: 1,
+            restrictions_enabled: true,
+            vram_accessible: true,
+            oam_accessible: true,
+        },
+        TestCase {
+            description: String::from("OAM search blocks OAM access only"),
+            mode: 2,
+            restrictions_enabled: true,
+            vram_accessible: true,
+            oam_accessible: false,
+        },
+        TestCase {
+            description: String::from("pixel transfer blocks VRAM and OAM access"),
+            mode: 3,
+            restrictions_enabled: true,
+            vram_accessible: false,
+            oam_accessible: false,
+        },
+        TestCase {
+            description: String::from("disabled restrictions allow access during pixel transfer"),
+            mode: 3,
+            restrictions_enabled: false,
+            vram_accessible: true,
+            oam_accessible: true,
+        },
+    ];
+
+    for tc in test_cases {
+        let mut memory = new_memory();
+        memory.write(0x8000, 0x11);
+        memory.write(0xFE00, 0x22);
+
+        memory.set_ppu_access_restrictions(tc.restrictions_enabled);
+        memory.set_stat(tc.mode);
+        memory.write(0x8001, 0x33);
+        memory.write(0xFE01, 0x44);
+
+        let expected_vram = match tc.vram_accessible {
+            true => (0x11, 0x33),
+            false => (0xFF, 0xFF),
+        };
+        let expected_oam = match tc.oam_accessible {
+            true => (0x22, 0x44),
+            false => (0xFF, 0xFF),
+        };
+
+        assert_eq!(
+            (memory.read(0x8000).unwrap(), memory.read(0x8001).unwrap()),
+            expected_vram,
+            "{}",
+            tc.description
+        );
+        assert_eq!(
+            (memory.read(0xFE00).unwrap(), memory.read(0xFE01).unwrap()),
+            expected_oam,
+            "{}",
+            tc.description
+        );
+
+        // Blocked writes are dropped, which the unrestricted DMA reads can confirm.
+        let expected_vram_write = match tc.vram_accessible {
+            true => 0x33,
+            false => 0x00,
+        };
+        let expected_oam_write = match tc.oam_accessible {
+            true => 0x44,
+            false => 0x00,
+        };
+        assert_eq!(
+            memory.dma_read(0x8001).unwrap(),
+            expected_vram_write,
+            "{}",
+            tc.description
+        );
+        assert_eq!(
+            memory.dma_read(0xFE01).unwrap(),
+            expected_oam_write,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn stat_write() {
+    let mut memory = new_memory();
+    memory.write(0x8000, 0x11);
+    memory.set_stat(0x07);
+
+    // Writes only change the interrupt enable bits, so the PPU keeps VRAM locked.
+    memory.write(io_registers::LCD_STAT_ADDR, 0x78);
+    assert_eq!(memory.read(io_registers::LCD_STAT_ADDR).unwrap(), 0x7F);
+    memory.write(io_registers::LCD_STAT_ADDR, 0x00);
+    assert_eq!(memory.read(io_registers::LCD_STAT_ADDR).unwrap(), 0x07);
+    memory.poke(io_registers::LCD_STAT_ADDR, 0x00);
+    assert_eq!(memory.read(io_registers::LCD_STAT_ADDR).unwrap(), 0x07);
+    assert_eq!(memory.read(0x8000).unwrap(), 0xFF);
+
+    memory.set_stat(0x40);
+    assert_eq!(memory.read(io_registers::LCD_STAT_ADDR).unwrap(), 0x40);
+    assert_eq!(memory.read(0x8000).unwrap(), 0x11);
+}
+
+#[test]
+fn joypad() {
+    let interrupt_bus = Arc::new(Mutex::new(interrupt::Bus::new()));
+    let mut memory = Memory::default(
+        Arc::new(Mutex::new(timers::Timers::new())),
+        interrupt_bus.clone(),
+    );
+    let interrupt_requested = || -> bool {
+        let flags = interrupt_bus
+            .lock()
+            .unwrap()
+            .read(io_registers::INTERRUPT_FLAG_REGISTER_ADDR);
+        return flags & (1 << 4) != 0;
+    };
+
+    memory.write(io_registers::JOYPAD_ADDR, 0x10);
+    memory.set_joypad_button(None, Some(ActionButton::B), ElementState::Pressed);
+    memory.set_joypad_button(None, Some(ActionButton::Start), ElementState::Pressed);
+    assert_eq!(memory.read(io_registers::JOYPAD_ADDR).unwrap(), 0xD5);
+    assert_eq!(memory.dma_read(io_registers::JOYPAD_ADDR).unwrap(), 0xD5);
+    assert!(interrupt_requested());
+
+    interrupt_bus
+        .lock()
+        .unwrap()
+        .clear_interrupt(interrupt::Interrupt::Joypad);
+
+    // Releases don't request the interrupt.
+    memory.set_joypad_button(None, Some(ActionButton::B), ElementState::Released);
+    assert_eq!(memory.read(io_registers::JOYPAD_ADDR).unwrap(), 0xD7);
+    assert!(!interrupt_requested());
+}
+
+#[test]
+fn rom_patches() {
+    let mut rom_data = vec![0x00; 0x8000];
+    rom_data[0x0150] = 0x3D;
+    rom_data[0x4000] = 0x05;
+
+    let mut memory = Memory::new(
+        cartridge::new(rom_data),
+        Arc::new(Mutex::new(timers::Timers::new())),
+        Arc::new(Mutex::new(interrupt::Bus::new())),
+    );
+    memory.set_rom_patches(vec![
+        RomPatch {
+            address: 0x0150,
+            value: 0x00,
+            compare: None,
+        },
+        RomPatch {
+            address: 0x4000,
+            value: 0x09,
+            compare: Some(0x3D),
+        },
+    ]);
+
+    assert_eq!(memory.read(0x0150).unwrap(), 0x00);
+    assert_eq!(memory.dma_read(0x0150).unwrap(), 0x00);
+    assert_eq!(memory.read(0x0151).unwrap(), 0x00);
+
+    // The mapped bank doesn't hold the compare value.
+    assert_eq!(memory.read(0x4000).unwrap(), 0x05);
+
+    memory.set_rom_patches(Vec::new());
+    assert_eq!(memory.read(0x0150).unwrap(), 0x3D);
+}
+
+#[test]
+fn poke() {
+    let mut memory = new_memory();
+
+    // Pokes go through while the PPU locks the CPU out of VRAM and OAM.
+    memory.set_stat(3);
+    memory.poke(0x8010, 0x12);
+    memory.poke(0xFE10, 0x34);
+    assert_eq!(memory.dma_read(0x8010).unwrap(), 0x12);
+    assert_eq!(memory.dma_read(0xFE10).unwrap(), 0x34);
+
+    memory.poke(0xC123, 0x56);
+    memory.poke(0xF000, 0x78);
+    memory.poke(0xFF90, 0x9A);
+    assert_eq!(memory.read(0xC123).unwrap(), 0x56);
+    assert_eq!(memory.read(0xD000).unwrap(), 0x78);
+    assert_eq!(memory.read(0xFF90).unwrap(), 0x9A);
+}
+
+#[test]
+fn oam_dma_register() {
+    let mut memory = new_memory();
+
+    // Poking the register sets it without starting a transfer, whatever the source.
+    memory.poke(io_registers::OAM_DMA_TRANSFER_ADDR, 0xFF);
+    assert_eq!(
+        memory
+            .dma_read(io_registers::OAM_DMA_TRANSFER_ADDR)
+            .unwrap(),
+        0xFF
+    );
+    memory.poke(0xC123, 0x56);
+    memory.poke(io_registers::OAM_DMA_TRANSFER_ADDR, 0xC1);
+    assert_eq!(memory.read(0xC123).unwrap(), 0x56);
+
+    // Writes from sources past 0xF1 are ignored.
+    memory.write(io_registers::OAM_DMA_TRANSFER_ADDR, 0xFE);
+    assert_eq!(memory.read(0xC123).unwrap(), 0x56);
+
+    memory.write(io_registers::OAM_DMA_TRANSFER_ADDR, 0xC1);
+    assert_eq!(memory.read(0xC123).unwrap(), 0xFF);
+    for _ in 0..40 {
+        memory.step_dma();
+    }
+    assert_eq!(memory.read(0xC123).unwrap(), 0x56);
+    assert_eq!(memory.read(0xFE23).unwrap(), 0x56);
+}
@@ -1,13 +1,23 @@
+#[path = "memory_test.rs"]
+#[cfg(test)]
+mod test;
+
+pub mod map;
+
 use glium::glutin::event::ElementState;
-use queues::{queue, IsQueue, Queue};
 
-use crate::joypad::{ActionButton, DirectionButton};
+use crate::cheats;
+use crate::joypad::{ActionButton, DirectionButton, Joypad};
+use crate::ppu::stat::StatMode;
 use crate::{cartridge, interrupt, timers};
 use std::sync;
 use std::{fmt::Debug, sync::Arc};
 
 const OAM_TRANSFER_CYCLES: u32 = 160;
 
+/// STAT bits holding the PPU mode and the LY=LYC flag.
+const STAT_READ_ONLY_MASK: u8 = 0x07;
+
 /// Struct emulating the DMG Gameboy's memory behaviour.
 /// This struct controls the access behaviour whenever the CPU
 /// makes reads or writes to the memory.
@@ -17,6 +27,9 @@ pub struct Memory {
     /// Mapped into memory locations 0x0000 - 0x7FFF.
     cartridge: Box<dyn cartridge::Interface>,
 
+    /// Game Genie patches applied to the bytes read from the cartridge ROM.
+    rom_patches: Vec<cheats::RomPatch>,
+
     /// Video RAM where tile data is located.
     /// Occupies memory locations 0x8000 ~ 0x9FFF.
     video_ram: [u8; 0x2000],
@@ -57,11 +70,12 @@ pub struct Memory {
 
     interrupt_bus_ref: Arc<sync::Mutex<interrupt::Bus>>,
 
-    joypad_dir_queue: Queue<(DirectionButton, ElementState)>,
-    joypad_action_queue: Queue<(ActionButton, ElementState)>,
+    joypad: Joypad,
 
-    joypad_direction_buffer: u8,
-    joypad_action_buffer: u8,
+    /// Whether the CPU is locked out of VRAM during pixel transfer, and out of OAM
+    /// during OAM search and pixel transfer, like on real hardware.
+    /// Can be turned off to debug homebrew that does not respect the PPU timings.
+    ppu_access_restrictions: bool,
 }
 
 /// Module containing important addresses for
@@ -105,6 +119,8 @@ pub mod io_registers {
     pub const LCD_LYC_ADDR: usize = 0xFF45;
     pub const OAM_DMA_TRANSFER_ADDR: usize = 0xFF46;
     pub const LCD_PALETTE_ADDR: usize = 0xFF47;
+    pub const LCD_OBJ_PALETTE0_ADDR: usize = 0xFF48;
+    pub const LCD_OBJ_PALETTE1_ADDR: usize = 0xFF49;
     pub const LCD_WINY_ADDR: usize = 0xFF4A;
     pub const LCD_WINX_ADDR: usize = 0xFF4B;
     pub const BOOT_ROM_DISABLE_ADDR: usize = 0xFF50;
@@ -140,6 +156,7 @@ impl Memory {
     ) -> Self {
         Self {
             cartridge,
+            rom_patches: Vec::new(),
             video_ram: [0x00; 0x2000],
             work_ram0: [0x00; 0x1000],
             work_ram1: [0x00; 0x1000],
@@ -152,10 +169,8 @@ impl Memory {
             oam_hi_byte: 0,
             timer_ref,
             interrupt_bus_ref,
-            joypad_dir_queue: queue![],
-            joypad_action_queue: queue![],
-            joypad_action_buffer: 0x0F,
-            joypad_direction_buffer: 0x0F,
+            joypad: Joypad::new(),
+            ppu_access_restrictions: true,
         }
     }
 
@@ -165,6 +180,7 @@ impl Memory {
     ) -> Self {
         Self {
             cartridge: cartridge::default(),
+            rom_patches: Vec::new(),
             video_ram: [0x00; 0x2000],
             work_ram0: [0x00; 0x1000],
             work_ram1: [0x00; 0x1000],
@@ -177,10 +193,8 @@ impl Memory {
             oam_hi_byte: 0,
             timer_ref,
             interrupt_bus_ref,
-            joypad_dir_queue: queue![],
-            joypad_action_queue: queue![],
-            joypad_action_buffer: 0x0F,
-            joypad_direction_buffer: 0x0F,
+            joypad: Joypad::new(),
+            ppu_access_restrictions: true,
         }
     }
 
@@ -192,6 +206,31 @@ impl Memory {
         return &self.io_registers;
     }
 
+    pub fn set_ppu_access_restrictions(&mut self, enabled: bool) {
+        self.ppu_access_restrictions = enabled;
+    }
+
+    /// Sets the STAT register, including the mode and LY=LYC bits that CPU writes leave alone.
+    pub fn set_stat(&mut self, val: u8) {
+        self.io_registers[io_registers::LCD_STAT_ADDR - 0xFF00] = val;
+    }
+
+    fn ppu_mode(&self) -> StatMode {
+        return StatMode::from_stat(self.io_registers[io_registers::LCD_STAT_ADDR - 0xFF00]);
+    }
+
+    fn vram_accessible(&self) -> bool {
+        return !self.ppu_access_restrictions || self.ppu_mode() != StatMode::PixelTransfer;
+    }
+
+    fn oam_accessible(&self) -> bool {
+        return !self.ppu_access_restrictions
+            || !matches!(
+                self.ppu_mode(),
+                StatMode::OAMSearch | StatMode::PixelTransfer
+            );
+    }
+
     fn boot_rom_enabled(&self) -> bool {
         return self.io_registers[io_registers::BOOT_ROM_DISABLE_ADDR - 0xFF00] == 0x00;
     }
@@ -224,13 +263,16 @@ impl Memory {
             }
 
             io_registers::OAM_DMA_TRANSFER_ADDR => {
-                log::trace!("OAM DMA transfer initiated");
-                self.oam_dma_transfer_in_progress = true;
+                self.io_registers[addr - 0xFF00] = val;
 
+                // Sources past 0xF1 would overlap OAM and the IO registers.
                 if val > 0xF1 {
-                    panic!("Invalid DMA transfer source address");
+                    log::warn!("Ignoring OAM DMA transfer from {:02X}00", val);
+                    return;
                 }
 
+                log::trace!("OAM DMA transfer initiated");
+                self.oam_dma_transfer_in_progress = true;
                 self.oam_hi_byte = val;
             }
             io_registers::TIMER_MOD_ADDR
@@ -246,104 +288,48 @@ impl Memory {
             io_registers::BOOT_ROM_DISABLE_ADDR => {
                 self.io_registers[addr - 0xFF00] = val;
             }
+
+            // The mode and LY=LYC bits are only set by the PPU, which locks VRAM and OAM
+            // based on the mode.
+            io_registers::LCD_STAT_ADDR => {
+                let stat = self.io_registers[addr - 0xFF00];
+                self.io_registers[addr - 0xFF00] =
+                    (stat & STAT_READ_ONLY_MASK) | (val & !STAT_READ_ONLY_MASK);
+            }
             _ => self.io_registers[addr - 0xFF00] = val,
         }
     }
 
-    pub fn write_joypad_queue(
+    /// Presses or releases one of the joypad's buttons.
+    pub fn set_joypad_button(
         &mut self,
-        direction_press: Option<DirectionButton>,
-        action_press: Option<ActionButton>,
-        input_state: ElementState,
+        direction: Option<DirectionButton>,
+        action: Option<ActionButton>,
+        state: ElementState,
     ) {
-        if direction_press.is_some() {
-            match self
-                .joypad_dir_queue
-                .add((direction_press.unwrap(), input_state))
-            {
-                Ok(_) => {}
-                Err(err) => panic!(
-                    "error occurred queuing direction press from joypad: {:?}",
-                    err
-                ),
-            }
-
-            self.interrupt_bus_ref
-                .lock()
-                .unwrap()
-                .request(interrupt::Interrupt::Joypad);
-
-            return;
+        if self.joypad.set_button(direction, action, state) {
+            self.request_joypad_interrupt();
         }
+    }
 
-        if action_press.is_some() {
-            match self
-                .joypad_action_queue
-                .add((action_press.unwrap(), input_state))
-            {
-                Ok(_) => {}
-                Err(err) => panic!("error occurred queuing action press from joypad: {:?}", err),
-            }
-
-            self.interrupt_bus_ref
-                .lock()
-                .unwrap()
-                .request(interrupt::Interrupt::Joypad);
-
-            return;
+    /// Sets the state of all of the joypad's buttons, as a `joypad::button_mask`.
+    pub fn set_joypad_pressed(&mut self, pressed: u8) {
+        if self.joypad.set_pressed(pressed) {
+            self.request_joypad_interrupt();
         }
     }
 
     fn handle_joypad_write(&mut self, val: u8) {
-        let action_read = val & (1 << 5) == 0;
-        let direction_read = val & (1 << 4) == 0;
-
-        let hi_nibble: u8 = (val | 0xC0) & 0xF0;
-        let mut lo_nibble: u8 = 0x0F;
-
-        if action_read {
-            match self.joypad_action_queue.remove() {
-                Ok((button, input_state)) => {
-                    if input_state == ElementState::Pressed {
-                        lo_nibble &= button.to_u8();
-                    } else {
-                        lo_nibble |= !button.to_u8();
-                    }
-
-                    self.joypad_action_buffer = lo_nibble & 0x0F;
-
-                    log::trace!(
-                        "action press: {:b}, state: {:?}",
-                        hi_nibble | self.joypad_action_buffer,
-                        input_state
-                    );
-                }
-                Err(_) => {}
-            };
-        }
-
-        if direction_read {
-            match self.joypad_dir_queue.remove() {
-                Ok((button, input_state)) => {
-                    if input_state == ElementState::Pressed {
-                        lo_nibble &= button.to_u8();
-                    } else {
-                        lo_nibble |= !button.to_u8();
-                    }
-
-                    self.joypad_direction_buffer = lo_nibble & 0x0F;
-
-                    log::trace!(
-                        "direction press: {:b}, state: {:?}",
-                        hi_nibble | self.joypad_direction_buffer,
-                        input_state
-                    );
-                }
-                Err(_) => {}
-            };
+        if self.joypad.write(val) {
+            self.request_joypad_interrupt();
         }
+    }
 
-        self.io_registers[io_registers::JOYPAD_ADDR - 0xFF00] = hi_nibble & 0xF0;
+    fn request_joypad_interrupt(&mut self) {
+        self.interrupt_bus_ref
+            .lock()
+            .unwrap()
+            .request(interrupt::Interrupt::Joypad);
     }
 
     pub fn set_post_boot_rom_state(&mut self) {
@@ -352,7 +338,7 @@ impl Memory {
         // Disable boot rom
         self.io_registers[io_registers::BOOT_ROM_DISABLE_ADDR - offset] = 0x01;
 
-        self.io_registers[io_registers::JOYPAD_ADDR - offset] = 0xCF;
+        self.joypad.write(0xCF);
         self.io_registers[io_registers::SERIAL_TRANSFER_DATA_ADDR - offset] = 0x00;
         self.io_registers[io_registers::SERIAL_TRANSFER_CONTROL_ADDR - offset] = 0x7E;
         self.io_registers[io_registers::TIMER_DIV_ADDR - offset] = 0xAB;
@@ -414,7 +400,7 @@ impl Memory {
 
         // Cartridge ROM
         if addr < 0x8000 {
-            return self.cartridge.read(addr);
+            return self.read_rom(addr);
         }
 
         // Video RAM
@@ -462,6 +448,7 @@ impl Memory {
                 io_registers::INTERRUPT_FLAG_REGISTER_ADDR => {
                     Some(self.interrupt_bus_ref.lock().unwrap().read(addr))
                 }
+                io_registers::JOYPAD_ADDR => self.handle_joypad_read(),
                 _ => Some(self.io_registers[addr - 0xFF00]),
             };
         }
@@ -497,11 +484,16 @@ impl Memory {
 
         // Cartridge ROM
         if addr < 0x8000 {
-            return self.cartridge.read(addr);
+            return self.read_rom(addr);
         }
 
         // Video RAM
+        // Inaccessible to the CPU while the PPU is transferring pixels.
         if addr >= 0x8000 && addr < 0xA000 {
+            if !self.vram_accessible() {
+                return Some(0xFF);
+            }
+
             return Some(self.video_ram[addr - 0x8000]);
         }
 
@@ -526,7 +518,12 @@ impl Memory {
         }
 
         // OAM / Sprite attributes
+        // Inaccessible to the CPU during OAM search and pixel transfer.
         if addr >= 0xFE00 && addr < 0xFEA0 {
+            if !self.oam_accessible() {
+                return Some(0xFF);
+            }
+
             return Some(self.oam[addr - 0xFE00]);
         }
 
@@ -579,7 +576,8 @@ impl Memory {
         }
 
         // Video RAM
-        if addr >= 0x8000 && addr < 0xA000 {
+        // Writes are ignored while the PPU is transferring pixels.
+        if addr >= 0x8000 && addr < 0xA000 && self.vram_accessible() {
             self.video_ram[addr - 0x8000] = val;
         }
 
@@ -604,9 +602,9 @@ impl Memory {
         }
 
         // OAM / Sprite attributes
-        // Not writable directly, needs to be performed via a OAM DMA transfer.
-        if addr >= 0xFE00 && addr < 0xFF00 {
-            //return;
+        // Writes are ignored during OAM search and pixel transfer.
+        if addr >= 0xFE00 && addr < 0xFEA0 && self.oam_accessible() {
+            self.oam[addr - 0xFE00] = val;
         }
 
         // IO Registers
@@ -625,27 +623,52 @@ impl Memory {
         }
     }
 
+    /// Writes to RAM or the IO registers regardless of OAM DMA transfers and of the PPU
+    /// locking VRAM and OAM, for cheats and debugging tools. ROM is left untouched, since
+    /// writes there control the cartridge's bank switching, and writes to the OAM DMA
+    /// register only set it rather than starting a transfer.
+    pub fn poke(&mut self, addr: usize, val: u8) {
+        match addr {
+            io_registers::OAM_DMA_TRANSFER_ADDR => self.io_registers[addr - 0xFF00] = val,
+            0x0000..=0x7FFF => {}
+            0x8000..=0x9FFF => self.video_ram[addr - 0x8000] = val,
+            0xA000..=0xBFFF => self.cartridge.write(addr, val),
+            0xC000..=0xCFFF => self.work_ram0[addr - 0xC000] = val,
+            0xD000..=0xDFFF => self.work_ram1[addr - 0xD000] = val,
+            0xE000..=0xFDFF => self.poke((addr - 0xE000) + 0xC000, val),
+            0xFE00..=0xFE9F => self.oam[addr - 0xFE00] = val,
+            0xFEA0..=0xFEFF => {}
+            0xFF00..=0xFF7F => self.write_io_registers(addr, val),
+            0xFF80..=0xFFFE => self.hi_ram[addr - 0xFF80] = val,
+            _ => self.interrupt_bus_ref.lock().unwrap().write(addr, val),
+        }
+    }
+
+    pub fn set_rom_patches(&mut self, patches: Vec<cheats::RomPatch>) {
+        self.rom_patches = patches;
+    }
+
+    /// ROM bank the cartridge maps at 0x4000 ~ 0x7FFF.
+    pub fn get_rom_bank(&self) -> usize {
+        return self.cartridge.get_rom_bank();
+    }
+
+    fn read_rom(&self, addr: usize) -> Option<u8> {
+        let val = self.cartridge.read(addr)?;
+        return Some(cheats::patch_rom(&self.rom_patches, addr, val));
+    }
+
     pub fn reset(&mut self, cartridge: Box<dyn cartridge::Interface>) {
+        let ppu_access_restrictions = self.ppu_access_restrictions;
         *self = Memory::new(
             cartridge,
             self.timer_ref.clone(),
             self.interrupt_bus_ref.clone(),
         );
+        self.ppu_access_restrictions = ppu_access_restrictions;
     }
 
     fn handle_joypad_read(&self) -> Option<u8> {
-        let hi_nibble = self.io_registers[io_registers::JOYPAD_ADDR - 0xFF00] & 0xF0;
-        let action_read = hi_nibble & (1 << 5) == 0;
-        let direction_read = hi_nibble & (1 << 4) == 0;
-
-        if action_read {
-            return Some(hi_nibble | self.joypad_action_buffer);
-        }
-
-        if direction_read {
-            return Some(hi_nibble | self.joypad_direction_buffer);
-        }
-
-        Some(hi_nibble | 0x0F)
+        Some(self.joypad.read())
     }
 }
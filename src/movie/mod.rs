@@ -0,0 +1,442 @@
+//! Input movies: the joypad state of every frame since the ROM was powered on, along with
+//! a hash of each frame the inputs produced. Playing a movie back feeds the same inputs
+//! into the emulator at the same frames, and reports the first frame whose output differs.
+//!
+//! Movies are plain text so that they can be attached to bug reports and diffed, e.g.
+//!
+//! ```text
+//! rustyfuugbemu-movie 1
+//! version 0.1.3
+//! rom_hash 9f1c0a6e2b5d4387
+//! skip_boot_rom true
+//! renderer pixel_fifo
+//! ppu_access_restrictions true
+//! cheat 3E0-F5D-4C1
+//!
+//! ........ 3c5a7e01d2b4f698
+//! R...A... 7d0e9b3c1a2f4856
+//! ```
+//!
+//! Cheats enabled when recording starts are part of the movie, since they change what the
+//! game does; a `cheat` line holds each of their codes. So are the PPU renderer and the
+//! VRAM/OAM access restrictions, which change the PPU timings and what the CPU reads.
+#[path = "movie_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cheats;
+use crate::gameboy;
+use crate::ppu;
+
+const HEADER: &str = "rustyfuugbemu-movie 1";
+pub const FILE_EXTENSION: &str = "gbm";
+
+/// Version of the emulator recording the movie. Playback of movies recorded by another
+/// version is attempted, but more likely to desync.
+pub const EMULATOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Characters standing for each pressed button in a frame line, in the bit order of
+/// `joypad::button_mask`: Right, Left, Up, Down, A, B, Select, Start.
+const BUTTON_CHARS: [char; 8] = ['R', 'L', 'U', 'D', 'A', 'B', 's', 'S'];
+const RELEASED_CHAR: char = '.';
+
+/// Directory movies recorded from the frontend are saved to.
+pub fn directory() -> PathBuf {
+    return PathBuf::from("movies");
+}
+
+/// 64-bit FNV-1a hash. Only used to detect changes, it is neither cryptographic nor
+/// collision resistant.
+pub fn hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01B3);
+    }
+
+    return hash;
+}
+
+/// Hash of the shades and layers of a frame's pixels.
+pub fn frame_hash(frame: &ppu::Frame) -> u64 {
+    let mut bytes = Vec::with_capacity(ppu::NATIVE_SCREEN_WIDTH * ppu::NATIVE_SCREEN_HEIGHT);
+    for (pixels, layers) in frame.pixels.iter().zip(frame.layers.iter()) {
+        for (pixel, layer) in pixels.iter().zip(layers.iter()) {
+            bytes.push((*pixel as u8) | ((*layer as u8) << 2));
+        }
+    }
+
+    return hash(&bytes);
+}
+
+/// Buttons held during a frame, and the hash of the frame they produced.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub buttons: u8,
+    pub hash: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Movie {
+    pub emulator_version: String,
+    pub rom_hash: u64,
+    pub skip_boot_rom: bool,
+    pub renderer: ppu::Renderer,
+    pub ppu_access_restrictions: bool,
+
+    /// Codes of the cheats enabled during the whole movie.
+    pub cheats: Vec<cheats::Code>,
+    pub frames: Vec<Frame>,
+}
+
+impl Movie {
+    /// An empty movie for the given ROM, recorded by this version of the emulator.
+    pub fn new(
+        rom_data: &[u8],
+        skip_boot_rom: bool,
+        renderer: ppu::Renderer,
+        ppu_access_restrictions: bool,
+        cheats: Vec<cheats::Code>,
+    ) -> Self {
+        Self {
+            emulator_version: EMULATOR_VERSION.to_string(),
+            rom_hash: hash(rom_data),
+            skip_boot_rom,
+            renderer,
+            ppu_access_restrictions,
+            cheats,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Checks that the movie was recorded with the given ROM.
+    pub fn check_rom(&self, rom_data: &[u8]) -> Result<(), String> {
+        let rom_hash = hash(rom_data);
+        if rom_hash != self.rom_hash {
+            return Err(format!(
+                "movie was recorded with another ROM (hash {:016x}, loaded ROM is {:016x})",
+                self.rom_hash, rom_hash
+            ));
+        }
+
+        return Ok(());
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut lines = contents.lines().enumerate();
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(String::from("not a movie file")),
+        }
+
+        let mut emulator_version = None;
+        let mut rom_hash = None;
+        let mut skip_boot_rom = None;
+        let mut renderer = None;
+        let mut ppu_access_restrictions = None;
+        let mut codes = Vec::new();
+
+        // Header fields, up to the blank line before the frames.
+        for (index, line) in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+
+            let error = || format!("line {}: invalid header '{}'", index + 1, line);
+            let (key, value) = line.split_once(' ').ok_or_else(error)?;
+            match key {
+                "version" => emulator_version = Some(value.to_string()),
+                "rom_hash" => rom_hash = Some(u64::from_str_radix(value, 16).map_err(|_| error())?),
+                "skip_boot_rom" => skip_boot_rom = Some(value.parse().map_err(|_| error())?),
+                "renderer" => renderer = Some(parse_renderer(value).ok_or_else(error)?),
+                "ppu_access_restrictions" => {
+                    ppu_access_restrictions = Some(value.parse().map_err(|_| error())?)
+                }
+                "cheat" => codes.push(
+                    cheats::parse_code(value)
+                        .map_err(|err| format!("line {}: {}", index + 1, err))?,
+                ),
+                _ => {}
+            }
+        }
+
+        let mut movie = Movie {
+            emulator_version: emulator_version.ok_or("missing 'version'")?,
+            rom_hash: rom_hash.ok_or("missing 'rom_hash'")?,
+            skip_boot_rom: skip_boot_rom.ok_or("missing 'skip_boot_rom'")?,
+
+            // Movies recorded before these were part of the header ran with the defaults.
+            renderer: renderer.unwrap_or(ppu::Renderer::PixelFifo),
+            ppu_access_restrictions: ppu_access_restrictions.unwrap_or(true),
+            cheats: codes,
+            frames: Vec::new(),
+        };
+
+        for (index, line) in lines {
+            let frame = parse_frame(line)
+                .ok_or_else(|| format!("line {}: invalid frame '{}'", index + 1, line))?;
+            movie.frames.push(frame);
+        }
+
+        return Ok(movie);
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\nversion {}\nrom_hash {:016x}\nskip_boot_rom {}\n",
+            HEADER, self.emulator_version, self.rom_hash, self.skip_boot_rom
+        );
+        text.push_str(&format!(
+            "renderer {}\nppu_access_restrictions {}\n",
+            renderer_name(self.renderer),
+            self.ppu_access_restrictions
+        ));
+        for code in self.cheats.iter() {
+            text.push_str(&format!("cheat {}\n", cheats::code_text(code)));
+        }
+        text.push('\n');
+
+        for frame in self.frames.iter() {
+            for (bit, button) in BUTTON_CHARS.iter().enumerate() {
+                text.push(match frame.buttons & (1 << bit) != 0 {
+                    true => *button,
+                    false => RELEASED_CHAR,
+                });
+            }
+            text.push_str(&format!(" {:016x}\n", frame.hash));
+        }
+
+        return text;
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+
+        return Movie::parse(&contents).map_err(|err| format!("{}: {}", path.display(), err));
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("could not create {}: {}", parent.display(), err))?;
+        }
+
+        return fs::write(path, self.to_text())
+            .map_err(|err| format!("could not write {}: {}", path.display(), err));
+    }
+}
+
+fn renderer_name(renderer: ppu::Renderer) -> &'static str {
+    match renderer {
+        ppu::Renderer::PixelFifo => "pixel_fifo",
+        ppu::Renderer::Scanline => "scanline",
+    }
+}
+
+fn parse_renderer(name: &str) -> Option<ppu::Renderer> {
+    match name {
+        "pixel_fifo" => Some(ppu::Renderer::PixelFifo),
+        "scanline" => Some(ppu::Renderer::Scanline),
+        _ => None,
+    }
+}
+
+fn parse_frame(line: &str) -> Option<Frame> {
+    let (buttons, hash) = line.split_once(' ')?;
+    if buttons.chars().count() != BUTTON_CHARS.len() {
+        return None;
+    }
+
+    let mut mask = 0;
+    for (bit, (button, expected)) in buttons.chars().zip(BUTTON_CHARS.iter()).enumerate() {
+        match button {
+            RELEASED_CHAR => {}
+            _ if button == *expected => mask |= 1 << bit,
+            _ => return None,
+        }
+    }
+
+    return Some(Frame {
+        buttons: mask,
+        hash: u64::from_str_radix(hash, 16).ok()?,
+    });
+}
+
+/// Requests sent by the frontend to the emulator core.
+#[derive(Debug)]
+pub enum Command {
+    /// Powers the loaded ROM back on and records the inputs from there.
+    Record,
+
+    /// Powers the loaded ROM back on and plays the movie's inputs.
+    Play(Movie),
+
+    /// Stops recording or playing, giving the inputs back to the frontend.
+    Stop,
+}
+
+/// Outcome of a movie's playback.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Playback {
+    pub frames_played: usize,
+    pub total_frames: usize,
+
+    /// Index of the first frame whose hash differed from the recorded one.
+    pub first_desync: Option<usize>,
+}
+
+impl Playback {
+    /// Whether the whole movie was played back with the exact same output as recorded.
+    pub fn is_in_sync(&self) -> bool {
+        return self.frames_played == self.total_frames && self.first_desync.is_none();
+    }
+
+    pub fn describe(&self) -> String {
+        let mut description = format!("played {}/{} frames", self.frames_played, self.total_frames);
+        match self.first_desync {
+            Some(frame) => description.push_str(&format!(", desynced at frame {}", frame)),
+            None => description.push_str(", in sync"),
+        }
+
+        return description;
+    }
+}
+
+/// Reports sent by the emulator core to the frontend.
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    /// Recording started, with no frames yet. Each recorded frame follows as a `Frame`.
+    Recording(Movie),
+    Frame(Frame),
+
+    /// Playback reached the end of the movie or was stopped.
+    PlaybackEnded(Playback),
+
+    Error(String),
+}
+
+/// Movie being recorded or played by the emulator core. Inputs are only applied at the
+/// start of a frame while a session runs, so that a movie replays them at the exact same
+/// point of emulation.
+pub enum Session {
+    Recording {
+        buttons: u8,
+    },
+    Playing {
+        movie: Movie,
+        frame: usize,
+        first_desync: Option<usize>,
+    },
+}
+
+impl Session {
+    pub fn play(movie: Movie) -> Self {
+        Session::Playing {
+            movie,
+            frame: 0,
+            first_desync: None,
+        }
+    }
+
+    /// Buttons to hold during the next frame, given those held on the frontend.
+    pub fn next_input(&mut self, live_buttons: u8) -> u8 {
+        match self {
+            Session::Recording { buttons } => {
+                *buttons = live_buttons;
+                return live_buttons;
+            }
+            Session::Playing { movie, frame, .. } => match movie.frames.get(*frame) {
+                Some(recorded) => return recorded.buttons,
+                None => return 0,
+            },
+        }
+    }
+
+    /// Completes the frame started with `next_input`, returning the event to report if any.
+    /// A `PlaybackEnded` event means the session is over.
+    pub fn end_frame(&mut self, hash: u64) -> Option<Event> {
+        match self {
+            Session::Recording { buttons } => {
+                return Some(Event::Frame(Frame {
+                    buttons: *buttons,
+                    hash,
+                }))
+            }
+            Session::Playing {
+                movie,
+                frame,
+                first_desync,
+            } => {
+                if let Some(recorded) = movie.frames.get(*frame) {
+                    if recorded.hash != hash && first_desync.is_none() {
+                        *first_desync = Some(*frame);
+                    }
+                }
+                *frame += 1;
+
+                if *frame < movie.frames.len() {
+                    return None;
+                }
+            }
+        }
+
+        return self.stop();
+    }
+
+    /// Event to report when the session ends. Recorded frames have all been reported
+    /// already, so only playback has one.
+    pub fn stop(&self) -> Option<Event> {
+        match self {
+            Session::Recording { .. } => None,
+            Session::Playing {
+                movie,
+                frame,
+                first_desync,
+            } => Some(Event::PlaybackEnded(Playback {
+                frames_played: *frame,
+                total_frames: movie.frames.len(),
+                first_desync: *first_desync,
+            })),
+        }
+    }
+}
+
+/// Plays the movie back without a window, as fast as the emulator runs, and returns the
+/// outcome once it ends.
+pub fn play_headless(rom_data: Vec<u8>, movie: Movie) -> Result<Playback, String> {
+    let (gameboy, _, _) = gameboy::Gameboy::new(movie.skip_boot_rom);
+    let frontend = gameboy.start();
+    frontend.send_rom_data_back_end(rom_data);
+    frontend.send_movie_command(Command::Play(movie));
+
+    // Events of a frame are always sent before the frame itself.
+    let result = loop {
+        match frontend.recv_movie_event() {
+            Some(Event::PlaybackEnded(playback)) => break Ok(playback),
+            Some(Event::Error(err)) => break Err(err),
+            Some(_) => continue,
+            None => {}
+        }
+
+        if frontend.recv_frame().is_none() {
+            break Err(String::from("emulator stopped during playback"));
+        }
+    };
+
+    frontend.send_close_back_end();
+
+    // Drain the frame data channel to avoid the backend blocking before it sees the close signal.
+    let _ = frontend.should_render_screen();
+
+    match frontend.join_back_end() {
+        Ok(_) => (),
+        Err(err) => panic!("error occurred when joining back end thread: {:?}", err),
+    }
+
+    return result;
+}
@@ -0,0 +1,386 @@
+use crate::cheats;
+use crate::gameboy;
+use crate::gameboy::channel::front_end::Frontend;
+use crate::input;
+use crate::joypad::{self, ActionButton, Button, DirectionButton};
+use crate::movie::{self, Command, Event, Frame, Movie, Playback, Session};
+use crate::ppu;
+use glium::glutin::event::ElementState;
+
+// ROM only cartridge which selects the directions and copies P1 to the background palette
+// in a loop, so that the screen's shade follows the Right and Left buttons.
+fn test_rom() -> Vec<u8> {
+    let mut rom_data = vec![0x00; 0x8000];
+    rom_data[0x100..0x10A].copy_from_slice(&[
+        0x3E, 0x20, // LD A, $20
+        0xE0, 0x00, // LDH ($00), A
+        0xF0, 0x00, // LDH A, ($00)
+        0xE0, 0x47, // LDH ($47), A
+        0x18, 0xFA, // JR -6
+    ]);
+
+    return rom_data;
+}
+
+// ROM only cartridge which copies the first byte of VRAM to the background palette in a loop.
+// VRAM is zeroed, but reads during pixel transfer return 0xFF while access is restricted.
+fn vram_read_rom() -> Vec<u8> {
+    let mut rom_data = vec![0x00; 0x8000];
+    rom_data[0x100..0x107].copy_from_slice(&[
+        0xFA, 0x00, 0x80, // LD A, ($8000)
+        0xE0, 0x47, // LDH ($47), A
+        0x18, 0xF9, // JR -7
+    ]);
+
+    return rom_data;
+}
+
+fn test_movie() -> Movie {
+    return Movie {
+        emulator_version: String::from("0.1.3"),
+        rom_hash: 0x0123_4567_89AB_CDEF,
+        skip_boot_rom: true,
+        renderer: ppu::Renderer::Scanline,
+        ppu_access_restrictions: false,
+        cheats: vec![cheats::parse_code("3E0-F5D-4C1").unwrap()],
+        frames: vec![
+            Frame {
+                buttons: 0x00,
+                hash: 0xFFFF_0000_FFFF_0000,
+            },
+            Frame {
+                buttons: 0x81,
+                hash: 0x0000_0000_0000_0001,
+            },
+            Frame {
+                buttons: 0xFF,
+                hash: 0xABCD_EF01_2345_6789,
+            },
+        ],
+    };
+}
+
+#[test]
+fn hash() {
+    // Reference FNV-1a values.
+    assert_eq!(movie::hash(b""), 0xCBF2_9CE4_8422_2325);
+    assert_eq!(movie::hash(b"a"), 0xAF63_DC4C_8601_EC8C);
+    assert_eq!(movie::hash(b"foobar"), 0x8594_4171_F739_67E8);
+}
+
+#[test]
+fn text_round_trip() {
+    let movie = test_movie();
+    let text = movie.to_text();
+
+    assert_eq!(
+        text,
+        "rustyfuugbemu-movie 1\n\
+         version 0.1.3\n\
+         rom_hash 0123456789abcdef\n\
+         skip_boot_rom true\n\
+         renderer scanline\n\
+         ppu_access_restrictions false\n\
+         cheat 3E0-F5D-4C1\n\
+         \n\
+         ........ ffff0000ffff0000\n\
+         R......S 0000000000000001\n\
+         RLUDABsS abcdef0123456789\n"
+    );
+    assert_eq!(Movie::parse(&text), Ok(movie));
+
+    let empty = Movie::new(
+        &test_rom(),
+        false,
+        ppu::Renderer::PixelFifo,
+        true,
+        Vec::new(),
+    );
+    assert_eq!(empty.emulator_version, movie::EMULATOR_VERSION);
+    assert_eq!(Movie::parse(&empty.to_text()), Ok(empty));
+
+    // Movies recorded before the PPU settings were part of the header ran with the defaults.
+    let legacy =
+        Movie::parse("rustyfuugbemu-movie 1\nversion 0.1.2\nrom_hash 1f\nskip_boot_rom true\n\n")
+            .unwrap();
+    assert_eq!(legacy.renderer, ppu::Renderer::PixelFifo);
+    assert!(legacy.ppu_access_restrictions);
+}
+
+#[test]
+fn parse_errors() {
+    struct TestCase {
+        description: String,
+        contents: String,
+        expected: String,
+    }
+
+    let header = "rustyfuugbemu-movie 1\nversion 0.1.3\nrom_hash 1f\nskip_boot_rom false\n\n";
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("not a movie"),
+            contents: String::from("[general]\n"),
+            expected: String::from("not a movie file"),
+        },
+        TestCase {
+            description: String::from("missing header field"),
+            contents: String::from("rustyfuugbemu-movie 1\nversion 0.1.3\nrom_hash 1f\n\n"),
+            expected: String::from("missing 'skip_boot_rom'"),
+        },
+        TestCase {
+            description: String::from("invalid ROM hash"),
+            contents: String::from("rustyfuugbemu-movie 1\nrom_hash xyz\n"),
+            expected: String::from("line 2: invalid header 'rom_hash xyz'"),
+        },
+        TestCase {
+            description: String::from("unknown renderer"),
+            contents: String::from("rustyfuugbemu-movie 1\nrenderer fast\n"),
+            expected: String::from("line 2: invalid header 'renderer fast'"),
+        },
+        TestCase {
+            description: String::from("invalid cheat"),
+            contents: String::from("rustyfuugbemu-movie 1\ncheat 000-000\n"),
+            expected: String::from("line 2: '000-000': patches 0xF000, outside of ROM"),
+        },
+        TestCase {
+            description: String::from("button out of place"),
+            contents: format!("{}........ 00\nA....... 00\n", header),
+            expected: String::from("line 7: invalid frame 'A....... 00'"),
+        },
+        TestCase {
+            description: String::from("missing frame hash"),
+            contents: format!("{}........\n", header),
+            expected: String::from("line 6: invalid frame '........'"),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            Movie::parse(&tc.contents),
+            Err(tc.expected),
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn check_rom() {
+    let movie = Movie::new(
+        &test_rom(),
+        true,
+        ppu::Renderer::PixelFifo,
+        true,
+        Vec::new(),
+    );
+    assert_eq!(movie.check_rom(&test_rom()), Ok(()));
+    assert!(movie.check_rom(&vec![0x00; 0x8000]).is_err());
+}
+
+#[test]
+fn session() {
+    let mut recording = Session::Recording { buttons: 0 };
+    assert_eq!(recording.next_input(0x11), 0x11);
+    assert_eq!(
+        recording.end_frame(0xAB),
+        Some(Event::Frame(Frame {
+            buttons: 0x11,
+            hash: 0xAB
+        }))
+    );
+    assert_eq!(recording.stop(), None);
+
+    let movie = test_movie();
+    let mut playing = Session::play(movie.clone());
+
+    // Recorded inputs take precedence over the live ones.
+    assert_eq!(playing.next_input(0x10), 0x00);
+    assert_eq!(playing.end_frame(movie.frames[0].hash), None);
+    assert_eq!(playing.next_input(0x10), 0x81);
+    assert_eq!(playing.end_frame(0x1234), None);
+    assert_eq!(
+        playing.stop(),
+        Some(Event::PlaybackEnded(Playback {
+            frames_played: 2,
+            total_frames: 3,
+            first_desync: Some(1),
+        }))
+    );
+
+    assert_eq!(playing.next_input(0x00), 0xFF);
+    assert_eq!(
+        playing.end_frame(movie.frames[2].hash),
+        Some(Event::PlaybackEnded(Playback {
+            frames_played: 3,
+            total_frames: 3,
+            first_desync: Some(1),
+        }))
+    );
+}
+
+// Records a movie holding Right for the first frames, then releasing it. `setup` sends the
+// settings to record the movie with, before the ROM is loaded.
+fn record(rom_data: Vec<u8>, frame_count: usize, setup: impl FnOnce(&Frontend)) -> Movie {
+    let (gameboy, _, _) = gameboy::Gameboy::new(true);
+    let frontend = gameboy.start();
+    setup(&frontend);
+    frontend.send_rom_data_back_end(rom_data);
+    frontend.send_joypad_data(Some(DirectionButton::Right), None, ElementState::Pressed);
+    frontend.send_movie_command(Command::Record);
+
+    let mut movie = None;
+    loop {
+        while let Some(event) = frontend.recv_movie_event() {
+            match (event, &mut movie) {
+                (Event::Recording(recording), None) => movie = Some(recording),
+                (Event::Frame(frame), Some(movie)) => movie.frames.push(frame),
+                (event, _) => panic!("unexpected movie event {:?}", event),
+            }
+        }
+
+        match &movie {
+            Some(movie) if movie.frames.len() == 3 => frontend.send_joypad_data(
+                Some(DirectionButton::Right),
+                None,
+                ElementState::Released,
+            ),
+            Some(movie) if movie.frames.len() >= frame_count => break,
+            _ => {}
+        }
+
+        frontend.recv_frame().unwrap();
+    }
+
+    frontend.send_close_back_end();
+    let _ = frontend.should_render_screen();
+    frontend.join_back_end().unwrap();
+
+    let mut movie = movie.unwrap();
+    movie.frames.truncate(frame_count);
+    return movie;
+}
+
+#[test]
+fn record_and_play_back() {
+    let movie = record(test_rom(), 12, |_| {});
+    let right = joypad::button_mask(Some(DirectionButton::Right), None);
+
+    assert!(movie.skip_boot_rom);
+    assert_eq!(movie.frames[0].buttons, right);
+    assert_eq!(movie.frames.last().unwrap().buttons, 0x00);
+
+    let playback = movie::play_headless(test_rom(), movie.clone()).unwrap();
+    assert!(playback.is_in_sync(), "{}", playback.describe());
+    assert_eq!(playback.frames_played, 12);
+
+    // Holding Right past the recorded release keeps the palette from changing, so the
+    // frame hashes differ from there on.
+    let mut altered = movie.clone();
+    let released = altered
+        .frames
+        .iter()
+        .position(|frame| frame.buttons == 0x00)
+        .unwrap();
+    for frame in altered.frames[released..].iter_mut() {
+        frame.buttons = right;
+    }
+
+    let playback = movie::play_headless(test_rom(), altered).unwrap();
+    assert!(!playback.is_in_sync());
+    assert!(playback.first_desync.unwrap() >= released);
+
+    assert!(movie::play_headless(vec![0x00; 0x8000], movie).is_err());
+}
+
+#[test]
+fn record_and_play_back_cheats() {
+    // Selects the action buttons instead of the directions, so that Right is ignored.
+    let codes = vec![cheats::parse_code("101-01F").unwrap()];
+    let movie = record(test_rom(), 6, |frontend| {
+        frontend.send_cheats(codes.clone())
+    });
+    assert_eq!(movie.cheats, codes);
+
+    let playback = movie::play_headless(test_rom(), movie.clone()).unwrap();
+    assert!(playback.is_in_sync(), "{}", playback.describe());
+
+    let mut without_cheats = movie;
+    without_cheats.cheats.clear();
+    let playback = movie::play_headless(test_rom(), without_cheats).unwrap();
+    assert_eq!(playback.first_desync, Some(0));
+}
+
+#[test]
+fn record_and_play_back_ppu_settings() {
+    // The Scanline renderer draws each line at its end, after the palette was last written
+    // outside of pixel transfer, so the restricted VRAM reads never show.
+    let movie = record(vram_read_rom(), 6, |frontend| {
+        frontend.send_set_renderer_back_end(ppu::Renderer::Scanline)
+    });
+    assert_eq!(movie.renderer, ppu::Renderer::Scanline);
+
+    let playback = movie::play_headless(vram_read_rom(), movie.clone()).unwrap();
+    assert!(playback.is_in_sync(), "{}", playback.describe());
+
+    let mut pixel_fifo = movie;
+    pixel_fifo.renderer = ppu::Renderer::PixelFifo;
+    let playback = movie::play_headless(vram_read_rom(), pixel_fifo).unwrap();
+    assert!(playback.first_desync.is_some());
+
+    // Without the access restrictions, the palette never changes.
+    let movie = record(vram_read_rom(), 6, |frontend| {
+        frontend.send_set_ppu_access_restrictions_back_end(false)
+    });
+    assert!(!movie.ppu_access_restrictions);
+
+    let playback = movie::play_headless(vram_read_rom(), movie.clone()).unwrap();
+    assert!(playback.is_in_sync(), "{}", playback.describe());
+
+    let mut restricted = movie;
+    restricted.ppu_access_restrictions = true;
+    let playback = movie::play_headless(vram_read_rom(), restricted).unwrap();
+    assert!(playback.first_desync.is_some());
+}
+
+#[test]
+fn record_macro() {
+    let (gameboy, _, _) = gameboy::Gameboy::new(true);
+    let frontend = gameboy.start();
+    frontend.send_rom_data_back_end(test_rom());
+    frontend.send_joypad_data(None, Some(ActionButton::B), ElementState::Pressed);
+    frontend.send_movie_command(Command::Record);
+    frontend.send_input_command(input::Command::Macro(
+        input::parse_steps("Down 2, Down+Right 1, - 1, A 1").unwrap(),
+    ));
+
+    let mut frames = Vec::new();
+    while frames.len() < 12 {
+        while let Some(event) = frontend.recv_movie_event() {
+            if let Event::Frame(frame) = event {
+                frames.push(frame.buttons);
+            }
+        }
+
+        frontend.recv_frame().unwrap();
+    }
+
+    frontend.send_close_back_end();
+    let _ = frontend.should_render_screen();
+    frontend.join_back_end().unwrap();
+
+    // Macros advance once per emulated frame, on top of the buttons held.
+    let b = Button::B.mask();
+    let down = Button::Down.mask();
+    let start = frames.iter().position(|&buttons| buttons != b).unwrap();
+    assert_eq!(
+        frames[start..start + 6],
+        [
+            b | down,
+            b | down,
+            b | down | Button::Right.mask(),
+            b,
+            b | Button::A.mask(),
+            b
+        ]
+    );
+}
@@ -0,0 +1,189 @@
+//! Colours the four DMG shades are displayed with. The PPU only outputs shades, which
+//! are turned into actual colours by the frontend, separately for the background and
+//! both sprite palettes.
+#[path = "palette_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::fs;
+use std::path::Path;
+
+use crate::ppu::{Layer, Pixel};
+
+/// Set of four colours, from the lightest shade to the darkest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    name: String,
+    colors: [(u8, u8, u8); 4],
+}
+
+impl Palette {
+    pub fn new(name: &str, colors: [(u8, u8, u8); 4]) -> Self {
+        Self {
+            name: String::from(name),
+            colors,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rgb(&self, pixel: Pixel) -> (u8, u8, u8) {
+        match pixel {
+            Pixel::White => self.colors[0],
+            Pixel::LightGray => self.colors[1],
+            Pixel::DarkGray => self.colors[2],
+            Pixel::Black => self.colors[3],
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new(
+            "Fuu",
+            [
+                (250, 251, 246),
+                (197, 182, 189),
+                (85, 89, 116),
+                (14, 14, 26),
+            ],
+        )
+    }
+}
+
+/// Built-in palettes, the first one being the default.
+pub fn presets() -> Vec<Palette> {
+    vec![
+        Palette::default(),
+        Palette::new(
+            "DMG Green",
+            [(155, 188, 15), (139, 172, 15), (48, 98, 48), (15, 56, 15)],
+        ),
+        Palette::new(
+            "Pocket",
+            [(196, 207, 161), (139, 149, 109), (77, 83, 60), (31, 31, 31)],
+        ),
+        Palette::new(
+            "Light",
+            [(0, 181, 129), (0, 154, 113), (0, 105, 74), (0, 81, 56)],
+        ),
+        Palette::new(
+            "High Contrast",
+            [(255, 255, 255), (170, 170, 170), (85, 85, 85), (0, 0, 0)],
+        ),
+    ]
+}
+
+/// Parses user defined palettes. Each non-empty line defines a palette as its name
+/// followed by four hex colours, from the lightest to the darkest:
+///
+/// `Sepia: #F8E8C8 #C8A878 #785838 #281808`
+///
+/// Lines starting with `;` are comments.
+pub fn parse(contents: &str) -> Result<Vec<Palette>, String> {
+    let mut palettes = Vec::new();
+
+    for (line_idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+
+        let (name, colors) = match line.split_once(':') {
+            Some((name, colors)) if !name.trim().is_empty() => (name.trim(), colors),
+            _ => return Err(format!("line {}: expected a palette name", line_idx + 1)),
+        };
+
+        let colors = colors
+            .split_whitespace()
+            .map(parse_color)
+            .collect::<Option<Vec<(u8, u8, u8)>>>();
+
+        match colors {
+            Some(colors) if colors.len() == 4 => palettes.push(Palette::new(
+                name,
+                [colors[0], colors[1], colors[2], colors[3]],
+            )),
+            _ => {
+                return Err(format!(
+                    "line {}: expected four colours formatted as #RRGGBB",
+                    line_idx + 1
+                ))
+            }
+        }
+    }
+
+    return Ok(palettes);
+}
+
+/// Loads the palettes of a user palette file. They are added to the given palettes,
+/// replacing any palette with the same name.
+pub fn load_file(palettes: &mut Vec<Palette>, path: &Path) -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let user_palettes = parse(&contents)?;
+
+    log::info!(
+        "Loaded {} palettes from {}",
+        user_palettes.len(),
+        path.display()
+    );
+    for palette in user_palettes {
+        palettes.retain(|existing| existing.get_name() != palette.get_name());
+        palettes.push(palette);
+    }
+
+    return Ok(());
+}
+
+fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+
+    return Some((r, g, b));
+}
+
+/// Palettes the background/window and each of the two sprite palettes are displayed with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayerPalettes {
+    background: Palette,
+    object0: Palette,
+    object1: Palette,
+}
+
+impl LayerPalettes {
+    pub fn new(palette: Palette) -> Self {
+        Self {
+            background: palette.clone(),
+            object0: palette.clone(),
+            object1: palette,
+        }
+    }
+
+    pub fn get(&self, layer: Layer) -> &Palette {
+        match layer {
+            Layer::Background => &self.background,
+            Layer::Object0 => &self.object0,
+            Layer::Object1 => &self.object1,
+        }
+    }
+
+    pub fn set(&mut self, layer: Layer, palette: Palette) {
+        match layer {
+            Layer::Background => self.background = palette,
+            Layer::Object0 => self.object0 = palette,
+            Layer::Object1 => self.object1 = palette,
+        }
+    }
+
+    pub fn rgb(&self, pixel: Pixel, layer: Layer) -> (u8, u8, u8) {
+        self.get(layer).rgb(pixel)
+    }
+}
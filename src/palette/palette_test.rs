@@ -0,0 +1,95 @@
+use crate::palette::{self, LayerPalettes, Palette};
+use crate::ppu::{Layer, Pixel};
+
+#[test]
+fn parse() {
+    struct TestCase {
+        description: String,
+        contents: String,
+        expected: Result<Vec<Palette>, String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("empty file"),
+            contents: String::from(""),
+            expected: Ok(vec![]),
+        },
+        TestCase {
+            description: String::from("comments and blank lines are skipped"),
+            contents: String::from("; my palettes\n\nSepia: #F8E8C8 #c8a878 #785838 #281808\n"),
+            expected: Ok(vec![Palette::new(
+                "Sepia",
+                [(248, 232, 200), (200, 168, 120), (120, 88, 56), (40, 24, 8)],
+            )]),
+        },
+        TestCase {
+            description: String::from("multiple palettes with spaces in names"),
+            contents: String::from(
+                "Ice Cream: #FFF6D3 #F9A875 #EB6B6F #7C3F58\n  Mono : #FFFFFF #AAAAAA #555555 #000000",
+            ),
+            expected: Ok(vec![
+                Palette::new(
+                    "Ice Cream",
+                    [(255, 246, 211), (249, 168, 117), (235, 107, 111), (124, 63, 88)],
+                ),
+                Palette::new(
+                    "Mono",
+                    [(255, 255, 255), (170, 170, 170), (85, 85, 85), (0, 0, 0)],
+                ),
+            ]),
+        },
+        TestCase {
+            description: String::from("missing name"),
+            contents: String::from("#FFFFFF #AAAAAA #555555 #000000"),
+            expected: Err(String::from("line 1: expected a palette name")),
+        },
+        TestCase {
+            description: String::from("too few colours"),
+            contents: String::from("; comment\nMono: #FFFFFF #AAAAAA #555555"),
+            expected: Err(String::from(
+                "line 2: expected four colours formatted as #RRGGBB",
+            )),
+        },
+        TestCase {
+            description: String::from("invalid colour"),
+            contents: String::from("Mono: #FFFFFF #AAAAAA #55555G #000000"),
+            expected: Err(String::from(
+                "line 1: expected four colours formatted as #RRGGBB",
+            )),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            palette::parse(&tc.contents),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn layer_palettes() {
+    let presets = palette::presets();
+    let mut palettes = LayerPalettes::new(presets[0].clone());
+    assert_eq!(
+        palettes.rgb(Pixel::White, Layer::Object1),
+        presets[0].rgb(Pixel::White)
+    );
+
+    palettes.set(Layer::Object0, presets[1].clone());
+    assert_eq!(
+        palettes.rgb(Pixel::Black, Layer::Background),
+        presets[0].rgb(Pixel::Black)
+    );
+    assert_eq!(
+        palettes.rgb(Pixel::Black, Layer::Object0),
+        presets[1].rgb(Pixel::Black)
+    );
+    assert_eq!(
+        palettes.rgb(Pixel::LightGray, Layer::Object1),
+        presets[0].rgb(Pixel::LightGray)
+    );
+}
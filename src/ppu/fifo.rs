@@ -0,0 +1,417 @@
+#[path = "fifo_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::collections::VecDeque;
+
+use crate::memory::{io_registers, Memory};
+use crate::ppu::{self, sprite, sprite::Sprite, window::Window, Frame, Layer, PPU};
+
+/// Number of dots each of the fetcher's first three steps (tile number,
+/// tile data low, tile data high) takes to complete.
+const FETCHER_STEP_DOTS: u8 = 2;
+
+/// Number of dots the pipeline is stalled while a sprite's tile data is fetched,
+/// once the background fetcher is done with its current tile.
+const SPRITE_FETCH_DOTS: u8 = 6;
+
+const LCDC_BG_WINDOW_ENABLE_MASK: u8 = 1 << 0;
+const LCDC_OBJ_ENABLE_MASK: u8 = 1 << 1;
+const LCDC_BG_TILE_MAP_MASK: u8 = 1 << 3;
+const LCDC_TILE_DATA_MASK: u8 = 1 << 4;
+const LCDC_WINDOW_TILE_MAP_MASK: u8 = 1 << 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FetcherStep {
+    TileNumber,
+    TileDataLow,
+    TileDataHigh,
+    Push,
+}
+
+/// Background / window tile fetcher. Runs one step every two dots and pushes
+/// a row of 8 pixels into the background FIFO whenever it is empty.
+#[derive(Debug)]
+struct Fetcher {
+    step: FetcherStep,
+    dots: u8,
+    tile_x: u8,
+    tile_id: u8,
+    data_lo: u8,
+    data_hi: u8,
+}
+
+impl Fetcher {
+    fn new() -> Self {
+        Self {
+            step: FetcherStep::TileNumber,
+            dots: 0,
+            tile_x: 0,
+            tile_id: 0,
+            data_lo: 0,
+            data_hi: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SpritePixel {
+    color: u8,
+    palette_addr: usize,
+    layer: Layer,
+    bg_priority: bool,
+}
+
+/// Dot based pixel pipeline used during pixel transfer (mode 3).
+/// Unlike the scanline renderer, registers are sampled as the fetcher needs them,
+/// so mid-scanline writes to SCX, BGP, LCDC, etc. take effect on the following pixels,
+/// and the duration of mode 3 varies with fine scrolling, the window and sprites.
+#[derive(Debug)]
+pub struct PixelFifo {
+    bg_fifo: VecDeque<u8>,
+    sprite_fifo: VecDeque<SpritePixel>,
+    fetcher: Fetcher,
+
+    /// Sprites selected by the OAM search of the current line, in OAM order,
+    /// that have not been fetched yet. Fetching them left to right, and in OAM order
+    /// for equal X coordinates, naturally yields the DMG sprite priority.
+    sprites: Vec<Sprite>,
+    sprite_fetch: Option<(Sprite, u8)>,
+
+    ly: u8,
+    lx: u8,
+
+    /// Pixels left to discard at the start of the line for SCX fine scrolling.
+    discard: u8,
+
+    /// The very first tile fetch of every line is thrown away by the hardware.
+    first_fetch: bool,
+    fetching_window: bool,
+    window_line: u8,
+    transferring: bool,
+}
+
+impl PixelFifo {
+    pub fn new() -> Self {
+        Self {
+            bg_fifo: VecDeque::with_capacity(8),
+            sprite_fifo: VecDeque::with_capacity(8),
+            fetcher: Fetcher::new(),
+            sprites: Vec::with_capacity(sprite::MAX_SPRITES_PER_LINE),
+            sprite_fetch: None,
+            ly: 0,
+            lx: 0,
+            discard: 0,
+            first_fetch: true,
+            fetching_window: false,
+            window_line: 0,
+            transferring: false,
+        }
+    }
+
+    pub fn is_transferring(&self) -> bool {
+        return self.transferring;
+    }
+
+    pub fn window_rendered(&self) -> bool {
+        return self.fetching_window;
+    }
+
+    /// Prepares the pipeline for the pixel transfer of the given line.
+    /// Must be called at the end of OAM search.
+    pub fn start_line(&mut self, ly: u8, memory: &Memory) {
+        let lcdc = read(memory, io_registers::LCD_CONTROL_ADDR);
+        let scx = read(memory, io_registers::LCD_SCX_ADDR);
+
+        self.bg_fifo.clear();
+        self.sprite_fifo.clear();
+        self.fetcher = Fetcher::new();
+        self.sprite_fetch = None;
+        self.ly = ly;
+        self.lx = 0;
+        self.discard = scx % 8;
+        self.first_fetch = true;
+        self.fetching_window = false;
+        self.transferring = true;
+
+        let sprite_height = sprite::height(lcdc);
+
+        self.sprites = sprite::oam_scan(memory, ly, sprite_height);
+    }
+
+    /// Advances the pipeline by a single dot, writing at most one pixel to the frame.
+    pub fn tick(&mut self, memory: &Memory, window: &Window, frame: &mut Frame) {
+        if !self.transferring {
+            return;
+        }
+
+        let lcdc = read(memory, io_registers::LCD_CONTROL_ADDR);
+
+        if self.sprite_fetch.is_none() && lcdc & LCDC_OBJ_ENABLE_MASK > 0 {
+            self.sprite_fetch = self.next_sprite_at_lx().map(|sprite| (sprite, 0));
+        }
+
+        if let Some((sprite, dots)) = self.sprite_fetch {
+            // The sprite fetch only starts once the background fetcher is done with
+            // its current tile, and pixel output is stalled for the whole duration.
+            if self.fetcher.step != FetcherStep::Push || self.bg_fifo.is_empty() {
+                self.tick_fetcher(memory, lcdc);
+                return;
+            }
+
+            if dots + 1 < SPRITE_FETCH_DOTS {
+                self.sprite_fetch = Some((sprite, dots + 1));
+                return;
+            }
+
+            self.load_sprite(memory, lcdc, &sprite);
+            self.sprite_fetch = None;
+            return;
+        }
+
+        // Switching to the window discards whatever is left of the background and
+        // restarts the fetcher, which costs a dot on top of the new tile fetch.
+        if let Some(start_x) = self.window_start_x(memory, window, lcdc) {
+            self.fetching_window = true;
+            self.window_line = window.get_line();
+            self.bg_fifo.clear();
+            self.fetcher = Fetcher::new();
+            self.first_fetch = false;
+
+            // With WX < 7 the window starts off-screen, and its first pixels are dropped.
+            // Otherwise, none are, even if the SCX fine scroll has not been discarded yet.
+            self.discard = match start_x < 0 {
+                true => (-start_x) as u8,
+                false => 0,
+            };
+            return;
+        }
+
+        self.tick_fetcher(memory, lcdc);
+
+        let bg_color = match self.bg_fifo.pop_front() {
+            Some(color) => color,
+            None => return,
+        };
+
+        if self.discard > 0 {
+            self.discard -= 1;
+            return;
+        }
+
+        let bg_pixel = PPU::palette_lookup(read(memory, io_registers::LCD_PALETTE_ADDR), bg_color);
+
+        let (pixel, layer) = match self.sprite_fifo.pop_front() {
+            Some(sprite_pixel)
+                if sprite_pixel.color != 0 && (!sprite_pixel.bg_priority || bg_color == 0) =>
+            {
+                (
+                    PPU::palette_lookup(
+                        read(memory, sprite_pixel.palette_addr),
+                        sprite_pixel.color,
+                    ),
+                    sprite_pixel.layer,
+                )
+            }
+            _ => (bg_pixel, Layer::Background),
+        };
+
+        frame.pixels[self.ly as usize][self.lx as usize] = pixel;
+        frame.layers[self.ly as usize][self.lx as usize] = layer;
+        self.lx += 1;
+
+        if self.lx as usize >= ppu::NATIVE_SCREEN_WIDTH {
+            self.transferring = false;
+        }
+    }
+
+    fn next_sprite_at_lx(&mut self) -> Option<Sprite> {
+        // Sprites partially hidden past the left edge of the screen are all fetched at lx = 0.
+        let position = self
+            .sprites
+            .iter()
+            .position(|sprite| sprite.get_x().wrapping_add(8).max(8) - 8 == self.lx)?;
+
+        return Some(self.sprites.remove(position));
+    }
+
+    // Returns the window's start X coordinate if the fetcher should switch to the window on this dot.
+    fn window_start_x(&self, memory: &Memory, window: &Window, lcdc: u8) -> Option<i16> {
+        if self.fetching_window {
+            return None;
+        }
+
+        let start_x = window.start_x(lcdc, read(memory, io_registers::LCD_WINX_ADDR))?;
+
+        if self.lx as i16 >= start_x {
+            return Some(start_x);
+        }
+
+        return None;
+    }
+
+    fn tick_fetcher(&mut self, memory: &Memory, lcdc: u8) {
+        if self.fetcher.step != FetcherStep::Push {
+            self.fetcher.dots += 1;
+            if self.fetcher.dots < FETCHER_STEP_DOTS {
+                return;
+            }
+            self.fetcher.dots = 0;
+        }
+
+        match self.fetcher.step {
+            FetcherStep::TileNumber => {
+                self.fetcher.tile_id = read(memory, self.tile_id_addr(memory, lcdc));
+                self.fetcher.step = FetcherStep::TileDataLow;
+            }
+            FetcherStep::TileDataLow => {
+                self.fetcher.data_lo = read(memory, self.tile_data_addr(memory, lcdc));
+                self.fetcher.step = FetcherStep::TileDataHigh;
+            }
+            FetcherStep::TileDataHigh => {
+                self.fetcher.data_hi = read(memory, self.tile_data_addr(memory, lcdc) + 1);
+                self.fetcher.step = FetcherStep::Push;
+
+                // Rows are pushed as soon as they are fetched if the FIFO is empty,
+                // except for the discarded first fetch which idles for a dot.
+                if !self.first_fetch {
+                    self.push_bg_row(lcdc);
+                }
+            }
+            FetcherStep::Push => self.push_bg_row(lcdc),
+        }
+    }
+
+    fn push_bg_row(&mut self, lcdc: u8) {
+        if !self.bg_fifo.is_empty() {
+            return;
+        }
+
+        self.fetcher.step = FetcherStep::TileNumber;
+
+        if self.first_fetch {
+            self.first_fetch = false;
+            return;
+        }
+
+        for bit in (0..8).rev() {
+            let color = match lcdc & LCDC_BG_WINDOW_ENABLE_MASK > 0 {
+                true => color_encoding(self.fetcher.data_lo, self.fetcher.data_hi, bit),
+                false => 0,
+            };
+            self.bg_fifo.push_back(color);
+        }
+
+        self.fetcher.tile_x = self.fetcher.tile_x.wrapping_add(1);
+    }
+
+    fn tile_id_addr(&self, memory: &Memory, lcdc: u8) -> usize {
+        if self.fetching_window {
+            let tile_map_ptr: usize = match lcdc & LCDC_WINDOW_TILE_MAP_MASK > 0 {
+                true => 0x9C00,
+                false => 0x9800,
+            };
+
+            return tile_map_ptr
+                + (self.window_line / 8) as usize * 32
+                + (self.fetcher.tile_x & 0x1F) as usize;
+        }
+
+        let scx = read(memory, io_registers::LCD_SCX_ADDR);
+        let scy = read(memory, io_registers::LCD_SCY_ADDR);
+
+        let tile_map_ptr: usize = match lcdc & LCDC_BG_TILE_MAP_MASK > 0 {
+            true => 0x9C00,
+            false => 0x9800,
+        };
+
+        let tile_map_x = ((scx / 8).wrapping_add(self.fetcher.tile_x) & 0x1F) as usize;
+        let tile_map_y = (self.ly.wrapping_add(scy) / 8) as usize * 32;
+
+        return tile_map_ptr + tile_map_y + tile_map_x;
+    }
+
+    fn tile_data_addr(&self, memory: &Memory, lcdc: u8) -> usize {
+        let tile_line = match self.fetching_window {
+            true => self.window_line % 8,
+            false => {
+                self.ly
+                    .wrapping_add(read(memory, io_registers::LCD_SCY_ADDR))
+                    % 8
+            }
+        };
+
+        let tile_id = self.fetcher.tile_id as usize;
+        let tile_addr: usize = match lcdc & LCDC_TILE_DATA_MASK > 0 {
+            true => 0x8000 + (tile_id * 16),
+            false => match tile_id < 128 {
+                true => 0x9000 + (tile_id * 16),
+                false => 0x8800 + ((tile_id - 128) * 16),
+            },
+        };
+
+        return tile_addr + (tile_line as usize * 2);
+    }
+
+    fn load_sprite(&mut self, memory: &Memory, lcdc: u8, sprite: &Sprite) {
+        let sprite_height = sprite::height(lcdc);
+
+        let mut pattern_number = sprite.get_pattern_number();
+        if sprite_height == 16 {
+            pattern_number &= 0xFE;
+        }
+
+        let mut line = self.ly.wrapping_sub(sprite.get_y());
+        if sprite.is_y_flipped() {
+            line = sprite_height - 1 - line;
+        }
+
+        let data_addr = 0x8000 + (pattern_number as usize * 16) + (line as usize * 2);
+        let data_lo = read(memory, data_addr);
+        let data_hi = read(memory, data_addr + 1);
+
+        // Pixels of sprites hanging past the left edge of the screen are dropped.
+        let raw_x = sprite.get_x().wrapping_add(8);
+        let hidden_pixels = 8u8.saturating_sub(raw_x);
+
+        for i in hidden_pixels..8 {
+            let bit = match sprite.is_x_flipped() {
+                true => i,
+                false => 7 - i,
+            };
+
+            let sprite_pixel = SpritePixel {
+                color: color_encoding(data_lo, data_hi, bit),
+                palette_addr: sprite.get_palette_addr(),
+                layer: sprite.get_layer(),
+                bg_priority: sprite.bg_has_priority(),
+            };
+
+            // Sprites already in the FIFO have priority, only their transparent pixels are replaced.
+            let fifo_idx = (i - hidden_pixels) as usize;
+            match self.sprite_fifo.get_mut(fifo_idx) {
+                Some(existing) if existing.color == 0 => *existing = sprite_pixel,
+                Some(_) => {}
+                None => self.sprite_fifo.push_back(sprite_pixel),
+            }
+        }
+    }
+}
+
+fn read(memory: &Memory, addr: usize) -> u8 {
+    return memory.dma_read(addr).unwrap();
+}
+
+fn color_encoding(data_lo: u8, data_hi: u8, bit: u8) -> u8 {
+    let mut color: u8 = 0x00;
+
+    if data_hi & (1 << bit) > 0 {
+        color |= 0b10;
+    }
+
+    if data_lo & (1 << bit) > 0 {
+        color |= 0b01;
+    }
+
+    return color;
+}
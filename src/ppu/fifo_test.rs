@@ -0,0 +1,257 @@
+use std::sync::{Arc, Mutex};
+
+use crate::interrupt;
+use crate::memory::{io_registers, Memory};
+use crate::ppu::fifo::PixelFifo;
+use crate::ppu::window::Window;
+use crate::ppu::{Frame, Layer, Pixel};
+use crate::timers;
+
+fn new_memory() -> Memory {
+    let mut memory = Memory::default(
+        Arc::new(Mutex::new(timers::Timers::new())),
+        Arc::new(Mutex::new(interrupt::Bus::new())),
+    );
+
+    // LCD on, BG on, unsigned tile data, 0x9800 tile maps.
+    memory.write(io_registers::LCD_CONTROL_ADDR, 0x91);
+    memory.write(io_registers::LCD_PALETTE_ADDR, 0xE4);
+
+    // Tile 1 is entirely color 3, the tile map defaults to tile 0 which is color 0.
+    for i in 0..16 {
+        memory.write(0x8010 + i, 0xFF);
+    }
+
+    return memory;
+}
+
+fn write_sprite(memory: &mut Memory, y: u8, x: u8, tile: u8, attributes: u8) {
+    memory.write(0xFE00, y);
+    memory.write(0xFE01, x);
+    memory.write(0xFE02, tile);
+    memory.write(0xFE03, attributes);
+}
+
+fn transfer_line(fifo: &mut PixelFifo, memory: &Memory, frame: &mut Frame, ly: u8) -> u32 {
+    let mut window = Window::new();
+    window.latch_y(ly, memory.dma_read(io_registers::LCD_WINY_ADDR).unwrap());
+
+    let mut dots = 0;
+    fifo.start_line(ly, memory);
+    while fifo.is_transferring() {
+        fifo.tick(memory, &window, frame);
+        dots += 1;
+        assert!(dots < 456, "pixel transfer never completed");
+    }
+
+    return dots;
+}
+
+#[test]
+fn mode3_duration() {
+    struct TestCase {
+        description: String,
+        setup_fn: fn(&mut Memory),
+        min_penalty: u32,
+        max_penalty: u32,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("fine scroll delays mode 3 by SCX % 8 dots"),
+            setup_fn: |memory| memory.write(io_registers::LCD_SCX_ADDR, 0x0D),
+            min_penalty: 5,
+            max_penalty: 5,
+        },
+        TestCase {
+            description: String::from("coarse scroll does not delay mode 3"),
+            setup_fn: |memory| memory.write(io_registers::LCD_SCX_ADDR, 0x10),
+            min_penalty: 0,
+            max_penalty: 0,
+        },
+        TestCase {
+            description: String::from("window triggering mid-line delays mode 3"),
+            setup_fn: |memory| {
+                memory.write(io_registers::LCD_CONTROL_ADDR, 0xB1);
+                memory.write(io_registers::LCD_WINX_ADDR, 87);
+            },
+            min_penalty: 6,
+            max_penalty: 6,
+        },
+        TestCase {
+            description: String::from("sprite on the line delays mode 3"),
+            setup_fn: |memory| {
+                memory.write(io_registers::LCD_CONTROL_ADDR, 0x93);
+                write_sprite(memory, 16, 50, 1, 0);
+            },
+            min_penalty: 6,
+            max_penalty: 12,
+        },
+        TestCase {
+            description: String::from("disabled sprites do not delay mode 3"),
+            setup_fn: |memory| write_sprite(memory, 16, 50, 1, 0),
+            min_penalty: 0,
+            max_penalty: 0,
+        },
+    ];
+
+    let mut frame = Frame::new();
+    let base_dots = transfer_line(&mut PixelFifo::new(), &new_memory(), &mut frame, 0);
+    assert_eq!(base_dots, 172, "unexpected base mode 3 duration");
+
+    for tc in test_cases {
+        let mut memory = new_memory();
+        (tc.setup_fn)(&mut memory);
+
+        let dots = transfer_line(&mut PixelFifo::new(), &memory, &mut frame, 0);
+        let penalty = dots - base_dots;
+        assert!(
+            penalty >= tc.min_penalty && penalty <= tc.max_penalty,
+            "{}: penalty was {} dots",
+            tc.description,
+            penalty
+        );
+    }
+}
+
+#[test]
+fn mid_scanline_palette_change() {
+    let mut memory = new_memory();
+    memory.write(0x9800, 0x01);
+    for i in 0..32 {
+        memory.write(0x9800 + i, 0x01);
+    }
+
+    let mut fifo = PixelFifo::new();
+    let window = Window::new();
+    let mut frame = Frame::new();
+
+    fifo.start_line(0, &memory);
+    while fifo.lx < 80 {
+        fifo.tick(&memory, &window, &mut frame);
+    }
+
+    memory.write(io_registers::LCD_PALETTE_ADDR, 0x00);
+    while fifo.is_transferring() {
+        fifo.tick(&memory, &window, &mut frame);
+    }
+
+    assert!(frame.pixels[0][..80]
+        .iter()
+        .all(|pixel| *pixel == Pixel::Black));
+    assert!(frame.pixels[0][80..]
+        .iter()
+        .all(|pixel| *pixel == Pixel::White));
+}
+
+#[test]
+fn sprite_priority() {
+    let mut memory = new_memory();
+    memory.write(io_registers::LCD_CONTROL_ADDR, 0x93);
+    memory.write(io_registers::LCD_OBJ_PALETTE0_ADDR, 0x54);
+
+    // Sprite at screen x = 10 using tile 1 (all color 3 -> OBP0 maps it to light gray).
+    write_sprite(&mut memory, 16, 18, 1, 0);
+
+    let mut frame = Frame::new();
+    transfer_line(&mut PixelFifo::new(), &memory, &mut frame, 0);
+    assert_eq!(frame.pixels[0][9], Pixel::White);
+    assert!(frame.pixels[0][10..18]
+        .iter()
+        .all(|pixel| *pixel == Pixel::LightGray));
+    assert_eq!(frame.pixels[0][18], Pixel::White);
+
+    // With the BG priority attribute, the sprite still shows over BG color 0.
+    write_sprite(&mut memory, 16, 18, 1, 1 << 7);
+    transfer_line(&mut PixelFifo::new(), &memory, &mut frame, 0);
+    assert_eq!(frame.pixels[0][10], Pixel::LightGray);
+
+    // But not over any other BG color.
+    memory.write(0x9801, 0x01);
+    memory.write(0x9802, 0x01);
+    transfer_line(&mut PixelFifo::new(), &memory, &mut frame, 0);
+    assert_eq!(frame.pixels[0][10], Pixel::Black);
+}
+
+#[test]
+fn overlapping_sprite_priority() {
+    let mut memory = new_memory();
+    memory.write(io_registers::LCD_CONTROL_ADDR, 0x93);
+    memory.write(io_registers::LCD_OBJ_PALETTE0_ADDR, 0xE4);
+    memory.write(io_registers::LCD_OBJ_PALETTE1_ADDR, 0x54);
+
+    // OAM entry 0 is further right than entry 1, so entry 1 wins where they overlap.
+    write_sprite(&mut memory, 16, 20, 1, 1 << 4);
+    memory.write(0xFE04, 16);
+    memory.write(0xFE05, 16);
+    memory.write(0xFE06, 1);
+    memory.write(0xFE07, 0);
+
+    let mut frame = Frame::new();
+    transfer_line(&mut PixelFifo::new(), &memory, &mut frame, 0);
+
+    assert!(frame.pixels[0][8..16]
+        .iter()
+        .all(|pixel| *pixel == Pixel::Black));
+    assert!(frame.pixels[0][16..20]
+        .iter()
+        .all(|pixel| *pixel == Pixel::LightGray));
+    assert_eq!(frame.pixels[0][20], Pixel::White);
+
+    assert_eq!(frame.layers[0][8], Layer::Object0);
+    assert_eq!(frame.layers[0][16], Layer::Object1);
+    assert_eq!(frame.layers[0][20], Layer::Background);
+}
+
+#[test]
+fn window_clipped_by_low_wx() {
+    let mut memory = new_memory();
+
+    // Window on, BG tile map at 0x9C00 (all tile 0), window tile map at 0x9800.
+    memory.write(io_registers::LCD_CONTROL_ADDR, 0xB9);
+    memory.write(0x9800, 0x01);
+
+    let mut frame = Frame::new();
+
+    // WX = 3 moves the window 4 pixels past the left edge of the screen.
+    memory.write(io_registers::LCD_WINX_ADDR, 3);
+    let mut fifo = PixelFifo::new();
+    transfer_line(&mut fifo, &memory, &mut frame, 0);
+    assert!(fifo.window_rendered());
+    assert!(frame.pixels[0][..4]
+        .iter()
+        .all(|pixel| *pixel == Pixel::Black));
+    assert!(frame.pixels[0][4..]
+        .iter()
+        .all(|pixel| *pixel == Pixel::White));
+
+    // WX = 166 does not show the window on the current line.
+    memory.write(io_registers::LCD_WINX_ADDR, 166);
+    let mut fifo = PixelFifo::new();
+    transfer_line(&mut fifo, &memory, &mut frame, 0);
+    assert!(!fifo.window_rendered());
+    assert!(frame.pixels[0].iter().all(|pixel| *pixel == Pixel::White));
+}
+
+#[test]
+fn window_at_left_edge_ignores_fine_scroll() {
+    let mut memory = new_memory();
+    memory.write(io_registers::LCD_CONTROL_ADDR, 0xB9);
+    memory.write(0x9800, 0x01);
+
+    // The window starts at the left edge of the screen, before the SCX fine scroll
+    // has been discarded, and none of its pixels are dropped.
+    memory.write(io_registers::LCD_WINX_ADDR, 7);
+    memory.write(io_registers::LCD_SCX_ADDR, 3);
+
+    let mut frame = Frame::new();
+    let mut fifo = PixelFifo::new();
+    transfer_line(&mut fifo, &memory, &mut frame, 0);
+    assert!(fifo.window_rendered());
+    assert!(frame.pixels[0][..8]
+        .iter()
+        .all(|pixel| *pixel == Pixel::Black));
+    assert!(frame.pixels[0][8..]
+        .iter()
+        .all(|pixel| *pixel == Pixel::White));
+}
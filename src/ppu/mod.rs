@@ -1,6 +1,8 @@
+mod fifo;
 mod ppu;
-mod sprite;
-mod stat;
+pub mod sprite;
+pub mod stat;
+mod window;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Pixel {
@@ -10,32 +12,69 @@ pub enum Pixel {
     Black,
 }
 
-impl Pixel {
-    pub fn to_rgb(&self) -> (f64, f64, f64) {
-        match self {
-            Self::White => (250.0 / 255.0, 251.0 / 255.0, 246.0 / 255.0),
-            Self::LightGray => (197.0 / 255.0, 182.0 / 255.0, 189.0 / 255.0),
-            Self::DarkGray => (85.0 / 255.0, 89.0 / 255.0, 116.0 / 255.0),
-            Self::Black => (14.0 / 255.0, 14.0 / 255.0, 26.0 / 255.0),
-        }
-    }
+pub const NATIVE_SCREEN_WIDTH: usize = 160;
+pub const NATIVE_SCREEN_HEIGHT: usize = 144;
+
+/// Clock cycles the LCD takes to draw a whole frame, VBlank included, which makes for a
+/// refresh rate of about 59.73 Hz.
+pub const CYCLES_PER_FRAME: u32 = 70224;
+
+/// Palette register a pixel's shade was mapped through. The frontend uses it to
+/// display background and sprite pixels with distinct colours.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layer {
+    Background,
+    Object0,
+    Object1,
+}
+
+/// A complete frame as produced by the PPU.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub pixels: [[Pixel; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT],
+    pub layers: [[Layer; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT],
+}
 
-    pub fn to_rgb_u8(&self) -> (u8, u8, u8) {
-        match self {
-            Self::White => (250, 251, 246),
-            Self::LightGray => (197, 182, 189),
-            Self::DarkGray => (85, 89, 116),
-            Self::Black => (14, 14, 26),
+impl Frame {
+    /// A blank frame, as shown when the LCD is off.
+    pub fn new() -> Self {
+        Self {
+            pixels: [[Pixel::White; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT],
+            layers: [[Layer::Background; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT],
         }
     }
 }
 
-pub const NATIVE_SCREEN_WIDTH: usize = 160;
-pub const NATIVE_SCREEN_HEIGHT: usize = 144;
+/// Selects how the PPU produces the pixels of each scanline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Renderer {
+    /// Dot based fetcher and pixel FIFO pipeline. Accurately reproduces
+    /// mid-scanline register changes and variable mode 3 timings.
+    PixelFifo,
+
+    /// Renders the whole scanline at once at the end of its dot budget.
+    /// Faster, but blind to anything happening during the scanline.
+    Scanline,
+}
 
 #[derive(Debug)]
 pub struct PPU {
-    pixels: [[Pixel; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT],
+    frame: Frame,
     pixel_encodings: [[Pixel; NATIVE_SCREEN_WIDTH]; NATIVE_SCREEN_HEIGHT],
     scanline_counter: i32,
+    renderer: Renderer,
+    fifo: fifo::PixelFifo,
+    window: window::Window,
+
+    /// Whether LCDC bit 7 was set on the previous step.
+    lcd_enabled: bool,
+
+    /// Set while the first line after the LCD was turned on is being drawn.
+    lcd_just_enabled: bool,
+
+    /// The first frame after the LCD is turned on is never shown on screen.
+    blank_frame: bool,
+
+    /// State of the combined STAT interrupt line on the previous step.
+    stat_line: bool,
 }
@@ -1,3 +1,7 @@
+#[path = "ppu_test.rs"]
+#[cfg(test)]
+mod test;
+
 use core::panic;
 use std::sync::{self, Arc};
 
@@ -8,9 +12,9 @@ use crate::{
         io_registers::{self},
     },
     ppu::{
-        self,
+        self, fifo,
         stat::{self, StatUpdater},
-        *,
+        window, *,
     },
 };
 
@@ -21,15 +25,29 @@ const LCDC_OBJ_ENABLE_MASK: u8 = 1 << 1;
 impl PPU {
     pub fn new() -> Self {
         PPU {
-            pixels: [[ppu::Pixel::White; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT],
+            frame: ppu::Frame::new(),
             pixel_encodings: [[ppu::Pixel::White; ppu::NATIVE_SCREEN_WIDTH];
                 ppu::NATIVE_SCREEN_HEIGHT],
             scanline_counter: 0,
+            renderer: Renderer::PixelFifo,
+            fifo: fifo::PixelFifo::new(),
+            window: window::Window::new(),
+            lcd_enabled: false,
+            lcd_just_enabled: false,
+            blank_frame: false,
+            stat_line: false,
         }
     }
 
     pub fn reset(&mut self) {
+        let renderer = self.renderer;
         *self = PPU::new();
+        self.renderer = renderer;
+    }
+
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        log::debug!("PPU renderer set to {:?}", renderer);
+        self.renderer = renderer;
     }
 
     pub fn step_graphics(
@@ -81,20 +99,67 @@ impl PPU {
             }
         };
 
+        let wy = match memory.lock().unwrap().dma_read(io_registers::LCD_WINY_ADDR) {
+            Some(value) => value,
+            None => {
+                log::error!("failed to read WY register");
+                return;
+            }
+        };
+
+        let wx = match memory.lock().unwrap().dma_read(io_registers::LCD_WINX_ADDR) {
+            Some(value) => value,
+            None => {
+                log::error!("failed to read WX register");
+                return;
+            }
+        };
+
+        let lcd_enabled = lcdc & LCDC_ENABLE_MASK > 0;
+        if lcd_enabled != self.lcd_enabled {
+            self.lcd_enabled = lcd_enabled;
+            match lcd_enabled {
+                true => self.enable_lcd(),
+                false => self.disable_lcd(),
+            }
+        }
+
         self.set_lcdc_status(lcdc, stat, current_scanline, ly, lyc, memory, interrupt_bus);
 
-        if lcdc & LCDC_ENABLE_MASK == 0 {
+        if !lcd_enabled {
             return;
         }
 
         for _ in 0..4 {
+            if current_scanline < 144 && self.scanline_counter == stat::OAM_SEARCH_BOUNDARY {
+                self.window.latch_y(current_scanline, wy);
+            }
+
+            if self.renderer == Renderer::PixelFifo && current_scanline < 144 {
+                self.step_pixel_fifo(current_scanline, memory);
+            }
+
             self.scanline_counter -= 1;
 
             if self.scanline_counter <= 0 {
                 self.scanline_counter += stat::MAX_SCANLINE_COUNT;
+                self.lcd_just_enabled = false;
 
                 if current_scanline < 144 {
-                    self.draw_scaline(lcdc, memory);
+                    let window_rendered = match self.renderer {
+                        Renderer::Scanline => self.draw_scaline(lcdc, memory),
+                        Renderer::PixelFifo => self.fifo.window_rendered(),
+                    };
+                    self.window.end_line(window_rendered, lcdc, wx);
+
+                    // Entering the V-Blank period
+                    if current_scanline == 143 {
+                        interrupt_bus
+                            .lock()
+                            .unwrap()
+                            .request(interrupt::Interrupt::VBlank);
+                    }
+
                     memory
                         .lock()
                         .unwrap()
@@ -103,11 +168,7 @@ impl PPU {
                 }
 
                 // V-Blank period
-                if current_scanline >= 144 && current_scanline < 154 {
-                    interrupt_bus
-                        .lock()
-                        .unwrap()
-                        .request(interrupt::Interrupt::VBlank);
+                if current_scanline < 153 {
                     memory
                         .lock()
                         .unwrap()
@@ -121,113 +182,144 @@ impl PPU {
                     .lock()
                     .unwrap()
                     .write(io_registers::LCD_LY_ADDR, 0x00);
+                self.window.reset();
+                self.blank_frame = false;
+                return;
             }
         }
     }
 
-    fn render_sprites(&mut self, lcdc: u8, memory: &Arc<sync::Mutex<memory::Memory>>) {
-        let sprite_8x16 = lcdc & (1 << 2) > 0;
+    // Turning the LCD on restarts the PPU at the beginning of line 0. That first line skips
+    // OAM search, and the whole first frame is not shown on screen.
+    fn enable_lcd(&mut self) {
+        log::debug!("LCD enabled");
+        self.scanline_counter = stat::MAX_SCANLINE_COUNT;
+        self.lcd_just_enabled = true;
+        self.blank_frame = true;
+    }
 
-        let sprites = sprite::process_from_memory(memory);
+    // Turning the LCD off abandons whatever the PPU was doing and blanks the screen.
+    // LY and the STAT mode are reset in set_lcdc_status for as long as the LCD stays off.
+    fn disable_lcd(&mut self) {
+        log::debug!("LCD disabled");
+        self.scanline_counter = stat::MAX_SCANLINE_COUNT;
+        self.fifo = fifo::PixelFifo::new();
+        self.window.reset();
+        self.stat_line = false;
+        self.frame = ppu::Frame::new();
+    }
 
-        let current_scanline = memory
-            .lock()
-            .unwrap()
-            .dma_read(io_registers::LCD_LY_ADDR)
-            .unwrap();
+    // Runs the pixel FIFO for a single dot. The pipeline is primed at the end of OAM search,
+    // and then pushes pixels to the frame until the whole line has been transferred.
+    fn step_pixel_fifo(&mut self, current_scanline: u8, memory: &Arc<sync::Mutex<memory::Memory>>) {
+        let memory = memory.lock().unwrap();
 
-        for mut sprite in sprites {
-            let sprite_height_pixel: u8;
-            if sprite_8x16 {
-                sprite_height_pixel = 16;
-                sprite.pattern_number &= 0xFE;
-            } else {
-                sprite_height_pixel = 8;
-            }
+        if self.scanline_counter == stat::OAM_SEARCH_BOUNDARY {
+            self.fifo.start_line(current_scanline, &memory);
+        }
 
-            // Only need to update pixels if the actual sprite falls within the current scanline
-            if current_scanline >= sprite.get_y()
-                && current_scanline < (sprite.get_y() + sprite_height_pixel)
-            {
-                let mut line: i32 = (current_scanline - sprite.get_y()) as i32;
+        self.fifo.tick(&memory, &self.window, &mut self.frame);
+    }
 
-                if sprite.is_y_flipped() {
-                    line -= sprite_height_pixel as i32;
-                    line *= -1;
-                }
+    fn render_sprites(&mut self, lcdc: u8, memory: &Arc<sync::Mutex<memory::Memory>>) {
+        let memory = memory.lock().unwrap();
 
-                line *= 2;
+        let sprite_height_pixel = sprite::height(lcdc);
 
-                let data_addr: usize =
-                    (0x8000 + (sprite.get_pattern_number() as usize * 16) as usize) + line as usize;
+        let current_scanline = memory.dma_read(io_registers::LCD_LY_ADDR).unwrap();
 
-                let data1 = memory.lock().unwrap().dma_read(data_addr).unwrap();
-                let data2 = memory.lock().unwrap().dma_read(data_addr + 1).unwrap();
+        // Only the sprites selected during OAM search are drawn, from highest to lowest priority.
+        let mut sprites = sprite::oam_scan(&memory, current_scanline, sprite_height_pixel);
+        sprite::sort_by_priority(&mut sprites);
 
-                for tile_pixel in (0..8).rev() {
-                    let mut color_bit = tile_pixel;
+        // Once a sprite draws an opaque pixel at some x coordinate, lower priority sprites can no
+        // longer be drawn there, even if that pixel ends up hidden behind the background.
+        let mut sprite_drawn = [false; ppu::NATIVE_SCREEN_WIDTH];
 
-                    if !sprite.is_x_flipped() {
-                        color_bit -= 7;
-                        color_bit *= -1;
-                    }
+        for sprite in sprites {
+            let mut pattern_number = sprite.get_pattern_number();
+            if sprite_height_pixel == 16 {
+                // In 8x16 mode, the top tile is always the even one, and the bottom tile
+                // immediately follows it in VRAM.
+                pattern_number &= 0xFE;
+            }
 
-                    let mut color_code: u8 = 0x00;
-                    if data2 & (1 << color_bit) > 0 {
-                        color_code |= 0x02;
-                    }
+            let mut line = current_scanline.wrapping_sub(sprite.get_y());
 
-                    if data1 & (1 << color_bit) > 0 {
-                        color_code |= 0x01;
-                    }
+            if sprite.is_y_flipped() {
+                line = sprite_height_pixel - 1 - line;
+            }
 
-                    // If the current pixel color code for the given sprite is 0x00,
-                    // then this is considered a "transparent" pixel, therefore nothing left to do.
-                    if color_code == 0x00 {
-                        continue;
-                    }
+            let data_addr: usize = 0x8000 + (pattern_number as usize * 16) + (line as usize * 2);
 
-                    // TODO: Document what this logic does
-                    let mut color_palette_addr: usize = 0xFF48;
+            let data1 = memory.dma_read(data_addr).unwrap();
+            let data2 = memory.dma_read(data_addr + 1).unwrap();
 
-                    if sprite.get_attributes() & (1 << 4) > 0 {
-                        color_palette_addr = 0xFF49;
-                    }
+            for tile_pixel in 0..8u8 {
+                let color_bit = match sprite.is_x_flipped() {
+                    true => tile_pixel,
+                    false => 7 - tile_pixel,
+                };
 
-                    let x: usize = sprite.get_x().wrapping_add(tile_pixel as u8) as usize;
+                let mut color_code: u8 = 0x00;
+                if data2 & (1 << color_bit) > 0 {
+                    color_code |= 0x02;
+                }
 
-                    // If the sprite's x pixel is out of the bounds of the screen, no reason to render it.
-                    // Also applies if the current scanline is in v-blank mode.
-                    if x > 159 || current_scanline > 143 {
-                        continue;
-                    }
+                if data1 & (1 << color_bit) > 0 {
+                    color_code |= 0x01;
+                }
 
-                    // Determine if the background / window has priority over this sprite.
-                    // If true, background pixel should not be overwritten, unless the pixel color
-                    // at that coordinate is 0x00 (White).
-                    if sprite.bg_has_priority()
-                        && self.pixel_encodings[current_scanline as usize][x] != ppu::Pixel::White
-                    {
-                        continue;
-                    }
+                // If the current pixel color code for the given sprite is 0x00,
+                // then this is considered a "transparent" pixel, therefore nothing left to do.
+                if color_code == 0x00 {
+                    continue;
+                }
 
-                    let pixel_rgb =
-                        PPU::determine_pixel_rgb(memory, color_palette_addr, color_code);
+                let x: usize = sprite.get_x().wrapping_add(tile_pixel) as usize;
 
-                    self.pixels[current_scanline as usize][x] = pixel_rgb;
+                // If the sprite's x pixel is out of the bounds of the screen, no reason to render it.
+                // Also applies if the current scanline is in v-blank mode.
+                if x > 159 || current_scanline > 143 {
+                    continue;
+                }
+
+                if sprite_drawn[x] {
+                    continue;
+                }
+                sprite_drawn[x] = true;
+
+                // Determine if the background / window has priority over this sprite.
+                // If true, background pixel should not be overwritten, unless the pixel color
+                // at that coordinate is 0x00 (White).
+                if sprite.bg_has_priority()
+                    && self.pixel_encodings[current_scanline as usize][x] != ppu::Pixel::White
+                {
+                    continue;
                 }
+
+                let color_palette = memory.dma_read(sprite.get_palette_addr()).unwrap();
+
+                self.frame.pixels[current_scanline as usize][x] =
+                    PPU::palette_lookup(color_palette, color_code);
+                self.frame.layers[current_scanline as usize][x] = sprite.get_layer();
             }
         }
     }
 
-    fn draw_scaline(&mut self, lcdc: u8, memory: &Arc<sync::Mutex<memory::Memory>>) {
+    // Returns whether the window was rendered on this scanline.
+    fn draw_scaline(&mut self, lcdc: u8, memory: &Arc<sync::Mutex<memory::Memory>>) -> bool {
+        let mut window_rendered = false;
+
         if lcdc & LCDC_BG_WINDOW_ENABLE_MASK > 0 {
-            self.render_tiles(memory);
+            window_rendered = self.render_tiles(memory);
         }
 
         if lcdc & LCDC_OBJ_ENABLE_MASK > 0 {
             self.render_sprites(lcdc, memory);
         }
+
+        return window_rendered;
     }
 
     fn set_lcdc_status(
@@ -250,28 +342,31 @@ impl PPU {
                 .write(io_registers::LCD_LY_ADDR, 0x00);
 
             // Reset the STAT register to 1111 1100
-            memory
-                .lock()
-                .unwrap()
-                .write(io_registers::LCD_STAT_ADDR, stat & !stat::MODE_MASK);
+            memory.lock().unwrap().set_stat(stat & !stat::MODE_MASK);
 
             // Exit pre-emptively, since LCD is disabled
             return;
         }
 
-        let (new_stat, requires_interrupt) = StatUpdater::new(stat)
-            .process_vblank(current_scanline)
-            .process_oam_search(self.scanline_counter)
-            .process_pixel_transfer(self.scanline_counter)
+        let in_pixel_transfer = match self.renderer {
+            Renderer::PixelFifo => self.fifo.is_transferring(),
+            Renderer::Scanline => self.scanline_counter >= stat::PIXEL_TRANSFER_BOUNDARY,
+        };
+
+        let (new_stat, stat_line) = StatUpdater::new(stat)
+            .process_vblank(current_scanline, self.scanline_counter)
+            .process_oam_search(self.scanline_counter, self.lcd_just_enabled)
+            .process_pixel_transfer(in_pixel_transfer)
             .process_hblank()
             .process_ly_lyc(ly, lyc)
             .build();
 
-        memory
-            .lock()
-            .unwrap()
-            .write(io_registers::LCD_STAT_ADDR, new_stat);
-        if requires_interrupt {
+        memory.lock().unwrap().set_stat(new_stat);
+
+        // Only a rising edge of the STAT line requests an interrupt.
+        let rising_edge = stat_line && !self.stat_line;
+        self.stat_line = stat_line;
+        if rising_edge {
             interrupt_bus
                 .lock()
                 .unwrap()
@@ -286,6 +381,10 @@ impl PPU {
     ) -> ppu::Pixel {
         let color_palette = memory.lock().unwrap().dma_read(color_palette_addr).unwrap();
 
+        return PPU::palette_lookup(color_palette, pixel_color_encoding);
+    }
+
+    pub fn palette_lookup(color_palette: u8, pixel_color_encoding: u8) -> ppu::Pixel {
         // Depending on the current color palette that is in the PALETTE register,
         // these encoding translate to different colors / shades of gray.
         let palette_00 = (color_palette) & 0b11;
@@ -333,14 +432,14 @@ impl PPU {
 
     // Background tiles make up the background environment, and typically have lower precedence then the window tiles.
     // Window tiles have precedence over background tiles, when enabled.
-    fn render_tiles(&mut self, memory: &Arc<sync::Mutex<memory::Memory>>) {
+    fn render_tiles(&mut self, memory: &Arc<sync::Mutex<memory::Memory>>) -> bool {
         let current_scanline = memory
             .lock()
             .unwrap()
             .dma_read(memory::io_registers::LCD_LY_ADDR)
             .unwrap();
         if current_scanline > 144 {
-            return;
+            return false;
         }
 
         let lcdc = memory
@@ -362,12 +461,6 @@ impl PPU {
             .lock()
             .unwrap()
             .dma_read(memory::io_registers::LCD_WINX_ADDR)
-            .unwrap()
-            .wrapping_sub(7); // TODO: Explain the sub 7
-        let win_y = memory
-            .lock()
-            .unwrap()
-            .dma_read(memory::io_registers::LCD_WINY_ADDR)
             .unwrap();
 
         let bg_tile_map_ptr = match lcdc & (1 << 3) > 0 {
@@ -380,29 +473,28 @@ impl PPU {
             false => 0x9800,
         };
 
-        let is_in_window_y = match window_enabled(lcdc) && current_scanline >= win_y {
-            true => true,
-            false => false,
-        };
+        // Screen X coordinate at which the window starts, if it is rendered on this scanline at all.
+        // WX is offset by 7, so WX values below 7 start the window partially off-screen.
+        let window_start_x = self.window.start_x(lcdc, win_x);
+        let mut window_rendered = false;
 
         // Main loop through each 160 pixels of the current scanline we are rendering
         for pixel_iter in 0..ppu::NATIVE_SCREEN_WIDTH as u8 {
             // Check if our current coordinate is inside a window.
             // If true, then we need to render the window instead of the background.
-            let is_in_window_x = match window_enabled(lcdc) && pixel_iter >= win_x {
-                true => true,
-                false => false,
+            let is_in_window = match window_start_x {
+                Some(start_x) => pixel_iter as i16 >= start_x,
+                None => false,
             };
+            window_rendered |= is_in_window;
 
-            let is_in_window = is_in_window_x && is_in_window_y && window_enabled(lcdc);
-
-            let pixel_x: u8 = match is_in_window {
-                true => pixel_iter.wrapping_sub(win_x),
-                false => pixel_iter.wrapping_add(scroll_x),
+            let pixel_x: u8 = match window_start_x {
+                Some(start_x) if is_in_window => (pixel_iter as i16 - start_x) as u8,
+                _ => pixel_iter.wrapping_add(scroll_x),
             };
 
             let pixel_y: u8 = match is_in_window {
-                true => current_scanline.wrapping_sub(win_y),
+                true => self.window.get_line(),
                 false => current_scanline.wrapping_add(scroll_y),
             };
 
@@ -464,19 +556,19 @@ impl PPU {
                     0b11 => Pixel::Black,
                     _ => panic!("invalid pixel color data fetched from vram"),
                 };
-            self.pixels[current_scanline as usize][pixel_iter as usize] = pixel_color;
+            self.frame.pixels[current_scanline as usize][pixel_iter as usize] = pixel_color;
+            self.frame.layers[current_scanline as usize][pixel_iter as usize] =
+                ppu::Layer::Background;
         }
-    }
 
-    pub fn get_frame_data(
-        &self,
-    ) -> [[ppu::Pixel; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT] {
-        return self.pixels.clone();
+        return window_rendered;
     }
-}
 
-// If the 5th bit (starting from the right, 0 based) of the LCDC register is '1', then the
-// window should be rendered. Else it should be ignored.
-fn window_enabled(lcdc: u8) -> bool {
-    return (lcdc & (1 << 5)) > 0;
+    pub fn get_frame_data(&self) -> ppu::Frame {
+        if !self.lcd_enabled || self.blank_frame {
+            return ppu::Frame::new();
+        }
+
+        return self.frame;
+    }
 }
@@ -0,0 +1,285 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::gameboy::Gameboy;
+use crate::interrupt;
+use crate::memory::{io_registers, Memory};
+use crate::ppu::stat::{self, StatMode};
+use crate::ppu::{Pixel, PPU};
+use crate::timers;
+
+const M_CYCLES_PER_LINE: u32 = stat::MAX_SCANLINE_COUNT as u32 / 4;
+const LINES_PER_FRAME: u32 = 154;
+
+const LCDC_ON: u8 = 0x91;
+const LCDC_OFF: u8 = 0x11;
+
+const MODE_0_INTERRUPT: u8 = 1 << 3;
+const LY_LYC_INTERRUPT: u8 = 1 << 6;
+
+const VBLANK_IF_BIT: u8 = 1 << 0;
+const LCD_STAT_IF_BIT: u8 = 1 << 1;
+
+// Mooneye test ROMs report success by loading the Fibonacci numbers into B, C, D, E, H and L,
+// and failure by loading 0x42 into all of them. They are done well within this many frames.
+const MOONEYE_PASS: [u8; 6] = [3, 5, 8, 13, 21, 34];
+const MOONEYE_FRAMES: u32 = 300;
+
+struct Harness {
+    ppu: PPU,
+    memory: Arc<Mutex<Memory>>,
+    interrupt_bus: Arc<Mutex<interrupt::Bus>>,
+}
+
+impl Harness {
+    // LCD initially off, with the whole background drawn with an all black tile.
+    fn new() -> Self {
+        let interrupt_bus = Arc::new(Mutex::new(interrupt::Bus::new()));
+        let mut memory = Memory::default(
+            Arc::new(Mutex::new(timers::Timers::new())),
+            interrupt_bus.clone(),
+        );
+
+        memory.write(io_registers::LCD_CONTROL_ADDR, LCDC_OFF);
+        memory.write(io_registers::LCD_PALETTE_ADDR, 0xE4);
+        for i in 0..16 {
+            memory.write(0x8000 + i, 0xFF);
+        }
+
+        Self {
+            ppu: PPU::new(),
+            memory: Arc::new(Mutex::new(memory)),
+            interrupt_bus,
+        }
+    }
+
+    fn step(&mut self, m_cycles: u32) {
+        for _ in 0..m_cycles {
+            self.ppu.step_graphics(&self.memory, &self.interrupt_bus);
+        }
+    }
+
+    fn write(&mut self, addr: usize, value: u8) {
+        self.memory.lock().unwrap().write(addr, value);
+    }
+
+    fn ly(&self) -> u8 {
+        self.memory
+            .lock()
+            .unwrap()
+            .dma_read(io_registers::LCD_LY_ADDR)
+            .unwrap()
+    }
+
+    fn mode(&self) -> StatMode {
+        let stat = self
+            .memory
+            .lock()
+            .unwrap()
+            .dma_read(io_registers::LCD_STAT_ADDR)
+            .unwrap();
+        StatMode::from_stat(stat)
+    }
+
+    // Returns the IF bits that were requested since the last call, and clears them.
+    fn take_interrupts(&mut self) -> u8 {
+        let mut interrupt_bus = self.interrupt_bus.lock().unwrap();
+        let flags = interrupt_bus.read(io_registers::INTERRUPT_FLAG_REGISTER_ADDR);
+        interrupt_bus.write(io_registers::INTERRUPT_FLAG_REGISTER_ADDR, 0x00);
+        return flags;
+    }
+
+    fn frame_is_blank(&self) -> bool {
+        self.ppu
+            .get_frame_data()
+            .pixels
+            .iter()
+            .all(|line| line.iter().all(|pixel| *pixel == Pixel::White))
+    }
+}
+
+#[test]
+fn lcd_off_resets_ly_and_mode() {
+    let mut harness = Harness::new();
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_ON);
+    harness.step(5 * M_CYCLES_PER_LINE + 30);
+    assert_eq!(harness.ly(), 5);
+    assert_eq!(harness.mode(), StatMode::PixelTransfer);
+
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_OFF);
+    harness.step(1);
+    assert_eq!(harness.ly(), 0);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+    assert_eq!(harness.ppu.scanline_counter, stat::MAX_SCANLINE_COUNT);
+    assert!(harness.frame_is_blank());
+
+    // LY stays at 0 for as long as the LCD is off.
+    harness.step(3 * M_CYCLES_PER_LINE);
+    assert_eq!(harness.ly(), 0);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+}
+
+#[test]
+fn first_line_after_lcd_on_skips_oam_search() {
+    let mut harness = Harness::new();
+    harness.step(10);
+
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_ON);
+
+    // The first line after enabling the LCD skips OAM search.
+    harness.step(1);
+    assert_eq!(harness.ly(), 0);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+    harness.step(80 / 4);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+    harness.step(1);
+    assert_eq!(harness.mode(), StatMode::PixelTransfer);
+
+    // LY then advances every 456 dots, and the following lines go through OAM search.
+    harness.step(M_CYCLES_PER_LINE - 80 / 4 - 3);
+    assert_eq!(harness.ly(), 0);
+    harness.step(1);
+    assert_eq!(harness.ly(), 1);
+    harness.step(1);
+    assert_eq!(harness.mode(), StatMode::OAMSearch);
+
+    // The first frame is never shown.
+    harness.step((LINES_PER_FRAME - 1) * M_CYCLES_PER_LINE - 2);
+    assert_eq!(harness.ly(), 153);
+    assert!(harness.frame_is_blank());
+
+    harness.step(1);
+    assert_eq!(harness.ly(), 0);
+    assert!(!harness.frame_is_blank());
+}
+
+#[test]
+fn vblank_interrupt_once_per_frame() {
+    let mut harness = Harness::new();
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_ON);
+
+    harness.step(144 * M_CYCLES_PER_LINE - 1);
+    assert_eq!(harness.take_interrupts() & VBLANK_IF_BIT, 0);
+    harness.step(1);
+    assert_eq!(harness.ly(), 144);
+    assert_eq!(harness.take_interrupts() & VBLANK_IF_BIT, VBLANK_IF_BIT);
+
+    harness.step(10 * M_CYCLES_PER_LINE - 1);
+    assert_eq!(harness.take_interrupts() & VBLANK_IF_BIT, 0);
+    harness.step(1);
+    assert_eq!(harness.ly(), 0);
+}
+
+#[test]
+fn stat_interrupt_on_rising_edge_only() {
+    let mut harness = Harness::new();
+    harness.write(io_registers::LCD_LYC_ADDR, 1);
+    harness.write(
+        io_registers::LCD_STAT_ADDR,
+        MODE_0_INTERRUPT | LY_LYC_INTERRUPT,
+    );
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_ON);
+
+    // Line 0: entering h-blank is a rising edge of the STAT line.
+    harness.step(M_CYCLES_PER_LINE - 1);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+    assert_eq!(harness.take_interrupts() & LCD_STAT_IF_BIT, LCD_STAT_IF_BIT);
+
+    // Line 1: LY=LYC holds the line high from the start of the line, so entering
+    // h-blank does not request another interrupt.
+    harness.step(2);
+    assert_eq!(harness.ly(), 1);
+    assert_eq!(harness.take_interrupts() & LCD_STAT_IF_BIT, 0);
+    harness.step(M_CYCLES_PER_LINE - 2);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+    assert_eq!(harness.take_interrupts() & LCD_STAT_IF_BIT, 0);
+
+    // Line 2: LY=LYC drops the line, and h-blank raises it again.
+    harness.step(M_CYCLES_PER_LINE);
+    assert_eq!(harness.ly(), 2);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+    assert_eq!(harness.take_interrupts() & LCD_STAT_IF_BIT, LCD_STAT_IF_BIT);
+}
+
+#[test]
+fn lyc_match_during_hblank_does_not_interrupt() {
+    let mut harness = Harness::new();
+    harness.write(io_registers::LCD_LYC_ADDR, 0xFF);
+    harness.write(
+        io_registers::LCD_STAT_ADDR,
+        MODE_0_INTERRUPT | LY_LYC_INTERRUPT,
+    );
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_ON);
+
+    harness.step(M_CYCLES_PER_LINE - 1);
+    assert_eq!(harness.mode(), StatMode::HBlank);
+    harness.take_interrupts();
+
+    // Matching LYC while h-blank already holds the line high is blocked.
+    harness.write(io_registers::LCD_LYC_ADDR, 0);
+    harness.step(1);
+    assert_eq!(harness.take_interrupts() & LCD_STAT_IF_BIT, 0);
+}
+
+#[test]
+fn lcd_off_frame_is_blank() {
+    let mut harness = Harness::new();
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_ON);
+    harness.step(2 * LINES_PER_FRAME * M_CYCLES_PER_LINE);
+    assert!(!harness.frame_is_blank());
+
+    harness.write(io_registers::LCD_CONTROL_ADDR, LCDC_OFF);
+    harness.step(1);
+    assert!(harness.frame_is_blank());
+
+    // The frame buffer itself is cleared, so turning the LCD back on does not show a stale frame.
+    assert!(harness
+        .ppu
+        .frame
+        .pixels
+        .iter()
+        .all(|line| line.iter().all(|pixel| *pixel == Pixel::White)));
+}
+
+// Runs a ROM from tests/roms without a window for the given number of frames, and returns
+// the registers test ROMs report their result in.
+fn run_test_rom(name: &str, frames: u32) -> [u8; 6] {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/roms")
+        .join(name);
+    let rom_data =
+        fs::read(&path).unwrap_or_else(|err| panic!("could not read {}: {}", path.display(), err));
+
+    let (gameboy, _, cpu_ref) = Gameboy::new(true);
+    let frontend = gameboy.start();
+    frontend.send_rom_data_back_end(rom_data);
+    for _ in 0..frames {
+        frontend.recv_frame().unwrap();
+    }
+    let registers = cpu_ref.lock().unwrap().get_result_registers();
+
+    frontend.send_close_back_end();
+    let _ = frontend.should_render_screen();
+    frontend.join_back_end().unwrap();
+
+    return registers;
+}
+
+#[test]
+#[ignore = "needs stat_irq_blocking.gb from the mooneye test suite in tests/roms"]
+fn mooneye_stat_irq_blocking() {
+    assert_eq!(
+        run_test_rom("stat_irq_blocking.gb", MOONEYE_FRAMES),
+        MOONEYE_PASS
+    );
+}
+
+#[test]
+#[ignore = "needs lcdon_timing-GS.gb from the mooneye test suite in tests/roms"]
+fn mooneye_lcdon_timing() {
+    assert_eq!(
+        run_test_rom("lcdon_timing-GS.gb", MOONEYE_FRAMES),
+        MOONEYE_PASS
+    );
+}
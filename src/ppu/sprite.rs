@@ -1,15 +1,25 @@
-use crate::memory::Memory;
-use std::sync::Arc;
-use std::sync::Mutex;
+#[path = "sprite_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::memory::{io_registers, Memory};
+use crate::ppu;
 
 const OAM_ADDR: usize = 0xFE00;
+pub const OAM_ENTRY_COUNT: usize = 40;
+
+/// Maximum number of sprites the PPU selects during the OAM search of a single line.
+pub const MAX_SPRITES_PER_LINE: usize = 10;
+
+const LCDC_OBJ_SIZE_MASK: u8 = 1 << 2;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct Sprite {
     x_pos: u8,
     y_pos: u8,
     pub pattern_number: u8,
     attributes: u8,
+    oam_index: u8,
 }
 
 impl Sprite {
@@ -37,45 +47,134 @@ impl Sprite {
         self.pattern_number
     }
 
-    pub fn get_attributes(&self) -> u8 {
-        self.attributes
+    pub fn get_oam_index(&self) -> u8 {
+        self.oam_index
+    }
+
+    /// Address of the object palette register (OBP0 or OBP1) selected by attribute bit 4.
+    pub fn get_palette_addr(&self) -> usize {
+        match self.attributes & (1 << 4) > 0 {
+            true => io_registers::LCD_OBJ_PALETTE1_ADDR,
+            false => io_registers::LCD_OBJ_PALETTE0_ADDR,
+        }
+    }
+
+    pub fn get_layer(&self) -> ppu::Layer {
+        match self.attributes & (1 << 4) > 0 {
+            true => ppu::Layer::Object1,
+            false => ppu::Layer::Object0,
+        }
+    }
+
+    pub fn overlaps_line(&self, ly: u8, sprite_height: u8) -> bool {
+        ly.wrapping_sub(self.y_pos) < sprite_height
+    }
+
+    /// Colour codes of the sprite's pixels as drawn on screen, flips included, row by row.
+    pub fn get_color_codes(&self, memory: &Memory, sprite_height: u8) -> Vec<[u8; 8]> {
+        let mut pattern_number = self.pattern_number;
+        if sprite_height == 16 {
+            pattern_number &= 0xFE;
+        }
+
+        let mut rows = Vec::with_capacity(sprite_height as usize);
+        for row in 0..sprite_height {
+            let line = match self.is_y_flipped() {
+                true => sprite_height - 1 - row,
+                false => row,
+            };
+
+            let data_addr = 0x8000 + (pattern_number as usize * 16) + (line as usize * 2);
+            let data_lo = memory.dma_read(data_addr).unwrap();
+            let data_hi = memory.dma_read(data_addr + 1).unwrap();
+
+            let mut codes = [0x00; 8];
+            for (x, code) in codes.iter_mut().enumerate() {
+                let bit = match self.is_x_flipped() {
+                    true => x,
+                    false => 7 - x,
+                };
+
+                *code = (((data_hi >> bit) & 1) << 1) | ((data_lo >> bit) & 1);
+            }
+            rows.push(codes);
+        }
+
+        return rows;
+    }
+}
+
+/// Height in pixels of every sprite, as selected by LCDC bit 2.
+pub fn height(lcdc: u8) -> u8 {
+    match lcdc & LCDC_OBJ_SIZE_MASK > 0 {
+        true => 16,
+        false => 8,
     }
 }
 
-pub fn process_from_memory(memory: &Arc<Mutex<Memory>>) -> [Sprite; 40] {
-    let mut sprites = [Sprite {
-        x_pos: 0,
-        y_pos: 0,
-        pattern_number: 0,
-        attributes: 0,
-    }; 40];
+/// Emulates the OAM search (mode 2) for the given line. Entries are scanned in OAM order,
+/// and the first 10 that vertically overlap the line are selected. The X coordinate plays no
+/// part in the selection, so sprites that are horizontally off-screen still count toward the limit.
+pub fn oam_scan(memory: &Memory, ly: u8, sprite_height: u8) -> Vec<Sprite> {
+    let mut sprites = Vec::with_capacity(MAX_SPRITES_PER_LINE);
 
-    for i in (0..40).rev() {
-        let transformed_idx = i as usize * 4;
+    for i in 0..OAM_ENTRY_COUNT {
+        let sprite = read_from_oam(memory, i);
 
-        sprites[i as usize].y_pos = memory
-            .lock()
-            .unwrap()
+        if sprite.overlaps_line(ly, sprite_height) {
+            sprites.push(sprite);
+        }
+
+        if sprites.len() == MAX_SPRITES_PER_LINE {
+            break;
+        }
+    }
+
+    return sprites;
+}
+
+/// Splits the sprites overlapping the line into those the OAM search selects, and those
+/// it drops past the limit of 10 per line.
+pub fn split_line(sprites: &[Sprite], ly: u8, sprite_height: u8) -> (Vec<Sprite>, Vec<Sprite>) {
+    let mut overlapping: Vec<Sprite> = sprites
+        .iter()
+        .filter(|sprite| sprite.overlaps_line(ly, sprite_height))
+        .copied()
+        .collect();
+    let dropped = overlapping.split_off(overlapping.len().min(MAX_SPRITES_PER_LINE));
+
+    return (overlapping, dropped);
+}
+
+/// Sorts sprites from highest to lowest drawing priority. On the DMG, the sprite with the
+/// lowest X coordinate wins, and ties are broken by the lowest OAM index.
+pub fn sort_by_priority(sprites: &mut [Sprite]) {
+    sprites.sort_by_key(|sprite| (sprite.get_x().wrapping_add(8), sprite.get_oam_index()));
+}
+
+/// Decodes all the OAM entries, in OAM order.
+pub fn read_all(memory: &Memory) -> Vec<Sprite> {
+    return (0..OAM_ENTRY_COUNT)
+        .map(|index| read_from_oam(memory, index))
+        .collect();
+}
+
+/// Decodes the OAM entry at the given index (0-39) without locking the memory,
+/// for callers that already hold it.
+pub fn read_from_oam(memory: &Memory, index: usize) -> Sprite {
+    let transformed_idx = index * 4;
+
+    Sprite {
+        y_pos: memory
             .dma_read(OAM_ADDR + transformed_idx)
             .unwrap()
-            .wrapping_sub(16);
-        sprites[i as usize].x_pos = memory
-            .lock()
-            .unwrap()
+            .wrapping_sub(16),
+        x_pos: memory
             .dma_read(OAM_ADDR + transformed_idx + 1)
             .unwrap()
-            .wrapping_sub(8);
-        sprites[i as usize].pattern_number = memory
-            .lock()
-            .unwrap()
-            .dma_read(OAM_ADDR + transformed_idx + 2)
-            .unwrap();
-        sprites[i as usize].attributes = memory
-            .lock()
-            .unwrap()
-            .dma_read(OAM_ADDR + transformed_idx + 3)
-            .unwrap();
+            .wrapping_sub(8),
+        pattern_number: memory.dma_read(OAM_ADDR + transformed_idx + 2).unwrap(),
+        attributes: memory.dma_read(OAM_ADDR + transformed_idx + 3).unwrap(),
+        oam_index: index as u8,
     }
-
-    return sprites;
 }
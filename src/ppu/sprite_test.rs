@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::interrupt;
+use crate::memory::Memory;
+use crate::palette::{LayerPalettes, Palette};
+use crate::ppu::sprite::{self, Sprite, MAX_SPRITES_PER_LINE};
+use crate::ppu::{Pixel, NATIVE_SCREEN_HEIGHT, NATIVE_SCREEN_WIDTH};
+use crate::screenshot;
+use crate::timers;
+
+// These cover sprite selection and priority one rule at a time, dmg-acid2 at the bottom
+// covers them all together.
+
+// dmg-acid2 draws its final image within a few frames, and then halts.
+const ACID2_FRAMES: u32 = 60;
+
+// Each entry is given as raw (y, x, tile, attributes) bytes, remaining entries are zeroed.
+fn new_memory_with_oam(entries: &[(u8, u8, u8, u8)]) -> Memory {
+    let mut memory = Memory::default(
+        Arc::new(Mutex::new(timers::Timers::new())),
+        Arc::new(Mutex::new(interrupt::Bus::new())),
+    );
+
+    for (i, (y, x, tile, attributes)) in entries.iter().enumerate() {
+        memory.write(0xFE00 + (i * 4), *y);
+        memory.write(0xFE00 + (i * 4) + 1, *x);
+        memory.write(0xFE00 + (i * 4) + 2, *tile);
+        memory.write(0xFE00 + (i * 4) + 3, *attributes);
+    }
+
+    return memory;
+}
+
+#[test]
+fn oam_scan() {
+    struct TestCase {
+        description: String,
+        entries: Vec<(u8, u8, u8, u8)>,
+        ly: u8,
+        sprite_height: u8,
+        expected_oam_indexes: Vec<u8>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("selects sprites overlapping the line only"),
+            entries: vec![(16, 8, 0, 0), (24, 8, 0, 0), (17, 8, 0, 0), (9, 8, 0, 0)],
+            ly: 0,
+            sprite_height: 8,
+            expected_oam_indexes: vec![0, 3],
+        },
+        TestCase {
+            description: String::from("selects at most 10 sprites, in OAM order"),
+            entries: vec![(16, 100, 0, 0); 12],
+            ly: 3,
+            sprite_height: 8,
+            expected_oam_indexes: (0..MAX_SPRITES_PER_LINE as u8).collect(),
+        },
+        TestCase {
+            description: String::from("off-screen sprites still count toward the limit"),
+            entries: vec![
+                (16, 0, 0, 0),
+                (16, 168, 0, 0),
+                (16, 200, 0, 0),
+                (16, 0, 0, 0),
+                (16, 0, 0, 0),
+                (16, 0, 0, 0),
+                (16, 0, 0, 0),
+                (16, 0, 0, 0),
+                (16, 0, 0, 0),
+                (16, 0, 0, 0),
+                (16, 50, 0, 0),
+            ],
+            ly: 0,
+            sprite_height: 8,
+            expected_oam_indexes: (0..MAX_SPRITES_PER_LINE as u8).collect(),
+        },
+        TestCase {
+            description: String::from("8x16 sprites cover 16 lines"),
+            entries: vec![(16, 8, 0, 0), (8, 8, 0, 0), (0, 8, 0, 0)],
+            ly: 7,
+            sprite_height: 16,
+            expected_oam_indexes: vec![0, 1],
+        },
+        TestCase {
+            description: String::from("sprites partially above the screen are selected"),
+            entries: vec![(2, 8, 0, 0), (1, 8, 0, 0)],
+            ly: 1,
+            sprite_height: 16,
+            expected_oam_indexes: vec![0],
+        },
+    ];
+
+    for tc in test_cases {
+        let memory = new_memory_with_oam(&tc.entries);
+        let oam_indexes: Vec<u8> = sprite::oam_scan(&memory, tc.ly, tc.sprite_height)
+            .iter()
+            .map(|sprite| sprite.get_oam_index())
+            .collect();
+
+        assert_eq!(oam_indexes, tc.expected_oam_indexes, "{}", tc.description);
+
+        // The viewer's split of the line selects the same sprites.
+        let (selected, _) = sprite::split_line(&sprite::read_all(&memory), tc.ly, tc.sprite_height);
+        assert_eq!(
+            indexes_of(&selected),
+            tc.expected_oam_indexes,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+fn indexes_of(sprites: &[Sprite]) -> Vec<u8> {
+    return sprites
+        .iter()
+        .map(|sprite| sprite.get_oam_index())
+        .collect();
+}
+
+#[test]
+fn split_line() {
+    let mut entries = vec![(16, 8, 0, 0); 12];
+    entries.insert(3, (40, 8, 0, 0));
+    let sprites = sprite::read_all(&new_memory_with_oam(&entries));
+    assert_eq!(sprites.len(), 40);
+
+    let (selected, dropped) = sprite::split_line(&sprites, 0, 8);
+    assert_eq!(indexes_of(&selected), vec![0, 1, 2, 4, 5, 6, 7, 8, 9, 10]);
+    assert_eq!(indexes_of(&dropped), vec![11, 12]);
+
+    let (selected, dropped) = sprite::split_line(&sprites, 24, 8);
+    assert_eq!(indexes_of(&selected), vec![3]);
+    assert!(dropped.is_empty());
+}
+
+#[test]
+fn color_codes() {
+    let mut memory = new_memory_with_oam(&[
+        (16, 8, 0x02, 0x00),
+        (16, 8, 0x02, 0x20),
+        (16, 8, 0x03, 0x40),
+    ]);
+
+    // Tile 2 has a single pixel of colour 3 in its top left corner, and tile 3 one of
+    // colour 2 in its bottom right corner.
+    memory.poke(0x8020, 0x80);
+    memory.poke(0x8021, 0x80);
+    memory.poke(0x803F, 0x01);
+
+    let sprites = sprite::read_all(&memory);
+
+    let codes = sprites[0].get_color_codes(&memory, 8);
+    assert_eq!(codes.len(), 8);
+    assert_eq!(codes[0], [3, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(codes[7], [0; 8]);
+
+    // Flipped horizontally.
+    let codes = sprites[1].get_color_codes(&memory, 8);
+    assert_eq!(codes[0], [0, 0, 0, 0, 0, 0, 0, 3]);
+
+    // 8x16 sprites start at the even tile, here flipped vertically.
+    let codes = sprites[2].get_color_codes(&memory, 16);
+    assert_eq!(codes.len(), 16);
+    assert_eq!(codes[0], [0, 0, 0, 0, 0, 0, 0, 2]);
+    assert_eq!(codes[15], [3, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn sort_by_priority() {
+    let memory = new_memory_with_oam(&[
+        (16, 30, 0, 0),
+        (16, 20, 0, 0),
+        (16, 30, 0, 0),
+        (16, 4, 0, 0),
+    ]);
+
+    let mut sprites = sprite::oam_scan(&memory, 0, 8);
+    sprite::sort_by_priority(&mut sprites);
+
+    let oam_indexes: Vec<u8> = sprites
+        .iter()
+        .map(|sprite| sprite.get_oam_index())
+        .collect();
+
+    assert_eq!(oam_indexes, vec![3, 1, 0, 2]);
+}
+
+// The reference image is in greys, from white to black. Each grey is shown as the default
+// palette's colour for the same shade, which is what the frame is compared against.
+fn reference_shade(luma: u8) -> Pixel {
+    return match luma {
+        0xC0..=0xFF => Pixel::White,
+        0x80..=0xBF => Pixel::LightGray,
+        0x40..=0x7F => Pixel::DarkGray,
+        _ => Pixel::Black,
+    };
+}
+
+#[test]
+#[ignore = "needs dmg-acid2.gb and reference-dmg.png from dmg-acid2 in tests/roms"]
+fn dmg_acid2() {
+    let roms = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms");
+    let rom_data = fs::read(roms.join("dmg-acid2.gb")).unwrap();
+    let reference = image::open(roms.join("reference-dmg.png"))
+        .unwrap()
+        .to_luma8();
+    assert_eq!(
+        reference.dimensions(),
+        (NATIVE_SCREEN_WIDTH as u32, NATIVE_SCREEN_HEIGHT as u32)
+    );
+
+    let frame = screenshot::capture_headless(rom_data, true, ACID2_FRAMES);
+
+    let default_palette = Palette::default();
+    let palettes = LayerPalettes::new(Palette::default());
+    let mut mismatches = Vec::new();
+    for y in 0..NATIVE_SCREEN_HEIGHT {
+        for x in 0..NATIVE_SCREEN_WIDTH {
+            let expected =
+                default_palette.rgb(reference_shade(reference.get_pixel(x as u32, y as u32)[0]));
+            let got = palettes.rgb(frame.pixels[y][x], frame.layers[y][x]);
+            if got != expected {
+                mismatches.push((x, y, got, expected));
+            }
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "{} pixels differ from the reference, first ones (x, y, got, expected): {:?}",
+        mismatches.len(),
+        &mismatches[..mismatches.len().min(8)]
+    );
+}
@@ -1,3 +1,7 @@
+#[path = "stat_test.rs"]
+#[cfg(test)]
+mod test;
+
 pub const MODE_MASK: u8 = (1 << 1) | (1 << 0);
 pub const MAX_SCANLINE_COUNT: i32 = 456;
 
@@ -7,16 +11,30 @@ const MODE_1_INTERRUPT_MASK: u8 = 1 << 4;
 const MODE_2_INTERRUPT_MASK: u8 = 1 << 5;
 const LY_LYC_INTERRUPT_MASK: u8 = 1 << 6;
 const V_BLANK_BOUNDARY: u8 = 144;
-const TRANSFER_PIXEL_BOUNDARY: i32 = MAX_SCANLINE_COUNT - 80;
-const OAM_SEARCH_BOUNDARY: i32 = TRANSFER_PIXEL_BOUNDARY - 172;
 
-enum StatMode {
+/// Scanline counter value at which OAM search (mode 2) ends and pixel transfer (mode 3) begins.
+/// OAM search always takes exactly 80 dots.
+pub const OAM_SEARCH_BOUNDARY: i32 = MAX_SCANLINE_COUNT - 80;
+
+/// Scanline counter value at which pixel transfer ends when it takes its minimum duration
+/// of 172 dots. Used by the scanline renderer, which does not model mode 3 penalties.
+pub const PIXEL_TRANSFER_BOUNDARY: i32 = OAM_SEARCH_BOUNDARY - 172;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatMode {
     HBlank,
     VBlank,
     OAMSearch,
     PixelTransfer,
 }
 
+impl StatMode {
+    /// Decodes the current PPU mode from the STAT register.
+    pub fn from_stat(stat: u8) -> Self {
+        StatMode::from(stat & MODE_MASK)
+    }
+}
+
 impl From<u8> for StatMode {
     fn from(value: u8) -> Self {
         match value {
@@ -29,10 +47,15 @@ impl From<u8> for StatMode {
     }
 }
 
+/// Computes the new STAT register value, along with the state of the combined STAT
+/// interrupt line. The line is the OR of every enabled interrupt source, and the LCD STAT
+/// interrupt is only requested when it goes from low to high. This means that a source
+/// becoming active while another one already holds the line high does not request a new
+/// interrupt ("STAT IRQ blocking").
 pub struct StatUpdater {
     current_stat: u8,
     new_stat: u8,
-    requires_interrupt: bool,
+    stat_line: bool,
     state_change_already_occured: bool,
 }
 
@@ -41,12 +64,12 @@ impl StatUpdater {
         Self {
             current_stat,
             new_stat: current_stat & !MODE_MASK,
-            requires_interrupt: false,
+            stat_line: false,
             state_change_already_occured: false,
         }
     }
 
-    pub fn process_vblank(mut self, current_scanline: u8) -> Self {
+    pub fn process_vblank(mut self, current_scanline: u8, scanline_counter: i32) -> Self {
         if self.state_change_already_occured {
             return self;
         }
@@ -56,36 +79,53 @@ impl StatUpdater {
             self.new_stat |= StatMode::VBlank as u8;
 
             if self.current_stat & MODE_1_INTERRUPT_MASK > 0 {
-                self.requires_interrupt = true;
+                self.stat_line = true;
+            }
+
+            // On the DMG, the mode 2 interrupt source also fires at the start of line 144,
+            // even though the PPU goes straight into v-blank.
+            if current_scanline == V_BLANK_BOUNDARY
+                && scanline_counter >= OAM_SEARCH_BOUNDARY
+                && self.current_stat & MODE_2_INTERRUPT_MASK > 0
+            {
+                self.stat_line = true;
             }
         }
 
         return self;
     }
 
-    pub fn process_oam_search(mut self, scanline_counter: i32) -> Self {
+    /// The first line after the LCD is turned on skips OAM search, the PPU reports
+    /// mode 0 instead of mode 2 until pixel transfer begins.
+    pub fn process_oam_search(mut self, scanline_counter: i32, lcd_just_enabled: bool) -> Self {
         if self.state_change_already_occured {
             return self;
         }
 
         if scanline_counter >= OAM_SEARCH_BOUNDARY {
             self.state_change_already_occured = true;
+
+            if lcd_just_enabled {
+                self.new_stat |= StatMode::HBlank as u8;
+                return self;
+            }
+
             self.new_stat |= StatMode::OAMSearch as u8;
 
             if self.current_stat & MODE_2_INTERRUPT_MASK > 0 {
-                self.requires_interrupt = true;
+                self.stat_line = true;
             }
         }
 
         return self;
     }
 
-    pub fn process_pixel_transfer(mut self, scanline_counter: i32) -> Self {
+    pub fn process_pixel_transfer(mut self, in_pixel_transfer: bool) -> Self {
         if self.state_change_already_occured {
             return self;
         }
 
-        if scanline_counter >= TRANSFER_PIXEL_BOUNDARY {
+        if in_pixel_transfer {
             self.state_change_already_occured = true;
             self.new_stat |= StatMode::PixelTransfer as u8;
         }
@@ -102,7 +142,7 @@ impl StatUpdater {
         self.new_stat |= StatMode::HBlank as u8;
 
         if self.current_stat & MODE_0_INTERRUPT_MASK > 0 {
-            self.requires_interrupt = true;
+            self.stat_line = true;
         }
 
         return self;
@@ -111,18 +151,19 @@ impl StatUpdater {
     pub fn process_ly_lyc(mut self, ly: u8, lyc: u8) -> Self {
         if ly == lyc {
             self.new_stat |= STAT_LY_LYC_MASK;
+
+            if self.current_stat & LY_LYC_INTERRUPT_MASK > 0 {
+                self.stat_line = true;
+            }
         } else {
             self.new_stat &= !STAT_LY_LYC_MASK;
         }
 
-        if self.current_stat & LY_LYC_INTERRUPT_MASK > 0 {
-            self.requires_interrupt = true;
-        }
-
         return self;
     }
 
+    /// Returns the new STAT register value and the state of the STAT interrupt line.
     pub fn build(&self) -> (u8, bool) {
-        (self.new_stat, self.requires_interrupt)
+        (self.new_stat, self.stat_line)
     }
 }
@@ -0,0 +1,158 @@
+use crate::ppu::stat::{self, StatMode, StatUpdater};
+
+const MODE_0_INTERRUPT: u8 = 1 << 3;
+const MODE_1_INTERRUPT: u8 = 1 << 4;
+const MODE_2_INTERRUPT: u8 = 1 << 5;
+const LY_LYC_INTERRUPT: u8 = 1 << 6;
+
+#[test]
+fn stat_line() {
+    struct TestCase {
+        description: String,
+        stat: u8,
+        ly: u8,
+        lyc: u8,
+        scanline_counter: i32,
+        in_pixel_transfer: bool,
+        lcd_just_enabled: bool,
+        expected_mode: StatMode,
+        expected_stat_line: bool,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("no interrupt sources enabled"),
+            stat: 0x00,
+            ly: 0,
+            lyc: 0,
+            scanline_counter: stat::MAX_SCANLINE_COUNT,
+            in_pixel_transfer: false,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::OAMSearch,
+            expected_stat_line: false,
+        },
+        TestCase {
+            description: String::from("mode 2 source during OAM search"),
+            stat: MODE_2_INTERRUPT,
+            ly: 0,
+            lyc: 1,
+            scanline_counter: stat::MAX_SCANLINE_COUNT,
+            in_pixel_transfer: false,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::OAMSearch,
+            expected_stat_line: true,
+        },
+        TestCase {
+            description: String::from("first line after LCD enable reports mode 0"),
+            stat: MODE_2_INTERRUPT,
+            ly: 0,
+            lyc: 1,
+            scanline_counter: stat::MAX_SCANLINE_COUNT,
+            in_pixel_transfer: false,
+            lcd_just_enabled: true,
+            expected_mode: StatMode::HBlank,
+            expected_stat_line: false,
+        },
+        TestCase {
+            description: String::from("no source during pixel transfer"),
+            stat: MODE_0_INTERRUPT | MODE_1_INTERRUPT | MODE_2_INTERRUPT,
+            ly: 0,
+            lyc: 1,
+            scanline_counter: stat::OAM_SEARCH_BOUNDARY - 1,
+            in_pixel_transfer: true,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::PixelTransfer,
+            expected_stat_line: false,
+        },
+        TestCase {
+            description: String::from("mode 0 source during h-blank"),
+            stat: MODE_0_INTERRUPT,
+            ly: 0,
+            lyc: 1,
+            scanline_counter: 10,
+            in_pixel_transfer: false,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::HBlank,
+            expected_stat_line: true,
+        },
+        TestCase {
+            description: String::from("mode 1 source during v-blank"),
+            stat: MODE_1_INTERRUPT,
+            ly: 150,
+            lyc: 0,
+            scanline_counter: 10,
+            in_pixel_transfer: false,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::VBlank,
+            expected_stat_line: true,
+        },
+        TestCase {
+            description: String::from("mode 2 source also fires at the start of line 144"),
+            stat: MODE_2_INTERRUPT,
+            ly: 144,
+            lyc: 0,
+            scanline_counter: stat::MAX_SCANLINE_COUNT,
+            in_pixel_transfer: false,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::VBlank,
+            expected_stat_line: true,
+        },
+        TestCase {
+            description: String::from("mode 2 source does not fire later in v-blank"),
+            stat: MODE_2_INTERRUPT,
+            ly: 145,
+            lyc: 0,
+            scanline_counter: stat::MAX_SCANLINE_COUNT,
+            in_pixel_transfer: false,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::VBlank,
+            expected_stat_line: false,
+        },
+        TestCase {
+            description: String::from("LY=LYC source only when LY matches LYC"),
+            stat: LY_LYC_INTERRUPT,
+            ly: 42,
+            lyc: 43,
+            scanline_counter: 10,
+            in_pixel_transfer: false,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::HBlank,
+            expected_stat_line: false,
+        },
+        TestCase {
+            description: String::from("LY=LYC source when LY matches LYC"),
+            stat: LY_LYC_INTERRUPT,
+            ly: 43,
+            lyc: 43,
+            scanline_counter: stat::OAM_SEARCH_BOUNDARY - 1,
+            in_pixel_transfer: true,
+            lcd_just_enabled: false,
+            expected_mode: StatMode::PixelTransfer,
+            expected_stat_line: true,
+        },
+    ];
+
+    for tc in test_cases {
+        let (new_stat, stat_line) = StatUpdater::new(tc.stat)
+            .process_vblank(tc.ly, tc.scanline_counter)
+            .process_oam_search(tc.scanline_counter, tc.lcd_just_enabled)
+            .process_pixel_transfer(tc.in_pixel_transfer)
+            .process_hblank()
+            .process_ly_lyc(tc.ly, tc.lyc)
+            .build();
+
+        assert_eq!(
+            StatMode::from_stat(new_stat),
+            tc.expected_mode,
+            "{}",
+            tc.description
+        );
+        assert_eq!(stat_line, tc.expected_stat_line, "{}", tc.description);
+        assert_eq!(
+            new_stat & (1 << 2) > 0,
+            tc.ly == tc.lyc,
+            "{}: unexpected coincidence flag",
+            tc.description
+        );
+    }
+}
@@ -0,0 +1,86 @@
+#[path = "window_test.rs"]
+#[cfg(test)]
+mod test;
+
+const LCDC_BG_WINDOW_ENABLE_MASK: u8 = 1 << 0;
+const LCDC_WINDOW_ENABLE_MASK: u8 = 1 << 5;
+
+/// WX value at which the window lines up with the left edge of the screen.
+pub const WX_OFFSET: u8 = 7;
+
+/// WX value which, due to a hardware bug, hides the window on the current
+/// line but makes it span the entirety of the following line.
+const WX_OVERFLOW: u8 = 166;
+
+/// Window state tracked by the PPU across scanlines.
+#[derive(Debug)]
+pub struct Window {
+    /// Internal line counter, used instead of LY - WY to pick which line of the window
+    /// to render. It only advances on lines where the window was actually rendered,
+    /// so disabling and re-enabling the window mid-frame resumes where it left off.
+    line_counter: u8,
+
+    /// Latched once LY == WY at the start of a line, until the end of the frame.
+    y_triggered: bool,
+
+    /// Set when a window line ended with WX = 166.
+    wx_overflow: bool,
+}
+
+impl Window {
+    pub fn new() -> Self {
+        Self {
+            line_counter: 0,
+            y_triggered: false,
+            wx_overflow: false,
+        }
+    }
+
+    /// Called at the start of every frame.
+    pub fn reset(&mut self) {
+        *self = Window::new();
+    }
+
+    /// Called at the start of every visible line, checks the window's Y condition.
+    pub fn latch_y(&mut self, ly: u8, wy: u8) {
+        if ly == wy {
+            self.y_triggered = true;
+        }
+    }
+
+    pub fn get_line(&self) -> u8 {
+        self.line_counter
+    }
+
+    pub fn is_enabled(lcdc: u8) -> bool {
+        lcdc & LCDC_WINDOW_ENABLE_MASK > 0 && lcdc & LCDC_BG_WINDOW_ENABLE_MASK > 0
+    }
+
+    /// Screen X coordinate (possibly negative) at which the window starts on the current line,
+    /// or None if the window is not rendered on this line at all.
+    pub fn start_x(&self, lcdc: u8, wx: u8) -> Option<i16> {
+        if !self.y_triggered || !Window::is_enabled(lcdc) {
+            return None;
+        }
+
+        // The previous line ended with WX = 166, the window spans this entire line.
+        if self.wx_overflow {
+            return Some(0);
+        }
+
+        if wx >= WX_OVERFLOW {
+            return None;
+        }
+
+        return Some(wx as i16 - WX_OFFSET as i16);
+    }
+
+    /// Called at the end of every visible line with whether any window pixel was rendered.
+    pub fn end_line(&mut self, rendered: bool, lcdc: u8, wx: u8) {
+        if rendered {
+            self.line_counter = self.line_counter.wrapping_add(1);
+        }
+
+        self.wx_overflow = self.y_triggered && Window::is_enabled(lcdc) && wx == WX_OVERFLOW;
+    }
+}
@@ -0,0 +1,101 @@
+use crate::ppu::window::Window;
+
+const LCDC_WINDOW_ON: u8 = 0xB1;
+const LCDC_WINDOW_OFF: u8 = 0x91;
+
+#[test]
+fn start_x() {
+    struct TestCase {
+        description: String,
+        wx: u8,
+        expected_start_x: Option<i16>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("WX = 7 lines up with the left edge of the screen"),
+            wx: 7,
+            expected_start_x: Some(0),
+        },
+        TestCase {
+            description: String::from("WX < 7 starts the window off-screen"),
+            wx: 0,
+            expected_start_x: Some(-7),
+        },
+        TestCase {
+            description: String::from("WX = 165 shows a single column of the window"),
+            wx: 165,
+            expected_start_x: Some(158),
+        },
+        TestCase {
+            description: String::from("WX = 166 hides the window on the current line"),
+            wx: 166,
+            expected_start_x: None,
+        },
+        TestCase {
+            description: String::from("WX > 166 hides the window"),
+            wx: 200,
+            expected_start_x: None,
+        },
+    ];
+
+    for tc in test_cases {
+        let mut window = Window::new();
+        window.latch_y(10, 10);
+        assert_eq!(
+            window.start_x(LCDC_WINDOW_ON, tc.wx),
+            tc.expected_start_x,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn y_condition() {
+    let mut window = Window::new();
+
+    window.latch_y(9, 10);
+    assert_eq!(window.start_x(LCDC_WINDOW_ON, 7), None);
+
+    window.latch_y(10, 10);
+    assert_eq!(window.start_x(LCDC_WINDOW_ON, 7), Some(0));
+    assert_eq!(window.start_x(LCDC_WINDOW_OFF, 7), None);
+
+    // Once triggered, the window stays active even if WY changes until the next frame.
+    window.latch_y(11, 50);
+    assert_eq!(window.start_x(LCDC_WINDOW_ON, 7), Some(0));
+
+    window.reset();
+    assert_eq!(window.start_x(LCDC_WINDOW_ON, 7), None);
+}
+
+#[test]
+fn line_counter() {
+    let mut window = Window::new();
+    window.latch_y(0, 0);
+
+    window.end_line(true, LCDC_WINDOW_ON, 7);
+    window.end_line(true, LCDC_WINDOW_ON, 7);
+    assert_eq!(window.get_line(), 2);
+
+    // Lines where the window was disabled do not advance the counter.
+    window.end_line(false, LCDC_WINDOW_OFF, 7);
+    window.end_line(false, LCDC_WINDOW_OFF, 7);
+    assert_eq!(window.get_line(), 2);
+
+    window.end_line(true, LCDC_WINDOW_ON, 7);
+    assert_eq!(window.get_line(), 3);
+}
+
+#[test]
+fn wx_overflow() {
+    let mut window = Window::new();
+    window.latch_y(0, 0);
+
+    window.end_line(false, LCDC_WINDOW_ON, 166);
+    assert_eq!(window.start_x(LCDC_WINDOW_ON, 166), Some(0));
+
+    window.end_line(true, LCDC_WINDOW_ON, 80);
+    assert_eq!(window.start_x(LCDC_WINDOW_ON, 80), Some(73));
+}
@@ -0,0 +1,161 @@
+//! Search of the RAM for the addresses holding a game's variables, such as lives or health.
+//! Candidates start as every byte of RAM, then are narrowed down by comparing each of them
+//! between snapshots taken as the game runs, e.g. keeping those that decreased by one after
+//! losing a life.
+#[path = "ram_search_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::memory::Memory;
+
+/// Parts of the memory map holding RAM the game can keep its variables in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Region {
+    /// Cartridge RAM, if the cartridge has any.
+    Sram,
+
+    /// Work RAM.
+    Wram,
+
+    /// High RAM.
+    Hram,
+}
+
+impl Region {
+    pub const ALL: [Region; 3] = [Region::Sram, Region::Wram, Region::Hram];
+
+    pub fn get_range(self) -> Range<u16> {
+        return match self {
+            Region::Sram => 0xA000..0xC000,
+            Region::Wram => 0xC000..0xE000,
+            Region::Hram => 0xFF80..0xFFFF,
+        };
+    }
+}
+
+/// Values held by the RAM at a point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    values: BTreeMap<u16, u8>,
+}
+
+impl Snapshot {
+    /// Reads the regions without side effects. Addresses the cartridge doesn't map are left
+    /// out.
+    pub fn capture(memory: &Memory, regions: &[Region]) -> Self {
+        let mut values = BTreeMap::new();
+        for region in regions {
+            for address in region.get_range() {
+                if let Some(value) = memory.dma_read(address as usize) {
+                    values.insert(address, value);
+                }
+            }
+        }
+
+        Self { values }
+    }
+
+    pub fn get(&self, address: u16) -> Option<u8> {
+        return self.values.get(&address).copied();
+    }
+}
+
+/// How the value of a candidate must compare between two snapshots for it to be kept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Equal,
+    Changed,
+    Increased,
+    Decreased,
+    IncreasedBy,
+    DecreasedBy,
+    Value,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 7] = [
+        Comparison::Equal,
+        Comparison::Changed,
+        Comparison::Increased,
+        Comparison::Decreased,
+        Comparison::IncreasedBy,
+        Comparison::DecreasedBy,
+        Comparison::Value,
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            Comparison::Equal => "Equal",
+            Comparison::Changed => "Changed",
+            Comparison::Increased => "Increased",
+            Comparison::Decreased => "Decreased",
+            Comparison::IncreasedBy => "Increased by",
+            Comparison::DecreasedBy => "Decreased by",
+            Comparison::Value => "Value =",
+        };
+    }
+
+    /// Whether the comparison uses the operand passed to `matches`.
+    pub fn takes_operand(self) -> bool {
+        return matches!(
+            self,
+            Comparison::IncreasedBy | Comparison::DecreasedBy | Comparison::Value
+        );
+    }
+
+    /// Increases and decreases wrap around, as counters stored in a byte do.
+    pub fn matches(self, operand: u8, previous: u8, current: u8) -> bool {
+        return match self {
+            Comparison::Equal => current == previous,
+            Comparison::Changed => current != previous,
+            Comparison::Increased => current > previous,
+            Comparison::Decreased => current < previous,
+            Comparison::IncreasedBy => current == previous.wrapping_add(operand),
+            Comparison::DecreasedBy => current == previous.wrapping_sub(operand),
+            Comparison::Value => current == operand,
+        };
+    }
+}
+
+pub struct Search {
+    candidates: Vec<u16>,
+
+    // Snapshot the candidates are compared against on the next filter.
+    previous: Snapshot,
+}
+
+impl Search {
+    /// Starts a search with every address of the snapshot as a candidate.
+    pub fn new(snapshot: Snapshot) -> Self {
+        Self {
+            candidates: snapshot.values.keys().copied().collect(),
+            previous: snapshot,
+        }
+    }
+
+    /// Keeps the candidates whose value compares as given between the previous snapshot
+    /// and this one, which the next filter then compares against.
+    pub fn filter(&mut self, comparison: Comparison, operand: u8, snapshot: Snapshot) {
+        let previous = &self.previous;
+        self.candidates.retain(|address| {
+            return match (previous.get(*address), snapshot.get(*address)) {
+                (Some(previous), Some(current)) => comparison.matches(operand, previous, current),
+                _ => false,
+            };
+        });
+
+        self.previous = snapshot;
+    }
+
+    pub fn get_candidates(&self) -> &[u16] {
+        return &self.candidates;
+    }
+
+    /// Value of the address in the snapshot the next filter compares against.
+    pub fn get_previous(&self, address: u16) -> Option<u8> {
+        return self.previous.get(address);
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+
+use crate::interrupt;
+use crate::memory::Memory;
+use crate::ram_search::{Comparison, Region, Search, Snapshot};
+use crate::timers;
+
+fn new_memory() -> Memory {
+    Memory::default(
+        Arc::new(Mutex::new(timers::Timers::new())),
+        Arc::new(Mutex::new(interrupt::Bus::new())),
+    )
+}
+
+#[test]
+fn comparison() {
+    struct TestCase {
+        description: String,
+        comparison: Comparison,
+        operand: u8,
+        previous: u8,
+        current: u8,
+        expected: bool,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("equal"),
+            comparison: Comparison::Equal,
+            operand: 0,
+            previous: 3,
+            current: 3,
+            expected: true,
+        },
+        TestCase {
+            description: String::from("changed"),
+            comparison: Comparison::Changed,
+            operand: 0,
+            previous: 3,
+            current: 3,
+            expected: false,
+        },
+        TestCase {
+            description: String::from("increased"),
+            comparison: Comparison::Increased,
+            operand: 0,
+            previous: 3,
+            current: 9,
+            expected: true,
+        },
+        TestCase {
+            description: String::from("decreased"),
+            comparison: Comparison::Decreased,
+            operand: 0,
+            previous: 3,
+            current: 9,
+            expected: false,
+        },
+        TestCase {
+            description: String::from("increased by"),
+            comparison: Comparison::IncreasedBy,
+            operand: 6,
+            previous: 3,
+            current: 9,
+            expected: true,
+        },
+        TestCase {
+            description: String::from("increased by, wrapping around"),
+            comparison: Comparison::IncreasedBy,
+            operand: 2,
+            previous: 0xFF,
+            current: 0x01,
+            expected: true,
+        },
+        TestCase {
+            description: String::from("decreased by"),
+            comparison: Comparison::DecreasedBy,
+            operand: 1,
+            previous: 3,
+            current: 1,
+            expected: false,
+        },
+        TestCase {
+            description: String::from("value"),
+            comparison: Comparison::Value,
+            operand: 9,
+            previous: 3,
+            current: 9,
+            expected: true,
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            tc.comparison.matches(tc.operand, tc.previous, tc.current),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn snapshot() {
+    let mut memory = new_memory();
+    memory.poke(0xC000, 0x12);
+    memory.poke(0xDFFF, 0x34);
+    memory.poke(0xFF80, 0x56);
+
+    let snapshot = Snapshot::capture(&memory, &[Region::Wram, Region::Hram]);
+    assert_eq!(snapshot.get(0xC000), Some(0x12));
+    assert_eq!(snapshot.get(0xDFFF), Some(0x34));
+    assert_eq!(snapshot.get(0xFF80), Some(0x56));
+
+    // Regions not captured, echo RAM and the interrupt enable register are left out.
+    assert_eq!(snapshot.get(0xA000), None);
+    assert_eq!(snapshot.get(0xE000), None);
+    assert_eq!(snapshot.get(0xFFFF), None);
+}
+
+#[test]
+fn search() {
+    let regions = [Region::Wram, Region::Hram];
+    let mut memory = new_memory();
+    memory.poke(0xC100, 3);
+    memory.poke(0xD200, 3);
+    memory.poke(0xFF90, 5);
+
+    let mut search = Search::new(Snapshot::capture(&memory, &regions));
+    assert_eq!(search.get_candidates().len(), 0x2000 + 0x7F);
+
+    // Lives go from 3 to 2, while another 3 goes up.
+    memory.poke(0xC100, 2);
+    memory.poke(0xD200, 4);
+    memory.poke(0xFF90, 4);
+    search.filter(
+        Comparison::DecreasedBy,
+        1,
+        Snapshot::capture(&memory, &regions),
+    );
+    assert_eq!(search.get_candidates(), &[0xC100, 0xFF90]);
+    assert_eq!(search.get_previous(0xC100), Some(2));
+
+    // Candidates are compared against the last snapshot.
+    memory.poke(0xFF90, 0);
+    search.filter(Comparison::Value, 2, Snapshot::capture(&memory, &regions));
+    assert_eq!(search.get_candidates(), &[0xC100]);
+
+    search.filter(Comparison::Equal, 0, Snapshot::capture(&memory, &regions));
+    assert_eq!(search.get_candidates(), &[0xC100]);
+}
@@ -0,0 +1,260 @@
+//! Recording of the emulator output, either to an animated GIF or to a lossless PNG
+//! frame sequence with a WAV track alongside it, to be muxed with an external tool.
+//!
+//! Frames are encoded on a separate thread so that recording does not hold up the UI.
+#[path = "recorder_test.rs"]
+#[cfg(test)]
+mod test;
+
+mod wav;
+
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+
+use crate::cpu::CPU_FREQUENCY;
+use crate::filters::Image;
+use crate::ppu::CYCLES_PER_FRAME;
+use crate::screenshot;
+
+/// Sample rate of the WAV track written alongside frame sequences.
+pub const AUDIO_SAMPLE_RATE: u32 = 48_000;
+const AUDIO_CHANNELS: u16 = 2;
+
+const AUDIO_FILE_NAME: &str = "audio.wav";
+
+/// Output format of a recording.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Gif,
+    HalfRateGif,
+    FrameSequence,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Gif, Format::HalfRateGif, Format::FrameSequence];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Format::Gif => "Animated GIF",
+            Format::HalfRateGif => "Animated GIF (half rate)",
+            Format::FrameSequence => "PNG Sequence + WAV",
+        }
+    }
+}
+
+/// Directory recordings started from the frontend are saved to.
+pub fn directory() -> PathBuf {
+    return PathBuf::from("recordings");
+}
+
+/// Timestamped path for a new recording in the given directory. GIFs are a single file,
+/// frame sequences a directory of their own.
+pub fn new_path(directory: &Path, format: Format) -> PathBuf {
+    let (unix_seconds, millis) = screenshot::now();
+    let name = format!("recording-{}", screenshot::timestamp(unix_seconds, millis));
+
+    return match format {
+        Format::Gif | Format::HalfRateGif => directory.join(name + ".gif"),
+        Format::FrameSequence => directory.join(name),
+    };
+}
+
+/// Delay of the given GIF frame, in hundredths of a second, or `None` when the frame is
+/// dropped. One frame out of every `frame_step` is kept, and lasts for the dropped ones
+/// after it. GIF delays are whole hundredths, so the ~1.674 hundredths of each Gameboy
+/// frame are rounded in a way that never drifts more than half a hundredth from its
+/// ~59.73 Hz refresh rate: every frame alternates between 1 and 2 hundredths, and every
+/// other frame between 3 and 4.
+///
+/// Browsers and most viewers play delays of a hundredth of a second or less as a tenth,
+/// which is what halving the frame rate is for.
+pub fn gif_frame_delay(index: u64, frame_step: u64) -> Option<u32> {
+    if !index.is_multiple_of(frame_step) {
+        return None;
+    }
+
+    let hundredths_at = |frame: u64| -> u64 {
+        let cycles = frame * CYCLES_PER_FRAME as u64 * 100;
+        return (cycles + CPU_FREQUENCY as u64 / 2) / CPU_FREQUENCY as u64;
+    };
+
+    return Some((hundredths_at(index + frame_step) - hundredths_at(index)) as u32);
+}
+
+/// Number of audio samples per channel spanning the given number of frames.
+pub fn audio_sample_count(frame_count: u64) -> u64 {
+    let cycles = frame_count * CYCLES_PER_FRAME as u64 * AUDIO_SAMPLE_RATE as u64;
+    return (cycles + CPU_FREQUENCY as u64 / 2) / CPU_FREQUENCY as u64;
+}
+
+trait FrameWriter: Send {
+    fn write_frame(&mut self, index: u64, image: &Image) -> Result<(), String>;
+
+    fn finish(self: Box<Self>, frame_count: u64) -> Result<(), String>;
+}
+
+struct GifWriter {
+    path: PathBuf,
+    encoder: GifEncoder<BufWriter<fs::File>>,
+    frame_step: u64,
+}
+
+impl GifWriter {
+    fn new(path: &Path, frame_step: u64) -> Result<Self, String> {
+        let file = fs::File::create(path)
+            .map_err(|err| format!("could not create {}: {}", path.display(), err))?;
+
+        let mut encoder = GifEncoder::new(BufWriter::new(file));
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|err| format!("could not write {}: {}", path.display(), err))?;
+
+        return Ok(Self {
+            path: path.to_path_buf(),
+            encoder,
+            frame_step,
+        });
+    }
+}
+
+impl FrameWriter for GifWriter {
+    fn write_frame(&mut self, index: u64, image: &Image) -> Result<(), String> {
+        let delay = match gif_frame_delay(index, self.frame_step) {
+            Some(delay) => image::Delay::from_numer_denom_ms(delay * 10, 1),
+            None => return Ok(()),
+        };
+
+        let buffer = image::RgbaImage::from_raw(
+            image.get_width() as u32,
+            image.get_height() as u32,
+            image.to_rgba_bytes(),
+        )
+        .unwrap();
+
+        // Frames have at most a handful of distinct colours, which the encoder maps to an
+        // exact palette, so no quality is lost.
+        return self
+            .encoder
+            .encode_frame(image::Frame::from_parts(buffer, 0, 0, delay))
+            .map_err(|err| format!("could not write {}: {}", self.path.display(), err));
+    }
+
+    fn finish(self: Box<Self>, _: u64) -> Result<(), String> {
+        // The GIF trailer is written and the file flushed when the encoder is dropped.
+        drop(self.encoder);
+        return Ok(());
+    }
+}
+
+struct FrameSequenceWriter {
+    directory: PathBuf,
+}
+
+impl FrameSequenceWriter {
+    fn new(directory: &Path) -> Result<Self, String> {
+        fs::create_dir_all(directory)
+            .map_err(|err| format!("could not create {}: {}", directory.display(), err))?;
+
+        return Ok(Self {
+            directory: directory.to_path_buf(),
+        });
+    }
+}
+
+impl FrameWriter for FrameSequenceWriter {
+    fn write_frame(&mut self, index: u64, image: &Image) -> Result<(), String> {
+        let path = self.directory.join(format!("frame_{:06}.png", index));
+        return screenshot::save_png(image, &path);
+    }
+
+    // The emulator has no APU yet, so the audio track is silent. It still spans exactly the
+    // recorded frames so that muxing it in keeps the video at the right frame rate.
+    fn finish(self: Box<Self>, frame_count: u64) -> Result<(), String> {
+        let sample_count = audio_sample_count(frame_count) as usize * AUDIO_CHANNELS as usize;
+        let path = self.directory.join(AUDIO_FILE_NAME);
+
+        return wav::write(
+            &path,
+            AUDIO_SAMPLE_RATE,
+            AUDIO_CHANNELS,
+            &vec![0; sample_count],
+        )
+        .map_err(|err| format!("could not write {}: {}", path.display(), err));
+    }
+}
+
+/// An in-progress recording.
+pub struct Recorder {
+    path: PathBuf,
+    frame_count: u64,
+    frame_sender: mpsc::Sender<Image>,
+    writer_thread: thread::JoinHandle<Result<(), String>>,
+}
+
+impl Recorder {
+    /// Starts recording to the given path, a GIF file or the directory the frame sequence
+    /// is written to.
+    pub fn start(format: Format, path: PathBuf) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("could not create {}: {}", parent.display(), err))?;
+        }
+
+        let mut writer: Box<dyn FrameWriter> = match format {
+            Format::Gif => Box::new(GifWriter::new(&path, 1)?),
+            Format::HalfRateGif => Box::new(GifWriter::new(&path, 2)?),
+            Format::FrameSequence => Box::new(FrameSequenceWriter::new(&path)?),
+        };
+
+        let (frame_sender, frame_receiver) = mpsc::channel::<Image>();
+        let writer_thread = thread::spawn(move || {
+            let mut frame_count = 0;
+            for image in frame_receiver {
+                writer.write_frame(frame_count, &image)?;
+                frame_count += 1;
+            }
+
+            return writer.finish(frame_count);
+        });
+
+        return Ok(Self {
+            path,
+            frame_count: 0,
+            frame_sender,
+            writer_thread,
+        });
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Queues a frame to be written. Frames are expected to be recorded once for every
+    /// frame output by the emulator.
+    pub fn record_frame(&mut self, image: Image) {
+        // The writer thread only hangs up after a write error, which `stop` reports.
+        if self.frame_sender.send(image).is_ok() {
+            self.frame_count += 1;
+        }
+    }
+
+    /// Waits for all queued frames to be written and finalizes the recording.
+    pub fn stop(self) -> Result<PathBuf, String> {
+        drop(self.frame_sender);
+
+        return match self.writer_thread.join() {
+            Ok(Ok(_)) => Ok(self.path),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(String::from("recording thread panicked")),
+        };
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::PathBuf;
+
+use image::AnimationDecoder;
+
+use crate::filters::Image;
+use crate::recorder::{self, wav, Format, Recorder};
+
+fn temp_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!(
+        "rusty_fuugbemu_recorder_test_{}_{}",
+        name,
+        std::process::id()
+    ));
+}
+
+fn test_frames() -> Vec<Image> {
+    let colors = [
+        [0xFF, 0xFF, 0xFF, 0xFF],
+        [0x80, 0x80, 0x80, 0xFF],
+        [0x00, 0x00, 0x00, 0xFF],
+    ];
+
+    return colors
+        .iter()
+        .map(|color| {
+            let mut image = Image::new(4, 2);
+            image.set(1, 1, *color);
+            image
+        })
+        .collect();
+}
+
+#[test]
+fn gif_frame_delay() {
+    let duration = |frames: u64, frame_step: u64| -> u64 {
+        return (0..frames)
+            .filter_map(|index| recorder::gif_frame_delay(index, frame_step))
+            .map(u64::from)
+            .sum();
+    };
+
+    // Every frame is kept, lasting 1 or 2 hundredths.
+    let delays: Vec<Option<u32>> = (0..1000)
+        .map(|index| recorder::gif_frame_delay(index, 1))
+        .collect();
+    assert!(delays
+        .iter()
+        .all(|delay| *delay == Some(1) || *delay == Some(2)));
+
+    // 100 frames at ~59.73 Hz last ~1.674 seconds, and half an hour's worth of frames
+    // ~1674.27 seconds, without drifting.
+    assert_eq!(duration(100, 1), 167);
+    assert_eq!(duration(1000, 1), 1674);
+    assert_eq!(duration(100_000, 1), 167_427);
+
+    // At half rate, every other frame is dropped and kept ones last 3 or 4 hundredths.
+    let delays: Vec<Option<u32>> = (0..1000)
+        .map(|index| recorder::gif_frame_delay(index, 2))
+        .collect();
+    assert!(delays.iter().skip(1).step_by(2).all(Option::is_none));
+    assert!(delays
+        .iter()
+        .step_by(2)
+        .all(|delay| *delay == Some(3) || *delay == Some(4)));
+    assert_eq!(duration(100, 2), 167);
+    assert_eq!(duration(100_000, 2), 167_427);
+}
+
+#[test]
+fn audio_sample_count() {
+    assert_eq!(recorder::audio_sample_count(0), 0);
+    assert_eq!(recorder::audio_sample_count(1), 804);
+    assert_eq!(recorder::audio_sample_count(60), 48219);
+}
+
+#[test]
+fn wav_header() {
+    let mut bytes: Vec<u8> = Vec::new();
+    wav::write_to(&mut bytes, 48_000, 2, &[0x0102, -1]).unwrap();
+
+    let expected: Vec<u8> = [
+        b"RIFF".as_slice(),
+        &40u32.to_le_bytes(),
+        b"WAVE",
+        b"fmt ",
+        &16u32.to_le_bytes(),
+        &1u16.to_le_bytes(),
+        &2u16.to_le_bytes(),
+        &48_000u32.to_le_bytes(),
+        &192_000u32.to_le_bytes(),
+        &4u16.to_le_bytes(),
+        &16u16.to_le_bytes(),
+        b"data",
+        &4u32.to_le_bytes(),
+        &[0x02, 0x01, 0xFF, 0xFF],
+    ]
+    .concat();
+
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn record_gif() {
+    for (format, frame_step) in [(Format::Gif, 1), (Format::HalfRateGif, 2)] {
+        let path = temp_path("gif").join("recording.gif");
+        let frames = test_frames();
+
+        let mut recorder = Recorder::start(format, path.clone()).unwrap();
+        for frame in frames.iter() {
+            recorder.record_frame(frame.clone());
+        }
+        assert_eq!(recorder.get_frame_count(), 3);
+        assert_eq!(recorder.stop().unwrap(), path);
+
+        let file = fs::File::open(&path).unwrap();
+        let decoder = image::codecs::gif::GifDecoder::new(std::io::BufReader::new(file)).unwrap();
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+
+        let kept: Vec<(usize, &Image)> = frames.iter().enumerate().step_by(frame_step).collect();
+        assert_eq!(decoded.len(), kept.len(), "{}", format.get_name());
+
+        for (decoded, (index, frame)) in decoded.iter().zip(kept) {
+            let (numerator, denominator) = decoded.delay().numer_denom_ms();
+            assert_eq!(
+                Some(numerator / denominator),
+                recorder::gif_frame_delay(index as u64, frame_step as u64).map(|delay| delay * 10)
+            );
+            assert_eq!(decoded.buffer().as_raw(), &frame.to_rgba_bytes());
+        }
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn record_frame_sequence() {
+    let path = temp_path("sequence");
+    let frames = test_frames();
+
+    let mut recorder = Recorder::start(Format::FrameSequence, path.clone()).unwrap();
+    for frame in frames.iter() {
+        recorder.record_frame(frame.clone());
+    }
+    recorder.stop().unwrap();
+
+    for (index, frame) in frames.iter().enumerate() {
+        let decoded = image::open(path.join(format!("frame_{:06}.png", index)))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(decoded.into_raw(), frame.to_rgba_bytes());
+    }
+
+    // Silent 16-bit stereo track spanning the 3 frames.
+    let wav = fs::read(path.join("audio.wav")).unwrap();
+    assert_eq!(
+        wav.len(),
+        44 + recorder::audio_sample_count(3) as usize * 2 * 2
+    );
+
+    fs::remove_dir_all(&path).unwrap();
+}
@@ -0,0 +1,44 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const BITS_PER_SAMPLE: u16 = 16;
+const PCM_FORMAT: u16 = 1;
+
+/// Writes interleaved 16-bit PCM samples to a WAV file.
+pub fn write(path: &Path, sample_rate: u32, channels: u16, samples: &[i16]) -> io::Result<()> {
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    write_to(&mut writer, sample_rate, channels, samples)?;
+    return writer.flush();
+}
+
+pub fn write_to<W: Write>(
+    writer: &mut W,
+    sample_rate: u32,
+    channels: u16,
+    samples: &[i16],
+) -> io::Result<()> {
+    let block_align = channels * BITS_PER_SAMPLE / 8;
+    let data_size = (samples.len() * 2) as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&PCM_FORMAT.to_le_bytes())?;
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+
+    return Ok(());
+}
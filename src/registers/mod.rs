@@ -0,0 +1,560 @@
+//! Descriptions of the hardware registers mapped in `memory::io_registers`, to decode them
+//! into named bitfields for debugging.
+#[path = "registers_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::memory::io_registers;
+
+/// Bits of a register holding a single value.
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub name: &'static str,
+    pub shift: u8,
+    pub width: u8,
+
+    /// Meaning of each of the field's values, or empty when the value speaks for itself.
+    pub meanings: &'static [&'static str],
+}
+
+impl Field {
+    const fn bit(name: &'static str, bit: u8, meanings: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            shift: bit,
+            width: 1,
+            meanings,
+        }
+    }
+
+    const fn bits(
+        name: &'static str,
+        shift: u8,
+        width: u8,
+        meanings: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            shift,
+            width,
+            meanings,
+        }
+    }
+
+    pub fn get_mask(&self) -> u8 {
+        return (((1u16 << self.width) - 1) as u8) << self.shift;
+    }
+
+    pub fn get_max(&self) -> u8 {
+        return self.get_mask() >> self.shift;
+    }
+
+    /// Value of the field in the register's value.
+    pub fn get(&self, register: u8) -> u8 {
+        return (register & self.get_mask()) >> self.shift;
+    }
+
+    /// Register's value with the field set to the value, which is truncated to fit.
+    pub fn set(&self, register: u8, value: u8) -> u8 {
+        return (register & !self.get_mask()) | ((value << self.shift) & self.get_mask());
+    }
+
+    /// Human readable value of the field in the register's value.
+    pub fn describe(&self, register: u8) -> String {
+        let value = self.get(register);
+
+        return match self.meanings.get(value as usize) {
+            Some(meaning) => String::from(*meaning),
+            None if self.width == 8 => format!("{} ({:#04X})", value, value),
+            None => format!("{}", value),
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Group {
+    Joypad,
+    Serial,
+    Timer,
+    Interrupts,
+    Audio,
+    Lcd,
+    Boot,
+}
+
+impl Group {
+    pub const ALL: [Group; 7] = [
+        Group::Joypad,
+        Group::Serial,
+        Group::Timer,
+        Group::Interrupts,
+        Group::Audio,
+        Group::Lcd,
+        Group::Boot,
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            Group::Joypad => "Joypad",
+            Group::Serial => "Serial",
+            Group::Timer => "Timer",
+            Group::Interrupts => "Interrupts",
+            Group::Audio => "Audio",
+            Group::Lcd => "LCD",
+            Group::Boot => "Boot",
+        };
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Register {
+    pub address: usize,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub group: Group,
+    pub fields: &'static [Field],
+}
+
+impl Register {
+    /// Whether the register can be edited while debugging. Writes to DMA start a transfer
+    /// over OAM, which is better done through the hex editor.
+    pub fn is_editable(&self) -> bool {
+        return self.address != io_registers::OAM_DMA_TRANSFER_ADDR;
+    }
+}
+
+const DISABLED_ENABLED: &[&str] = &["Disabled", "Enabled"];
+const OFF_ON: &[&str] = &["Off", "On"];
+const SHADES: &[&str] = &["White", "Light gray", "Dark gray", "Black"];
+const TILE_MAPS: &[&str] = &["0x9800-0x9BFF", "0x9C00-0x9FFF"];
+
+// P1 lines are low while selected or pressed.
+const SELECTED: &[&str] = &["Selected", "Not selected"];
+const PRESSED: &[&str] = &["Pressed", "Released"];
+
+const INTERRUPT_NAMES: [&str; 5] = ["VBlank", "LCD STAT", "Timer", "Serial", "Joypad"];
+
+const fn interrupt_fields(meanings: &'static [&'static str]) -> [Field; 5] {
+    return [
+        Field::bit(INTERRUPT_NAMES[0], 0, meanings),
+        Field::bit(INTERRUPT_NAMES[1], 1, meanings),
+        Field::bit(INTERRUPT_NAMES[2], 2, meanings),
+        Field::bit(INTERRUPT_NAMES[3], 3, meanings),
+        Field::bit(INTERRUPT_NAMES[4], 4, meanings),
+    ];
+}
+
+const INTERRUPT_FLAG_FIELDS: [Field; 5] = interrupt_fields(&["-", "Requested"]);
+const INTERRUPT_ENABLE_FIELDS: [Field; 5] = interrupt_fields(DISABLED_ENABLED);
+
+const DUTY_LENGTH_FIELDS: [Field; 2] = [
+    Field::bits("Duty cycle", 6, 2, &["12.5%", "25%", "50%", "75%"]),
+    Field::bits("Initial length timer", 0, 6, &[]),
+];
+
+const ENVELOPE_FIELDS: [Field; 3] = [
+    Field::bits("Initial volume", 4, 4, &[]),
+    Field::bit("Envelope direction", 3, &["Decrease", "Increase"]),
+    Field::bits("Envelope pace", 0, 3, &[]),
+];
+
+const PERIOD_LOW_FIELDS: [Field; 1] = [Field::bits("Period low", 0, 8, &[])];
+
+const PERIOD_HIGH_FIELDS: [Field; 3] = [
+    Field::bit("Trigger", 7, &["-", "Triggered"]),
+    Field::bit("Length timer", 6, DISABLED_ENABLED),
+    Field::bits("Period high", 0, 3, &[]),
+];
+
+const PALETTE_FIELDS: [Field; 4] = [
+    Field::bits("Colour 0", 0, 2, SHADES),
+    Field::bits("Colour 1", 2, 2, SHADES),
+    Field::bits("Colour 2", 4, 2, SHADES),
+    Field::bits("Colour 3", 6, 2, SHADES),
+];
+
+pub const LCDC: Register = Register {
+    address: io_registers::LCD_CONTROL_ADDR,
+    name: "LCDC",
+    description: "LCD control",
+    group: Group::Lcd,
+    fields: &[
+        Field::bit("LCD & PPU", 7, OFF_ON),
+        Field::bit("Window tile map", 6, TILE_MAPS),
+        Field::bit("Window", 5, DISABLED_ENABLED),
+        Field::bit(
+            "BG & window tile data",
+            4,
+            &["0x8800-0x97FF (signed)", "0x8000-0x8FFF (unsigned)"],
+        ),
+        Field::bit("BG tile map", 3, TILE_MAPS),
+        Field::bit("Sprite size", 2, &["8x8", "8x16"]),
+        Field::bit("Sprites", 1, DISABLED_ENABLED),
+        Field::bit("BG & window", 0, DISABLED_ENABLED),
+    ],
+};
+
+/// Every register, in address order.
+pub const REGISTERS: &[Register] = &[
+    Register {
+        address: io_registers::JOYPAD_ADDR,
+        name: "P1",
+        description: "Joypad",
+        group: Group::Joypad,
+        fields: &[
+            Field::bit("Action buttons", 5, SELECTED),
+            Field::bit("Direction buttons", 4, SELECTED),
+            Field::bit("Start / Down", 3, PRESSED),
+            Field::bit("Select / Up", 2, PRESSED),
+            Field::bit("B / Left", 1, PRESSED),
+            Field::bit("A / Right", 0, PRESSED),
+        ],
+    },
+    Register {
+        address: io_registers::SERIAL_TRANSFER_DATA_ADDR,
+        name: "SB",
+        description: "Serial transfer data",
+        group: Group::Serial,
+        fields: &[Field::bits("Data", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::SERIAL_TRANSFER_CONTROL_ADDR,
+        name: "SC",
+        description: "Serial transfer control",
+        group: Group::Serial,
+        fields: &[
+            Field::bit("Transfer", 7, &["Idle", "In progress"]),
+            Field::bit("Clock", 0, &["External", "Internal"]),
+        ],
+    },
+    Register {
+        address: io_registers::TIMER_DIV_ADDR,
+        name: "DIV",
+        description: "Divider",
+        group: Group::Timer,
+        fields: &[Field::bits("Divider", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::TIMER_COUNTER_ADDR,
+        name: "TIMA",
+        description: "Timer counter",
+        group: Group::Timer,
+        fields: &[Field::bits("Counter", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::TIMER_MOD_ADDR,
+        name: "TMA",
+        description: "Timer modulo",
+        group: Group::Timer,
+        fields: &[Field::bits("Modulo", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::TIMER_CTRL_ADDR,
+        name: "TAC",
+        description: "Timer control",
+        group: Group::Timer,
+        fields: &[
+            Field::bit("Timer", 2, DISABLED_ENABLED),
+            Field::bits(
+                "Clock",
+                0,
+                2,
+                &["4096 Hz", "262144 Hz", "65536 Hz", "16384 Hz"],
+            ),
+        ],
+    },
+    Register {
+        address: io_registers::INTERRUPT_FLAG_REGISTER_ADDR,
+        name: "IF",
+        description: "Interrupt flags",
+        group: Group::Interrupts,
+        fields: &INTERRUPT_FLAG_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH1_SWEEP_ADDR,
+        name: "NR10",
+        description: "Channel 1 sweep",
+        group: Group::Audio,
+        fields: &[
+            Field::bits("Sweep pace", 4, 3, &[]),
+            Field::bit("Sweep direction", 3, &["Increase", "Decrease"]),
+            Field::bits("Sweep step", 0, 3, &[]),
+        ],
+    },
+    Register {
+        address: io_registers::AUDIO_CH1_LENGTH_ADDR,
+        name: "NR11",
+        description: "Channel 1 duty cycle & length timer",
+        group: Group::Audio,
+        fields: &DUTY_LENGTH_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH1_VOLUME_ADDR,
+        name: "NR12",
+        description: "Channel 1 volume & envelope",
+        group: Group::Audio,
+        fields: &ENVELOPE_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH1_WAV_LO_ADDR,
+        name: "NR13",
+        description: "Channel 1 period low",
+        group: Group::Audio,
+        fields: &PERIOD_LOW_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH1_WAV_HI_ADDR,
+        name: "NR14",
+        description: "Channel 1 period high & control",
+        group: Group::Audio,
+        fields: &PERIOD_HIGH_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH2_LENGTH_ADDR,
+        name: "NR21",
+        description: "Channel 2 duty cycle & length timer",
+        group: Group::Audio,
+        fields: &DUTY_LENGTH_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH2_VOLUME_ADDR,
+        name: "NR22",
+        description: "Channel 2 volume & envelope",
+        group: Group::Audio,
+        fields: &ENVELOPE_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH2_WAV_LO_ADDR,
+        name: "NR23",
+        description: "Channel 2 period low",
+        group: Group::Audio,
+        fields: &PERIOD_LOW_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH2_WAV_HI_ADDR,
+        name: "NR24",
+        description: "Channel 2 period high & control",
+        group: Group::Audio,
+        fields: &PERIOD_HIGH_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH3_DAC_ENABLE_ADDR,
+        name: "NR30",
+        description: "Channel 3 DAC enable",
+        group: Group::Audio,
+        fields: &[Field::bit("DAC", 7, OFF_ON)],
+    },
+    Register {
+        address: io_registers::AUDIO_CH3_LENGTH_ADDR,
+        name: "NR31",
+        description: "Channel 3 length timer",
+        group: Group::Audio,
+        fields: &[Field::bits("Initial length timer", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::AUDIO_CH3_OUTPUT_LVL_ADDR,
+        name: "NR32",
+        description: "Channel 3 output level",
+        group: Group::Audio,
+        fields: &[Field::bits(
+            "Output level",
+            5,
+            2,
+            &["Mute", "100%", "50%", "25%"],
+        )],
+    },
+    Register {
+        address: io_registers::AUDIO_CH3_WAV_LO_ADDR,
+        name: "NR33",
+        description: "Channel 3 period low",
+        group: Group::Audio,
+        fields: &PERIOD_LOW_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH3_WAV_HI_ADDR,
+        name: "NR34",
+        description: "Channel 3 period high & control",
+        group: Group::Audio,
+        fields: &PERIOD_HIGH_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH4_LENGTH_ADDR,
+        name: "NR41",
+        description: "Channel 4 length timer",
+        group: Group::Audio,
+        fields: &[Field::bits("Initial length timer", 0, 6, &[])],
+    },
+    Register {
+        address: io_registers::AUDIO_CH4_VOLUME_ADDR,
+        name: "NR42",
+        description: "Channel 4 volume & envelope",
+        group: Group::Audio,
+        fields: &ENVELOPE_FIELDS,
+    },
+    Register {
+        address: io_registers::AUDIO_CH4_FREQ_ADDR,
+        name: "NR43",
+        description: "Channel 4 frequency & randomness",
+        group: Group::Audio,
+        fields: &[
+            Field::bits("Clock shift", 4, 4, &[]),
+            Field::bit("LFSR width", 3, &["15 bits", "7 bits"]),
+            Field::bits("Clock divider", 0, 3, &[]),
+        ],
+    },
+    Register {
+        address: io_registers::AUDIO_CH4_CTRL_ADDR,
+        name: "NR44",
+        description: "Channel 4 control",
+        group: Group::Audio,
+        fields: &[
+            Field::bit("Trigger", 7, &["-", "Triggered"]),
+            Field::bit("Length timer", 6, DISABLED_ENABLED),
+        ],
+    },
+    Register {
+        address: io_registers::AUDIO_GLOBAL_VOLUME_ADDR,
+        name: "NR50",
+        description: "Master volume & VIN panning",
+        group: Group::Audio,
+        fields: &[
+            Field::bit("VIN left", 7, OFF_ON),
+            Field::bits("Left volume", 4, 3, &[]),
+            Field::bit("VIN right", 3, OFF_ON),
+            Field::bits("Right volume", 0, 3, &[]),
+        ],
+    },
+    Register {
+        address: io_registers::AUDIO_GLOBAL_PANNING_ADDR,
+        name: "NR51",
+        description: "Sound panning",
+        group: Group::Audio,
+        fields: &[
+            Field::bit("Channel 4 left", 7, OFF_ON),
+            Field::bit("Channel 3 left", 6, OFF_ON),
+            Field::bit("Channel 2 left", 5, OFF_ON),
+            Field::bit("Channel 1 left", 4, OFF_ON),
+            Field::bit("Channel 4 right", 3, OFF_ON),
+            Field::bit("Channel 3 right", 2, OFF_ON),
+            Field::bit("Channel 2 right", 1, OFF_ON),
+            Field::bit("Channel 1 right", 0, OFF_ON),
+        ],
+    },
+    Register {
+        address: io_registers::AUDIO_GLOBAL_CTRL_ADDR,
+        name: "NR52",
+        description: "Audio master control",
+        group: Group::Audio,
+        fields: &[
+            Field::bit("Audio", 7, OFF_ON),
+            Field::bit("Channel 4", 3, OFF_ON),
+            Field::bit("Channel 3", 2, OFF_ON),
+            Field::bit("Channel 2", 1, OFF_ON),
+            Field::bit("Channel 1", 0, OFF_ON),
+        ],
+    },
+    LCDC,
+    Register {
+        address: io_registers::LCD_STAT_ADDR,
+        name: "STAT",
+        description: "LCD status",
+        group: Group::Lcd,
+        fields: &[
+            Field::bit("LYC interrupt", 6, DISABLED_ENABLED),
+            Field::bit("Mode 2 interrupt", 5, DISABLED_ENABLED),
+            Field::bit("Mode 1 interrupt", 4, DISABLED_ENABLED),
+            Field::bit("Mode 0 interrupt", 3, DISABLED_ENABLED),
+            Field::bit("LY = LYC", 2, &["No", "Yes"]),
+            Field::bits(
+                "Mode",
+                0,
+                2,
+                &["0 - HBlank", "1 - VBlank", "2 - OAM search", "3 - Drawing"],
+            ),
+        ],
+    },
+    Register {
+        address: io_registers::LCD_SCY_ADDR,
+        name: "SCY",
+        description: "Background scroll Y",
+        group: Group::Lcd,
+        fields: &[Field::bits("Scroll Y", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::LCD_SCX_ADDR,
+        name: "SCX",
+        description: "Background scroll X",
+        group: Group::Lcd,
+        fields: &[Field::bits("Scroll X", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::LCD_LY_ADDR,
+        name: "LY",
+        description: "LCD Y coordinate",
+        group: Group::Lcd,
+        fields: &[Field::bits("Line", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::LCD_LYC_ADDR,
+        name: "LYC",
+        description: "LY compare",
+        group: Group::Lcd,
+        fields: &[Field::bits("Line", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::OAM_DMA_TRANSFER_ADDR,
+        name: "DMA",
+        description: "OAM DMA source address",
+        group: Group::Lcd,
+        fields: &[Field::bits("Source high byte", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::LCD_PALETTE_ADDR,
+        name: "BGP",
+        description: "Background palette",
+        group: Group::Lcd,
+        fields: &PALETTE_FIELDS,
+    },
+    Register {
+        address: io_registers::LCD_OBJ_PALETTE0_ADDR,
+        name: "OBP0",
+        description: "Sprite palette 0",
+        group: Group::Lcd,
+        fields: &PALETTE_FIELDS,
+    },
+    Register {
+        address: io_registers::LCD_OBJ_PALETTE1_ADDR,
+        name: "OBP1",
+        description: "Sprite palette 1",
+        group: Group::Lcd,
+        fields: &PALETTE_FIELDS,
+    },
+    Register {
+        address: io_registers::LCD_WINY_ADDR,
+        name: "WY",
+        description: "Window Y position",
+        group: Group::Lcd,
+        fields: &[Field::bits("Window Y", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::LCD_WINX_ADDR,
+        name: "WX",
+        description: "Window X position plus 7",
+        group: Group::Lcd,
+        fields: &[Field::bits("Window X + 7", 0, 8, &[])],
+    },
+    Register {
+        address: io_registers::BOOT_ROM_DISABLE_ADDR,
+        name: "BOOT",
+        description: "Boot ROM disable",
+        group: Group::Boot,
+        fields: &[Field::bit("Boot ROM", 0, &["Mapped", "Unmapped"])],
+    },
+    Register {
+        address: io_registers::INTERRUPT_ENABLE_REGISTER_ADDR,
+        name: "IE",
+        description: "Interrupt enable",
+        group: Group::Interrupts,
+        fields: &INTERRUPT_ENABLE_FIELDS,
+    },
+];
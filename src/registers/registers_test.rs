@@ -0,0 +1,147 @@
+use std::sync::{Arc, Mutex};
+
+use crate::interrupt;
+use crate::memory::{io_registers, Memory};
+use crate::registers::{self, Field, REGISTERS};
+use crate::timers;
+
+#[test]
+fn field() {
+    struct TestCase {
+        description: String,
+        field: Field,
+        register: u8,
+        expected_value: u8,
+        expected_description: String,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("single bit"),
+            field: Field::bit("Window", 5, &["Disabled", "Enabled"]),
+            register: 0b0010_0000,
+            expected_value: 1,
+            expected_description: String::from("Enabled"),
+        },
+        TestCase {
+            description: String::from("several bits"),
+            field: Field::bits(
+                "Colour 2",
+                4,
+                2,
+                &["White", "Light gray", "Dark gray", "Black"],
+            ),
+            register: 0b1110_0100,
+            expected_value: 2,
+            expected_description: String::from("Dark gray"),
+        },
+        TestCase {
+            description: String::from("number"),
+            field: Field::bits("Initial volume", 4, 4, &[]),
+            register: 0xA3,
+            expected_value: 10,
+            expected_description: String::from("10"),
+        },
+        TestCase {
+            description: String::from("whole register"),
+            field: Field::bits("Line", 0, 8, &[]),
+            register: 0x90,
+            expected_value: 0x90,
+            expected_description: String::from("144 (0x90)"),
+        },
+        TestCase {
+            description: String::from("value without a meaning"),
+            field: Field::bits("Mode", 0, 2, &["HBlank"]),
+            register: 0x03,
+            expected_value: 3,
+            expected_description: String::from("3"),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            tc.field.get(tc.register),
+            tc.expected_value,
+            "{}",
+            tc.description
+        );
+        assert_eq!(
+            tc.field.describe(tc.register),
+            tc.expected_description,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn field_set() {
+    let field = Field::bits("Clock", 0, 2, &[]);
+    assert_eq!(field.get_max(), 3);
+    assert_eq!(field.set(0b0000_0101, 2), 0b0000_0110);
+
+    // Values too wide for the field are truncated, leaving the other bits alone.
+    assert_eq!(field.set(0b1111_0000, 0xFF), 0b1111_0011);
+
+    let field = Field::bits("Data", 0, 8, &[]);
+    assert_eq!(field.get_max(), 0xFF);
+    assert_eq!(field.set(0x12, 0x34), 0x34);
+}
+
+#[test]
+fn registers() {
+    for (index, register) in REGISTERS.iter().enumerate() {
+        if let Some(next) = REGISTERS.get(index + 1) {
+            assert!(
+                register.address < next.address,
+                "{} comes before {}",
+                register.name,
+                next.name
+            );
+        }
+
+        // Fields of a register don't overlap.
+        let mut mask = 0;
+        for field in register.fields {
+            assert_eq!(
+                mask & field.get_mask(),
+                0,
+                "{} {} overlaps another field",
+                register.name,
+                field.name
+            );
+            assert!(
+                field.meanings.is_empty() || field.meanings.len() == field.get_max() as usize + 1,
+                "{} {} has a meaning for each value",
+                register.name,
+                field.name
+            );
+            mask |= field.get_mask();
+        }
+    }
+
+    assert!(REGISTERS.contains(&registers::LCDC));
+}
+
+#[test]
+fn edit_fields() {
+    let mut memory = Memory::default(
+        Arc::new(Mutex::new(timers::Timers::new())),
+        Arc::new(Mutex::new(interrupt::Bus::new())),
+    );
+    memory.set_post_boot_rom_state();
+
+    // Editing any field to its maximum, the way the IO inspector does, is safe.
+    for register in REGISTERS.iter().filter(|register| register.is_editable()) {
+        for field in register.fields {
+            let value = memory.dma_read(register.address).unwrap();
+            memory.poke(register.address, field.set(value, field.get_max()));
+        }
+    }
+
+    let dma = REGISTERS
+        .iter()
+        .find(|register| register.address == io_registers::OAM_DMA_TRANSFER_ADDR)
+        .unwrap();
+    assert!(!dma.is_editable());
+}
@@ -1,135 +1,283 @@
-use glium::{implement_vertex, Surface};
+use glium::texture::{MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat};
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
+use glium::{implement_vertex, uniform, Surface};
 extern crate glium;
 
 mod shaders;
+mod viewport;
+use crate::effects;
+use crate::filters;
+use crate::palette;
 use crate::ppu;
-use crate::ui;
+use crate::screenshot;
+
+pub use viewport::Scaling;
+use viewport::Viewport;
 
 #[derive(Copy, Clone)]
 struct Vertex {
     position: [f32; 2],
-    color: [f64; 3],
+    tex_coords: [f32; 2],
 }
-implement_vertex!(Vertex, position, color);
+implement_vertex!(Vertex, position, tex_coords);
+
+// Single quad covering the whole viewport. The frame's first scanline is the top of the
+// screen, whereas OpenGL textures start at the bottom, hence the flipped V coordinates.
+const QUAD: [Vertex; 4] = [
+    Vertex {
+        position: [-1.0, 1.0],
+        tex_coords: [0.0, 0.0],
+    },
+    Vertex {
+        position: [1.0, 1.0],
+        tex_coords: [1.0, 0.0],
+    },
+    Vertex {
+        position: [-1.0, -1.0],
+        tex_coords: [0.0, 1.0],
+    },
+    Vertex {
+        position: [1.0, -1.0],
+        tex_coords: [1.0, 1.0],
+    },
+];
 
-const X_DIV: f32 = 2.0 / ppu::NATIVE_SCREEN_WIDTH as f32;
-const Y_DIV: f32 = 2.0 / ppu::NATIVE_SCREEN_HEIGHT as f32;
-const TOP_MENUBAR_COMPENSATION: f32 = 2.0 / (ui::TOP_MENUBAR_HEIGHT * ui::SCALE_FACTOR as f32);
-const VERTICES_PER_PIXEL: usize = 6;
-const VERTEX_COUNT: usize =
-    ppu::NATIVE_SCREEN_HEIGHT * ppu::NATIVE_SCREEN_WIDTH * VERTICES_PER_PIXEL;
+/// Texture sampling used when the frame is scaled up to the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filtering {
+    /// Sharp pixels.
+    Nearest,
+
+    /// Bilinear interpolation, smooths out non-integer scaling.
+    Linear,
+}
 
 pub struct OpenGL {
+    display: glium::backend::glutin::Display,
     program: glium::Program,
     vertex_buffer: glium::VertexBuffer<Vertex>,
     indices: glium::index::NoIndices,
+    texture: Texture2d,
+    frame: ppu::Frame,
+    frame_image: filters::Image,
+    palettes: palette::LayerPalettes,
+    effects: effects::Effects,
+    filter: filters::Filter,
+    filtering: Filtering,
+    scaling: Scaling,
 }
 
 impl OpenGL {
     pub fn new(display: &glium::backend::glutin::Display) -> Self {
-        let mut position_vertices = [Vertex {
-            position: [0.0, 0.0],
-            color: [1.0, 1.0, 1.0],
-        }; VERTEX_COUNT];
-
-        let mut i = 0;
-        let mut x = 0.0;
-        let mut y = 0.0;
-        while i < VERTEX_COUNT {
-            position_vertices[i] = Vertex {
-                position: [
-                    -1.0 + (X_DIV * x),
-                    (1.0 - TOP_MENUBAR_COMPENSATION - (Y_DIV * y)),
-                ],
-                color: [1.0, 1.0, 1.0],
-            };
-            position_vertices[i + 1] = Vertex {
-                position: [
-                    -1.0 + ((X_DIV * x) + X_DIV),
-                    (1.0 - TOP_MENUBAR_COMPENSATION - (Y_DIV * y)),
-                ],
-                color: [1.0, 1.0, 1.0],
-            };
-            position_vertices[i + 2] = Vertex {
-                position: [
-                    -1.0 + (X_DIV * x),
-                    1.0 - TOP_MENUBAR_COMPENSATION - ((Y_DIV * y) - Y_DIV),
-                ],
-                color: [1.0, 1.0, 1.0],
-            };
-            position_vertices[i + 3] = Vertex {
-                position: [
-                    -1.0 + ((X_DIV * x) + X_DIV),
-                    (1.0 - TOP_MENUBAR_COMPENSATION - (Y_DIV * y)),
-                ],
-                color: [1.0, 1.0, 1.0],
-            };
-            position_vertices[i + 4] = Vertex {
-                position: [
-                    -1.0 + (X_DIV * x),
-                    1.0 - TOP_MENUBAR_COMPENSATION - ((Y_DIV * y) - Y_DIV),
-                ],
-                color: [1.0, 1.0, 1.0],
-            };
-            position_vertices[i + 5] = Vertex {
-                position: [
-                    -1.0 + ((X_DIV * x) + X_DIV),
-                    1.0 - TOP_MENUBAR_COMPENSATION - ((Y_DIV * y) - Y_DIV),
-                ],
-                color: [1.0, 1.0, 1.0],
-            };
-
-            x += 1.0;
-            if x >= ppu::NATIVE_SCREEN_WIDTH as f32 {
-                x = 0.0;
-                y += 1.0;
-            }
-
-            i += VERTICES_PER_PIXEL;
-        }
-
-        let vertex_buffer = glium::VertexBuffer::new(display, &position_vertices).unwrap();
-        let indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
+        let vertex_buffer = glium::VertexBuffer::new(display, &QUAD).unwrap();
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip);
 
         let program =
             glium::Program::from_source(display, shaders::VERTEX, shaders::FRAGMENT, None).unwrap();
 
-        return Self {
+        let texture =
+            OpenGL::create_texture(display, ppu::NATIVE_SCREEN_WIDTH, ppu::NATIVE_SCREEN_HEIGHT);
+
+        let frame = ppu::Frame::new();
+        let palettes = palette::LayerPalettes::new(palette::Palette::default());
+        let frame_image = filters::Image::from_frame(&frame, &palettes);
+
+        let mut renderer = Self {
+            display: display.clone(),
             program,
             vertex_buffer,
             indices,
+            texture,
+            frame,
+            frame_image,
+            palettes,
+            effects: effects::Effects::new(),
+            filter: filters::Filter::None,
+            filtering: Filtering::Nearest,
+            scaling: Scaling::Integer,
         };
+
+        // The texture's initial contents are undefined, start off with a blank screen.
+        renderer.upload_frame();
+
+        return renderer;
     }
 
-    pub fn update_frame(
-        &mut self,
+    fn create_texture(
         display: &glium::backend::glutin::Display,
-        frame_data: [[ppu::Pixel; ppu::NATIVE_SCREEN_WIDTH]; ppu::NATIVE_SCREEN_HEIGHT],
-    ) {
-        let mut i = 0;
-        let mut current_vertices = self.vertex_buffer.read().unwrap();
-
-        for scanline in frame_data {
-            for pixel in scanline {
-                let (r, g, b) = pixel.to_rgb();
-                for j in 0..VERTICES_PER_PIXEL {
-                    current_vertices[i + j].color = [r, g, b];
+        width: usize,
+        height: usize,
+    ) -> Texture2d {
+        Texture2d::empty_with_format(
+            display,
+            UncompressedFloatFormat::U8U8U8U8,
+            MipmapsOption::NoMipmap,
+            width as u32,
+            height as u32,
+        )
+        .unwrap()
+    }
+
+    pub fn get_palettes(&self) -> &palette::LayerPalettes {
+        &self.palettes
+    }
+
+    /// Changes the colours the given layer is displayed with. Takes effect immediately,
+    /// even while the emulation is paused.
+    pub fn set_palette(&mut self, layer: ppu::Layer, palette: palette::Palette) {
+        self.palettes.set(layer, palette);
+        self.frame_image = filters::Image::from_frame(&self.frame, &self.palettes);
+        self.effects.reset_ghosting(&self.frame_image);
+        self.upload_frame();
+    }
+
+    pub fn get_effects(&self) -> &effects::Effects {
+        &self.effects
+    }
+
+    pub fn set_ghosting(&mut self, enabled: bool) {
+        self.effects.set_ghosting(enabled);
+    }
+
+    pub fn set_color_bleed(&mut self, enabled: bool) {
+        self.effects.set_color_bleed(enabled);
+        self.upload_frame();
+    }
+
+    pub fn set_pixel_grid(&mut self, enabled: bool) {
+        self.effects.set_pixel_grid(enabled);
+        self.upload_frame();
+    }
+
+    pub fn get_filter(&self) -> filters::Filter {
+        self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: filters::Filter) {
+        self.filter = filter;
+        self.upload_frame();
+    }
+
+    pub fn get_filtering(&self) -> Filtering {
+        self.filtering
+    }
+
+    pub fn set_filtering(&mut self, filtering: Filtering) {
+        self.filtering = filtering;
+    }
+
+    pub fn get_scaling(&self) -> Scaling {
+        self.scaling
+    }
+
+    pub fn set_scaling(&mut self, scaling: Scaling) {
+        self.scaling = scaling;
+    }
+
+    pub fn update_frame(&mut self, frame_data: ppu::Frame) {
+        self.frame = frame_data;
+        self.frame_image = self
+            .effects
+            .apply_ghosting(filters::Image::from_frame(&self.frame, &self.palettes));
+        self.upload_frame();
+    }
+
+    /// Returns the current frame coloured with the layer palettes, without any effects or
+    /// upscaling applied.
+    pub fn native_image(&self) -> filters::Image {
+        return filters::Image::from_frame(&self.frame, &self.palettes);
+    }
+
+    /// Returns the current frame as it would be saved in a screenshot. `window_size` and
+    /// `top_inset` are the window's framebuffer size and menubar height in physical pixels.
+    pub fn screenshot(
+        &self,
+        resolution: screenshot::Resolution,
+        window_size: (u32, u32),
+        top_inset: u32,
+    ) -> filters::Image {
+        match resolution {
+            screenshot::Resolution::Native => {
+                return self.native_image();
+            }
+            screenshot::Resolution::Scaled => {
+                let image = self.process_frame();
+                let viewport =
+                    Viewport::compute(self.scaling, window_size.0, window_size.1, top_inset);
+                if viewport.width == 0 || viewport.height == 0 {
+                    return image;
                 }
-                i += VERTICES_PER_PIXEL;
+
+                return image.resize_nearest(viewport.width as usize, viewport.height as usize);
             }
         }
+    }
 
-        self.vertex_buffer = glium::VertexBuffer::new(display, &current_vertices).unwrap();
+    // Post-processes the current coloured frame and upscales it with the selected filter.
+    fn process_frame(&self) -> filters::Image {
+        let image = self.effects.apply_pre_filter(self.frame_image.clone());
+        let image = self.filter.apply(&image);
+        return self.effects.apply_post_filter(image);
     }
 
-    pub fn render(&self, frame: &mut glium::Frame) {
+    fn upload_frame(&mut self) {
+        let image = self.process_frame();
+
+        let (width, height) = (image.get_width() as u32, image.get_height() as u32);
+        if self.texture.width() != width || self.texture.height() != height {
+            self.texture =
+                OpenGL::create_texture(&self.display, image.get_width(), image.get_height());
+        }
+
+        self.texture.write(
+            glium::Rect {
+                left: 0,
+                bottom: 0,
+                width,
+                height,
+            },
+            RawImage2d::from_raw_rgba(image.to_rgba_bytes(), (width, height)),
+        );
+    }
+
+    /// Draws the current frame, fitted to the part of the window below the menubar.
+    /// `top_inset` is the menubar's height in physical pixels.
+    pub fn render(&self, frame: &mut glium::Frame, top_inset: u32) {
+        let (window_width, window_height) = frame.get_dimensions();
+        let viewport = Viewport::compute(self.scaling, window_width, window_height, top_inset);
+        if viewport.width == 0 || viewport.height == 0 {
+            return;
+        }
+
+        let (magnify_filter, minify_filter) = match self.filtering {
+            Filtering::Nearest => (MagnifySamplerFilter::Nearest, MinifySamplerFilter::Nearest),
+            Filtering::Linear => (MagnifySamplerFilter::Linear, MinifySamplerFilter::Linear),
+        };
+
+        let uniforms = uniform! {
+            frame_texture: self.texture
+                .sampled()
+                .magnify_filter(magnify_filter)
+                .minify_filter(minify_filter),
+        };
+
+        let draw_parameters = glium::DrawParameters {
+            viewport: Some(glium::Rect {
+                left: viewport.left,
+                bottom: viewport.bottom,
+                width: viewport.width,
+                height: viewport.height,
+            }),
+            ..Default::default()
+        };
+
         frame
             .draw(
                 &self.vertex_buffer,
                 &self.indices,
                 &self.program,
-                &glium::uniforms::EmptyUniforms,
-                &Default::default(),
+                &uniforms,
+                &draw_parameters,
             )
             .unwrap();
     }
@@ -2,12 +2,12 @@ pub const VERTEX: &str = r#"
 #version 330 core
 
 layout (location = 0) in vec2 position;
-layout (location = 1) in vec3 color;
-out vec4 outputColor;
+layout (location = 1) in vec2 tex_coords;
+out vec2 v_tex_coords;
 
 void main() 
 {
-    outputColor = vec4(color, 1.0);
+    v_tex_coords = tex_coords;
     gl_Position = vec4(position.x, position.y, 0.0, 1.0);
 }
 "#;
@@ -15,12 +15,14 @@ void main()
 pub const FRAGMENT: &str = r#"
 #version 330 core
 
-in vec4 outputColor;
+in vec2 v_tex_coords;
+
+uniform sampler2D frame_texture;
 
 out vec4 FragColor;
 
 void main()
 {
-    FragColor = outputColor;
+    FragColor = texture(frame_texture, v_tex_coords);
 }
 "#;
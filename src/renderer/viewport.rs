@@ -0,0 +1,76 @@
+#[path = "viewport_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::ppu;
+
+/// How the frame is scaled to fit the part of the window it is drawn in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scaling {
+    /// Largest whole multiple of the native resolution that fits, so that every
+    /// Gameboy pixel covers the same number of screen pixels.
+    Integer,
+
+    /// Fills all of the available space, ignoring the aspect ratio.
+    Stretch,
+
+    /// Largest size that fits while preserving the 10:9 aspect ratio, letterboxed.
+    AspectFit,
+}
+
+/// Region of the window the frame is drawn to, in physical pixels.
+/// Like OpenGL viewports, the origin is at the bottom left of the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub left: u32,
+    pub bottom: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    /// Computes the viewport for a window of the given size, where the top `top_inset`
+    /// pixels are covered by the menubar. Upscaling filters only change the texture the
+    /// frame is drawn from, so the viewport is sized from the native resolution.
+    pub fn compute(
+        scaling: Scaling,
+        window_width: u32,
+        window_height: u32,
+        top_inset: u32,
+    ) -> Self {
+        let available_width = window_width;
+        let available_height = window_height.saturating_sub(top_inset);
+
+        let native_width = ppu::NATIVE_SCREEN_WIDTH as u32;
+        let native_height = ppu::NATIVE_SCREEN_HEIGHT as u32;
+
+        let (width, height) = match scaling {
+            Scaling::Stretch => (available_width, available_height),
+            Scaling::Integer
+                if available_width >= native_width && available_height >= native_height =>
+            {
+                let scale = (available_width / native_width).min(available_height / native_height);
+                (native_width * scale, native_height * scale)
+            }
+            // Windows smaller than the native resolution cannot be integer scaled,
+            // fall back to preserving the aspect ratio.
+            Scaling::Integer | Scaling::AspectFit => {
+                let scale = f64::min(
+                    available_width as f64 / native_width as f64,
+                    available_height as f64 / native_height as f64,
+                );
+                (
+                    (native_width as f64 * scale).round() as u32,
+                    (native_height as f64 * scale).round() as u32,
+                )
+            }
+        };
+
+        return Self {
+            left: (available_width - width) / 2,
+            bottom: (available_height - height) / 2,
+            width,
+            height,
+        };
+    }
+}
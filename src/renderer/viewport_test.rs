@@ -0,0 +1,117 @@
+use crate::renderer::viewport::{Scaling, Viewport};
+
+#[test]
+fn compute() {
+    struct TestCase {
+        description: String,
+        scaling: Scaling,
+        window_size: (u32, u32),
+        top_inset: u32,
+        expected: Viewport,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("stretch fills the area below the menubar"),
+            scaling: Scaling::Stretch,
+            window_size: (1000, 600),
+            top_inset: 20,
+            expected: Viewport {
+                left: 0,
+                bottom: 0,
+                width: 1000,
+                height: 580,
+            },
+        },
+        TestCase {
+            description: String::from("integer scaling picks the largest multiple that fits"),
+            scaling: Scaling::Integer,
+            window_size: (700, 740),
+            top_inset: 20,
+            expected: Viewport {
+                left: 30,
+                bottom: 72,
+                width: 640,
+                height: 576,
+            },
+        },
+        TestCase {
+            description: String::from("integer scaling fills the default window at 5x"),
+            scaling: Scaling::Integer,
+            window_size: (800, 740),
+            top_inset: 20,
+            expected: Viewport {
+                left: 0,
+                bottom: 0,
+                width: 800,
+                height: 720,
+            },
+        },
+        TestCase {
+            description: String::from("integer scaling centers the frame on both axes"),
+            scaling: Scaling::Integer,
+            window_size: (500, 500),
+            top_inset: 0,
+            expected: Viewport {
+                left: 10,
+                bottom: 34,
+                width: 480,
+                height: 432,
+            },
+        },
+        TestCase {
+            description: String::from("integer scaling falls back to aspect fit below 1x"),
+            scaling: Scaling::Integer,
+            window_size: (80, 100),
+            top_inset: 0,
+            expected: Viewport {
+                left: 0,
+                bottom: 14,
+                width: 80,
+                height: 72,
+            },
+        },
+        TestCase {
+            description: String::from("aspect fit letterboxes a wide window"),
+            scaling: Scaling::AspectFit,
+            window_size: (1000, 308),
+            top_inset: 20,
+            expected: Viewport {
+                left: 340,
+                bottom: 0,
+                width: 320,
+                height: 288,
+            },
+        },
+        TestCase {
+            description: String::from("aspect fit letterboxes a tall window"),
+            scaling: Scaling::AspectFit,
+            window_size: (320, 1020),
+            top_inset: 20,
+            expected: Viewport {
+                left: 0,
+                bottom: 356,
+                width: 320,
+                height: 288,
+            },
+        },
+        TestCase {
+            description: String::from("window entirely covered by the menubar"),
+            scaling: Scaling::AspectFit,
+            window_size: (320, 10),
+            top_inset: 20,
+            expected: Viewport {
+                left: 160,
+                bottom: 0,
+                width: 0,
+                height: 0,
+            },
+        },
+    ];
+
+    for tc in test_cases {
+        let viewport =
+            Viewport::compute(tc.scaling, tc.window_size.0, tc.window_size.1, tc.top_inset);
+        assert_eq!(viewport, tc.expected, "{}", tc.description);
+    }
+}
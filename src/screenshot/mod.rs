@@ -0,0 +1,148 @@
+//! Saving the emulator screen to PNG files, either from the running frontend or
+//! headlessly from the command line.
+#[path = "screenshot_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::filters::Image;
+use crate::gameboy;
+use crate::ppu;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Size at which a screenshot is saved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    /// 160x144, one image pixel per Gameboy pixel.
+    Native,
+
+    /// As currently displayed, with the effects, upscaling filter and window scaling applied.
+    Scaled,
+}
+
+/// Directory screenshots taken from the frontend are saved to.
+pub fn directory() -> PathBuf {
+    return PathBuf::from("screenshots");
+}
+
+/// Saves the image as a timestamped PNG in the given directory, which is created if needed,
+/// with a file name starting with the prefix. Returns the path of the new file.
+pub fn save_timestamped(image: &Image, directory: &Path, prefix: &str) -> Result<PathBuf, String> {
+    if let Err(err) = fs::create_dir_all(directory) {
+        return Err(format!("could not create {}: {}", directory.display(), err));
+    }
+
+    let (unix_seconds, millis) = now();
+    let mut path = directory.join(file_name(prefix, unix_seconds, millis, 0));
+
+    // Screenshots taken within the same millisecond would otherwise overwrite each other.
+    let mut index = 1;
+    while path.exists() {
+        path = directory.join(file_name(prefix, unix_seconds, millis, index));
+        index += 1;
+    }
+
+    save_png(image, &path)?;
+    return Ok(path);
+}
+
+pub fn save_png(image: &Image, path: &Path) -> Result<(), String> {
+    return image::save_buffer_with_format(
+        path,
+        &image.to_rgba_bytes(),
+        image.get_width() as u32,
+        image.get_height() as u32,
+        image::ColorType::Rgba8,
+        image::ImageFormat::Png,
+    )
+    .map_err(|err| format!("could not save {}: {}", path.display(), err));
+}
+
+/// Current time as seconds since the unix epoch and milliseconds within that second.
+pub fn now() -> (u64, u32) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    return (now.as_secs(), now.subsec_millis());
+}
+
+/// Formats the given UTC time as e.g. `20240131-235959-123`, for use in file names.
+pub fn timestamp(unix_seconds: u64, millis: u32) -> String {
+    let (year, month, day) = civil_from_days(unix_seconds / SECONDS_PER_DAY);
+    let seconds_of_day = unix_seconds % SECONDS_PER_DAY;
+
+    return format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        (seconds_of_day / 60) % 60,
+        seconds_of_day % 60,
+        millis,
+    );
+}
+
+/// File name for the given prefix and UTC time, e.g. `screenshot-20240131-235959-123.png`.
+/// A non-zero index is appended to tell apart images saved at the same time.
+pub fn file_name(prefix: &str, unix_seconds: u64, millis: u32, index: u32) -> String {
+    let mut name = format!("{}-{}", prefix, timestamp(unix_seconds, millis));
+    if index > 0 {
+        name.push_str(&format!("-{}", index));
+    }
+    name.push_str(".png");
+
+    return name;
+}
+
+// Converts a number of days since 1970-01-01 to a (year, month, day) date, using
+// Howard Hinnant's algorithm for the proleptic Gregorian calendar.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = match mp < 10 {
+        true => mp + 3,
+        false => mp - 9,
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+
+    return (year, month, day);
+}
+
+/// Runs the given ROM without a window for the given number of frames, and returns the
+/// last frame output by the PPU.
+pub fn capture_headless(rom_data: Vec<u8>, skip_boot_rom: bool, frames: u32) -> ppu::Frame {
+    let (gameboy, _, _) = gameboy::Gameboy::new(skip_boot_rom);
+    let frontend = gameboy.start();
+    frontend.send_rom_data_back_end(rom_data);
+
+    let mut frame = ppu::Frame::new();
+    for _ in 0..frames.max(1) {
+        frame = match frontend.recv_frame() {
+            Some(frame) => frame,
+            None => break,
+        };
+    }
+
+    frontend.send_close_back_end();
+
+    // Drain the frame data channel to avoid the backend blocking before it sees the close signal.
+    let _ = frontend.should_render_screen();
+
+    match frontend.join_back_end() {
+        Ok(_) => (),
+        Err(err) => panic!("error occurred when joining back end thread: {:?}", err),
+    }
+
+    return frame;
+}
@@ -0,0 +1,105 @@
+use crate::filters::Image;
+use crate::ppu::{self, Pixel};
+use crate::screenshot;
+
+#[test]
+fn file_name() {
+    struct TestCase {
+        description: String,
+        prefix: String,
+        unix_seconds: u64,
+        millis: u32,
+        index: u32,
+        expected: String,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("unix epoch"),
+            prefix: String::from("screenshot"),
+            unix_seconds: 0,
+            millis: 0,
+            index: 0,
+            expected: String::from("screenshot-19700101-000000-000.png"),
+        },
+        TestCase {
+            description: String::from("leap day"),
+            prefix: String::from("screenshot"),
+            unix_seconds: 1_709_251_199,
+            millis: 999,
+            index: 0,
+            expected: String::from("screenshot-20240229-235959-999.png"),
+        },
+        TestCase {
+            description: String::from("end of year"),
+            prefix: String::from("screenshot"),
+            unix_seconds: 1_704_067_199,
+            millis: 42,
+            index: 0,
+            expected: String::from("screenshot-20231231-235959-042.png"),
+        },
+        TestCase {
+            description: String::from("duplicate index"),
+            prefix: String::from("screenshot"),
+            unix_seconds: 951_782_400,
+            millis: 7,
+            index: 2,
+            expected: String::from("screenshot-20000229-000000-007-2.png"),
+        },
+        TestCase {
+            description: String::from("other prefix"),
+            prefix: String::from("tileset"),
+            unix_seconds: 0,
+            millis: 0,
+            index: 0,
+            expected: String::from("tileset-19700101-000000-000.png"),
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            screenshot::file_name(&tc.prefix, tc.unix_seconds, tc.millis, tc.index),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn save_png_round_trip() {
+    let directory = std::env::temp_dir().join(format!(
+        "rusty_fuugbemu_screenshot_test_{}",
+        std::process::id()
+    ));
+
+    let mut image = Image::new(3, 2);
+    image.set(0, 0, [0xFF, 0x00, 0x00, 0xFF]);
+    image.set(2, 1, [0x12, 0x34, 0x56, 0xFF]);
+
+    let first = screenshot::save_timestamped(&image, &directory, "screenshot").unwrap();
+    let second = screenshot::save_timestamped(&image, &directory, "screenshot").unwrap();
+    assert_ne!(first, second, "screenshots should not overwrite each other");
+
+    let loaded = image::open(&first).unwrap().to_rgba8();
+    assert_eq!(loaded.dimensions(), (3, 2));
+    assert_eq!(loaded.into_raw(), image.to_rgba_bytes());
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn capture_headless() {
+    // ROM only cartridge which spins on a JR -2 at the entry point. With the boot ROM
+    // skipped, the LCD is on and the background is made up of the blank tile 0.
+    let mut rom_data = vec![0x00; 0x8000];
+    rom_data[0x100] = 0x18;
+    rom_data[0x101] = 0xFE;
+
+    let frame = screenshot::capture_headless(rom_data, true, 3);
+    assert!(frame
+        .pixels
+        .iter()
+        .all(|line| line.iter().all(|pixel| *pixel == Pixel::White)));
+    assert_eq!(frame.pixels.len(), ppu::NATIVE_SCREEN_HEIGHT);
+}
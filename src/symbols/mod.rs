@@ -0,0 +1,229 @@
+//! Symbol files written by assemblers next to a ROM, naming the addresses of its labels so
+//! that they can be shown in place of bare addresses when debugging homebrew.
+#[path = "symbols_test.rs"]
+#[cfg(test)]
+mod test;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::cpu::trace::Location;
+use crate::hex_editor;
+use crate::memory::map::Region;
+
+/// How often the symbol file is checked for changes, e.g. after rebuilding the ROM.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Labels by location, and locations by label.
+#[derive(Debug, Default, PartialEq)]
+pub struct Symbols {
+    names: BTreeMap<(usize, u16), String>,
+    locations: BTreeMap<String, Location>,
+}
+
+impl Symbols {
+    /// Parses RGBDS and wla-dx symbol files, which both list labels as `bank:address name`
+    /// in hexadecimal, e.g.
+    ///
+    /// `01:4A2F Player.update`
+    ///
+    /// Lines starting with `;` are comments. wla-dx files split the labels from other
+    /// definitions in `[section]`s, of which only `[labels]` is read.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut symbols = Symbols::default();
+        let mut in_labels = true;
+
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = match line.split_once(';') {
+                Some((line, _)) => line.trim(),
+                None => line.trim(),
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                in_labels = line == "[labels]";
+                continue;
+            }
+
+            if !in_labels {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let location = parts.next().and_then(parse_location);
+            let name = parts.next();
+            match (location, name) {
+                (Some(location), Some(name)) => symbols.insert(location, name),
+                _ => {
+                    return Err(format!(
+                        "line {}: expected a label formatted as bank:address name",
+                        line_idx + 1
+                    ))
+                }
+            }
+        }
+
+        return Ok(symbols);
+    }
+
+    fn insert(&mut self, location: Location, name: &str) {
+        // The first label of a location names it, later ones are usually local aliases.
+        self.names
+            .entry((location.bank, location.address))
+            .or_insert_with(|| String::from(name));
+        self.locations.insert(String::from(name), location);
+    }
+
+    /// Location of the label.
+    pub fn find(&self, name: &str) -> Option<Location> {
+        return self.locations.get(name.trim()).copied();
+    }
+
+    /// Label of the location, or of the closest one before it in the same memory region
+    /// along with the offset from it, e.g. `Player.update+$1C`.
+    pub fn describe(&self, location: Location) -> Option<String> {
+        let ((bank, address), name) = self
+            .names
+            .range(..=(location.bank, location.address))
+            .next_back()?;
+
+        if *bank != location.bank || Region::of(*address) != Region::of(location.address) {
+            return None;
+        }
+
+        return match location.address - address {
+            0 => Some(name.clone()),
+            offset => Some(format!("{}+${:X}", name, offset)),
+        };
+    }
+
+    /// Location along with the label it falls under, if any, e.g. `01:4A3B Player.update+$C`.
+    pub fn format(&self, location: Location) -> String {
+        return match self.describe(location) {
+            Some(label) => format!("{} {}", location, label),
+            None => format!("{}", location),
+        };
+    }
+
+    /// Resolves a label, a `bank:address` or an address outside of the switchable ROM bank,
+    /// e.g. to set a breakpoint.
+    pub fn resolve(&self, text: &str) -> Result<Location, String> {
+        if let Some(location) = self.find(text) {
+            return Ok(location);
+        }
+
+        if let Some(location) = parse_location(text.trim()) {
+            return Ok(location);
+        }
+
+        let address = hex_editor::parse_address(text)
+            .map_err(|_| format!("unknown label or address '{}'", text.trim()))?;
+        return match Region::of(address) {
+            Region::RomBankN => Err(format!(
+                "'{}' is in the switchable ROM bank, write it as bank:address",
+                text.trim()
+            )),
+            _ => Ok(Location::new(address, 0)),
+        };
+    }
+}
+
+// Parses `bank:address`, with banks of 2 digits for RGBDS and 4 for wla-dx.
+fn parse_location(text: &str) -> Option<Location> {
+    let (bank, address) = text.split_once(':')?;
+    let bank = usize::from_str_radix(bank, 16).ok()?;
+    let address = u16::from_str_radix(address, 16).ok()?;
+
+    return Some(Location::new(address, bank));
+}
+
+/// Symbol file of a ROM, `game.sym` for `game.gb`.
+pub fn path_for(rom_path: &Path) -> PathBuf {
+    return rom_path.with_extension("sym");
+}
+
+/// Symbols of the loaded ROM, reloaded when the file changes.
+pub struct SymbolFile {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    checked_at: Instant,
+    symbols: Symbols,
+}
+
+impl SymbolFile {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            modified: None,
+            checked_at: Instant::now(),
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Loads the symbol file next to the ROM, if there is one.
+    pub fn load_rom(&mut self, rom_path: &Path) {
+        self.path = Some(path_for(rom_path));
+        self.modified = None;
+        self.symbols = Symbols::default();
+        self.reload_if_changed();
+    }
+
+    /// Reloads the file when it was modified since it was last loaded, at most once every
+    /// `RELOAD_CHECK_INTERVAL`. The symbols are cleared when the file is deleted or fails to
+    /// load, rather than naming addresses of an older build.
+    pub fn check_for_changes(&mut self) {
+        if self.checked_at.elapsed() >= RELOAD_CHECK_INTERVAL {
+            self.reload_if_changed();
+        }
+    }
+
+    fn reload_if_changed(&mut self) {
+        self.checked_at = Instant::now();
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let modified = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            // Most ROMs don't come with symbols.
+            Err(_) => {
+                if self.modified.take().is_some() {
+                    log::info!("Symbol file {} was removed", path.display());
+                    self.symbols = Symbols::default();
+                }
+                return;
+            }
+        };
+        if self.modified == Some(modified) {
+            return;
+        }
+        self.modified = Some(modified);
+
+        let result = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| Symbols::parse(&contents));
+        match result {
+            Ok(symbols) => {
+                log::info!(
+                    "Loaded {} symbols from {}",
+                    symbols.locations.len(),
+                    path.display()
+                );
+                self.symbols = symbols;
+            }
+            Err(err) => {
+                log::error!("Failed to load symbols {}: {}", path.display(), err);
+                self.symbols = Symbols::default();
+            }
+        }
+    }
+
+    pub fn get(&self) -> &Symbols {
+        return &self.symbols;
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::cpu::trace::Location;
+use crate::symbols::{self, SymbolFile, Symbols};
+
+const RGBDS_SYMBOLS: &str = "; File generated by rgblink
+00:0000 RST_00
+00:0150 Main
+00:0158 Main.loop
+01:4000 Player
+01:4000 Player.init
+02:4000 Enemy
+00:C000 wLives
+01:D000 wFoo
+00:FF80 hCounter
+";
+
+const WLA_DX_SYMBOLS: &str = "; this file was created with wlalink
+[labels]
+0000:0150 main
+0003:4A2F update_player
+[definitions]
+00000010 LIVES
+";
+
+#[test]
+fn parse() {
+    let symbols = Symbols::parse(RGBDS_SYMBOLS).unwrap();
+    assert_eq!(symbols.find("Main"), Some(Location::new(0x0150, 0)));
+    assert_eq!(symbols.find("Enemy"), Some(Location::new(0x4000, 2)));
+    assert_eq!(symbols.find("Player.init"), Some(Location::new(0x4000, 1)));
+    assert_eq!(symbols.find("Missing"), None);
+
+    // Banks outside of the switchable ROM bank are dropped, e.g. for labels in WRAM bank 1.
+    assert_eq!(symbols.find("wFoo").unwrap().bank, 0);
+
+    let symbols = Symbols::parse(WLA_DX_SYMBOLS).unwrap();
+    assert_eq!(symbols.find("main"), Some(Location::new(0x0150, 0)));
+    assert_eq!(
+        symbols.find("update_player"),
+        Some(Location::new(0x4A2F, 3))
+    );
+    assert_eq!(symbols.find("LIVES"), None);
+
+    assert_eq!(
+        Symbols::parse("00:0150 Main\n0150 Broken\n"),
+        Err(String::from(
+            "line 2: expected a label formatted as bank:address name"
+        ))
+    );
+}
+
+#[test]
+fn describe() {
+    struct TestCase {
+        description: String,
+        location: Location,
+        expected: Option<String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("exact"),
+            location: Location::new(0x0150, 1),
+            expected: Some(String::from("Main")),
+        },
+        TestCase {
+            description: String::from("offset from the closest label"),
+            location: Location::new(0x015A, 1),
+            expected: Some(String::from("Main.loop+$2")),
+        },
+        TestCase {
+            description: String::from("first label of a location"),
+            location: Location::new(0x4000, 1),
+            expected: Some(String::from("Player")),
+        },
+        TestCase {
+            description: String::from("other bank"),
+            location: Location::new(0x4010, 2),
+            expected: Some(String::from("Enemy+$10")),
+        },
+        TestCase {
+            description: String::from("bank without symbols"),
+            location: Location::new(0x4010, 3),
+            expected: None,
+        },
+        TestCase {
+            description: String::from("other memory region"),
+            location: Location::new(0x8000, 1),
+            expected: None,
+        },
+        TestCase {
+            description: String::from("switchable WRAM bank"),
+            location: Location::new(0xD004, 1),
+            expected: Some(String::from("wFoo+$4")),
+        },
+        TestCase {
+            description: String::from("RAM"),
+            location: Location::new(0xFF81, 1),
+            expected: Some(String::from("hCounter+$1")),
+        },
+    ];
+
+    let symbols = Symbols::parse(RGBDS_SYMBOLS).unwrap();
+    for tc in test_cases {
+        assert_eq!(
+            symbols.describe(tc.location),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn format() {
+    let symbols = Symbols::parse(RGBDS_SYMBOLS).unwrap();
+    assert_eq!(
+        symbols.format(Location::new(0x4003, 1)),
+        "01:4003 Player+$3"
+    );
+    assert_eq!(symbols.format(Location::new(0x8000, 1)), "00:8000");
+}
+
+#[test]
+fn resolve() {
+    struct TestCase {
+        description: String,
+        text: String,
+        expected: Result<Location, String>,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("label"),
+            text: String::from(" Player.init "),
+            expected: Ok(Location::new(0x4000, 1)),
+        },
+        TestCase {
+            description: String::from("bank and address"),
+            text: String::from("02:4A2F"),
+            expected: Ok(Location::new(0x4A2F, 2)),
+        },
+        TestCase {
+            description: String::from("address"),
+            text: String::from("$0200"),
+            expected: Ok(Location::new(0x0200, 0)),
+        },
+        TestCase {
+            description: String::from("address in the switchable ROM bank"),
+            text: String::from("4A2F"),
+            expected: Err(String::from(
+                "'4A2F' is in the switchable ROM bank, write it as bank:address",
+            )),
+        },
+        TestCase {
+            description: String::from("unknown label"),
+            text: String::from("Boss"),
+            expected: Err(String::from("unknown label or address 'Boss'")),
+        },
+    ];
+
+    let symbols = Symbols::parse(RGBDS_SYMBOLS).unwrap();
+    for tc in test_cases {
+        assert_eq!(symbols.resolve(&tc.text), tc.expected, "{}", tc.description);
+    }
+}
+
+#[test]
+fn path_for() {
+    assert_eq!(
+        symbols::path_for(Path::new("roms/game.gb")),
+        Path::new("roms/game.sym")
+    );
+}
+
+#[test]
+fn reload() {
+    let directory = std::env::temp_dir().join(format!(
+        "rusty_fuugbemu_symbols_test_{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&directory).unwrap();
+    let rom_path = directory.join("game.gb");
+    let symbols_path = directory.join("game.sym");
+
+    let mut symbol_file = SymbolFile::new();
+    symbol_file.load_rom(&rom_path);
+    assert_eq!(symbol_file.get().find("Main"), None);
+
+    fs::write(&symbols_path, "00:0150 Main\n").unwrap();
+    symbol_file.load_rom(&rom_path);
+    assert_eq!(
+        symbol_file.get().find("Main"),
+        Some(Location::new(0x0150, 0))
+    );
+
+    // Rebuilding the ROM moves the label.
+    fs::write(&symbols_path, "00:0200 Main\n").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&symbols_path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    symbol_file.reload_if_changed();
+    assert_eq!(
+        symbol_file.get().find("Main"),
+        Some(Location::new(0x0200, 0))
+    );
+
+    // Symbols that fail to parse don't leave the previous ones in place.
+    fs::write(&symbols_path, "Main\n").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&symbols_path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(20))
+        .unwrap();
+    symbol_file.reload_if_changed();
+    assert_eq!(symbol_file.get().find("Main"), None);
+
+    // Deleting the file clears the symbols too, and they come back with it.
+    fs::write(
+        &symbols_path,
+        "00:0300 Main
+",
+    )
+    .unwrap();
+    symbol_file.reload_if_changed();
+    assert_eq!(
+        symbol_file.get().find("Main"),
+        Some(Location::new(0x0300, 0))
+    );
+    fs::remove_file(&symbols_path).unwrap();
+    symbol_file.reload_if_changed();
+    assert_eq!(symbol_file.get().find("Main"), None);
+
+    let _ = fs::remove_dir_all(&directory);
+}
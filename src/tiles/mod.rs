@@ -0,0 +1,204 @@
+//! Decoding of the tiles held in VRAM, to display them with the palettes the game sets and
+//! export them as images.
+#[path = "tiles_test.rs"]
+#[cfg(test)]
+mod test;
+
+use crate::filters::{Image, Rgba};
+use crate::memory::io_registers;
+use crate::palette::Palette;
+use crate::ppu::{Layer, NATIVE_SCREEN_HEIGHT, NATIVE_SCREEN_WIDTH, PPU};
+
+const VRAM_START: usize = 0x8000;
+const TILE_SIZE: usize = 16;
+
+/// Tiles in VRAM, in the three blocks of 128 tiles starting at 0x8000, 0x8800 and 0x9000.
+pub const TILE_COUNT: usize = 384;
+
+/// Tiles per row of the tileset sheet.
+pub const TILESET_COLUMNS: usize = 16;
+
+/// Tiles per row and per column of a tile map.
+pub const MAP_TILES: usize = 32;
+
+/// Palette register value mapping each colour code to the shade of the same number.
+const IDENTITY_PALETTE: u8 = 0b11_10_01_00;
+
+/// Palette register tiles are displayed through.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TilePalette {
+    /// Colour codes as is, ignoring the palette registers.
+    Shades,
+    Background,
+    Object0,
+    Object1,
+}
+
+impl TilePalette {
+    pub const ALL: [TilePalette; 4] = [
+        TilePalette::Shades,
+        TilePalette::Background,
+        TilePalette::Object0,
+        TilePalette::Object1,
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            TilePalette::Shades => "Shades",
+            TilePalette::Background => "BGP",
+            TilePalette::Object0 => "OBP0",
+            TilePalette::Object1 => "OBP1",
+        };
+    }
+
+    /// Layer whose colours the tiles are displayed with.
+    pub fn get_layer(self) -> Layer {
+        return match self {
+            TilePalette::Shades | TilePalette::Background => Layer::Background,
+            TilePalette::Object0 => Layer::Object0,
+            TilePalette::Object1 => Layer::Object1,
+        };
+    }
+
+    /// Current value of the palette register, mapping colour codes to shades.
+    pub fn get_register(self, io_reg: &[u8; 0x80]) -> u8 {
+        let address = match self {
+            TilePalette::Shades => return IDENTITY_PALETTE,
+            TilePalette::Background => io_registers::LCD_PALETTE_ADDR,
+            TilePalette::Object0 => io_registers::LCD_OBJ_PALETTE0_ADDR,
+            TilePalette::Object1 => io_registers::LCD_OBJ_PALETTE1_ADDR,
+        };
+
+        return io_reg[address - io_registers::JOYPAD_ADDR];
+    }
+}
+
+/// Address of the tile at the index, in VRAM order.
+pub fn tile_address(index: usize) -> usize {
+    return VRAM_START + index * TILE_SIZE;
+}
+
+/// Address of the tile with the ID found in a tile map, depending on the addressing mode
+/// LCDC selects for the background and window.
+pub fn map_tile_address(lcdc: u8, tile_id: u8) -> usize {
+    return match lcdc & (1 << 4) > 0 {
+        true => tile_address(tile_id as usize),
+        false => (0x9000 + (tile_id as i8 as isize) * TILE_SIZE as isize) as usize,
+    };
+}
+
+/// Address of the background tile map LCDC selects.
+pub fn background_map_address(lcdc: u8) -> usize {
+    return match lcdc & (1 << 3) > 0 {
+        true => 0x9C00,
+        false => 0x9800,
+    };
+}
+
+/// Address of the window tile map LCDC selects.
+pub fn window_map_address(lcdc: u8) -> usize {
+    return match lcdc & (1 << 6) > 0 {
+        true => 0x9C00,
+        false => 0x9800,
+    };
+}
+
+/// Colour code of each pixel of the tile at the address, row by row.
+pub fn color_codes(vram: &[u8; 0x2000], address: usize) -> [[u8; 8]; 8] {
+    let mut codes = [[0; 8]; 8];
+
+    for (y, row) in codes.iter_mut().enumerate() {
+        let low = vram[address - VRAM_START + y * 2];
+        let high = vram[address - VRAM_START + y * 2 + 1];
+
+        for (x, code) in row.iter_mut().enumerate() {
+            let bit = 7 - x;
+            *code = (((high >> bit) & 1) << 1) | ((low >> bit) & 1);
+        }
+    }
+
+    return codes;
+}
+
+/// Colour the code is displayed with through the palette register.
+pub fn rgba(code: u8, register: u8, palette: &Palette) -> Rgba {
+    let (r, g, b) = palette.rgb(PPU::palette_lookup(register, code));
+    return [r, g, b, 0xFF];
+}
+
+/// Every tile in VRAM order, 16 tiles per row.
+pub fn tileset_sheet(vram: &[u8; 0x2000], register: u8, palette: &Palette) -> Image {
+    let mut image = Image::new(TILESET_COLUMNS * 8, TILE_COUNT / TILESET_COLUMNS * 8);
+
+    for index in 0..TILE_COUNT {
+        let x = (index % TILESET_COLUMNS) * 8;
+        let y = (index / TILESET_COLUMNS) * 8;
+        draw_tile(
+            &mut image,
+            vram,
+            tile_address(index),
+            x,
+            y,
+            register,
+            palette,
+        );
+    }
+
+    return image;
+}
+
+/// The whole 256x256 tile map at the address.
+pub fn tile_map(
+    vram: &[u8; 0x2000],
+    lcdc: u8,
+    map_address: usize,
+    register: u8,
+    palette: &Palette,
+) -> Image {
+    let mut image = Image::new(MAP_TILES * 8, MAP_TILES * 8);
+
+    for index in 0..MAP_TILES * MAP_TILES {
+        let tile_id = vram[map_address - VRAM_START + index];
+        let address = map_tile_address(lcdc, tile_id);
+        let x = (index % MAP_TILES) * 8;
+        let y = (index / MAP_TILES) * 8;
+        draw_tile(&mut image, vram, address, x, y, register, palette);
+    }
+
+    return image;
+}
+
+/// Outlines the area of the background map shown on screen at the scroll position, which
+/// wraps around the edges of the map.
+pub fn draw_viewport(image: &mut Image, scx: u8, scy: u8, color: Rgba) {
+    let last_x = scx.wrapping_add(NATIVE_SCREEN_WIDTH as u8 - 1);
+    let last_y = scy.wrapping_add(NATIVE_SCREEN_HEIGHT as u8 - 1);
+
+    for offset in 0..NATIVE_SCREEN_WIDTH as u8 {
+        let x = scx.wrapping_add(offset) as usize;
+        image.set(x, scy as usize, color);
+        image.set(x, last_y as usize, color);
+    }
+
+    for offset in 0..NATIVE_SCREEN_HEIGHT as u8 {
+        let y = scy.wrapping_add(offset) as usize;
+        image.set(scx as usize, y, color);
+        image.set(last_x as usize, y, color);
+    }
+}
+
+fn draw_tile(
+    image: &mut Image,
+    vram: &[u8; 0x2000],
+    address: usize,
+    left: usize,
+    top: usize,
+    register: u8,
+    palette: &Palette,
+) {
+    for (y, row) in color_codes(vram, address).iter().enumerate() {
+        for (x, code) in row.iter().enumerate() {
+            image.set(left + x, top + y, rgba(*code, register, palette));
+        }
+    }
+}
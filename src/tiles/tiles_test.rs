@@ -0,0 +1,148 @@
+use crate::memory::io_registers;
+use crate::palette::Palette;
+use crate::tiles::{self, TilePalette};
+
+const WHITE: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+const LIGHT_GRAY: [u8; 4] = [0xAA, 0xAA, 0xAA, 0xFF];
+const DARK_GRAY: [u8; 4] = [0x55, 0x55, 0x55, 0xFF];
+const BLACK: [u8; 4] = [0x00, 0x00, 0x00, 0xFF];
+
+fn gray_palette() -> Palette {
+    Palette::new(
+        "Gray",
+        [
+            (0xFF, 0xFF, 0xFF),
+            (0xAA, 0xAA, 0xAA),
+            (0x55, 0x55, 0x55),
+            (0x00, 0x00, 0x00),
+        ],
+    )
+}
+
+// Tile whose first row holds colour codes 0, 1, 2 and 3 twice each.
+fn write_tile(vram: &mut [u8; 0x2000], address: usize) {
+    vram[address - 0x8000] = 0b0011_0011;
+    vram[address - 0x8000 + 1] = 0b0000_1111;
+}
+
+#[test]
+fn color_codes() {
+    let mut vram = [0; 0x2000];
+    write_tile(&mut vram, 0x8010);
+
+    let codes = tiles::color_codes(&vram, 0x8010);
+    assert_eq!(codes[0], [0, 0, 1, 1, 2, 2, 3, 3]);
+    assert_eq!(codes[1], [0; 8]);
+}
+
+#[test]
+fn map_tile_address() {
+    struct TestCase {
+        description: String,
+        lcdc: u8,
+        tile_id: u8,
+        expected: usize,
+    }
+
+    let test_cases: Vec<TestCase> = vec![
+        TestCase {
+            description: String::from("unsigned, first tile"),
+            lcdc: 0x10,
+            tile_id: 0,
+            expected: 0x8000,
+        },
+        TestCase {
+            description: String::from("unsigned, last tile"),
+            lcdc: 0x10,
+            tile_id: 0xFF,
+            expected: 0x8FF0,
+        },
+        TestCase {
+            description: String::from("signed, positive"),
+            lcdc: 0x00,
+            tile_id: 0x01,
+            expected: 0x9010,
+        },
+        TestCase {
+            description: String::from("signed, negative"),
+            lcdc: 0x00,
+            tile_id: 0x80,
+            expected: 0x8800,
+        },
+    ];
+
+    for tc in test_cases {
+        assert_eq!(
+            tiles::map_tile_address(tc.lcdc, tc.tile_id),
+            tc.expected,
+            "{}",
+            tc.description
+        );
+    }
+}
+
+#[test]
+fn tile_palette() {
+    let mut io_reg = [0; 0x80];
+    io_reg[io_registers::LCD_PALETTE_ADDR - 0xFF00] = 0x1B;
+    io_reg[io_registers::LCD_OBJ_PALETTE1_ADDR - 0xFF00] = 0xD2;
+
+    assert_eq!(TilePalette::Shades.get_register(&io_reg), 0xE4);
+    assert_eq!(TilePalette::Background.get_register(&io_reg), 0x1B);
+    assert_eq!(TilePalette::Object1.get_register(&io_reg), 0xD2);
+}
+
+#[test]
+fn tileset_sheet() {
+    let mut vram = [0; 0x2000];
+    write_tile(&mut vram, tiles::tile_address(17));
+
+    // Inverted palette.
+    let image = tiles::tileset_sheet(&vram, 0x1B, &gray_palette());
+    assert_eq!(image.get_width(), 128);
+    assert_eq!(image.get_height(), 192);
+
+    // Second tile of the second row.
+    assert_eq!(image.get(8, 8), BLACK);
+    assert_eq!(image.get(10, 8), DARK_GRAY);
+    assert_eq!(image.get(12, 8), LIGHT_GRAY);
+    assert_eq!(image.get(14, 8), WHITE);
+    assert_eq!(image.get(14, 9), BLACK);
+}
+
+#[test]
+fn tile_map() {
+    let mut vram = [0; 0x2000];
+    write_tile(&mut vram, 0x8800);
+
+    // Last tile of the first row of the map at 0x9C00 is tile 0x80, signed.
+    vram[0x9C00 - 0x8000 + 31] = 0x80;
+
+    let lcdc = 0x08;
+    let map_address = tiles::background_map_address(lcdc);
+    let image = tiles::tile_map(&vram, lcdc, map_address, 0xE4, &gray_palette());
+    assert_eq!(image.get_width(), 256);
+    assert_eq!(image.get_height(), 256);
+    assert_eq!(image.get(248, 0), WHITE);
+    assert_eq!(image.get(254, 0), BLACK);
+    assert_eq!(image.get(6, 0), WHITE);
+}
+
+#[test]
+fn draw_viewport() {
+    let vram = [0; 0x2000];
+    let red = [0xFF, 0x00, 0x00, 0xFF];
+    let mut image = tiles::tile_map(&vram, 0x00, 0x9800, 0xE4, &gray_palette());
+
+    // The viewport wraps around the right and bottom edges of the map.
+    tiles::draw_viewport(&mut image, 200, 150, red);
+    assert_eq!(image.get(200, 150), red);
+    assert_eq!(image.get(255, 150), red);
+    assert_eq!(image.get(0, 150), red);
+    assert_eq!(image.get(103, 150), red);
+    assert_eq!(image.get(104, 150), WHITE);
+    assert_eq!(image.get(200, 37), red);
+    assert_eq!(image.get(103, 37), red);
+    assert_eq!(image.get(200, 38), WHITE);
+    assert_eq!(image.get(150, 0), WHITE);
+}
@@ -0,0 +1,209 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Context, RichText};
+
+use crate::cpu::trace::{Flow, Location, Trace};
+use crate::cpu::LR35902;
+use crate::gameboy::channel::front_end::Frontend;
+use crate::symbols::Symbols;
+
+pub struct Ui {
+    show: bool,
+
+    // Labels or addresses to break at, resolved again whenever the symbols change.
+    breakpoints: Vec<String>,
+    breakpoint_text: String,
+    breakpoint_error: Option<String>,
+
+    // Locations last sent to the emulator.
+    sent_breakpoints: Vec<Location>,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            breakpoints: Vec::new(),
+            breakpoint_text: String::new(),
+            breakpoint_error: None,
+            sent_breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn show(&mut self, show: bool) {
+        self.show = show;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.show
+    }
+
+    pub fn render(
+        &mut self,
+        ctx: &Context,
+        cpu_ref: &Arc<Mutex<LR35902>>,
+        symbols: &Symbols,
+        frontend: &Frontend,
+    ) {
+        egui::Window::new(RichText::new("Call Stack").size(24.0))
+            .collapsible(false)
+            .default_width(400.0)
+            .open(&mut self.show)
+            .show(ctx, |ui| {
+                Ui::render_breakpoints(
+                    ui,
+                    &mut self.breakpoints,
+                    &mut self.breakpoint_text,
+                    &mut self.breakpoint_error,
+                    symbols,
+                );
+
+                ui.separator();
+                let cpu = cpu_ref.lock().unwrap();
+                let trace = cpu.get_trace();
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Pause the emulator to inspect a crash").weak());
+                    if ui.button("Copy Trace").clicked() {
+                        ui.output().copied_text = trace.to_text(symbols);
+                    }
+                });
+
+                ui.separator();
+                ui.label(RichText::new("Calls, innermost first").strong());
+                Ui::render_calls(ui, trace, symbols);
+
+                ui.separator();
+                ui.label(RichText::new("Executed instructions, most recent first").strong());
+                Ui::render_history(ui, trace, symbols);
+            });
+
+        // Labels move when the symbols are reloaded, e.g. after rebuilding the ROM.
+        let locations: Vec<Location> = self
+            .breakpoints
+            .iter()
+            .filter_map(|text| symbols.resolve(text).ok())
+            .collect();
+        if locations != self.sent_breakpoints {
+            frontend.send_breakpoints(locations.clone());
+            self.sent_breakpoints = locations;
+        }
+    }
+
+    fn render_breakpoints(
+        ui: &mut egui::Ui,
+        breakpoints: &mut Vec<String>,
+        text: &mut String,
+        error: &mut Option<String>,
+        symbols: &Symbols,
+    ) {
+        ui.label(RichText::new("Breakpoints").strong());
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(text).desired_width(200.0))
+                .on_hover_text("Label, bank:address or address");
+            if ui.button("Add").clicked() {
+                match symbols.resolve(text) {
+                    Ok(_) => {
+                        breakpoints.push(String::from(text.trim()));
+                        text.clear();
+                        *error = None;
+                    }
+                    Err(err) => *error = Some(err),
+                }
+            }
+        });
+
+        if let Some(err) = error {
+            ui.colored_label(egui::Color32::RED, err.as_str());
+        }
+
+        let mut removed = None;
+        for (index, breakpoint) in breakpoints.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").clicked() {
+                    removed = Some(index);
+                }
+
+                match symbols.resolve(breakpoint) {
+                    Ok(location) => {
+                        ui.label(RichText::new(format!("{} {}", breakpoint, location)).monospace())
+                    }
+                    Err(_) => ui.label(
+                        RichText::new(format!("{} (not found)", breakpoint))
+                            .monospace()
+                            .weak(),
+                    ),
+                };
+            });
+        }
+
+        if let Some(index) = removed {
+            breakpoints.remove(index);
+        }
+    }
+
+    fn render_calls(ui: &mut egui::Ui, trace: &Trace, symbols: &Symbols) {
+        if trace.get_calls().is_empty() {
+            ui.label("No calls");
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .id_source("call_stack_calls")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("call_stack_calls")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["", "From", "To", ""] {
+                            ui.label(header);
+                        }
+                        ui.end_row();
+
+                        for frame in trace.get_calls().iter().rev() {
+                            ui.label(format!("{}", frame.entry));
+                            ui.label(Ui::location_text(frame.from, symbols));
+                            ui.label(Ui::location_text(frame.to, symbols));
+                            match frame.count {
+                                1 => ui.label(""),
+                                count => ui.label(format!("x{}", count)),
+                            };
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn render_history(ui: &mut egui::Ui, trace: &Trace, symbols: &Symbols) {
+        let history = trace.get_history();
+        let row_height = ui.spacing().interact_size.y;
+
+        egui::ScrollArea::vertical()
+            .id_source("call_stack_history")
+            .max_height(300.0)
+            .auto_shrink([false, true])
+            .show_rows(ui, row_height, history.len(), |ui, rows| {
+                for row in rows {
+                    let executed = &history[history.len() - 1 - row];
+                    let flow = match Flow::of(executed.opcode) {
+                        Some(flow) => flow.to_string(),
+                        None => String::new(),
+                    };
+
+                    ui.horizontal(|ui| {
+                        ui.label(Ui::location_text(executed.location, symbols));
+                        ui.label(
+                            RichText::new(format!("{:02X} {}", executed.opcode, flow)).monospace(),
+                        );
+                        if executed.count > 1 {
+                            ui.label(RichText::new(format!("x{}", executed.count)).weak());
+                        }
+                    });
+                }
+            });
+    }
+
+    // Location along with the label it falls under, if any.
+    fn location_text(location: Location, symbols: &Symbols) -> RichText {
+        return RichText::new(symbols.format(location)).monospace();
+    }
+}
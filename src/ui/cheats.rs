@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+
+use egui::{Context, RichText};
+
+use crate::cheats::{self, Cheat};
+use crate::gameboy::channel::front_end::Frontend;
+
+pub struct Ui {
+    show: bool,
+
+    // File the cheats of the loaded ROM are saved to, if a ROM is loaded.
+    path: Option<PathBuf>,
+    cheats: Vec<Cheat>,
+
+    // Cheat being entered.
+    new_name: String,
+    new_code: String,
+    error: Option<String>,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            path: None,
+            cheats: Vec::new(),
+            new_name: String::new(),
+            new_code: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn show(&mut self, show: bool) {
+        self.show = show;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.show
+    }
+
+    /// Loads the cheats saved for the ROM and applies the enabled ones.
+    pub fn load_rom(&mut self, rom_data: &[u8], frontend: &Frontend) {
+        self.path = cheats::path(rom_data);
+        self.cheats = Vec::new();
+        self.error = None;
+
+        if let Some(path) = &self.path {
+            match cheats::load(path) {
+                Ok(loaded) => self.cheats = loaded,
+                Err(err) => log::error!("Failed to load cheats: {}", err),
+            }
+        }
+
+        frontend.send_cheats(cheats::enabled_codes(&self.cheats));
+    }
+
+    /// Adds a cheat for the loaded ROM and applies it.
+    pub fn add(&mut self, cheat: Cheat, frontend: &Frontend) {
+        self.cheats.push(cheat);
+        self.apply(frontend);
+    }
+
+    pub fn render(&mut self, egui_ctx: &Context, frontend: &Frontend) {
+        let mut changed = false;
+
+        egui::Window::new(RichText::new("Cheats").size(24.0))
+            .collapsible(false)
+            .open(&mut self.show)
+            .show(egui_ctx, |ui| {
+                if self.path.is_none() {
+                    ui.label("Load a ROM to add cheats");
+                    return;
+                }
+
+                changed |= Ui::render_cheat_list(ui, &mut self.cheats);
+
+                ui.separator();
+                ui.label(RichText::new("Add Cheat").size(18.0));
+                ui.label(
+                    RichText::new(
+                        "Game Genie (ABC-DEF-GHI) or GameShark (01VVLLHH) codes, separated by spaces",
+                    )
+                    .weak(),
+                );
+
+                egui::Grid::new("new_cheat").show(ui, |ui| {
+                    ui.label("Name: ");
+                    ui.text_edit_singleline(&mut self.new_name);
+                    ui.end_row();
+
+                    ui.label("Codes: ");
+                    ui.text_edit_singleline(&mut self.new_code);
+                    ui.end_row();
+                });
+
+                if ui.button("Add").clicked() {
+                    match cheats::parse_codes(&self.new_code) {
+                        Ok(_) => {
+                            let name = match self.new_name.trim() {
+                                "" => self.new_code.trim().to_string(),
+                                name => name.to_string(),
+                            };
+
+                            self.cheats.push(Cheat {
+                                name,
+                                code: self.new_code.trim().to_string(),
+                                enabled: true,
+                            });
+                            self.new_name.clear();
+                            self.new_code.clear();
+                            self.error = None;
+                            changed = true;
+                        }
+                        Err(err) => self.error = Some(err),
+                    }
+                }
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            });
+
+        if changed {
+            self.apply(frontend);
+        }
+    }
+
+    // Sends the enabled cheats to the emulator and saves them all.
+    fn apply(&self, frontend: &Frontend) {
+        frontend.send_cheats(cheats::enabled_codes(&self.cheats));
+
+        if let Some(path) = &self.path {
+            if let Err(err) = cheats::save(path, &self.cheats) {
+                log::error!("Failed to save cheats: {}", err);
+            }
+        }
+    }
+
+    // Returns whether a cheat was changed.
+    fn render_cheat_list(ui: &mut egui::Ui, cheats: &mut Vec<Cheat>) -> bool {
+        if cheats.is_empty() {
+            ui.label("No cheats for this ROM");
+            return false;
+        }
+
+        let mut changed = false;
+        let mut removed = None;
+
+        egui::Grid::new("cheats").striped(true).show(ui, |ui| {
+            for (index, cheat) in cheats.iter_mut().enumerate() {
+                changed |= ui.checkbox(&mut cheat.enabled, "").changed();
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut cheat.name).desired_width(150.0))
+                    .lost_focus();
+                ui.label(RichText::new(&cheat.code).monospace());
+
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(index) = removed {
+            cheats.remove(index);
+            changed = true;
+        }
+
+        return changed;
+    }
+}
@@ -1,11 +1,43 @@
+use std::collections::HashSet;
+
 use egui::{Context, RichText};
 use glium::glutin::event::{ElementState, VirtualKeyCode, WindowEvent};
 
 use crate::{
+    config::KeyBindings,
     gameboy::channel::front_end::Frontend,
-    joypad::{ActionButton, DirectionButton},
+    gamepad::{self, Gamepads},
+    input::{self, Command, Macro, Mixer, Source, Step, Turbo},
+    joypad::{ActionButton, Button, DirectionButton},
 };
 
+/// Action a key is being bound to.
+#[derive(Clone, Copy, Debug)]
+enum Binding {
+    None,
+    Joypad(Option<DirectionButton>, Option<ActionButton>),
+    TurboA,
+    TurboB,
+    Macro(usize),
+}
+
+// Macro along with the steps as typed, which only replace its steps once valid.
+struct MacroEditor {
+    definition: Macro,
+    steps: String,
+    error: Option<String>,
+}
+
+impl MacroEditor {
+    fn new(definition: Macro) -> Self {
+        Self {
+            steps: input::format_steps(&definition.steps),
+            definition,
+            error: None,
+        }
+    }
+}
+
 pub struct Ui {
     show: bool,
     show_key_bind_window: bool,
@@ -21,12 +53,25 @@ pub struct Ui {
     key_left: (VirtualKeyCode, ElementState),
     key_right: (VirtualKeyCode, ElementState),
 
+    turbo: Turbo,
+    macros: Vec<MacroEditor>,
+
+    // Keys currently held, to hold turbo buttons and start macros once per press despite
+    // key repeat.
+    held_keys: HashSet<VirtualKeyCode>,
+
+    // Buttons held on the keyboard and controllers.
+    mixer: Mixer,
+
+    // Turbo rate last sent to the emulator, which plays the turbo buttons and macros.
+    sent_turbo_rate: Option<u32>,
+
     // Key to modify
-    key_to_modify: (Option<DirectionButton>, Option<ActionButton>),
+    key_to_modify: Binding,
 }
 
 impl Ui {
-    pub fn new() -> Self {
+    pub fn new(key_bindings: &KeyBindings, turbo: &Turbo, macros: &[Macro]) -> Self {
         let gb_control_png = include_bytes!("./assets/gb_controls_cropped.png");
 
         Self {
@@ -34,28 +79,60 @@ impl Ui {
             show_key_bind_window: false,
             gb_control_png,
 
-            key_a: (VirtualKeyCode::A, ElementState::Released),
-            key_b: (VirtualKeyCode::S, ElementState::Released),
-            key_start: (VirtualKeyCode::D, ElementState::Released),
-            key_select: (VirtualKeyCode::F, ElementState::Released),
-            key_up: (VirtualKeyCode::Up, ElementState::Released),
-            key_down: (VirtualKeyCode::Down, ElementState::Released),
-            key_left: (VirtualKeyCode::Left, ElementState::Released),
-            key_right: (VirtualKeyCode::Right, ElementState::Released),
+            key_a: (key_bindings.a, ElementState::Released),
+            key_b: (key_bindings.b, ElementState::Released),
+            key_start: (key_bindings.start, ElementState::Released),
+            key_select: (key_bindings.select, ElementState::Released),
+            key_up: (key_bindings.up, ElementState::Released),
+            key_down: (key_bindings.down, ElementState::Released),
+            key_left: (key_bindings.left, ElementState::Released),
+            key_right: (key_bindings.right, ElementState::Released),
+
+            turbo: turbo.clone(),
+            macros: macros.iter().cloned().map(MacroEditor::new).collect(),
+            held_keys: HashSet::new(),
+            mixer: Mixer::new(),
+            sent_turbo_rate: None,
+
+            key_to_modify: Binding::None,
+        }
+    }
 
-            key_to_modify: (None, None),
+    pub fn get_key_bindings(&self) -> KeyBindings {
+        KeyBindings {
+            a: self.key_a.0,
+            b: self.key_b.0,
+            start: self.key_start.0,
+            select: self.key_select.0,
+            up: self.key_up.0,
+            down: self.key_down.0,
+            left: self.key_left.0,
+            right: self.key_right.0,
         }
     }
 
+    pub fn get_turbo(&self) -> Turbo {
+        return self.turbo.clone();
+    }
+
+    pub fn get_macros(&self) -> Vec<Macro> {
+        return self
+            .macros
+            .iter()
+            .map(|editor| editor.definition.clone())
+            .collect();
+    }
+
     pub fn show(&mut self, show: bool) {
         self.show = show;
     }
 
-    pub fn render(&mut self, egui_ctx: &Context) {
+    pub fn render(&mut self, egui_ctx: &Context, gamepads: &mut Gamepads) {
         self.render_key_bind_window(egui_ctx);
 
         if !self.show {
             self.show_key_bind_window = false;
+            gamepads.cancel_remap();
         }
 
         egui::Window::new(egui::RichText::new("Controls").size(24.0))
@@ -95,7 +172,8 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (Some(DirectionButton::Up), None);
+                                self.key_to_modify =
+                                    Binding::Joypad(Some(DirectionButton::Up), None);
                             }
                         });
 
@@ -111,7 +189,8 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (Some(DirectionButton::Down), None);
+                                self.key_to_modify =
+                                    Binding::Joypad(Some(DirectionButton::Down), None);
                             }
                         });
 
@@ -127,7 +206,8 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (Some(DirectionButton::Left), None);
+                                self.key_to_modify =
+                                    Binding::Joypad(Some(DirectionButton::Left), None);
                             }
                         });
 
@@ -143,7 +223,8 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (Some(DirectionButton::Right), None);
+                                self.key_to_modify =
+                                    Binding::Joypad(Some(DirectionButton::Right), None);
                             }
                         });
                     });
@@ -159,7 +240,7 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (None, Some(ActionButton::A));
+                                self.key_to_modify = Binding::Joypad(None, Some(ActionButton::A));
                             }
                         });
 
@@ -173,7 +254,7 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (None, Some(ActionButton::B));
+                                self.key_to_modify = Binding::Joypad(None, Some(ActionButton::B));
                             }
                         });
 
@@ -189,7 +270,8 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (None, Some(ActionButton::Start));
+                                self.key_to_modify =
+                                    Binding::Joypad(None, Some(ActionButton::Start));
                             }
                         });
 
@@ -205,14 +287,194 @@ impl Ui {
                                 .clicked()
                             {
                                 self.show_key_bind_window = true;
-                                self.key_to_modify = (None, Some(ActionButton::Select));
+                                self.key_to_modify =
+                                    Binding::Joypad(None, Some(ActionButton::Select));
                             }
                         });
                     });
                 });
+
+                ui.separator();
+                let turbo_binding = Ui::render_turbo_settings(ui, &mut self.turbo);
+
+                ui.separator();
+                let macro_binding = Ui::render_macros(ui, &mut self.macros);
+
+                if let Some(binding) = turbo_binding.or(macro_binding) {
+                    self.show_key_bind_window = true;
+                    self.key_to_modify = binding;
+                }
+
+                ui.separator();
+                Ui::render_gamepad_settings(ui, gamepads);
             });
     }
 
+    // Returns the turbo button to bind a key to, if clicked.
+    fn render_turbo_settings(ui: &mut egui::Ui, turbo: &mut Turbo) -> Option<Binding> {
+        ui.label(RichText::new("Turbo").size(18.0));
+
+        let mut binding = None;
+        ui.horizontal(|ui| {
+            for (name, key, button_binding) in [
+                ("Turbo A: ", &mut turbo.a, Binding::TurboA),
+                ("Turbo B: ", &mut turbo.b, Binding::TurboB),
+            ] {
+                ui.label(RichText::new(name).size(15.0));
+                let response = ui
+                    .button(RichText::new(key_name(*key)).size(15.0))
+                    .on_hover_text("Right-click to unbind");
+                if response.clicked() {
+                    binding = Some(button_binding);
+                }
+                if response.secondary_clicked() {
+                    *key = None;
+                }
+            }
+        });
+
+        ui.add(
+            egui::Slider::new(
+                &mut turbo.rate,
+                input::MIN_TURBO_RATE..=input::MAX_TURBO_RATE,
+            )
+            .text("Presses per Second"),
+        );
+
+        return binding;
+    }
+
+    // Returns the macro to bind a key to, if clicked.
+    fn render_macros(ui: &mut egui::Ui, macros: &mut Vec<MacroEditor>) -> Option<Binding> {
+        ui.label(RichText::new("Macros").size(18.0));
+        ui.label(
+            RichText::new(
+                "Steps are buttons held for a number of frames, e.g. 'Down 2, Down+Right 2, Right+A 4, - 10'",
+            )
+            .weak(),
+        );
+
+        let mut binding = None;
+        let mut removed = None;
+        egui::Grid::new("macros").show(ui, |ui| {
+            for (index, editor) in macros.iter_mut().enumerate() {
+                ui.add(
+                    egui::TextEdit::singleline(&mut editor.definition.name).desired_width(100.0),
+                );
+
+                let response = ui
+                    .button(key_name(editor.definition.key))
+                    .on_hover_text("Right-click to unbind");
+                if response.clicked() {
+                    binding = Some(Binding::Macro(index));
+                }
+                if response.secondary_clicked() {
+                    editor.definition.key = None;
+                }
+
+                let response =
+                    ui.add(egui::TextEdit::singleline(&mut editor.steps).desired_width(250.0));
+                if response.changed() {
+                    match input::parse_steps(&editor.steps) {
+                        Ok(steps) => {
+                            editor.definition.steps = steps;
+                            editor.error = None;
+                        }
+                        Err(err) => editor.error = Some(err),
+                    }
+                }
+
+                match &editor.error {
+                    Some(error) => {
+                        ui.colored_label(egui::Color32::RED, "Invalid")
+                            .on_hover_text(error);
+                    }
+                    None => {
+                        ui.label("");
+                    }
+                }
+
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(index) = removed {
+            macros.remove(index);
+        }
+
+        if ui.button("Add Macro").clicked() {
+            macros.push(MacroEditor::new(Macro {
+                name: format!("Macro {}", macros.len() + 1),
+                key: None,
+                steps: vec![Step {
+                    buttons: Button::A.mask(),
+                    frames: 1,
+                }],
+            }));
+        }
+
+        return binding;
+    }
+
+    fn render_gamepad_settings(ui: &mut egui::Ui, gamepads: &mut Gamepads) {
+        ui.label(RichText::new("Controllers").size(18.0));
+
+        let mut deadzone = gamepads.get_settings().deadzone;
+        if ui
+            .add(
+                egui::Slider::new(&mut deadzone, gamepad::MIN_DEADZONE..=gamepad::MAX_DEADZONE)
+                    .text("Stick Deadzone"),
+            )
+            .on_hover_text("How far the analog stick has to be pushed to press a direction")
+            .changed()
+        {
+            gamepads.set_deadzone(deadzone);
+        }
+
+        let connected = gamepads.get_connected();
+        if connected.is_empty() {
+            ui.label("No controllers connected");
+        }
+
+        for (id, name) in connected {
+            egui::CollapsingHeader::new(&name)
+                .id_source(("gamepad", id))
+                .show(ui, |ui| {
+                    let mapping = gamepads.get_settings().get_mapping(&name);
+
+                    egui::Grid::new(("gamepad_mapping", id)).show(ui, |ui| {
+                        for input in Button::ALL {
+                            ui.label(RichText::new(format!("{}: ", input.get_name())).size(15.0));
+
+                            let text = match (mapping.get(input), input) {
+                                _ if gamepads.get_remap() == Some((id, input)) => {
+                                    String::from("Press a button...")
+                                }
+                                (Some(button), _) => format!("Button {}", button),
+                                (
+                                    None,
+                                    Button::Up | Button::Down | Button::Left | Button::Right,
+                                ) => String::from("Stick / D-pad"),
+                                (None, _) => String::from("Unmapped"),
+                            };
+
+                            if ui.button(RichText::new(text).size(15.0)).clicked() {
+                                gamepads.start_remap(id, input);
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                    if ui.button("Reset to Default").clicked() {
+                        gamepads.reset_mapping(&name);
+                    }
+                });
+        }
+    }
+
     fn render_key_bind_window(&mut self, egui_ctx: &Context) {
         if !self.show_key_bind_window {}
 
@@ -238,7 +500,7 @@ impl Ui {
                         self.show_key_bind_window = false;
 
                         match self.key_to_modify {
-                            (Some(direction), None) => match direction {
+                            Binding::Joypad(Some(direction), None) => match direction {
                                 DirectionButton::Up => self.key_up = (key, ElementState::Released),
                                 DirectionButton::Down => {
                                     self.key_down = (key, ElementState::Released)
@@ -250,7 +512,7 @@ impl Ui {
                                     self.key_right = (key, ElementState::Released)
                                 }
                             },
-                            (None, Some(action)) => match action {
+                            Binding::Joypad(None, Some(action)) => match action {
                                 ActionButton::A => self.key_a = (key, ElementState::Released),
                                 ActionButton::B => self.key_b = (key, ElementState::Released),
                                 ActionButton::Start => {
@@ -260,10 +522,17 @@ impl Ui {
                                     self.key_select = (key, ElementState::Released)
                                 }
                             },
+                            Binding::TurboA => self.turbo.a = Some(key),
+                            Binding::TurboB => self.turbo.b = Some(key),
+                            Binding::Macro(index) => {
+                                if let Some(editor) = self.macros.get_mut(index) {
+                                    editor.definition.key = Some(key);
+                                }
+                            }
                             _ => {}
                         }
 
-                        self.key_to_modify = (None, None);
+                        self.key_to_modify = Binding::None;
                     }
                 }
                 _ => {}
@@ -279,59 +548,96 @@ impl Ui {
                 if key_pressed == self.key_a.0 {
                     if input.state != self.key_a.1 {
                         self.key_a.1 = input.state;
-                        frontend.send_joypad_data(None, Some(ActionButton::A), input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::A, input.state);
                     }
                 }
 
                 if key_pressed == self.key_b.0 {
                     if input.state != self.key_b.1 {
                         self.key_b.1 = input.state;
-                        frontend.send_joypad_data(None, Some(ActionButton::B), input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::B, input.state);
                     }
                 }
 
                 if key_pressed == self.key_start.0 {
                     if input.state != self.key_start.1 {
                         self.key_start.1 = input.state;
-                        frontend.send_joypad_data(None, Some(ActionButton::Start), input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::Start, input.state);
                     }
                 }
 
                 if key_pressed == self.key_select.0 {
                     if input.state != self.key_select.1 {
                         self.key_select.1 = input.state;
-                        frontend.send_joypad_data(None, Some(ActionButton::Select), input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::Select, input.state);
                     }
                 }
 
                 if key_pressed == self.key_up.0 {
                     if input.state != self.key_up.1 {
                         self.key_up.1 = input.state;
-                        frontend.send_joypad_data(Some(DirectionButton::Up), None, input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::Up, input.state);
                     }
                 }
 
                 if key_pressed == self.key_down.0 {
                     if input.state != self.key_down.1 {
                         self.key_down.1 = input.state;
-                        frontend.send_joypad_data(Some(DirectionButton::Down), None, input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::Down, input.state);
                     }
                 }
 
                 if key_pressed == self.key_left.0 {
                     if input.state != self.key_left.1 {
                         self.key_left.1 = input.state;
-                        frontend.send_joypad_data(Some(DirectionButton::Left), None, input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::Left, input.state);
                     }
                 }
 
                 if key_pressed == self.key_right.0 {
                     if input.state != self.key_right.1 {
                         self.key_right.1 = input.state;
-                        frontend.send_joypad_data(Some(DirectionButton::Right), None, input.state);
+                        self.mixer
+                            .set_held(Source::Keyboard, Button::Right, input.state);
                     }
                 }
 
+                // Held keys repeat their press, while turbo buttons and macros only change
+                // once per press.
+                let changed = match input.state {
+                    ElementState::Pressed => self.held_keys.insert(key_pressed),
+                    ElementState::Released => self.held_keys.remove(&key_pressed),
+                };
+
+                if changed {
+                    if Some(key_pressed) == self.turbo.a {
+                        frontend.send_input_command(Command::Turbo(Button::A, input.state));
+                    }
+
+                    if Some(key_pressed) == self.turbo.b {
+                        frontend.send_input_command(Command::Turbo(Button::B, input.state));
+                    }
+                }
+
+                if changed && input.state == ElementState::Pressed {
+                    for editor in self.macros.iter() {
+                        if editor.definition.key == Some(key_pressed) {
+                            frontend.send_input_command(Command::Macro(
+                                editor.definition.steps.clone(),
+                            ));
+                        }
+                    }
+                }
+
+                self.send_joypad_changes(frontend);
+
                 log::trace!(
                     "key scancode: {:?}, state: {:?}, virt: {:?}",
                     input.scancode,
@@ -342,4 +648,33 @@ impl Ui {
             _ => {}
         }
     }
+
+    /// Passes the buttons pressed and released on the controllers, and the turbo rate when
+    /// it changes, on to the emulator.
+    pub fn update(&mut self, gamepad_changes: Vec<(Button, ElementState)>, frontend: &Frontend) {
+        for (button, state) in gamepad_changes {
+            self.mixer.set_held(Source::Gamepad, button, state);
+        }
+
+        self.send_joypad_changes(frontend);
+
+        if self.sent_turbo_rate != Some(self.turbo.rate) {
+            self.sent_turbo_rate = Some(self.turbo.rate);
+            frontend.send_input_command(Command::TurboRate(self.turbo.rate));
+        }
+    }
+
+    fn send_joypad_changes(&mut self, frontend: &Frontend) {
+        for (button, state) in self.mixer.changes() {
+            let (direction, action) = button.to_joypad();
+            frontend.send_joypad_data(direction, action, state);
+        }
+    }
+}
+
+fn key_name(key: Option<VirtualKeyCode>) -> String {
+    match key {
+        Some(key) => format!("{:?}", key),
+        None => String::from("Unbound"),
+    }
 }
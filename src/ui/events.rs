@@ -1,5 +1,7 @@
+use crate::screenshot;
+
 #[derive(Debug)]
 pub enum UiEvent {
     CloseWindow,
+    Screenshot(screenshot::Resolution),
 }
-
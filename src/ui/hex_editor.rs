@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use egui::{Color32, Context, RichText};
+
+use crate::cpu::trace::Location;
+use crate::hex_editor;
+use crate::memory::map::Region;
+use crate::memory::Memory;
+use crate::symbols::Symbols;
+
+const BYTES_PER_ROW: usize = 16;
+const ROW_COUNT: usize = hex_editor::ADDRESS_SPACE_SIZE / BYTES_PER_ROW;
+
+/// How long bytes stay highlighted after changing.
+const CHANGE_HIGHLIGHT_DURATION: Duration = Duration::from_secs(1);
+const CHANGE_HIGHLIGHT_COLOR: Color32 = Color32::YELLOW;
+
+pub struct Ui {
+    show: bool,
+    rom_loaded: bool,
+
+    goto_text: String,
+    pattern_text: String,
+    error: Option<String>,
+
+    // Byte being edited, and the value entered for it.
+    selected: Option<u16>,
+    value_text: String,
+
+    // Row to scroll to on the next render.
+    scroll_to: Option<usize>,
+
+    changes: Changes,
+}
+
+// Bytes displayed on the last render, and when the displayed ones last changed. Bytes
+// scrolled out of view aren't followed.
+struct Changes {
+    displayed: HashMap<u16, u8>,
+    changed_at: HashMap<u16, Instant>,
+}
+
+impl Changes {
+    fn new() -> Self {
+        Self {
+            displayed: HashMap::new(),
+            changed_at: HashMap::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.displayed.clear();
+        self.changed_at.clear();
+    }
+
+    // Records the byte displayed at the address, and returns whether it changed recently.
+    fn update(&mut self, address: u16, byte: u8, now: Instant) -> bool {
+        if let Some(previous) = self.displayed.insert(address, byte) {
+            if previous != byte {
+                self.changed_at.insert(address, now);
+            }
+        }
+
+        return self.changed_at.contains_key(&address);
+    }
+
+    // Forgets the bytes that weren't displayed, and the changes no longer highlighted.
+    fn finish(&mut self, displayed: &[u16], now: Instant) {
+        self.displayed
+            .retain(|address, _| displayed.contains(address));
+        self.changed_at
+            .retain(|_, changed_at| now.duration_since(*changed_at) < CHANGE_HIGHLIGHT_DURATION);
+    }
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            rom_loaded: false,
+            goto_text: String::new(),
+            pattern_text: String::new(),
+            error: None,
+            selected: None,
+            value_text: String::new(),
+            scroll_to: None,
+            changes: Changes::new(),
+        }
+    }
+
+    pub fn show(&mut self, show: bool) {
+        self.show = show;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.show
+    }
+
+    pub fn load_rom(&mut self) {
+        self.rom_loaded = true;
+        self.changes.clear();
+    }
+
+    /// Bytes can only be edited while the emulator is paused, so that edits aren't
+    /// immediately overwritten by the game.
+    pub fn render(
+        &mut self,
+        egui_ctx: &Context,
+        memory_ref: &Arc<Mutex<Memory>>,
+        is_paused: bool,
+        symbols: &Symbols,
+    ) {
+        egui::Window::new(RichText::new("Hex Editor").size(24.0))
+            .collapsible(false)
+            .default_width(700.0)
+            .open(&mut self.show)
+            .show(egui_ctx, |ui| {
+                if !self.rom_loaded {
+                    ui.label("Load a ROM to view its memory");
+                    return;
+                }
+
+                let mut memory = memory_ref.lock().unwrap();
+
+                ui.horizontal(|ui| {
+                    ui.label("Go to: ");
+                    ui.add(egui::TextEdit::singleline(&mut self.goto_text).desired_width(100.0))
+                        .on_hover_text("Address or label");
+                    if ui.button("Go").clicked() {
+                        match Ui::resolve_address(&self.goto_text, symbols) {
+                            Ok(address) => {
+                                Ui::select(
+                                    address,
+                                    &memory,
+                                    &mut self.selected,
+                                    &mut self.value_text,
+                                );
+                                self.scroll_to = Some(address as usize / BYTES_PER_ROW);
+                                self.error = None;
+                            }
+                            Err(err) => self.error = Some(err),
+                        }
+                    }
+
+                    ui.separator();
+                    ui.label("Find: ");
+                    ui.add(egui::TextEdit::singleline(&mut self.pattern_text).desired_width(150.0))
+                        .on_hover_text("Hexadecimal bytes, with ?? matching any byte");
+                    if ui.button("Find Next").clicked() {
+                        match Ui::find_next(&self.pattern_text, self.selected, &memory) {
+                            Ok(address) => {
+                                Ui::select(
+                                    address,
+                                    &memory,
+                                    &mut self.selected,
+                                    &mut self.value_text,
+                                );
+                                self.scroll_to = Some(address as usize / BYTES_PER_ROW);
+                                self.error = None;
+                            }
+                            Err(err) => self.error = Some(err),
+                        }
+                    }
+                });
+
+                if let Some(address) = self.selected {
+                    ui.horizontal(|ui| {
+                        let rom_bank = memory.get_rom_bank();
+                        let region = Region::of(address).get_name(rom_bank);
+                        let text = match symbols.describe(Location::new(address, rom_bank)) {
+                            Some(label) => format!("{:04X} {} ({}): ", address, label, region),
+                            None => format!("{:04X} ({}): ", address, region),
+                        };
+                        ui.label(RichText::new(text).monospace());
+
+                        ui.add_enabled_ui(is_paused, |ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.value_text)
+                                    .desired_width(30.0),
+                            );
+                            if ui.button("Write").clicked() {
+                                match u8::from_str_radix(self.value_text.trim(), 16) {
+                                    Ok(value) => {
+                                        memory.poke(address as usize, value);
+                                        self.error = None;
+                                    }
+                                    Err(_) => {
+                                        self.error =
+                                            Some(format!("invalid byte '{}'", self.value_text));
+                                    }
+                                }
+                            }
+                        });
+
+                        if !is_paused {
+                            ui.label(RichText::new("Pause the emulator to edit").weak());
+                        }
+                    });
+                }
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+
+                ui.separator();
+                Ui::render_rows(
+                    ui,
+                    &memory,
+                    &mut self.selected,
+                    &mut self.value_text,
+                    &mut self.scroll_to,
+                    &mut self.changes,
+                );
+            });
+    }
+
+    fn render_rows(
+        ui: &mut egui::Ui,
+        memory: &Memory,
+        selected: &mut Option<u16>,
+        value_text: &mut String,
+        scroll_to: &mut Option<usize>,
+        changes: &mut Changes,
+    ) {
+        let row_height = ui.spacing().interact_size.y;
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_source("hex_editor_rows")
+            .auto_shrink([false, false]);
+        if let Some(row) = scroll_to.take() {
+            let offset = row as f32 * (row_height + ui.spacing().item_spacing.y);
+            scroll_area = scroll_area.vertical_scroll_offset(offset);
+        }
+
+        let rom_bank = memory.get_rom_bank();
+        let now = Instant::now();
+        let mut displayed = Vec::new();
+
+        scroll_area.show_rows(ui, row_height, ROW_COUNT, |ui, rows| {
+            for row in rows {
+                let row_address = (row * BYTES_PER_ROW) as u16;
+                let bytes: Vec<u8> = (0..BYTES_PER_ROW)
+                    .map(|offset| {
+                        memory
+                            .dma_read(row_address as usize + offset)
+                            .unwrap_or(0xFF)
+                    })
+                    .collect();
+
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 4.0;
+                    ui.label(RichText::new(format!("{:04X}", row_address)).monospace());
+                    ui.add_space(8.0);
+
+                    for (offset, byte) in bytes.iter().enumerate() {
+                        let address = row_address + offset as u16;
+                        displayed.push(address);
+
+                        let mut text = RichText::new(format!("{:02X}", byte)).monospace();
+                        if changes.update(address, *byte, now) {
+                            text = text.color(CHANGE_HIGHLIGHT_COLOR);
+                        }
+
+                        if ui
+                            .selectable_label(*selected == Some(address), text)
+                            .clicked()
+                        {
+                            Ui::select(address, memory, selected, value_text);
+                        }
+                    }
+
+                    ui.add_space(8.0);
+                    let ascii: String = bytes
+                        .iter()
+                        .map(|byte| match byte {
+                            0x20..=0x7E => *byte as char,
+                            _ => '.',
+                        })
+                        .collect();
+                    ui.label(RichText::new(ascii).monospace());
+
+                    ui.add_space(8.0);
+                    ui.label(RichText::new(Region::of(row_address).get_name(rom_bank)).weak());
+                });
+            }
+        });
+
+        changes.finish(&displayed, now);
+    }
+
+    // Address of the label, or the address written out. ROM labels are in the bank they
+    // were assembled for, which may not be the one mapped.
+    fn resolve_address(text: &str, symbols: &Symbols) -> Result<u16, String> {
+        return match symbols.find(text) {
+            Some(location) => Ok(location.address),
+            None => hex_editor::parse_address(text),
+        };
+    }
+
+    // Finds the pattern after the selected byte, or from the start when none is.
+    fn find_next(pattern: &str, selected: Option<u16>, memory: &Memory) -> Result<u16, String> {
+        let pattern = hex_editor::parse_pattern(pattern)?;
+        let start = selected.map_or(0, |address| address.wrapping_add(1));
+        let bytes = hex_editor::read_all(memory);
+
+        return hex_editor::find_pattern(&bytes, &pattern, start)
+            .ok_or_else(|| String::from("pattern not found"));
+    }
+
+    fn select(address: u16, memory: &Memory, selected: &mut Option<u16>, value_text: &mut String) {
+        *selected = Some(address);
+        *value_text = format!("{:02X}", memory.dma_read(address as usize).unwrap_or(0xFF));
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Context, RichText};
+
+use crate::memory::Memory;
+use crate::registers::{self, Field, Group, Register};
+
+pub struct Ui {
+    show: bool,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self { show: false }
+    }
+
+    pub fn show(&mut self, show: bool) {
+        self.show = show;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.show
+    }
+
+    /// Fields can only be edited while the emulator is paused, so that edits aren't
+    /// immediately overwritten by the game.
+    pub fn render(&mut self, ctx: &Context, memory_ref: &Arc<Mutex<Memory>>, is_paused: bool) {
+        egui::Window::new(RichText::new("IO Registers").size(24.0))
+            .collapsible(false)
+            .default_width(450.0)
+            .open(&mut self.show)
+            .show(ctx, |ui| {
+                if !is_paused {
+                    ui.label(RichText::new("Pause the emulator to edit").weak());
+                }
+
+                let mut memory = memory_ref.lock().unwrap();
+                egui::ScrollArea::vertical()
+                    .id_source("io_inspector_registers")
+                    .max_height(600.0)
+                    .show(ui, |ui| {
+                        for group in Group::ALL {
+                            egui::CollapsingHeader::new(group.get_name())
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    for register in registers::REGISTERS
+                                        .iter()
+                                        .filter(|register| register.group == group)
+                                    {
+                                        Ui::render_register(ui, &mut memory, register, is_paused);
+                                    }
+                                });
+                        }
+                    });
+            });
+    }
+
+    fn render_register(
+        ui: &mut egui::Ui,
+        memory: &mut Memory,
+        register: &Register,
+        is_paused: bool,
+    ) {
+        let value = memory.dma_read(register.address).unwrap_or(0xFF);
+        ui.label(
+            RichText::new(format!(
+                "{:04X} {} - {}: {:02X}",
+                register.address, register.name, register.description, value
+            ))
+            .monospace()
+            .strong(),
+        );
+
+        egui::Grid::new(register.name)
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for field in register.fields {
+                    ui.label(format!("  {}", field.name));
+                    ui.add_enabled_ui(is_paused && register.is_editable(), |ui| {
+                        if let Some(field_value) = Ui::render_field(ui, register, field, value) {
+                            memory.poke(register.address, field.set(value, field_value));
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        ui.add_space(4.0);
+    }
+
+    // Shows the field's value, and returns the one picked by the user, if any.
+    fn render_field(
+        ui: &mut egui::Ui,
+        register: &Register,
+        field: &Field,
+        value: u8,
+    ) -> Option<u8> {
+        let mut field_value = field.get(value);
+
+        if field.meanings.is_empty() {
+            let response =
+                ui.add(egui::DragValue::new(&mut field_value).clamp_range(0..=field.get_max()));
+            if field.width == 8 {
+                response.on_hover_text(field.describe(value));
+            }
+        } else {
+            egui::ComboBox::from_id_source((register.name, field.name))
+                .selected_text(field.describe(value))
+                .width(200.0)
+                .show_ui(ui, |ui| {
+                    for (index, meaning) in field.meanings.iter().enumerate() {
+                        ui.selectable_value(&mut field_value, index as u8, *meaning);
+                    }
+                });
+        }
+
+        return match field_value != field.get(value) {
+            true => Some(field_value),
+            false => None,
+        };
+    }
+}
@@ -1,51 +1,191 @@
+use crate::config;
+use crate::cpu;
+use crate::filters;
 use crate::gameboy;
+use crate::gamepad;
 use crate::memory::Memory;
+use crate::movie;
+use crate::palette;
+use crate::ppu;
+use crate::recorder;
+use crate::renderer;
+use crate::screenshot;
+use crate::symbols;
 
 use egui::epaint::Shadow;
 use egui::Color32;
 use egui::Visuals;
-use glium::glutin::event::WindowEvent;
+use glium::glutin::event::{ElementState, ModifiersState, VirtualKeyCode, WindowEvent};
 use glium::glutin::event_loop::ControlFlow;
 use glium::glutin::event_loop::EventLoopProxy;
+use glium::glutin::window::Fullscreen;
 use glium::Display;
 use glium::Frame;
 use std::fs;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+mod call_stack;
+mod cheats;
 mod controls;
 pub mod events;
+mod hex_editor;
+mod io_inspector;
+mod ram_search;
+mod sprite_viewer;
 mod vram_viewer;
 use gameboy::channel::front_end::Frontend;
 
 pub const TOP_MENUBAR_HEIGHT: f32 = 20.0;
 pub const SCALE_FACTOR: i32 = 5;
 
+/// Saves a screenshot at native resolution, or as displayed when shift is held.
+const SCREENSHOT_KEY: VirtualKeyCode = VirtualKeyCode::F12;
+const FULLSCREEN_KEY: VirtualKeyCode = VirtualKeyCode::F11;
+const MENUBAR_KEY: VirtualKeyCode = VirtualKeyCode::F10;
+
+/// Input movie being recorded or played back by the emulator.
+enum MovieState {
+    Idle,
+
+    /// Recording to the given path, with the movie received from the emulator so far.
+    Recording(PathBuf, Option<movie::Movie>),
+
+    Playing(PathBuf),
+}
+
 pub struct Ui {
     egui_glium_client: egui_glium::EguiGlium,
     ui_event_loop_proxy: EventLoopProxy<events::UiEvent>,
     skip_boot_rom: bool,
     controls: controls::Ui,
+    gamepads: gamepad::Gamepads,
+    cheats: cheats::Ui,
+    ram_search: ram_search::Ui,
+    hex_editor: hex_editor::Ui,
+    io_inspector: io_inspector::Ui,
+    call_stack: call_stack::Ui,
+    symbols: symbols::SymbolFile,
+    sprite_viewer: sprite_viewer::Ui,
     vram_viewer: vram_viewer::Ui,
     is_paused: bool,
+    renderer: ppu::Renderer,
+    ppu_access_restrictions: bool,
+    palettes: Vec<palette::Palette>,
     memory_ref: Arc<Mutex<Memory>>,
+    cpu_ref: Arc<Mutex<cpu::LR35902>>,
+    modifiers: ModifiersState,
+    recorder: Option<recorder::Recorder>,
+    movie: MovieState,
+    window_layout: config::WindowLayout,
+
+    // Settings saved to the config file, which differ from the ones in use when overridden
+    // from the command line.
+    config: config::Config,
 }
 
 impl Ui {
+    /// `settings` are the settings to run with, `config` those saved in the config file.
     pub fn new(
         egui_glium_client: egui_glium::EguiGlium,
         event_loop_proxy: EventLoopProxy<events::UiEvent>,
-        skip_boot_rom: bool,
         memory_ref: Arc<Mutex<Memory>>,
+        cpu_ref: Arc<Mutex<cpu::LR35902>>,
+        config: config::Config,
+        settings: &config::Config,
     ) -> Self {
-        Self {
+        let palettes = settings.palettes.load_palettes();
+
+        let mut ui = Self {
             egui_glium_client,
             ui_event_loop_proxy: event_loop_proxy,
-            skip_boot_rom,
-            controls: controls::Ui::new(),
+            skip_boot_rom: settings.skip_boot_rom,
+            controls: controls::Ui::new(&settings.key_bindings, &settings.turbo, &settings.macros),
+            gamepads: gamepad::Gamepads::new(gamepad::default_backend(), settings.gamepad.clone()),
+            cheats: cheats::Ui::new(),
+            ram_search: ram_search::Ui::new(),
+            hex_editor: hex_editor::Ui::new(),
+            io_inspector: io_inspector::Ui::new(),
+            call_stack: call_stack::Ui::new(),
+            symbols: symbols::SymbolFile::new(),
+            sprite_viewer: sprite_viewer::Ui::new(),
             vram_viewer: vram_viewer::Ui::new(),
             is_paused: false,
+            renderer: ppu::Renderer::PixelFifo,
+            ppu_access_restrictions: true,
+            palettes,
             memory_ref,
+            cpu_ref,
+            modifiers: ModifiersState::empty(),
+            recorder: None,
+            movie: MovieState::Idle,
+            window_layout: settings.window.clone(),
+            config,
+        };
+
+        for window in config::DebugWindow::ALL {
+            ui.show_window(window, settings.is_shown(window));
+        }
+
+        return ui;
+    }
+
+    fn show_window(&mut self, window: config::DebugWindow, show: bool) {
+        match window {
+            config::DebugWindow::Cheats => self.cheats.show(show),
+            config::DebugWindow::VramViewer => self.vram_viewer.show(show),
+            config::DebugWindow::SpriteViewer => self.sprite_viewer.show(show),
+            config::DebugWindow::RamSearch => self.ram_search.show(show),
+            config::DebugWindow::HexEditor => self.hex_editor.show(show),
+            config::DebugWindow::IoRegisters => self.io_inspector.show(show),
+            config::DebugWindow::CallStack => self.call_stack.show(show),
+        }
+    }
+
+    fn is_window_shown(&self, window: config::DebugWindow) -> bool {
+        return match window {
+            config::DebugWindow::Cheats => self.cheats.is_shown(),
+            config::DebugWindow::VramViewer => self.vram_viewer.is_shown(),
+            config::DebugWindow::SpriteViewer => self.sprite_viewer.is_shown(),
+            config::DebugWindow::RamSearch => self.ram_search.is_shown(),
+            config::DebugWindow::HexEditor => self.hex_editor.is_shown(),
+            config::DebugWindow::IoRegisters => self.io_inspector.is_shown(),
+            config::DebugWindow::CallStack => self.call_stack.is_shown(),
+        };
+    }
+
+    // Saves which debug windows are shown when one was opened or closed.
+    fn save_window_visibility(&mut self) {
+        let mut changed = false;
+        for window in config::DebugWindow::ALL {
+            let shown = self.is_window_shown(window);
+            if shown != self.config.is_shown(window) {
+                self.config.set_shown(window, shown);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.config.save();
+        }
+    }
+
+    /// Applies the palettes selected in the settings to the renderer and VRAM viewer.
+    pub fn restore_palettes(
+        &mut self,
+        settings: &config::Config,
+        opengl_renderer: &mut renderer::OpenGL,
+    ) {
+        let layer_palettes = settings.palettes.layer_palettes(&self.palettes);
+        for layer in [
+            ppu::Layer::Background,
+            ppu::Layer::Object0,
+            ppu::Layer::Object1,
+        ] {
+            let palette = layer_palettes.get(layer).clone();
+            self.vram_viewer.set_palette(layer, palette.clone());
+            opengl_renderer.set_palette(layer, palette);
         }
     }
 
@@ -55,6 +195,7 @@ impl Ui {
         display: &Display,
         frame: &mut Frame,
         frontend: &mut Frontend,
+        opengl_renderer: &mut renderer::OpenGL,
     ) {
         let egui_redraw_timer = self.egui_glium_client.run(display, |ctx| {
             let mut visuals = Visuals::default();
@@ -63,14 +204,21 @@ impl Ui {
             visuals.override_text_color = Some(Color32::WHITE);
             ctx.set_visuals(visuals);
 
-            // Top menubar
+            // Top menubar, which can be hidden to give the whole window to the game
             egui::TopBottomPanel::top("top_panel")
                 .exact_height(TOP_MENUBAR_HEIGHT)
-                .show(ctx, |ui| {
+                .show_animated(ctx, !self.window_layout.hide_menubar, |ui| {
                     ui.horizontal(|ui| {
                         ui.menu_button("File", |ui| {
                             if ui.button("Load ROM").clicked() {
-                                Ui::load_rom_from_file_dialog(frontend);
+                                Ui::load_rom_from_file_dialog(
+                                    frontend,
+                                    &mut self.config,
+                                    &mut self.cheats,
+                                    &mut self.ram_search,
+                                    &mut self.hex_editor,
+                                    &mut self.symbols,
+                                );
 
                                 // When loading a rom, un-pause the emulator to avoid weirdness
                                 self.is_paused = false;
@@ -79,6 +227,52 @@ impl Ui {
                                 ui.close_menu();
                             }
 
+                            ui.menu_button("Save Screenshot", |ui| {
+                                for (resolution, label) in [
+                                    (screenshot::Resolution::Native, "Native Resolution (F12)"),
+                                    (screenshot::Resolution::Scaled, "As Displayed (Shift+F12)"),
+                                ] {
+                                    if ui.button(label).clicked() {
+                                        self.ui_event_loop_proxy
+                                            .send_event(events::UiEvent::Screenshot(resolution))
+                                            .unwrap();
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+
+                            match &self.recorder {
+                                Some(recorder) => {
+                                    let label = format!(
+                                        "Stop Recording ({} frames)",
+                                        recorder.get_frame_count()
+                                    );
+                                    if ui
+                                        .button(label)
+                                        .on_hover_text(recorder.get_path().display().to_string())
+                                        .clicked()
+                                    {
+                                        Ui::finish_recording(&mut self.recorder);
+                                        ui.close_menu();
+                                    }
+                                }
+                                None => {
+                                    ui.menu_button("Start Recording", |ui| {
+                                        for format in recorder::Format::ALL {
+                                            if ui.button(format.get_name()).clicked() {
+                                                Ui::start_recording(&mut self.recorder, format);
+                                                ui.close_menu();
+                                            }
+                                        }
+                                    });
+                                }
+                            }
+
+                            ui.menu_button("Input Movie", |ui| {
+                                Ui::render_movie_menu(ui, &mut self.movie, frontend);
+                            });
+
+                            ui.separator();
                             if ui.button("Exit").clicked() {
                                 self.is_paused = false;
                                 frontend.send_pause(self.is_paused);
@@ -96,17 +290,86 @@ impl Ui {
                                 .clicked()
                             {
                                 frontend.send_set_skip_boot_rom_back_end(self.skip_boot_rom);
+                                self.config.skip_boot_rom = self.skip_boot_rom;
+                                self.config.save();
                             }
 
                             if ui.checkbox(&mut self.is_paused, "Pause").clicked() {
                                 frontend.send_pause(self.is_paused);
                             }
 
+                            ui.menu_button("Renderer", |ui| {
+                                let pixel_fifo_clicked = ui
+                                    .radio_value(
+                                        &mut self.renderer,
+                                        ppu::Renderer::PixelFifo,
+                                        "Pixel FIFO (accurate)",
+                                    )
+                                    .clicked();
+                                let scanline_clicked = ui
+                                    .radio_value(
+                                        &mut self.renderer,
+                                        ppu::Renderer::Scanline,
+                                        "Scanline (fast)",
+                                    )
+                                    .clicked();
+
+                                if pixel_fifo_clicked || scanline_clicked {
+                                    frontend.send_set_renderer_back_end(self.renderer);
+                                    ui.close_menu();
+                                }
+                            });
+
+                            ui.menu_button("Video", |ui| {
+                                if ui
+                                    .checkbox(
+                                        &mut self.window_layout.fullscreen,
+                                        "Fullscreen (F11)",
+                                    )
+                                    .clicked()
+                                {
+                                    Ui::apply_fullscreen(display, self.window_layout.fullscreen);
+                                    self.config.window.fullscreen = self.window_layout.fullscreen;
+                                    self.config.save();
+                                }
+
+                                if ui
+                                    .checkbox(
+                                        &mut self.window_layout.hide_menubar,
+                                        "Hide Menubar (F10)",
+                                    )
+                                    .clicked()
+                                {
+                                    self.config.window.hide_menubar =
+                                        self.window_layout.hide_menubar;
+                                    self.config.save();
+                                    ui.close_menu();
+                                }
+
+                                ui.separator();
+                                Ui::render_video_settings(ui, opengl_renderer, &mut self.config);
+                            });
+
+                            ui.menu_button("Palette", |ui| {
+                                Ui::render_palette_settings(
+                                    ui,
+                                    &mut self.palettes,
+                                    &mut self.vram_viewer,
+                                    &mut self.config,
+                                    opengl_renderer,
+                                );
+                            });
+
                             ui.separator();
                             if ui.button("Controls").clicked() {
                                 self.controls.show(true);
                                 ui.close_menu();
                             }
+
+                            if ui.button("Cheats").clicked() {
+                                self.cheats.show(true);
+                                ui.close_menu();
+                            }
                         });
 
                         ui.menu_button("Debug", |ui| {
@@ -114,17 +377,91 @@ impl Ui {
                                 self.vram_viewer.show(true);
                                 ui.close_menu();
                             }
+
+                            if ui.button("Sprite Viewer").clicked() {
+                                self.sprite_viewer.show(true);
+                                ui.close_menu();
+                            }
+
+                            if ui.button("RAM Search").clicked() {
+                                self.ram_search.show(true);
+                                ui.close_menu();
+                            }
+
+                            if ui.button("Hex Editor").clicked() {
+                                self.hex_editor.show(true);
+                                ui.close_menu();
+                            }
+
+                            if ui.button("IO Registers").clicked() {
+                                self.io_inspector.show(true);
+                                ui.close_menu();
+                            }
+
+                            if ui.button("Call Stack").clicked() {
+                                self.call_stack.show(true);
+                                ui.close_menu();
+                            }
+
+                            ui.separator();
+                            if ui
+                                .checkbox(
+                                    &mut self.ppu_access_restrictions,
+                                    "Restrict VRAM/OAM Access",
+                                )
+                                .on_hover_text(
+                                    "Lock the CPU out of VRAM and OAM while the PPU uses them",
+                                )
+                                .clicked()
+                            {
+                                frontend.send_set_ppu_access_restrictions_back_end(
+                                    self.ppu_access_restrictions,
+                                );
+                            }
                         });
                     });
                 });
 
+            self.symbols.check_for_changes();
+
             // Controls window
-            self.controls.render(ctx);
+            self.controls.render(ctx, &mut self.gamepads);
+            Ui::save_gamepad_settings(&self.gamepads, &mut self.config);
+            Ui::save_control_settings(&self.controls, &mut self.config);
+
+            // RAM Search window, which freezes addresses through cheats
+            if let Some(cheat) = self
+                .ram_search
+                .render(ctx, &self.memory_ref, self.symbols.get())
+            {
+                self.cheats.add(cheat, frontend);
+            }
+
+            // Hex Editor window
+            self.hex_editor
+                .render(ctx, &self.memory_ref, self.is_paused, self.symbols.get());
+
+            // IO Registers window
+            self.io_inspector
+                .render(ctx, &self.memory_ref, self.is_paused);
+
+            // Call Stack window
+            self.call_stack
+                .render(ctx, &self.cpu_ref, self.symbols.get(), frontend);
+
+            // Cheats window
+            self.cheats.render(ctx, frontend);
 
             // VRAM Viewer window
             self.vram_viewer.render(ctx, &self.memory_ref);
+
+            // Sprite Viewer window
+            self.sprite_viewer
+                .render(ctx, &self.memory_ref, opengl_renderer.get_palettes());
         });
 
+        self.save_window_visibility();
+
         let time_until_next_redraw = std::time::Instant::now().checked_add(egui_redraw_timer);
 
         if egui_redraw_timer.is_zero() {
@@ -139,6 +476,173 @@ impl Ui {
         self.egui_glium_client.paint(display, frame);
     }
 
+    /// Height of the top menubar in physical pixels, which the emulator screen is drawn below.
+    pub fn top_inset(&self) -> u32 {
+        if self.window_layout.hide_menubar {
+            return 0;
+        }
+
+        let pixels_per_point = self.egui_glium_client.egui_ctx.pixels_per_point();
+        return (TOP_MENUBAR_HEIGHT * pixels_per_point).round() as u32;
+    }
+
+    fn render_video_settings(
+        ui: &mut egui::Ui,
+        opengl_renderer: &mut renderer::OpenGL,
+        config: &mut config::Config,
+    ) {
+        let mut scaling = opengl_renderer.get_scaling();
+        ui.label("Scaling");
+        let integer_clicked = ui
+            .radio_value(&mut scaling, renderer::Scaling::Integer, "Integer")
+            .clicked();
+        let aspect_fit_clicked = ui
+            .radio_value(
+                &mut scaling,
+                renderer::Scaling::AspectFit,
+                "Keep Aspect Ratio",
+            )
+            .clicked();
+        let stretch_clicked = ui
+            .radio_value(&mut scaling, renderer::Scaling::Stretch, "Stretch")
+            .clicked();
+
+        if integer_clicked || aspect_fit_clicked || stretch_clicked {
+            opengl_renderer.set_scaling(scaling);
+            config.window.scaling = scaling;
+            config.save();
+        }
+
+        ui.separator();
+
+        let mut filtering = opengl_renderer.get_filtering();
+        ui.label("Filtering");
+        let nearest_clicked = ui
+            .radio_value(&mut filtering, renderer::Filtering::Nearest, "Nearest")
+            .clicked();
+        let linear_clicked = ui
+            .radio_value(&mut filtering, renderer::Filtering::Linear, "Linear")
+            .clicked();
+
+        if nearest_clicked || linear_clicked {
+            opengl_renderer.set_filtering(filtering);
+        }
+
+        ui.separator();
+
+        let mut filter = opengl_renderer.get_filter();
+        ui.label("Upscaling Filter");
+        for option in filters::Filter::ALL {
+            if ui
+                .radio_value(&mut filter, option, option.get_name())
+                .clicked()
+            {
+                opengl_renderer.set_filter(filter);
+            }
+        }
+
+        ui.separator();
+
+        ui.label("LCD Effects");
+        let mut ghosting = opengl_renderer.get_effects().is_ghosting_enabled();
+        if ui
+            .checkbox(&mut ghosting, "Ghosting")
+            .on_hover_text("Blend consecutive frames like the DMG's slow LCD")
+            .clicked()
+        {
+            opengl_renderer.set_ghosting(ghosting);
+        }
+
+        let mut color_bleed = opengl_renderer.get_effects().is_color_bleed_enabled();
+        if ui.checkbox(&mut color_bleed, "Colour Bleed").clicked() {
+            opengl_renderer.set_color_bleed(color_bleed);
+        }
+
+        let mut pixel_grid = opengl_renderer.get_effects().is_pixel_grid_enabled();
+        if ui.checkbox(&mut pixel_grid, "Pixel Grid").clicked() {
+            opengl_renderer.set_pixel_grid(pixel_grid);
+        }
+    }
+
+    fn render_palette_settings(
+        ui: &mut egui::Ui,
+        palettes: &mut Vec<palette::Palette>,
+        vram_viewer: &mut vram_viewer::Ui,
+        config: &mut config::Config,
+        opengl_renderer: &mut renderer::OpenGL,
+    ) {
+        ui.menu_button("All Layers", |ui| {
+            for palette in palettes.iter() {
+                if ui.button(palette.get_name()).clicked() {
+                    for layer in [
+                        ppu::Layer::Background,
+                        ppu::Layer::Object0,
+                        ppu::Layer::Object1,
+                    ] {
+                        opengl_renderer.set_palette(layer, palette.clone());
+                        vram_viewer.set_palette(layer, palette.clone());
+                        config.palettes.set(layer, palette.get_name());
+                    }
+                    config.save();
+                    ui.close_menu();
+                }
+            }
+        });
+
+        ui.separator();
+
+        for (layer, label) in [
+            (ppu::Layer::Background, "Background"),
+            (ppu::Layer::Object0, "Sprites (OBP0)"),
+            (ppu::Layer::Object1, "Sprites (OBP1)"),
+        ] {
+            ui.menu_button(label, |ui| {
+                for palette in palettes.iter() {
+                    let selected = opengl_renderer.get_palettes().get(layer) == palette;
+                    if ui.radio(selected, palette.get_name()).clicked() {
+                        opengl_renderer.set_palette(layer, palette.clone());
+                        vram_viewer.set_palette(layer, palette.clone());
+                        config.palettes.set(layer, palette.get_name());
+                        config.save();
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+
+        ui.separator();
+
+        if ui.button("Load Palette File").clicked() {
+            Ui::load_palettes_from_file_dialog(palettes, config);
+            ui.close_menu();
+        }
+    }
+
+    // Loaded palette files are remembered so that their palettes are available on the next run.
+    fn load_palettes_from_file_dialog(
+        palettes: &mut Vec<palette::Palette>,
+        config: &mut config::Config,
+    ) {
+        let selected_file = rfd::FileDialog::new()
+            .add_filter("Palette file", &["txt", "pal"])
+            .pick_file();
+
+        let path = match selected_file {
+            Some(path) => path,
+            None => return,
+        };
+
+        match palette::load_file(palettes, &path) {
+            Ok(_) => {
+                if !config.palettes.files.contains(&path) {
+                    config.palettes.files.push(path);
+                    config.save();
+                }
+            }
+            Err(err) => log::error!("Failed to load palette file {}: {}", path.display(), err),
+        }
+    }
+
     pub fn process_window_event(
         &mut self,
         event: WindowEvent<'_>,
@@ -146,6 +650,42 @@ impl Ui {
         frontend: &Frontend,
     ) {
         match event {
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers,
+            WindowEvent::KeyboardInput { input, .. } if input.state == ElementState::Pressed => {
+                match input.virtual_keycode {
+                    Some(SCREENSHOT_KEY) => {
+                        let resolution = match self.modifiers.shift() {
+                            true => screenshot::Resolution::Scaled,
+                            false => screenshot::Resolution::Native,
+                        };
+                        self.ui_event_loop_proxy
+                            .send_event(events::UiEvent::Screenshot(resolution))
+                            .unwrap();
+                    }
+                    Some(FULLSCREEN_KEY) => {
+                        self.window_layout.fullscreen = !self.window_layout.fullscreen;
+                        Ui::apply_fullscreen(display, self.window_layout.fullscreen);
+                        self.config.window.fullscreen = self.window_layout.fullscreen;
+                        self.config.save();
+                    }
+                    Some(MENUBAR_KEY) => {
+                        self.window_layout.hide_menubar = !self.window_layout.hide_menubar;
+                        display.gl_window().window().request_redraw();
+                        self.config.window.hide_menubar = self.window_layout.hide_menubar;
+                        self.config.save();
+                    }
+                    _ => {}
+                }
+            }
+            // Only the windowed layout is remembered, fullscreen always covers the monitor.
+            WindowEvent::Resized(size)
+                if !self.window_layout.fullscreen && size.width > 0 && size.height > 0 =>
+            {
+                self.window_layout.size = Some((size.width, size.height));
+            }
+            WindowEvent::Moved(position) if !self.window_layout.fullscreen => {
+                self.window_layout.position = Some((position.x, position.y));
+            }
             _ => {}
         }
 
@@ -155,19 +695,264 @@ impl Ui {
             display.gl_window().window().request_redraw();
         }
 
-        self.controls.process_window_event(event, frontend);
+        // Keys typed into text fields, such as macro steps, aren't passed on to the emulator.
+        if !event_response.consumed {
+            self.controls.process_window_event(event, frontend);
+        }
+
+        Ui::save_control_settings(&self.controls, &mut self.config);
     }
 
-    fn load_rom_from_file_dialog(frontend: &mut Frontend) {
-        let selected_rom = rfd::FileDialog::new()
-            .add_filter("Gameboy ROM", &["gb"])
-            .pick_file();
+    fn save_control_settings(controls: &controls::Ui, config: &mut config::Config) {
+        let key_bindings = controls.get_key_bindings();
+        let turbo = controls.get_turbo();
+        let macros = controls.get_macros();
+
+        if key_bindings != config.key_bindings || turbo != config.turbo || macros != config.macros {
+            config.key_bindings = key_bindings;
+            config.turbo = turbo;
+            config.macros = macros;
+            config.save();
+        }
+    }
+
+    fn render_movie_menu(ui: &mut egui::Ui, movie_state: &mut MovieState, frontend: &Frontend) {
+        match movie_state {
+            MovieState::Idle => {
+                if ui
+                    .button("Record From Power-On")
+                    .on_hover_text("Restart the ROM and record the inputs of every frame")
+                    .clicked()
+                {
+                    let (unix_seconds, millis) = screenshot::now();
+                    let path = movie::directory().join(format!(
+                        "movie-{}.{}",
+                        screenshot::timestamp(unix_seconds, millis),
+                        movie::FILE_EXTENSION
+                    ));
+
+                    frontend.send_movie_command(movie::Command::Record);
+                    *movie_state = MovieState::Recording(path, None);
+                    ui.close_menu();
+                }
+
+                if ui.button("Play Movie...").clicked() {
+                    let selected_movie = rfd::FileDialog::new()
+                        .add_filter("Input Movie", &[movie::FILE_EXTENSION])
+                        .set_directory(movie::directory())
+                        .pick_file();
+
+                    if let Some(path) = selected_movie {
+                        match movie::Movie::load(&path) {
+                            Ok(movie) => {
+                                log::info!("Playing movie: {}", path.display());
+                                frontend.send_movie_command(movie::Command::Play(movie));
+                                *movie_state = MovieState::Playing(path);
+                            }
+                            Err(err) => log::error!("Failed to load movie: {}", err),
+                        }
+                    }
+                    ui.close_menu();
+                }
+            }
+            MovieState::Recording(_, movie) => {
+                let frame_count = movie.as_ref().map_or(0, |movie| movie.frames.len());
+                if ui
+                    .button(format!("Stop Recording ({} frames)", frame_count))
+                    .clicked()
+                {
+                    Ui::finish_movie(movie_state, frontend);
+                    ui.close_menu();
+                }
+            }
+            MovieState::Playing(_) => {
+                if ui.button("Stop Playback").clicked() {
+                    Ui::finish_movie(movie_state, frontend);
+                    ui.close_menu();
+                }
+            }
+        }
+    }
+
+    /// Stops the movie being recorded or played, saving a recording.
+    pub fn stop_movie(&mut self, frontend: &Frontend) {
+        Ui::finish_movie(&mut self.movie, frontend);
+    }
+
+    fn finish_movie(movie_state: &mut MovieState, frontend: &Frontend) {
+        match std::mem::replace(movie_state, MovieState::Idle) {
+            MovieState::Idle => return,
+            MovieState::Recording(path, movie) => {
+                let movie = match movie {
+                    Some(movie) => movie,
+                    None => return,
+                };
+
+                match movie.save(&path) {
+                    Ok(_) => log::info!(
+                        "Saved movie ({} frames): {}",
+                        movie.frames.len(),
+                        path.display()
+                    ),
+                    Err(err) => log::error!("Failed to save movie: {}", err),
+                }
+            }
+            MovieState::Playing(_) => {}
+        }
+
+        frontend.send_movie_command(movie::Command::Stop);
+    }
+
+    /// Pauses the UI along with the emulator when it hits a breakpoint, and shows the call
+    /// stack that led there.
+    pub fn poll_breakpoints(&mut self, frontend: &Frontend) {
+        if let Some(location) = frontend.recv_breakpoint_hit() {
+            log::info!("Hit breakpoint {}", self.symbols.get().format(location));
+            self.is_paused = true;
+            self.call_stack.show(true);
+        }
+    }
+
+    /// Processes the reports of the movie being recorded or played by the emulator.
+    pub fn poll_movie(&mut self, frontend: &Frontend) {
+        while let Some(event) = frontend.recv_movie_event() {
+            match (event, &mut self.movie) {
+                (movie::Event::Recording(started), MovieState::Recording(path, movie)) => {
+                    log::info!("Recording movie: {}", path.display());
+                    *movie = Some(started);
+                }
+                (movie::Event::Frame(frame), MovieState::Recording(_, Some(movie))) => {
+                    movie.frames.push(frame);
+                }
+                (movie::Event::PlaybackEnded(playback), MovieState::Playing(path)) => {
+                    match playback.is_in_sync() {
+                        true => log::info!("Movie {}: {}", path.display(), playback.describe()),
+                        false => log::error!("Movie {}: {}", path.display(), playback.describe()),
+                    }
+                    self.movie = MovieState::Idle;
+                }
+                (movie::Event::Error(err), _) => {
+                    log::error!("Movie failed: {}", err);
+                    self.movie = MovieState::Idle;
+                }
+                // Reports of a movie already stopped from the UI.
+                _ => {}
+            }
+        }
+    }
+
+    /// Passes the buttons held on the controllers, along with the turbo buttons and macros,
+    /// on to the emulator. Called once per frame.
+    pub fn poll_input(&mut self, frontend: &Frontend) {
+        self.controls.update(self.gamepads.poll(), frontend);
+
+        Ui::save_gamepad_settings(&self.gamepads, &mut self.config);
+    }
+
+    fn save_gamepad_settings(gamepads: &gamepad::Gamepads, config: &mut config::Config) {
+        if *gamepads.get_settings() != config.gamepad {
+            config.gamepad = gamepads.get_settings().clone();
+            config.save();
+        }
+    }
+
+    /// Saves the config file along with the current window size and position, which
+    /// are only saved on exit since they change continuously while resizing.
+    pub fn save_config(&mut self) {
+        self.config.window.size = self.window_layout.size;
+        self.config.window.position = self.window_layout.position;
+        self.config.save();
+    }
+
+    fn apply_fullscreen(display: &Display, fullscreen: bool) {
+        let mode = match fullscreen {
+            true => Some(Fullscreen::Borderless(None)),
+            false => None,
+        };
+        display.gl_window().window().set_fullscreen(mode);
+    }
+
+    /// Saves what is currently on screen to the screenshots directory.
+    pub fn take_screenshot(
+        &self,
+        display: &Display,
+        opengl_renderer: &renderer::OpenGL,
+        resolution: screenshot::Resolution,
+    ) {
+        let image = opengl_renderer.screenshot(
+            resolution,
+            display.get_framebuffer_dimensions(),
+            self.top_inset(),
+        );
+
+        match screenshot::save_timestamped(&image, &screenshot::directory(), "screenshot") {
+            Ok(path) => log::info!("Saved screenshot: {}", path.display()),
+            Err(err) => log::error!("Failed to save screenshot: {}", err),
+        }
+    }
+
+    fn start_recording(recorder: &mut Option<recorder::Recorder>, format: recorder::Format) {
+        let path = recorder::new_path(&recorder::directory(), format);
+        match recorder::Recorder::start(format, path) {
+            Ok(new_recorder) => {
+                log::info!("Started recording: {}", new_recorder.get_path().display());
+                *recorder = Some(new_recorder);
+            }
+            Err(err) => log::error!("Failed to start recording: {}", err),
+        }
+    }
+
+    /// Finalizes the current recording, if any.
+    pub fn stop_recording(&mut self) {
+        Ui::finish_recording(&mut self.recorder);
+    }
+
+    fn finish_recording(recorder: &mut Option<recorder::Recorder>) {
+        let recorder = match recorder.take() {
+            Some(recorder) => recorder,
+            None => return,
+        };
+
+        let frame_count = recorder.get_frame_count();
+        match recorder.stop() {
+            Ok(path) => log::info!("Saved {} frames to {}", frame_count, path.display()),
+            Err(err) => log::error!("Failed to save recording: {}", err),
+        }
+    }
+
+    /// Adds the frame the renderer just received to the current recording, if any.
+    pub fn record_frame(&mut self, opengl_renderer: &renderer::OpenGL) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_frame(opengl_renderer.native_image());
+        }
+    }
+
+    fn load_rom_from_file_dialog(
+        frontend: &mut Frontend,
+        config: &mut config::Config,
+        cheats: &mut cheats::Ui,
+        ram_search: &mut ram_search::Ui,
+        hex_editor: &mut hex_editor::Ui,
+        symbols: &mut symbols::SymbolFile,
+    ) {
+        let mut file_dialog = rfd::FileDialog::new().add_filter("Gameboy ROM", &["gb"]);
+        if let Some(directory) = &config.last_rom_directory {
+            file_dialog = file_dialog.set_directory(directory);
+        }
+        let selected_rom = file_dialog.pick_file();
 
         match selected_rom {
             Some(rom_path) => match fs::read(rom_path.as_path()) {
                 Ok(rom_data) => {
                     log::info!("Loaded ROM: {}", rom_path.display());
+                    cheats.load_rom(&rom_data, frontend);
+                    ram_search.load_rom();
+                    hex_editor.load_rom();
+                    symbols.load_rom(&rom_path);
                     frontend.send_rom_data_back_end(rom_data);
+
+                    config.last_rom_directory = rom_path.parent().map(PathBuf::from);
+                    config.save();
                 }
                 Err(err) => {
                     // TODO: Add UI dialog indicating error
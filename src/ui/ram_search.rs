@@ -0,0 +1,252 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Context, RichText};
+
+use crate::cheats::{self, Cheat};
+use crate::cpu::trace::Location;
+use crate::memory::Memory;
+use crate::ram_search::{Comparison, Region, Search, Snapshot};
+use crate::symbols::Symbols;
+
+/// Candidates listed at most, since a new search starts with every byte of RAM.
+const MAX_LISTED_CANDIDATES: usize = 100;
+
+// Address kept on display along with its live value.
+struct Watch {
+    name: String,
+    address: u16,
+}
+
+pub struct Ui {
+    show: bool,
+    rom_loaded: bool,
+    search: Option<Search>,
+    comparison: Comparison,
+    operand: u8,
+    watches: Vec<Watch>,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            rom_loaded: false,
+            search: None,
+            comparison: Comparison::Equal,
+            operand: 0,
+            watches: Vec::new(),
+        }
+    }
+
+    pub fn show(&mut self, show: bool) {
+        self.show = show;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.show
+    }
+
+    /// Clears the search and watches, which belong to the previous ROM.
+    pub fn load_rom(&mut self) {
+        self.rom_loaded = true;
+        self.search = None;
+        self.watches.clear();
+    }
+
+    /// Returns the cheat freezing an address when one is requested, for the cheats window
+    /// to add.
+    pub fn render(
+        &mut self,
+        egui_ctx: &Context,
+        memory_ref: &Arc<Mutex<Memory>>,
+        symbols: &Symbols,
+    ) -> Option<Cheat> {
+        let mut freeze = None;
+
+        egui::Window::new(RichText::new("RAM Search").size(24.0))
+            .collapsible(false)
+            .open(&mut self.show)
+            .show(egui_ctx, |ui| {
+                if !self.rom_loaded {
+                    ui.label("Load a ROM to search its RAM");
+                    return;
+                }
+
+                let memory = memory_ref.lock().unwrap();
+
+                ui.horizontal(|ui| {
+                    if ui.button("New Search").clicked() {
+                        self.search = Some(Search::new(Snapshot::capture(&memory, &Region::ALL)));
+                    }
+
+                    egui::ComboBox::from_id_source("ram_search_comparison")
+                        .selected_text(self.comparison.get_name())
+                        .show_ui(ui, |ui| {
+                            for comparison in Comparison::ALL {
+                                ui.selectable_value(
+                                    &mut self.comparison,
+                                    comparison,
+                                    comparison.get_name(),
+                                );
+                            }
+                        });
+
+                    if self.comparison.takes_operand() {
+                        ui.add(egui::DragValue::new(&mut self.operand));
+                    }
+
+                    if let Some(search) = &mut self.search {
+                        if ui.button("Filter").clicked() {
+                            search.filter(
+                                self.comparison,
+                                self.operand,
+                                Snapshot::capture(&memory, &Region::ALL),
+                            );
+                        }
+                    }
+                });
+                ui.label(
+                    RichText::new("Values are compared with the ones at the last search or filter")
+                        .weak(),
+                );
+
+                if let Some(search) = &self.search {
+                    ui.separator();
+                    if let Some(watch) =
+                        Ui::render_candidates(ui, search, &memory, symbols, &mut freeze)
+                    {
+                        self.watches.push(watch);
+                    }
+                }
+
+                ui.separator();
+                Ui::render_watches(ui, &mut self.watches, &memory, &mut freeze);
+            });
+
+        return freeze;
+    }
+
+    // Returns the watch to add when one is requested.
+    fn render_candidates(
+        ui: &mut egui::Ui,
+        search: &Search,
+        memory: &Memory,
+        symbols: &Symbols,
+        freeze: &mut Option<Cheat>,
+    ) -> Option<Watch> {
+        let candidates = search.get_candidates();
+        ui.label(format!("{} candidates", candidates.len()));
+        if candidates.len() > MAX_LISTED_CANDIDATES {
+            ui.label(
+                RichText::new(format!(
+                    "Showing the first {}, filter further to narrow them down",
+                    MAX_LISTED_CANDIDATES
+                ))
+                .weak(),
+            );
+        }
+
+        let mut watch = None;
+
+        egui::ScrollArea::vertical()
+            .id_source("ram_search_candidates")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("ram_search_candidates")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Address");
+                        ui.label("Previous");
+                        ui.label("Current");
+                        ui.end_row();
+
+                        for address in candidates.iter().take(MAX_LISTED_CANDIDATES) {
+                            let current = memory.dma_read(*address as usize).unwrap_or(0xFF);
+
+                            let label = symbols.describe(Location::new(*address, 0));
+                            let text = match &label {
+                                Some(label) => format!("{:04X} {}", address, label),
+                                None => format!("{:04X}", address),
+                            };
+                            ui.label(RichText::new(text).monospace());
+                            ui.label(Ui::format_value(search.get_previous(*address)));
+                            ui.label(Ui::format_value(Some(current)));
+
+                            if ui.button("Watch").clicked() {
+                                watch = Some(Watch {
+                                    name: label.unwrap_or_else(|| format!("{:04X}", address)),
+                                    address: *address,
+                                });
+                            }
+
+                            if ui.button("Freeze").clicked() {
+                                *freeze = Some(Ui::freeze(*address, current));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        return watch;
+    }
+
+    fn render_watches(
+        ui: &mut egui::Ui,
+        watches: &mut Vec<Watch>,
+        memory: &Memory,
+        freeze: &mut Option<Cheat>,
+    ) {
+        ui.label(RichText::new("Watches").size(18.0));
+        if watches.is_empty() {
+            ui.label("No watches, add them from the search candidates");
+            return;
+        }
+
+        let mut removed = None;
+
+        egui::Grid::new("ram_search_watches")
+            .striped(true)
+            .show(ui, |ui| {
+                for (index, watch) in watches.iter_mut().enumerate() {
+                    let current = memory.dma_read(watch.address as usize).unwrap_or(0xFF);
+
+                    ui.add(egui::TextEdit::singleline(&mut watch.name).desired_width(150.0));
+                    ui.label(RichText::new(format!("{:04X}", watch.address)).monospace());
+                    ui.label(Ui::format_value(Some(current)));
+
+                    if ui.button("Freeze").clicked() {
+                        let mut cheat = Ui::freeze(watch.address, current);
+                        cheat.name = watch.name.clone();
+                        *freeze = Some(cheat);
+                    }
+
+                    if ui.button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = removed {
+            watches.remove(index);
+        }
+    }
+
+    fn format_value(value: Option<u8>) -> RichText {
+        let text = match value {
+            Some(value) => format!("{:3} ({:02X})", value, value),
+            None => String::from("-"),
+        };
+
+        return RichText::new(text).monospace();
+    }
+
+    // Cheat holding the address at its current value.
+    fn freeze(address: u16, value: u8) -> Cheat {
+        return Cheat {
+            name: format!("Freeze {:04X}", address),
+            code: cheats::game_shark_code(address, value),
+            enabled: true,
+        };
+    }
+}
@@ -0,0 +1,294 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Color32, ColorImage, Context, Rect, RichText, Sense, Stroke, TextureHandle, Vec2};
+
+use crate::memory::io_registers::LCD_CONTROL_ADDR;
+use crate::memory::Memory;
+use crate::palette::LayerPalettes;
+use crate::ppu::sprite::{self, Sprite};
+use crate::ppu::{Layer, NATIVE_SCREEN_HEIGHT, NATIVE_SCREEN_WIDTH, PPU};
+
+/// Points per pixel of the OAM layout and of the sprite previews.
+const LAYOUT_SCALE: f32 = 2.0;
+const PREVIEW_SCALE: f32 = 3.0;
+
+/// Sprite coordinates are offset by 8 horizontally and 16 vertically in OAM, so that
+/// sprites can be hidden past the top and left edges of the screen.
+const OAM_X_OFFSET: f32 = 8.0;
+const OAM_Y_OFFSET: f32 = 16.0;
+const OAM_COORDINATE_RANGE: f32 = 256.0;
+
+const SELECTED_COLOR: Color32 = Color32::GREEN;
+const DROPPED_COLOR: Color32 = Color32::RED;
+
+pub struct Ui {
+    show: bool,
+}
+
+// Sprites overlapping a line, split by whether the OAM search selects them.
+struct LineSprites {
+    ly: u8,
+    selected: Vec<Sprite>,
+    dropped: Vec<Sprite>,
+}
+
+impl LineSprites {
+    fn new(sprites: &[Sprite], ly: u8, sprite_height: u8) -> Self {
+        let (selected, dropped) = sprite::split_line(sprites, ly, sprite_height);
+        Self {
+            ly,
+            selected,
+            dropped,
+        }
+    }
+
+    // Colour of the sprite when it is drawn on, or dropped from, the line.
+    fn highlight(&self, sprite: &Sprite) -> Option<Color32> {
+        let index = sprite.get_oam_index();
+        if self
+            .selected
+            .iter()
+            .any(|other| other.get_oam_index() == index)
+        {
+            return Some(SELECTED_COLOR);
+        }
+
+        if self
+            .dropped
+            .iter()
+            .any(|other| other.get_oam_index() == index)
+        {
+            return Some(DROPPED_COLOR);
+        }
+
+        return None;
+    }
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self { show: false }
+    }
+
+    pub fn show(&mut self, show: bool) {
+        self.show = show;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.show
+    }
+
+    pub fn render(
+        &mut self,
+        ctx: &Context,
+        memory_ref: &Arc<Mutex<Memory>>,
+        palettes: &LayerPalettes,
+    ) {
+        egui::Window::new(RichText::new("Sprite Viewer").size(24.0))
+            .collapsible(false)
+            .open(&mut self.show)
+            .show(ctx, |ui| {
+                let memory = memory_ref.lock().unwrap();
+                let lcdc = memory.dma_read(LCD_CONTROL_ADDR).unwrap();
+                let sprite_height = sprite::height(lcdc);
+                let sprites = sprite::read_all(&memory);
+
+                ui.label(format!("Sprite size: 8x{}", sprite_height));
+                ui.label(RichText::new("Hover a line of the layout to see its sprites").weak());
+
+                let hovered_line = Ui::render_layout(ui, &sprites, sprite_height);
+                match &hovered_line {
+                    Some(line) => ui.label(format!(
+                        "Line {}: {} sprites drawn, {} dropped",
+                        line.ly,
+                        line.selected.len(),
+                        line.dropped.len()
+                    )),
+                    None => ui.label(""),
+                };
+
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .id_source("sprite_viewer_entries")
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        Ui::render_entries(
+                            ctx,
+                            ui,
+                            &memory,
+                            &sprites,
+                            sprite_height,
+                            palettes,
+                            hovered_line.as_ref(),
+                        );
+                    });
+            });
+    }
+
+    // Draws the screen along with the outline of every sprite, and returns the sprites of
+    // the visible line the pointer is over, if any.
+    fn render_layout(
+        ui: &mut egui::Ui,
+        sprites: &[Sprite],
+        sprite_height: u8,
+    ) -> Option<LineSprites> {
+        let size = Vec2::splat(OAM_COORDINATE_RANGE * LAYOUT_SCALE);
+        let (response, painter) = ui.allocate_painter(size, Sense::hover());
+        let origin = response.rect.min;
+
+        // Position of a point given in screen pixels.
+        let to_layout = |x: f32, y: f32| {
+            return origin + Vec2::new(x + OAM_X_OFFSET, y + OAM_Y_OFFSET) * LAYOUT_SCALE;
+        };
+
+        painter.rect_filled(response.rect, 0.0, Color32::from_gray(32));
+        painter.rect_stroke(
+            Rect::from_min_max(
+                to_layout(0.0, 0.0),
+                to_layout(NATIVE_SCREEN_WIDTH as f32, NATIVE_SCREEN_HEIGHT as f32),
+            ),
+            0.0,
+            Stroke::new(1.0, Color32::WHITE),
+        );
+
+        let hovered_line = response.hover_pos().and_then(|pos| {
+            let y = (pos.y - origin.y) / LAYOUT_SCALE - OAM_Y_OFFSET;
+            match y >= 0.0 && y < NATIVE_SCREEN_HEIGHT as f32 {
+                true => Some(LineSprites::new(sprites, y as u8, sprite_height)),
+                false => None,
+            }
+        });
+
+        for sprite in sprites {
+            // Coordinates wrap around, so sprites at X or Y 0 are hidden past the edges.
+            let x = sprite.get_x().wrapping_add(8) as f32 - OAM_X_OFFSET;
+            let y = sprite.get_y().wrapping_add(16) as f32 - OAM_Y_OFFSET;
+            let rect = Rect::from_min_max(
+                to_layout(x, y),
+                to_layout(x + 8.0, y + sprite_height as f32),
+            );
+
+            let color = hovered_line
+                .as_ref()
+                .and_then(|line| line.highlight(sprite))
+                .unwrap_or(Color32::GRAY);
+            painter.rect_stroke(rect, 0.0, Stroke::new(1.0, color));
+        }
+
+        if let Some(line) = &hovered_line {
+            let y = to_layout(0.0, line.ly as f32 + 0.5).y;
+            painter.hline(
+                response.rect.x_range(),
+                y,
+                Stroke::new(1.0, Color32::YELLOW),
+            );
+        }
+
+        return hovered_line;
+    }
+
+    fn render_entries(
+        ctx: &Context,
+        ui: &mut egui::Ui,
+        memory: &Memory,
+        sprites: &[Sprite],
+        sprite_height: u8,
+        palettes: &LayerPalettes,
+        hovered_line: Option<&LineSprites>,
+    ) {
+        // Sprites dropped on any of the visible lines.
+        let mut dropped_lines = [0; sprite::OAM_ENTRY_COUNT];
+        for ly in 0..NATIVE_SCREEN_HEIGHT as u8 {
+            for sprite in sprite::split_line(sprites, ly, sprite_height).1 {
+                dropped_lines[sprite.get_oam_index() as usize] += 1;
+            }
+        }
+
+        egui::Grid::new("sprite_viewer_entries")
+            .striped(true)
+            .show(ui, |ui| {
+                for header in ["#", "", "X", "Y", "Tile", "Flip", "Palette", "Priority", ""] {
+                    ui.label(header);
+                }
+                ui.end_row();
+
+                for sprite in sprites {
+                    let index = sprite.get_oam_index();
+                    let text = RichText::new(format!("{}", index)).monospace();
+                    match hovered_line.and_then(|line| line.highlight(sprite)) {
+                        Some(color) => ui.label(text.color(color)),
+                        None => ui.label(text),
+                    };
+
+                    let texture = Ui::render_preview(ctx, memory, sprite, sprite_height, palettes);
+                    ui.image(
+                        &texture,
+                        Vec2::new(8.0, sprite_height as f32) * PREVIEW_SCALE,
+                    );
+
+                    ui.label(format!("{}", sprite.get_x().wrapping_add(8)));
+                    ui.label(format!("{}", sprite.get_y().wrapping_add(16)));
+                    ui.label(format!("{:02X}", sprite.get_pattern_number()));
+
+                    let flip = match (sprite.is_x_flipped(), sprite.is_y_flipped()) {
+                        (false, false) => "-",
+                        (true, false) => "X",
+                        (false, true) => "Y",
+                        (true, true) => "XY",
+                    };
+                    ui.label(flip);
+
+                    let palette = match sprite.get_layer() {
+                        Layer::Object1 => "OBP1",
+                        _ => "OBP0",
+                    };
+                    ui.label(palette);
+
+                    let priority = match sprite.bg_has_priority() {
+                        true => "Behind BG",
+                        false => "Above BG",
+                    };
+                    ui.label(priority);
+
+                    match dropped_lines[index as usize] {
+                        0 => ui.label(""),
+                        lines => {
+                            ui.colored_label(DROPPED_COLOR, format!("Dropped on {} lines", lines))
+                        }
+                    };
+                    ui.end_row();
+                }
+            });
+    }
+
+    // Sprite as drawn through its palette register, with colour 0 left transparent.
+    fn render_preview(
+        ctx: &Context,
+        memory: &Memory,
+        sprite: &Sprite,
+        sprite_height: u8,
+        palettes: &LayerPalettes,
+    ) -> TextureHandle {
+        let color_palette = memory.dma_read(sprite.get_palette_addr()).unwrap();
+        let palette = palettes.get(sprite.get_layer());
+
+        let mut rgba = Vec::with_capacity(8 * sprite_height as usize * 4);
+        for row in sprite.get_color_codes(memory, sprite_height) {
+            for code in row {
+                let (r, g, b) = palette.rgb(PPU::palette_lookup(color_palette, code));
+                let alpha = match code {
+                    0 => 0,
+                    _ => 0xFF,
+                };
+                rgba.extend_from_slice(&[r, g, b, alpha]);
+            }
+        }
+
+        let image = ColorImage::from_rgba_unmultiplied([8, sprite_height as usize], &rgba);
+        return ctx.load_texture(
+            format!("sprite_{}", sprite.get_oam_index()),
+            image,
+            egui::TextureOptions::NEAREST,
+        );
+    }
+}
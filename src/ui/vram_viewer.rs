@@ -1,27 +1,51 @@
+use crate::filters::{Image, Rgba};
 use crate::memory::io_registers::{
-    LCD_CONTROL_ADDR, LCD_PALETTE_ADDR, LCD_SCX_ADDR, LCD_SCY_ADDR, LCD_WINX_ADDR, LCD_WINY_ADDR,
+    LCD_CONTROL_ADDR, LCD_SCX_ADDR, LCD_SCY_ADDR, LCD_WINX_ADDR, LCD_WINY_ADDR,
 };
 use crate::memory::Memory;
-use crate::ppu::Pixel;
-use core::panic;
+use crate::palette::{LayerPalettes, Palette};
+use crate::ppu::{Layer, NATIVE_SCREEN_HEIGHT, NATIVE_SCREEN_WIDTH};
+use crate::registers;
+use crate::screenshot;
+use crate::tiles::{self, TilePalette};
 use egui::{
-    self, containers, style::Margin, Color32, ColorImage, Context, Label, RichText, TextureHandle,
-    Vec2,
+    self, containers, style::Margin, Color32, ColorImage, Context, Label, Pos2, Rect, RichText,
+    Stroke, TextureHandle, Vec2,
 };
 use std::sync::{Arc, Mutex};
 
+/// Colour the viewport is outlined with in exported background maps.
+const VIEWPORT_COLOR: Rgba = [0xFF, 0x00, 0x00, 0xFF];
+
 pub struct Ui {
     show: bool,
+    palettes: LayerPalettes,
+
+    // Palette register tiles are displayed and exported through.
+    tile_palette: TilePalette,
 }
 
 impl Ui {
     pub fn new() -> Self {
-        Self { show: false }
+        Self {
+            show: false,
+            palettes: LayerPalettes::new(Palette::default()),
+            tile_palette: TilePalette::Background,
+        }
     }
     pub fn show(&mut self, show: bool) {
         self.show = show;
     }
 
+    pub fn is_shown(&self) -> bool {
+        self.show
+    }
+
+    /// Sets the colours tiles are displayed with through the layer's palette register.
+    pub fn set_palette(&mut self, layer: Layer, palette: Palette) {
+        self.palettes.set(layer, palette);
+    }
+
     pub fn render(&mut self, ctx: &egui::Context, memory_ref: &Arc<Mutex<Memory>>) {
         let _ = egui::SidePanel::new(egui::panel::Side::Right, egui::Id::new("vram_viewer"))
             .min_width(650.0)
@@ -44,6 +68,9 @@ impl Ui {
                             ui.add_space(10.0);
                             self.render_lcdc_view(ui, &io_reg);
 
+                            ui.add_space(10.0);
+                            self.render_palette_view(ui, &io_reg);
+
                             ui.add_space(10.0);
                             self.render_tile_data_view(ctx, ui, &vram, &io_reg);
 
@@ -61,59 +88,60 @@ impl Ui {
         parent.push_id("lcdc_view", |ui| {
             let lcdc = io_reg[LCD_CONTROL_ADDR - 0xFF00];
 
-            let bg_win_enabled: &str = match lcdc & (1 << 0) > 0 {
-                true => "Enabled",
-                false => "Disabled",
-            };
-
-            let obj_enabled: &str = match lcdc & (1 << 1) > 0 {
-                true => "Enabled",
-                false => "Disabled",
-            };
-
-            let obj_size: &str = match lcdc & (1 << 2) > 0 {
-                true => "8x16",
-                false => "8x8",
-            };
-
-            let bg_win_tile_map_range: &str = match lcdc & (1 << 3) > 0 {
-                true => "0x9C00-0x9FFF",
-                false => "0x9800-0x9BFF",
-            };
-
-            let addressing_mode: &str = match lcdc & (1 << 4) > 0 {
-                true => "Unsigned / BG & Window Range: 0x8000 - 0x8FFF",
-                false => "Signed / BG & Window Range: 0x8800 - 0x97FF",
-            };
-
-            let window_enabled: &str = match lcdc & (1 >> 5) > 0 {
-                true => "Enabled",
-                false => "Disabled",
-            };
-
-            let window_tile_map_range: &str = match lcdc & (1 >> 6) > 0 {
-                true => "0x9C00-0x9FFF",
-                false => "0x9800-0x9BFF",
-            };
-
-            let lcd_enabled: &str = match lcdc & (1 << 7) > 0 {
-                true => "Enabled",
-                false => "Disabled",
-            };
-
             ui.label(RichText::new("LCDC Register").size(24.0));
             ui.separator();
-            ui.label(format!("0 - BG & Win: {}", bg_win_enabled));
-            ui.label(format!("1 - Objects: {}", obj_enabled));
-            ui.label(format!("2 - Object Size: {}", obj_size));
-            ui.label(format!("3 - Tile Map Range: {}", bg_win_tile_map_range));
-            ui.label(format!("4 - Addressing mode: {}", addressing_mode));
-            ui.label(format!("5 - Window: {}", window_enabled));
-            ui.label(format!(
-                "6 - Window Tile Map Range: {}",
-                window_tile_map_range
-            ));
-            ui.label(format!("7 - LCD / PPU: {}", lcd_enabled));
+            for field in registers::LCDC.fields.iter().rev() {
+                ui.label(format!(
+                    "{} - {}: {}",
+                    field.shift,
+                    field.name,
+                    field.describe(lcdc)
+                ));
+            }
+        });
+    }
+
+    fn render_palette_view(&mut self, parent: &mut egui::Ui, io_reg: &[u8; 0x80]) {
+        parent.push_id("palette_view", |ui| {
+            ui.label(RichText::new("Palettes").size(24.0));
+            ui.separator();
+
+            egui::Grid::new("palette_grid").show(ui, |ui| {
+                for tile_palette in [
+                    TilePalette::Background,
+                    TilePalette::Object0,
+                    TilePalette::Object1,
+                ] {
+                    let register = tile_palette.get_register(io_reg);
+                    let palette = self.palettes.get(tile_palette.get_layer());
+                    ui.label(format!("{}: {:02X}", tile_palette.get_name(), register));
+
+                    for code in 0..4 {
+                        let [r, g, b, _] = tiles::rgba(code, register, palette);
+                        let (response, painter) =
+                            ui.allocate_painter(Vec2::new(24.0, 24.0), egui::Sense::hover());
+                        painter.rect_filled(response.rect, 0.0, Color32::from_rgb(r, g, b));
+                        response.on_hover_text(format!("Colour {}", code));
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.label(RichText::new("Colour 0 of the sprite palettes is transparent").weak());
+
+            ui.horizontal(|ui| {
+                ui.label("Display tiles with: ");
+                egui::ComboBox::from_id_source("tile_palette")
+                    .selected_text(self.tile_palette.get_name())
+                    .show_ui(ui, |ui| {
+                        for tile_palette in TilePalette::ALL {
+                            ui.selectable_value(
+                                &mut self.tile_palette,
+                                tile_palette,
+                                tile_palette.get_name(),
+                            );
+                        }
+                    });
+            });
         });
     }
 
@@ -124,17 +152,32 @@ impl Ui {
         vram: &[u8; 0x2000],
         io_reg: &[u8; 0x80],
     ) {
-        let winx = io_reg[LCD_WINX_ADDR - 0xFF00].wrapping_sub(7);
+        let winx = io_reg[LCD_WINX_ADDR - 0xFF00];
         let winy = io_reg[LCD_WINY_ADDR - 0xFF00];
         let lcdc = io_reg[LCD_CONTROL_ADDR - 0xFF00];
+        let register = self.tile_palette.get_register(io_reg);
+        let palette = self.palettes.get(self.tile_palette.get_layer());
 
         parent.push_id("win_tile_map_view", |ui| {
             ui.label(RichText::new("Window Tile Mapping").size(24.0));
             ui.separator();
 
-            ui.label(format!("Window X: {}", winx));
+            ui.label(format!("Window X: {} (WX: {})", winx as i16 - 7, winx));
             ui.label(format!("Window Y: {}", winy));
 
+            let visible_region = visible_window_region(lcdc, winx, winy);
+            match visible_region {
+                Some(region) => ui.label(format!(
+                    "Visible region: {}x{} pixels",
+                    region.width(),
+                    region.height()
+                )),
+                None => ui.label("Visible region: none"),
+            };
+
+            // Top left corner of the first tile, used to overlay the visible region on the grid.
+            let mut origin = Pos2::ZERO;
+
             ui.spacing_mut().item_spacing = Vec2::new(1.0, 1.0);
             for y in 0..33 {
                 ui.horizontal(|ui| {
@@ -154,18 +197,31 @@ impl Ui {
                             continue;
                         }
 
-                        let base_addr = match lcdc & (1 << 6) > 0 {
-                            true => 0x9C00,
-                            false => 0x9800,
-                        };
+                        let base_addr = tiles::window_map_address(lcdc);
                         let offset: usize = ((y - 1) * 32) + (x - 1);
 
-                        let tile_id = vram[(base_addr + offset) - 0x8000] as usize;
-                        let texture = Self::render_tile(ctx, &vram, &io_reg, tile_id);
-                        ui.image(&texture, Vec2::new(24.0, 24.0));
+                        let tile_id = vram[(base_addr + offset) - 0x8000];
+                        let address = tiles::map_tile_address(lcdc, tile_id);
+                        let texture = Self::render_tile(ctx, vram, address, register, palette);
+                        let response = ui.image(&texture, Vec2::new(24.0, 24.0));
+
+                        if x == 1 && y == 1 {
+                            origin = response.rect.min;
+                        }
                     }
                 });
             }
+
+            if let Some(region) = visible_region {
+                // Each 8 pixel tile takes up 24 points, plus 1 point of spacing.
+                let points_per_pixel = 25.0 / 8.0;
+                let rect = Rect::from_min_max(
+                    origin + region.min.to_vec2() * points_per_pixel,
+                    origin + region.max.to_vec2() * points_per_pixel,
+                );
+                ui.painter()
+                    .rect_stroke(rect, 0.0, Stroke::new(2.0, Color32::RED));
+            }
         });
     }
 
@@ -179,11 +235,24 @@ impl Ui {
         let lcdc = io_reg[LCD_CONTROL_ADDR - 0xFF00];
         let scx = io_reg[LCD_SCX_ADDR - 0xFF00];
         let scy = io_reg[LCD_SCY_ADDR - 0xFF00];
+        let register = self.tile_palette.get_register(io_reg);
+        let palette = self.palettes.get(self.tile_palette.get_layer());
 
         parent.push_id("tile_map_view", |ui| {
             ui.label(egui::RichText::new("Background Tile Mapping").size(24.0));
             ui.separator();
 
+            if ui
+                .button("Export PNG")
+                .on_hover_text("Save the whole 256x256 map with the viewport outlined")
+                .clicked()
+            {
+                let map_address = tiles::background_map_address(lcdc);
+                let mut image = tiles::tile_map(vram, lcdc, map_address, register, palette);
+                tiles::draw_viewport(&mut image, scx, scy, VIEWPORT_COLOR);
+                Ui::export(&image, "bg-map");
+            }
+
             ui.label(format!("Scroll X: {}", scx));
             ui.label(format!("Scroll Y: {}", scy));
 
@@ -207,14 +276,12 @@ impl Ui {
                             continue;
                         }
 
-                        let base_addr = match lcdc & (1 << 3) > 0 {
-                            true => 0x9C00,
-                            false => 0x9800,
-                        };
+                        let base_addr = tiles::background_map_address(lcdc);
                         let offset: usize = ((y - 1) * 32) + (x - 1);
 
-                        let tile_id = vram[(base_addr + offset) - 0x8000] as usize;
-                        let texture = Self::render_tile(ctx, &vram, &io_reg, tile_id);
+                        let tile_id = vram[(base_addr + offset) - 0x8000];
+                        let address = tiles::map_tile_address(lcdc, tile_id);
+                        let texture = Self::render_tile(ctx, vram, address, register, palette);
                         ui.image(&texture, Vec2::new(24.0, 24.0));
                     }
                 });
@@ -229,10 +296,21 @@ impl Ui {
         vram: &[u8; 0x2000],
         io_reg: &[u8; 0x80],
     ) {
+        let register = self.tile_palette.get_register(io_reg);
+        let palette = self.palettes.get(self.tile_palette.get_layer());
+
         parent.push_id("tile_data_view", |ui| {
             ui.label(egui::RichText::new("Tile Data").size(24.0));
             ui.separator();
 
+            if ui
+                .button("Export PNG")
+                .on_hover_text("Save every tile as a 16 tiles wide sheet")
+                .clicked()
+            {
+                Ui::export(&tiles::tileset_sheet(vram, register, palette), "tileset");
+            }
+
             ui.spacing_mut().item_spacing = Vec2::new(1.0, 1.0);
 
             for y in 0..25 {
@@ -262,8 +340,9 @@ impl Ui {
                         }
 
                         let tile_id = ((y - 1) * 16) + (x - 1);
+                        let address = tiles::tile_address(tile_id);
 
-                        let texture = Self::render_tile(ctx, &vram, &io_reg, tile_id);
+                        let texture = Self::render_tile(ctx, vram, address, register, palette);
                         ui.image(&texture, Vec2::new(24.0, 24.0));
                     }
                 });
@@ -274,76 +353,51 @@ impl Ui {
     fn render_tile(
         ctx: &Context,
         vram: &[u8; 0x2000],
-        io_reg: &[u8; 0x80],
-        tile_id: usize,
+        address: usize,
+        register: u8,
+        palette: &Palette,
     ) -> TextureHandle {
-        let mut tile_rgb: [u8; 8 * 8 * 3] = [0x0; 8 * 8 * 3];
-        let mut iter: usize = 0;
-
-        let lcdc = io_reg[LCD_CONTROL_ADDR - 0xFF00];
-        let is_unsigned = lcdc & (1 << 4) > 0;
-
-        let effective_addr: usize = match is_unsigned {
-            true => 0x8000 + (tile_id * 16),
-            false => match tile_id < 128 {
-                true => 0x9000 + (tile_id * 16),
-                false => 0x8800 + ((tile_id - 128) * 16),
-            },
-        };
-
-        for i in (0..16).step_by(2) {
-            let data1 = vram[(effective_addr + i) - 0x8000];
-            let data2 = vram[(effective_addr + i + 1) - 0x8000];
-
-            for j in (0..8).rev() {
-                let mut pixel_color_encoding: u8 = 0x00;
-
-                if data2 & (1 << j) > 0 {
-                    pixel_color_encoding |= 0b10;
-                }
-
-                if data1 & (1 << j) > 0 {
-                    pixel_color_encoding |= 0b01;
-                }
-
-                let color_palette = io_reg[LCD_PALETTE_ADDR - 0xFF00];
-
-                let palette_00 = (color_palette) & 0b11;
-                let palette_01 = (color_palette >> 2) & 0b11;
-                let palette_10 = (color_palette >> 4) & 0b11;
-                let palette_11 = (color_palette >> 6) & 0b11;
-
-                let translated_color = match pixel_color_encoding {
-                    0b00 => palette_00,
-                    0b01 => palette_01,
-                    0b10 => palette_10,
-                    0b11 => palette_11,
-                    _ => panic!("invalid pixel encoding"),
-                };
-
-                let pixel = match translated_color {
-                    0b00 => Pixel::White,
-                    0b01 => Pixel::LightGray,
-                    0b10 => Pixel::DarkGray,
-                    0b11 => Pixel::Black,
-                    _ => panic!("invalid pixel encoding"),
-                };
-
-                let rgb = pixel.to_rgb_u8();
-                tile_rgb[iter] = rgb.0;
-                iter += 1;
-                tile_rgb[iter] = rgb.1;
-                iter += 1;
-                tile_rgb[iter] = rgb.2;
-                iter += 1;
+        let mut tile_rgba = Vec::with_capacity(8 * 8 * 4);
+        for row in tiles::color_codes(vram, address) {
+            for code in row {
+                tile_rgba.extend_from_slice(&tiles::rgba(code, register, palette));
             }
         }
 
-        let image = ColorImage::from_rgb([8, 8], &tile_rgb);
+        let image = ColorImage::from_rgba_unmultiplied([8, 8], &tile_rgba);
         return ctx.load_texture(
-            format!("tile_id_{}", tile_id),
+            format!("tile_{:04X}", address),
             image,
             egui::TextureOptions::NEAREST,
         );
     }
+
+    // Saves the image to the screenshots directory.
+    fn export(image: &Image, prefix: &str) {
+        match screenshot::save_timestamped(image, &screenshot::directory(), prefix) {
+            Ok(path) => log::info!("Exported {}", path.display()),
+            Err(err) => log::error!("Failed to export {}: {}", prefix, err),
+        }
+    }
+}
+
+// Area of the window tile map that ends up on screen, in window pixel coordinates.
+// Assumes the window stays enabled for the whole frame, since the internal line counter
+// only advances on lines where the window is rendered.
+fn visible_window_region(lcdc: u8, winx: u8, winy: u8) -> Option<Rect> {
+    let window_enabled = lcdc & (1 << 5) > 0 && lcdc & (1 << 0) > 0;
+    if !window_enabled || winx >= 166 || winy as usize >= NATIVE_SCREEN_HEIGHT {
+        return None;
+    }
+
+    // WX values below 7 push the first columns of the window past the left edge of the screen.
+    let start_x = winx as f32 - 7.0;
+    let min_x = (-start_x).max(0.0);
+    let width = NATIVE_SCREEN_WIDTH as f32 - start_x.max(0.0);
+    let height = (NATIVE_SCREEN_HEIGHT - winy as usize) as f32;
+
+    return Some(Rect::from_min_size(
+        Pos2::new(min_x, 0.0),
+        Vec2::new(width, height),
+    ));
 }